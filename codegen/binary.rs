@@ -33,10 +33,20 @@ fn get_decode_method(kind: &str) -> String {
     snake_casify(kind)
 }
 
-/// Returns the generated operand decoding errors for binary::Decoder by
-/// walking the given SPIR-V operand kinds `grammar`.
-pub fn gen_operand_decode_errors(grammar: &Vec<structs::OperandKind>)
-                                 -> String {
+/// Returns the code decoding an operand of the given `kind` in grammar for
+/// binary::InstParser.
+fn get_decode_expr(kind: &str) -> String {
+    let decode = format!("try_decode!(self.decoder.{}())", get_decode_method(kind));
+    if kind == "LiteralString" {
+        // Strings are shared among operands with the same value.
+        format!("intern(&mut self.strings, {})", decode)
+    } else {
+        decode
+    }
+}
+
+/// Returns the generated operand decoding errors for binary::Decoder.
+pub fn gen_operand_decode_errors() -> String {
     let mut ret = String::new();
 
     { // Comments, attributes, uses.
@@ -44,25 +54,20 @@ pub fn gen_operand_decode_errors(grammar: &Vec<structs::OperandKind>)
         ret.push_str("\n\nuse spirv;\nuse std::{error, fmt};\n\n");
     }
 
-    let kinds: Vec<&str> = grammar.iter().filter(|element| {
-        !(element.kind.starts_with("Pair") ||
-          element.kind.starts_with("Id") ||
-          element.kind.starts_with("Literal"))
-    }).map(|element| {
-        element.kind.as_str()
-    }).collect();
-
     // The Error enum.
-    let errors: Vec<String> = kinds.iter().map(|element| {
-        format!("    {}Unknown(usize, spirv::Word),", element)
-    }).collect();
     let error_enum = format!(
         "/// Decoder Error.\n\
          #[derive(Debug, PartialEq)]\n\
          pub enum Error {{\n\
          {s:4}StreamExpected(usize),\n\
          {s:4}LimitReached(usize),\n\
-         {errors}\n\
+         {s:4}SeekFailed(usize),\n\
+         {s:4}/// Unknown value for an operand of a value enum kind\n\
+         {s:4}/// (offset, kind, value).\n\
+         {s:4}EnumerantUnknown(usize, &'static str, spirv::Word),\n\
+         {s:4}/// Unknown bits set for an operand of a bit enum kind\n\
+         {s:4}/// (offset, kind, unknown bits).\n\
+         {s:4}FlagBitsUnknown(usize, &'static str, spirv::Word),\n\
          {s:4}/// Failed to decode a string.\n\
          {s:4}///\n\
          {s:4}/// For structured error handling, the second element could be\n\
@@ -70,32 +75,32 @@ pub fn gen_operand_decode_errors(grammar: &Vec<structs::OperandKind>)
          {s:4}/// from generating `PartialEq` for this enum.\n\
          {s:4}DecodeStringFailed(usize, String),\n\
          }}\n\n",
-        s = "",
-        errors = errors.join("\n"));
+        s = "");
     ret.push_str(&error_enum);
 
     // impl fmt::Display for the Error enum.
-    let errors: Vec<String> = kinds.iter().map(|element| {
-        format!("{s:12}Error::{kind}Unknown(index, word) => write!(\
-                 f, \"unknown value {{}} for operand kind {kind} \
-                 at index {{}}\", word, index),",
-                s = "",
-                kind = element)
-    }).collect();
     let display_impl = format!(
         "impl fmt::Display for Error {{\n\
          {s:4}fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {{\n\
          {s:8}match *self {{\n\
          {s:12}Error::StreamExpected(index) => write!(f, \"expected more \
-             bytes in the stream at index {{}}\", index),\n\
+             bytes in the stream at word {{}} (byte {{}})\", index / 4, \
+             index),\n\
          {s:12}Error::LimitReached(index) => write!(f, \"reached word limit \
-             at index {{}}\", index),\n\
-         {errors}\n\
+             at word {{}} (byte {{}})\", index / 4, index),\n\
+         {s:12}Error::SeekFailed(index) => write!(f, \"cannot seek to \
+             word {{}} (byte {{}})\", index / 4, index),\n\
+         {s:12}Error::EnumerantUnknown(index, kind, word) => write!(f, \
+             \"unknown value {{}} for operand kind {{}} at word {{}} \
+             (byte {{}})\", word, kind, index / 4, index),\n\
+         {s:12}Error::FlagBitsUnknown(index, kind, bits) => write!(f, \
+             \"unknown bits {{:#x}} for operand kind {{}} at word {{}} \
+             (byte {{}})\", bits, kind, index / 4, index),\n\
          {s:12}Error::DecodeStringFailed(index, ref e) => write!(f, \
-             \"cannot decode string at index {{}}: {{}}\", index, e),\n\
+             \"cannot decode string at word {{}} (byte {{}}): {{}}\", \
+             index / 4, index, e),\n\
          {s:8}}}\n{s:4}}}\n}}\n\n",
-        s = "",
-        errors = errors.join("\n"));
+        s = "");
     ret.push_str(&display_impl);
 
     // impl error::Error for the Error enum.
@@ -105,11 +110,38 @@ pub fn gen_operand_decode_errors(grammar: &Vec<structs::OperandKind>)
          {s:8}match *self {{\n\
          {s:12}Error::StreamExpected(_) => \"expected more bytes \
              in the stream\",\n\
-         {s:12}_ => \"unknown operand value for the given kind\",\n\
+         {s:12}Error::LimitReached(_) => \"reached word limit\",\n\
+         {s:12}Error::SeekFailed(_) => \"cannot seek to the given \
+             position\",\n\
+         {s:12}Error::EnumerantUnknown(..) => \"unknown value for the \
+             given operand kind\",\n\
+         {s:12}Error::FlagBitsUnknown(..) => \"unknown bits for the \
+             given operand kind\",\n\
+         {s:12}Error::DecodeStringFailed(..) => \"cannot decode string\",\n\
          {s:8}}}\n{s:4}}}\n}}\n",
         s = "");
     ret.push_str(&error_impl);
 
+    // Offset accessors for the Error enum.
+    let offset_impl = format!(
+        "\nimpl Error {{\n\
+         {s:4}/// Returns the byte offset of the word failed decoding.\n\
+         {s:4}pub fn offset(&self) -> usize {{\n\
+         {s:8}match *self {{\n\
+         {s:12}Error::StreamExpected(index) |\n\
+         {s:12}Error::LimitReached(index) |\n\
+         {s:12}Error::SeekFailed(index) |\n\
+         {s:12}Error::EnumerantUnknown(index, _, _) |\n\
+         {s:12}Error::FlagBitsUnknown(index, _, _) |\n\
+         {s:12}Error::DecodeStringFailed(index, _) => index,\n\
+         {s:8}}}\n{s:4}}}\n\n\
+         {s:4}/// Returns the offset of the word failed decoding, in words.\n\
+         {s:4}pub fn word_offset(&self) -> usize {{\n\
+         {s:8}self.offset() / 4\n\
+         {s:4}}}\n}}\n",
+        s = "");
+    ret.push_str(&offset_impl);
+
     ret
 }
 
@@ -129,22 +161,35 @@ pub fn gen_operand_decode_methods(grammar: &Vec<structs::OperandKind>)
           element.kind.starts_with("Literal"))
     }).map(|element| {
         // Method definition for decoding values of a particular operand
-        // kind. If the operand kind belongs to BitEnum, we use from_bits(),
-        // otherwise, from_u32().
+        // kind. If the operand kind belongs to BitEnum, we keep the known
+        // bits and let the decoder handle the unknown ones, otherwise, we
+        // use from_u32().
+        let decode = if element.category == "BitEnum" {
+            format!("{s:12}let value = spirv::{kind}::from_bits_truncate(word);\n\
+                     {s:12}self.check_flag_bits(\"{kind}\", word, value.bits())?;\n\
+                     {s:12}Ok(value)\n",
+                    s = "",
+                    kind = element.kind)
+        } else {
+            format!("{s:12}spirv::{kind}::from_u32(word).ok_or(Error::\
+                     EnumerantUnknown(self.offset - WORD_NUM_BYTES, \
+                     \"{kind}\", word))\n",
+                    s = "",
+                    kind = element.kind)
+        };
         format!(
             "{s:4}/// Decodes and returns the next SPIR-V word as\n\
              {s:4}/// a SPIR-V {kind} value.\n\
              {s:4}pub fn {fname}(&mut self) -> Result<spirv::{kind}> {{\n\
              {s:8}if let Ok(word) = self.word() {{\n\
-                 {s:12}spirv::{kind}::from_{ty}(word).ok_or(Error::\
-                 {kind}Unknown(self.offset - WORD_NUM_BYTES, word))\n\
+             {decode}\
              {s:8}}} else {{\n\
                  {s:12}Err(Error::StreamExpected(self.offset))\n\
              {s:8}}}\n{s:4}}}\n",
              s = "",
              fname = snake_casify(&element.kind),
              kind = element.kind,
-             ty = if element.category == "BitEnum" { "bits" } else { "u32" })
+             decode = decode)
     }).collect();
     ret.push_str(&format!("impl<'a> Decoder<'a> {{\n{}}}\n", methods.join("\n")));
 
@@ -197,10 +242,9 @@ fn gen_operand_param_parse_methods(grammar: &Vec<structs::OperandKind>)
             // associated parameters.
             let cases: Vec<String> = pairs.into_iter().map(|(symbol, params)| {
                 let params: Vec<String> = params.iter().map(|element| {
-                    format!("mr::Operand::{kind}(\
-                             try_decode!(self.decoder.{decode}()))",
+                    format!("mr::Operand::{kind}({decode})",
                             kind = get_mr_operand_kind(element),
-                            decode = get_decode_method(element))
+                            decode = get_decode_expr(element))
                 }).collect();
                 format!(
                     "{s:8}if {arg}.contains(spirv::{k}_{bit}) {{\n\
@@ -226,10 +270,9 @@ fn gen_operand_param_parse_methods(grammar: &Vec<structs::OperandKind>)
         } else {  // ValueEnum
             let cases: Vec<String> = pairs.into_iter().map(|(symbol, params)| {
                 let params: Vec<String> = params.iter().map(|element| {
-                    format!("mr::Operand::{kind}(\
-                             try_decode!(self.decoder.{decode}()))",
+                    format!("mr::Operand::{kind}({decode})",
                             kind = get_mr_operand_kind(element),
-                            decode = get_decode_method(element))
+                            decode = get_decode_expr(element))
                 }).collect();
                 format!(
                     "{s:12}spirv::{kind}::{symbol} => vec![{params}],",
@@ -255,7 +298,7 @@ fn gen_operand_param_parse_methods(grammar: &Vec<structs::OperandKind>)
     }).collect()
 }
 
-/// Returns the generated operand parsing methods for binary::Parser by
+/// Returns the generated operand parsing methods for binary::InstParser by
 /// walking the given SPIR-V operand kinds `grammar`.
 pub fn gen_operand_parse_methods(grammar: &Vec<structs::OperandKind>) -> String {
     // Operand kinds whose enumerants have parameters. For these kinds, we need
@@ -314,12 +357,11 @@ pub fn gen_operand_parse_methods(grammar: &Vec<structs::OperandKind>) -> String
             }
     }).map(|kind| {
         format!(
-            "{s:12}GOpKind::{gkind} => vec![mr::Operand::{mkind}\
-             (try_decode!(self.decoder.{decode}()))],",
+            "{s:12}GOpKind::{gkind} => vec![mr::Operand::{mkind}({decode})],",
              s = "",
              gkind = kind,
              mkind = get_mr_operand_kind(kind),
-             decode = get_decode_method(kind))
+             decode = get_decode_expr(kind))
     }).collect();
 
     let manual_cases: Vec<String> =
@@ -330,7 +372,7 @@ pub fn gen_operand_parse_methods(grammar: &Vec<structs::OperandKind>) -> String
         }).collect();
 
     format!(
-        "impl<'c, 'd> Parser<'c, 'd> {{\n\
+        "impl<'d> InstParser<'d> {{\n\
          {s:4}fn parse_operand(&mut self, kind: GOpKind) \
              -> Result<Vec<mr::Operand>> {{\n\
              {s:8}Ok(match kind {{\n\
@@ -384,3 +426,134 @@ pub fn gen_disas_bit_enum_operands(grammar: &Vec<structs::OperandKind>) -> Strin
 
     elements.join("\n\n")
 }
+
+/// Returns the code constructing the mr::Operand for the given `enumerant`
+/// of the given enum operand `kind`.
+fn get_enumerant_operand(kind: &structs::OperandKind, enumerant: &structs::Enumerant) -> String {
+    if kind.category == "BitEnum" {
+        let mut symbol = snake_casify(&enumerant.symbol).to_uppercase();
+        if &symbol == "NOT_NA_N" {
+            symbol = "NOT_NAN".to_string()
+        }
+        format!("mr::Operand::{k}(spirv::{uk}_{s})",
+                k = kind.kind,
+                uk = snake_casify(&kind.kind).to_uppercase(),
+                s = symbol)
+    } else if kind.kind == "Dim" {
+        format!("mr::Operand::Dim(spirv::Dim::Dim{})", enumerant.symbol)
+    } else {
+        format!("mr::Operand::{k}(spirv::{k}::{s})", k = kind.kind, s = enumerant.symbol)
+    }
+}
+
+/// Returns the code constructing a representative mr::Operand of the given
+/// operand `kind`, for use as an enumerant parameter.
+fn get_param_operand(kind: &str, grammar: &Vec<structs::OperandKind>) -> String {
+    if kind.starts_with("Id") {
+        format!("mr::Operand::{}(1)", kind)
+    } else if kind == "LiteralInteger" {
+        "mr::Operand::LiteralInt32(1)".to_string()
+    } else if kind == "LiteralString" {
+        "mr::Operand::from(\"param\")".to_string()
+    } else {
+        let element = grammar.iter().find(|element| element.kind == kind).unwrap();
+        get_enumerant_operand(element, element.enumerants.last().unwrap())
+    }
+}
+
+/// Returns lists of representative operands for the given operand `kind`
+/// without enumerants.
+fn get_literal_operands(kind: &str) -> Vec<Vec<&'static str>> {
+    match kind {
+        "IdResultType" | "IdResult" => vec![vec!["mr::Operand::IdRef(42)"]],
+        "IdMemorySemantics" => vec![vec!["mr::Operand::IdMemorySemantics(42)"]],
+        "IdScope" => vec![vec!["mr::Operand::IdScope(42)"]],
+        "IdRef" => vec![vec!["mr::Operand::IdRef(1)"], vec!["mr::Operand::IdRef(0xffffffff)"]],
+        "LiteralInteger" => {
+            vec![vec!["mr::Operand::LiteralInt32(0)"],
+                 vec!["mr::Operand::LiteralInt32(0xffffffff)"]]
+        }
+        // Covers each amount of padding.
+        "LiteralString" => {
+            vec![vec!["mr::Operand::from(\"\")"],
+                 vec!["mr::Operand::from(\"a\")"],
+                 vec!["mr::Operand::from(\"ab\")"],
+                 vec!["mr::Operand::from(\"abc\")"],
+                 vec!["mr::Operand::from(\"abcd\")"],
+                 vec!["mr::Operand::from(\"UTF-8: \\u{e9}\")"]]
+        }
+        // Decoded by the number of words without the type.
+        "LiteralContextDependentNumber" => {
+            vec![vec!["mr::Operand::LiteralInt32(0xdeadbeef)"],
+                 vec!["mr::Operand::LiteralInt64(0x0123456789abcdef)"]]
+        }
+        "LiteralExtInstInteger" => vec![vec!["mr::Operand::LiteralExtInstInteger(7)"]],
+        "LiteralSpecConstantOpInteger" => {
+            vec![vec!["mr::Operand::LiteralSpecConstantOpInteger(spirv::Op::IAdd)"]]
+        }
+        "PairLiteralIntegerIdRef" => {
+            vec![vec!["mr::Operand::LiteralInt32(1)", "mr::Operand::IdRef(2)"]]
+        }
+        "PairIdRefLiteralInteger" => {
+            vec![vec!["mr::Operand::IdRef(1)", "mr::Operand::LiteralInt32(2)"]]
+        }
+        "PairIdRefIdRef" => vec![vec!["mr::Operand::IdRef(1)", "mr::Operand::IdRef(2)"]],
+        _ => panic!("unexpected operand kind: {}", kind),
+    }
+}
+
+/// Returns the generated tests encoding representative operands of each
+/// operand kind and parsing them back with binary::parse_operand.
+///
+/// Each enumerant of enum kinds is covered, followed by representative
+/// values of its parameters, if any.
+pub fn gen_operand_round_trip_tests(grammar: &Vec<structs::OperandKind>) -> String {
+    let mut ret = String::new();
+
+    { // Attributes, uses, and the helper.
+        ret.push_str(RUSTFMT_SKIP_BANG);
+        ret.push_str("\n\nuse mr;\nuse spirv;\n\n\
+                      use binary::{Assemble, parse_operand};\n\
+                      use grammar::OperandKind as GOpKind;\n\n");
+        ret.push_str("/// Encodes the given `operands` and checks that parsing the words as a\n\
+                      /// lone operand of the given `kind` gives them back.\n\
+                      fn check(kind: GOpKind, operands: Vec<mr::Operand>) {\n\
+                      \x20   let mut words = vec![];\n\
+                      \x20   for operand in &operands {\n\
+                      \x20       operand.assemble_into(&mut words);\n\
+                      \x20   }\n\
+                      \x20   match parse_operand(kind, &words) {\n\
+                      \x20       Ok(parsed) => assert_eq!(operands, parsed),\n\
+                      \x20       Err(err) => panic!(\"failed to parse {:?} from {:?}: {}\", kind, words, err),\n\
+                      \x20   }\n\
+                      }\n\n");
+    }
+
+    let tests: Vec<String> = grammar.iter().map(|element| {
+        let operands: Vec<Vec<String>> = if element.enumerants.is_empty() {
+            get_literal_operands(&element.kind).into_iter().map(|operands| {
+                operands.into_iter().map(|o| o.to_string()).collect()
+            }).collect()
+        } else {
+            element.enumerants.iter().map(|enumerant| {
+                let mut operands = vec![get_enumerant_operand(element, enumerant)];
+                operands.extend(enumerant.parameters.iter().map(|p| {
+                    get_param_operand(&p.kind, grammar)
+                }));
+                operands
+            }).collect()
+        };
+        let checks: Vec<String> = operands.iter().map(|operands| {
+            format!("{s:4}check(GOpKind::{k}, vec![{o}]);",
+                    s = "",
+                    k = element.kind,
+                    o = operands.join(", "))
+        }).collect();
+        format!("#[test]\nfn test_{name}() {{\n{checks}\n}}",
+                name = snake_casify(&element.kind),
+                checks = checks.join("\n"))
+    }).collect();
+    ret.push_str(&tests.join("\n\n"));
+    ret.push('\n');
+    ret
+}
@@ -154,7 +154,7 @@ fn main() {
     {
         // Path to the generated decoding errors.
         let path = codegen_src_dir.join("../rspirv/binary/error.rs");
-        let c = binary::gen_operand_decode_errors(&grammar.operand_kinds);
+        let c = binary::gen_operand_decode_errors();
         write!(c, path);
     }
 
@@ -177,6 +177,13 @@ fn main() {
         write!(c, path);
     }
 
+    {
+        // Path to the generated operand encoding and parsing tests.
+        let path = codegen_src_dir.join("../rspirv/binary/test_operand.rs");
+        let c = binary::gen_operand_round_trip_tests(&grammar.operand_kinds);
+        write!(c, path);
+    }
+
     {
         let path = codegen_src_dir.join("../rspirv/sr/decoration.rs");
         let c = sr::gen_sr_decoration(&grammar);
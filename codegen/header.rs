@@ -17,7 +17,8 @@ use structs;
 use utils::*;
 
 static VAULE_ENUM_ATTRIBUTE: &'static str = "\
-#[repr(u32)]\n#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]";
+#[repr(u32)]\n#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]\n\
+#[cfg_attr(feature = \"serialize\", derive(Serialize, Deserialize))]";
 
 /// Returns the markdown string containing a link to the spec for the given
 /// operand `kind`.
@@ -46,7 +47,9 @@ fn gen_bit_enum_operand_kind(grammar: &structs::OperandKind) -> String {
                 symbol.to_uppercase(),
                 enumerant.value.string)
     }).collect();
-    format!("bitflags!{{\n    {doc}\n    pub flags {kind} : u32 \
+    format!("bitflags!{{\n    {doc}\n    \
+             #[cfg_attr(feature = \"serialize\", derive(Serialize, Deserialize))]\n    \
+             pub flags {kind} : u32 \
              {{\n{enumerants}\n    }}\n}}\n",
             doc = format!("/// SPIR-V operand kind: {}",
                           get_spec_link(&grammar.kind)),
@@ -93,7 +96,11 @@ pub fn gen_spirv_header(grammar: &structs::Grammar) -> String {
                                pub const MAGIC_NUMBER: u32 = {};\n\
                                pub const MAJOR_VERSION: u32 = {};\n\
                                pub const MINOR_VERSION: u32 = {};\n\
-                               pub const REVISION: u32 = {};\n\n",
+                               pub const REVISION: u32 = {};\n\n\
+                               /// Generator magic number of rspirv: the vendor id registered \
+                               for rspirv (15) in the high 16 bits and the tool version in \
+                               the low 16 bits.\n\
+                               pub const GENERATOR_RSPIRV: u32 = 0x000f0000;\n\n",
                               grammar.magic_number,
                               grammar.major_version,
                               grammar.minor_version,
@@ -90,7 +90,7 @@ fn get_init_list(params: &[structs::Operand]) -> Vec<String> {
                 let name = get_param_name(param);
                 let kind = get_mr_operand_kind(&param.kind);
                 Some(if kind == "LiteralString" {
-                    format!("mr::Operand::LiteralString({}.into())", name)
+                    format!("mr::Operand::from({}.into())", name)
                 } else {
                     format!("mr::Operand::{}({})", kind, name)
                 })
@@ -113,15 +113,14 @@ fn get_push_extras(params: &[structs::Operand],
             let kind = get_mr_operand_kind(&param.kind);
             Some(format!(
                     "{s:8}if let Some(v) = {name} {{\n\
-                     {s:12}{container}.push(mr::Operand::{kind}(v{into}));\n\
+                     {s:12}{container}.push({operand});\n\
                      {s:8}}}",
                     s = "",
-                    kind = kind,
                     name = name,
-                    into = if kind == "LiteralString" {
-                        ".into()"
+                    operand = if kind == "LiteralString" {
+                        "mr::Operand::from(v.into())".to_string()
                     } else {
-                        ""
+                        format!("mr::Operand::{}(v)", kind)
                     },
                     container = container))
         } else {
@@ -212,8 +211,12 @@ pub fn gen_mr_operand_kinds(grammar: &Vec<structs::OperandKind>) -> String {
         let str_kinds: Vec<String> = kinds.iter().filter(|element| {
             element.ends_with("String")
         }).map(|element| {
-            format!("    {}(String),", element)
+            format!("    {}(Arc<str>),", element)
         }).collect();
+        // Words of instructions unknown to the grammar, kept verbatim, and
+        // enumerant values unknown to the grammar, with their operand kind.
+        let raw_kinds = "    RawWords(Vec<spirv::Word>),\n    \
+                         UnknownEnumerant(&'static str, spirv::Word),";
         let enum_kinds: Vec<String> = kinds.iter().filter(|element| {
             !(element.starts_with("Id") ||
               element.ends_with("String") ||
@@ -225,28 +228,93 @@ pub fn gen_mr_operand_kinds(grammar: &Vec<structs::OperandKind>) -> String {
 
         let kind_enum = format!(
             "/// Data representation of a SPIR-V operand.\n\
-             #[derive(Debug, PartialEq, From)]\n\
+             #[derive(Clone, Debug, From)]\n\
+             #[cfg_attr(feature = \"serialize\", derive(Serialize))]\n\
              pub enum Operand {{\n\
-             {enum_kinds}\n{id_kinds}\n{num_kinds}\n{str_kinds}\n\
+             {enum_kinds}\n{id_kinds}\n{num_kinds}\n{str_kinds}\n{raw_kinds}\n\
              }}\n\n",
              enum_kinds = enum_kinds.join("\n"),
              id_kinds = id_kinds.join("\n"),
              num_kinds = num_kinds.join("\n"),
-             str_kinds = str_kinds.join("\n"));
+             str_kinds = str_kinds.join("\n"),
+             raw_kinds = raw_kinds);
         ret.push_str(&kind_enum);
+
+        // The operand kind of unknown enumerants is a static string, which
+        // cannot be deserialized from borrowed input. So deserialize into a
+        // mirror owning the kind, and look the kind up among the known ones.
+        let mut variants: Vec<&str> = vec![];
+        for line in enum_kinds.iter().chain(id_kinds.iter()).chain(str_kinds.iter()) {
+            variants.push(line.trim().split('(').next().unwrap());
+        }
+        for line in &num_kinds {
+            variants.push(line.trim().split('(').next().unwrap());
+        }
+        variants.push("RawWords");
+        let kind_names: Vec<String> = enum_kinds.iter().map(|line| {
+            format!("    \"{}\",", line.trim().split('(').next().unwrap())
+        }).collect();
+        let cases: Vec<String> = variants.iter().map(|kind| {
+            format!("{s:12}OperandDe::{k}(v) => Operand::{k}(v),", s = "", k = kind)
+        }).collect();
+        let mirror = format!(
+            "/// Names of the operand kinds unknown enumerants can be of.\n\
+             #[cfg(feature = \"serialize\")]\n\
+             static ENUM_KIND_NAMES: &'static [&'static str] = &[\n{kind_names}\n];\n\n\
+             /// Mirror of `Operand` for deserialization, owning the operand kind of\n\
+             /// unknown enumerants.\n\
+             #[cfg(feature = \"serialize\")]\n\
+             #[derive(Deserialize)]\n\
+             #[serde(rename = \"Operand\")]\n\
+             enum OperandDe {{\n\
+             {enum_kinds}\n{id_kinds}\n{num_kinds}\n{str_kinds}\n\
+             {s:4}RawWords(Vec<spirv::Word>),\n\
+             {s:4}UnknownEnumerant(String, spirv::Word),\n\
+             }}\n\n\
+             #[cfg(feature = \"serialize\")]\n\
+             impl<'de> serde::Deserialize<'de> for Operand {{\n\
+             {s:4}fn deserialize<D>(deserializer: D) -> Result<Operand, D::Error>\n\
+             {s:8}where D: serde::Deserializer<'de>\n\
+             {s:4}{{\n\
+             {s:8}Ok(match try!(<OperandDe as serde::Deserialize>::deserialize(deserializer)) {{\n\
+             {cases}\n\
+             {s:12}OperandDe::UnknownEnumerant(kind, v) => {{\n\
+             {s:16}match ENUM_KIND_NAMES.iter().find(|name| **name == kind) {{\n\
+             {s:20}Some(name) => Operand::UnknownEnumerant(name, v),\n\
+             {s:20}None => {{\n\
+             {s:24}let msg = format!(\"unknown operand kind `{{}}`\", kind);\n\
+             {s:24}return Err(serde::de::Error::custom(msg));\n\
+             {s:20}}}\n\
+             {s:16}}}\n\
+             {s:12}}}\n\
+             {s:8}}})\n\
+             {s:4}}}\n\
+             }}\n\n",
+             s = "",
+             kind_names = kind_names.join("\n"),
+             enum_kinds = enum_kinds.join("\n"),
+             id_kinds = id_kinds.join("\n"),
+             num_kinds = num_kinds.join("\n"),
+             str_kinds = str_kinds.join("\n"),
+             cases = cases.join("\n"));
+        ret.push_str(&mirror);
     }
 
     { // impl fmt::Display for mr::Operand.
-        let mut kinds = kinds;
+        let mut kinds = kinds.clone();
         kinds.append(&mut vec!["LiteralInt32", "LiteralInt64",
-                               "LiteralFloat32", "LiteralFloat64"]);
-        let cases: Vec<String> =
+                               "LiteralFloat32", "LiteralFloat64",
+                               "RawWords"]);
+        let mut cases: Vec<String> =
             kinds.iter().map(|element| {
                 format!("{s:12}Operand::{kind}(ref v) => \
                          write!(f, \"{{:?}}\", v),",
                         s = "",
                         kind = element)
             }).collect();
+        cases.push(format!("{s:12}Operand::UnknownEnumerant(kind, v) => \
+                            write!(f, \"{{}}({{}})\", kind, v),",
+                           s = ""));
         let impl_code = format!(
             "impl fmt::Display for Operand {{\n\
              {s:4}fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {{\n\
@@ -256,6 +324,223 @@ pub fn gen_mr_operand_kinds(grammar: &Vec<structs::OperandKind>) -> String {
         ret.push_str(&impl_code);
     }
 
+    { // impl mr::Operand for the number of enumerant parameters.
+        let cases: Vec<String> = grammar.iter().filter(|element| {
+            has_additional_params(element)
+        }).map(|element| {
+            let params: Vec<(&str, usize)> = element.enumerants.iter().filter(|e| {
+                !e.parameters.is_empty()
+            }).map(|e| (e.symbol.as_str(), e.parameters.len())).collect();
+            if element.category == "BitEnum" {
+                // Each bit set brings its own parameters.
+                let up_kind = snake_casify(&element.kind).to_uppercase();
+                let checks: Vec<String> = params.iter().map(|&(symbol, count)| {
+                    format!("{s:16}if v.contains(spirv::{k}_{bit}) {{\n\
+                             {s:20}count += {c};\n\
+                             {s:16}}}",
+                            s = "",
+                            k = up_kind,
+                            bit = snake_casify(symbol).to_uppercase(),
+                            c = count)
+                }).collect();
+                format!("{s:12}Operand::{kind}(v) => {{\n\
+                         {s:16}let mut count = 0;\n\
+                         {checks}\n\
+                         {s:16}count\n\
+                         {s:12}}}",
+                        s = "",
+                        kind = element.kind,
+                        checks = checks.join("\n"))
+            } else {
+                let arms: Vec<String> = params.iter().map(|&(symbol, count)| {
+                    format!("{s:16}spirv::{kind}::{symbol} => {c},",
+                            s = "",
+                            kind = element.kind,
+                            symbol = symbol,
+                            c = count)
+                }).collect();
+                format!("{s:12}Operand::{kind}(v) => {{\n\
+                         {s:16}match v {{\n{arms}\n{s:20}_ => 0,\n{s:16}}}\n\
+                         {s:12}}}",
+                        s = "",
+                        kind = element.kind,
+                        arms = arms.iter().map(|arm| format!("    {}", arm))
+                                   .collect::<Vec<String>>().join("\n"))
+            }
+        }).collect();
+        let impl_code = format!(
+            "\nimpl Operand {{\n\
+             {s:4}/// Returns the number of operands following this operand as its\n\
+             {s:4}/// parameters, as decided by its enumerant value or bits set.\n\
+             {s:4}pub fn num_parameters(&self) -> usize {{\n\
+             {s:8}match *self {{\n{cases}\n{s:12}_ => 0,\n{s:8}}}\n{s:4}}}\n}}\n",
+             s = "",
+             cases = cases.join("\n"));
+        ret.push_str(&impl_code);
+    }
+
+    { // impl mr::Operand for checking against grammar operand kinds.
+        let cases: Vec<String> = kinds.iter().map(|element| {
+            format!("{s:12}(&Operand::{kind}(_), grammar::OperandKind::{kind}) => true,",
+                    s = "",
+                    kind = element)
+        }).collect();
+        let impl_code = format!(
+            "\nimpl Operand {{\n\
+             {s:4}/// Returns true if this operand is of the given grammar operand\n\
+             {s:4}/// `kind`.\n\
+             {s:4}///\n\
+             {s:4}/// Literal numbers and pairs are not checked here, since their\n\
+             {s:4}/// representation depends on the context.\n\
+             {s:4}pub fn is_of_kind(&self, kind: grammar::OperandKind) -> bool {{\n\
+             {s:8}match (self, kind) {{\n{cases}\n{s:12}_ => false,\n{s:8}}}\n{s:4}}}\n}}\n",
+             s = "",
+             cases = cases.join("\n"));
+        ret.push_str(&impl_code);
+    }
+
+    // All variants holding a single value, with the type of the value.
+    let mut variants: Vec<(&str, String)> = kinds.iter().filter(|element| {
+        !(element.starts_with("Id") ||
+          element.ends_with("String") ||
+          element.ends_with("Integer") ||
+          element.ends_with("Number"))
+    }).map(|element| (*element, format!("spirv::{}", element))).collect();
+    variants.extend(kinds.iter().filter(|element| {
+        element.starts_with("Id")
+    }).map(|element| (*element, "spirv::Word".to_string())));
+    variants.extend(vec![("LiteralInt32", "u32"),
+                         ("LiteralInt64", "u64"),
+                         ("LiteralFloat32", "f32"),
+                         ("LiteralFloat64", "f64"),
+                         ("LiteralExtInstInteger", "u32"),
+                         ("LiteralSpecConstantOpInteger", "spirv::Op")]
+                    .into_iter().map(|(kind, ty)| (kind, ty.to_string())));
+    variants.extend(kinds.iter().filter(|element| {
+        element.ends_with("String")
+    }).map(|element| (*element, "Arc<str>".to_string())));
+    variants.push(("RawWords", "Vec<spirv::Word>".to_string()));
+
+    { // impl mr::Operand for accessing the value of each variant.
+        let methods: Vec<String> = variants.iter().map(|&(kind, ref ty)| {
+            // Owned values are returned as borrowed slices.
+            let (pattern, ret_ty, value) = match ty.as_str() {
+                "Arc<str>" => ("ref v", "&str", "&**v"),
+                "Vec<spirv::Word>" => ("ref v", "&[spirv::Word]", "&v[..]"),
+                ty => ("v", ty, "v"),
+            };
+            let name = snake_casify(kind);
+            format!("{s:4}/// Returns true if this operand is an `Operand::{kind}`.\n\
+                     {s:4}pub fn is_{name}(&self) -> bool {{\n\
+                     {s:8}match *self {{\n\
+                     {s:12}Operand::{kind}(..) => true,\n\
+                     {s:12}_ => false,\n\
+                     {s:8}}}\n\
+                     {s:4}}}\n\n\
+                     {s:4}/// Returns the value if this operand is an `Operand::{kind}`.\n\
+                     {s:4}pub fn {name}(&self) -> Option<{ret_ty}> {{\n\
+                     {s:8}match *self {{\n\
+                     {s:12}Operand::{kind}({pattern}) => Some({value}),\n\
+                     {s:12}_ => None,\n\
+                     {s:8}}}\n\
+                     {s:4}}}\n\n\
+                     {s:4}/// Returns the value of this `Operand::{kind}`.\n\
+                     {s:4}///\n\
+                     {s:4}/// # Panics\n\
+                     {s:4}///\n\
+                     {s:4}/// Panics if this operand is not an `Operand::{kind}`.\n\
+                     {s:4}pub fn unwrap_{name}(&self) -> {ret_ty} {{\n\
+                     {s:8}match *self {{\n\
+                     {s:12}Operand::{kind}({pattern}) => {value},\n\
+                     {s:12}ref other => {{\n\
+                     {s:16}panic!(\"called `Operand::unwrap_{name}()` on {{:?}}\", other)\n\
+                     {s:12}}}\n\
+                     {s:8}}}\n\
+                     {s:4}}}",
+                    s = "",
+                    kind = kind,
+                    name = name,
+                    pattern = pattern,
+                    ret_ty = ret_ty,
+                    value = value)
+        }).collect();
+        let impl_code = format!("\nimpl Operand {{\n{methods}\n}}\n",
+                                methods = methods.join("\n\n"));
+        ret.push_str(&impl_code);
+    }
+
+    { // impl mr::Operand for getting the name of each variant.
+        let mut cases: Vec<String> = variants.iter().map(|&(kind, _)| {
+            format!("{s:12}Operand::{k}(..) => \"{k}\",", s = "", k = kind)
+        }).collect();
+        cases.push(format!("{s:12}Operand::UnknownEnumerant(..) => \"UnknownEnumerant\",",
+                           s = ""));
+        let impl_code = format!(
+            "\nimpl Operand {{\n\
+             {s:4}/// Returns the name of the kind of this operand, which is the name\n\
+             {s:4}/// of its variant, e.g., `\"IdRef\"`.\n\
+             {s:4}pub fn kind_name(&self) -> &'static str {{\n\
+             {s:8}match *self {{\n{cases}\n{s:8}}}\n{s:4}}}\n}}\n",
+            s = "",
+            cases = cases.join("\n"));
+        ret.push_str(&impl_code);
+    }
+
+    { // impl PartialEq, Eq, and Hash for mr::Operand.
+        let mut eq_cases: Vec<String> = vec![];
+        let mut hash_cases: Vec<String> = vec![];
+        for (index, &(kind, ref ty)) in variants.iter().enumerate() {
+            // Floating point numbers go by their bits.
+            let (a, b, v) = match ty.as_str() {
+                "f32" => ("f32_to_u32(a)", "f32_to_u32(b)", "f32_to_u32(v)"),
+                "f64" => ("f64_to_u64(a)", "f64_to_u64(b)", "f64_to_u64(v)"),
+                _ => ("a", "b", "v"),
+            };
+            let pattern = if a == "a" { "ref " } else { "" };
+            eq_cases.push(format!("{s:12}(&Operand::{kind}({p}a), &Operand::{kind}({p}b)) => \
+                                   {a} == {b},",
+                                  s = "",
+                                  kind = kind,
+                                  p = pattern,
+                                  a = a,
+                                  b = b));
+            hash_cases.push(format!("{s:12}Operand::{kind}({p}v) => {{\n\
+                                     {s:16}{index}u8.hash(state);\n\
+                                     {s:16}{v}.hash(state)\n\
+                                     {s:12}}}",
+                                    s = "",
+                                    kind = kind,
+                                    p = pattern,
+                                    index = index,
+                                    v = v));
+        }
+        eq_cases.push(format!("{s:12}(&Operand::UnknownEnumerant(ka, a), \
+                               &Operand::UnknownEnumerant(kb, b)) => ka == kb && a == b,",
+                              s = ""));
+        hash_cases.push(format!("{s:12}Operand::UnknownEnumerant(kind, v) => {{\n\
+                                 {s:16}{index}u8.hash(state);\n\
+                                 {s:16}kind.hash(state);\n\
+                                 {s:16}v.hash(state)\n\
+                                 {s:12}}}",
+                                s = "",
+                                index = variants.len()));
+        let impl_code = format!(
+            "\n/// Floating point literals are compared and hashed by their bits, so\n\
+             /// that NaNs with the same bits are equal while `0.0` and `-0.0` are\n\
+             /// not, as needed for deduplicating constants.\n\
+             impl PartialEq for Operand {{\n\
+             {s:4}fn eq(&self, other: &Operand) -> bool {{\n\
+             {s:8}match (self, other) {{\n{eq_cases}\n{s:12}_ => false,\n{s:8}}}\n{s:4}}}\n}}\n\n\
+             impl Eq for Operand {{}}\n\n\
+             impl Hash for Operand {{\n\
+             {s:4}fn hash<H: Hasher>(&self, state: &mut H) {{\n\
+             {s:8}match *self {{\n{hash_cases}\n{s:8}}}\n{s:4}}}\n}}\n",
+             s = "",
+             eq_cases = eq_cases.join("\n"),
+             hash_cases = hash_cases.join("\n"));
+        ret.push_str(&impl_code);
+    }
+
     ret
 }
 
@@ -274,27 +559,31 @@ pub fn gen_mr_builder_types(grammar: &structs::Grammar) -> String {
         // for Instruction.
         let init_list = get_init_list(&inst.operands[1..]).join(", ");
         // Parameters that are not single values thus need special treatment.
-        let extras = get_push_extras(&inst.operands[1..],
-                                     kinds,
-                                     "self.module.types_global_values.last_mut()\
-                                     .expect(\"interal error\").operands").join(";\n");
-        format!("{s:4}/// Appends an Op{opcode} instruction and returns the result id.\n\
+        let extras = get_push_extras(&inst.operands[1..], kinds, "inst.operands").join(";\n");
+        // Types are looked up among those declared by their instructions
+        // without result ids.
+        let inst_code = format!("mr::Instruction::new(spirv::Op::{opcode}, None, None, vec![{init}])",
+                                opcode = &inst.opname[2..],
+                                init = init_list);
+        let body = if extras.len() != 0 {
+            format!("{s:8}let mut inst = {inst};\n{extras};\n{s:8}self.declare_type(inst)",
+                    s = "",
+                    inst = inst_code,
+                    extras = extras)
+        } else {
+            format!("{s:8}self.declare_type({inst})", s = "", inst = inst_code)
+        };
+        format!("{s:4}/// Appends an Op{opcode} instruction and returns the result id, or\n\
+                 {s:4}/// returns the result id of the same type appended before.\n\
                  {s:4}pub fn {name}(&mut self{sep}{param}) -> spirv::Word {{\n\
-                 {s:8}let id = self.id();\n\
-                 {s:8}self.module.types_global_values.push(\
-                     mr::Instruction::new(spirv::Op::{opcode}, \
-                     None, Some(id), vec![{init}]));\n\
-                 {extras}{x}\
-                 {s:8}id\n\
+                 {body}\n\
                  {s:4}}}",
                 s = "",
                 sep = if param_list.len() != 0 { ", " } else { "" },
                 opcode = &inst.opname[2..],
                 name = snake_casify(&inst.opname[2..]),
                 param = param_list,
-                init = init_list,
-                extras = extras,
-                x = if extras.len() != 0 { ";\n" } else { "" })
+                body = body)
     }).collect();
     format!("impl Builder {{\n{}\n}}", elements.join("\n\n"))
 }
@@ -396,6 +685,27 @@ pub fn gen_mr_builder_constants(grammar: &structs::Grammar) -> String {
     }).map(|inst| {
         let params = get_param_list(&inst.operands, false, kinds).join(", ");
         let extras = get_push_extras(&inst.operands, kinds, "inst.operands").join(";\n");
+        // Constants, but not specialization constants, are looked up among
+        // those declared by their instructions without result ids.
+        if !inst.opname.starts_with("OpSpec") {
+            return format!("{s:4}/// Appends an Op{opcode} instruction and returns the result id, or\n\
+                            {s:4}/// returns the result id of the same constant appended before.\n\
+                            {s:4}pub fn {name}(&mut self{x}{params}) -> spirv::Word {{\n\
+                            {s:8}let {m}inst = mr::Instruction::new(\
+                                spirv::Op::{opcode}, Some(result_type), None, vec![{init}]);\n\
+                            {extras}{y}\
+                            {s:8}self.declare_constant(inst)\n\
+                            {s:4}}}",
+                           s = "",
+                           name = get_function_name(&inst.opname),
+                           extras = extras,
+                           params = params,
+                           x = if params.len() == 0 { "" } else { ", " },
+                           m = if extras.len() == 0 { "" } else { "mut " },
+                           y = if extras.len() != 0 { ";\n" } else { "" },
+                           init = get_init_list(&inst.operands).join(", "),
+                           opcode = &inst.opname[2..]);
+        }
         format!("{s:4}/// Appends an Op{opcode} instruction.\n\
                  {s:4}pub fn {name}(&mut self{x}{params}) -> spirv::Word {{\n\
                  {s:8}let id = self.id();\n\
@@ -454,12 +764,15 @@ pub fn gen_mr_builder_annotation(grammar: &structs::Grammar) -> String {
     }).map(|inst| {
         let params = get_param_list(&inst.operands, false, kinds).join(", ");
         let extras = get_push_extras(&inst.operands, kinds, "inst.operands").join(";\n");
-        format!("{s:4}/// Appends an Op{opcode} instruction.\n\
-                 {s:4}pub fn {name}(&mut self{x}{params}) {{\n\
+        // Decorations have their parameters checked against the grammar.
+        let decorated = inst.operands.iter().any(|o| o.kind == "Decoration");
+        format!("{s:4}/// Appends an Op{opcode} instruction.{doc}\n\
+                 {s:4}pub fn {name}(&mut self{x}{params}){ret} {{\n\
+                 {check}\
                  {s:8}let {m}inst = mr::Instruction::new(\
                      spirv::Op::{opcode}, None, None, vec![{init}]);\n\
                  {extras}{y}\
-                 {s:8}self.module.annotations.push(inst);\n\
+                 {s:8}self.module.annotations.push(inst){end}\n\
                  {s:4}}}",
                 s = "",
                 name = get_function_name(&inst.opname),
@@ -468,6 +781,20 @@ pub fn gen_mr_builder_annotation(grammar: &structs::Grammar) -> String {
                 x = if params.len() == 0 { "" } else { ", " },
                 m = if extras.len() == 0 { "" } else { "mut " },
                 y = if extras.len() != 0 { ";\n" } else { "" },
+                doc = if decorated {
+                    "\n    ///\n    \
+                     /// Returns `Error::WrongDecorationOperand` if the number of operands\n    \
+                     /// in `additional_params` is not the number of parameters expected by\n    \
+                     /// the grammar for `decoration`."
+                } else { "" },
+                ret = if decorated { " -> BuildResult<()>" } else { "" },
+                check = if decorated {
+                    "        if additional_params.len() != \
+                     mr::Operand::Decoration(decoration).num_parameters() {\n\
+                     \x20           return Err(Error::WrongDecorationOperand);\n\
+                     \x20       }\n"
+                } else { "" },
+                end = if decorated { ";\n        Ok(())" } else { ";" },
                 init = get_init_list(&inst.operands).join(", "),
                 opcode = &inst.opname[2..])
     }).collect();
@@ -0,0 +1,82 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares patching a single function in an assembled module against
+//! reassembling the whole module. Run with `cargo bench --bench patch`.
+
+extern crate rspirv;
+extern crate spirv_headers as spirv;
+
+use rspirv::binary::{Assemble, load_with_spans, patch_function};
+use rspirv::mr;
+
+use std::time::{Duration, Instant};
+
+const NUM_GLOBALS: usize = 2000;
+const NUM_FUNCTIONS: usize = 500;
+const ITERATIONS: u32 = 50;
+
+fn module() -> mr::Module {
+    let mut b = mr::Builder::new();
+    b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+    let void = b.type_void();
+    let float = b.type_float(32);
+    let voidfvoid = b.type_function(void, vec![]);
+    for i in 0..NUM_GLOBALS {
+        b.constant_f32(float, i as f32);
+    }
+    for _ in 0..NUM_FUNCTIONS {
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        for _ in 0..8 {
+            b.undef(float, None);
+        }
+        b.ret().unwrap();
+        b.end_function().unwrap();
+    }
+    b.module()
+}
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn nanos(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1e9 + duration.subsec_nanos() as f64
+}
+
+fn main() {
+    let original = module().assemble_bytes();
+    let (mut module, spans) = load_with_spans(&original).unwrap();
+    let index = NUM_FUNCTIONS / 2;
+    let mut grown = module.functions[index].basic_blocks[0].instructions[0].clone();
+    grown.result_id = Some(spans.bound);
+    module.functions[index].basic_blocks[0].instructions.push(grown);
+
+    let patch = time(|| {
+        let mut spans = spans.clone();
+        patch_function(&original, &mut spans, &module, index).unwrap();
+    });
+    let assemble = time(|| {
+        module.assemble_bytes();
+    });
+    println!("module: {} bytes, {} functions", original.len(), NUM_FUNCTIONS);
+    println!("patch_function: {:>12.0} ns/iter", nanos(patch));
+    println!("assemble_bytes: {:>12.0} ns/iter", nanos(assemble));
+    println!("speedup: {:.1}x", nanos(assemble) / nanos(patch));
+}
@@ -13,150 +13,682 @@
 // limitations under the License.
 
 use mr;
+use spirv;
 
-use utils::num::{bytes_to_u32_le, f32_to_u32};
+use grammar::OperandKind as GOpKind;
+use std::{error, fmt, fs, io};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use super::decoder::Endianness;
+use utils::num::{bytes_to_u32_le, f32_to_u32, f64_to_u64};
 
 /// Trait for assembling functionalities.
 pub trait Assemble {
+    /// Assembles the current object and appends the binary code to `result`.
+    fn assemble_into(&self, result: &mut Vec<u32>);
+
     /// Assembles the current object and returns the binary code.
-    fn assemble(&self) -> Vec<u32>;
+    fn assemble(&self) -> Vec<u32> {
+        let mut code = vec![];
+        self.assemble_into(&mut code);
+        code
+    }
+
+    /// Assembles the current object and returns the binary code as bytes,
+    /// with each word in little-endian byte order.
+    fn assemble_bytes(&self) -> Vec<u8> {
+        words_to_bytes(&self.assemble())
+    }
+
+    /// Assembles the current object and writes the binary code to `writer`,
+    /// with each word in little-endian byte order.
+    ///
+    /// Writes are issued per chunk of words assembled, so a buffered
+    /// writer is recommended:
+    ///
+    /// ```no_run
+    /// extern crate rspirv;
+    ///
+    /// use rspirv::binary::Assemble;
+    /// use std::fs::File;
+    /// use std::io::BufWriter;
+    ///
+    /// fn main() {
+    ///     let module = rspirv::mr::Builder::new().module();
+    ///     let file = File::create("out.spv").unwrap();
+    ///     module.write_to(BufWriter::new(file)).unwrap();
+    /// }
+    /// ```
+    fn write_to<W: io::Write>(&self, mut writer: W) -> io::Result<()>
+        where Self: Sized
+    {
+        write_words(&mut writer, &self.assemble(), Endianness::Little)
+    }
+
+    /// Assembles the current object and writes the binary code to the file
+    /// at `path`, with each word in little-endian byte order.
+    ///
+    /// The code is first written to a temporary file next to `path`, which
+    /// is then renamed to `path`, so that `path` never holds a partially
+    /// written module.
+    fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()>
+        where Self: Sized
+    {
+        let path = path.as_ref();
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        let result = fs::File::create(&temp).and_then(|file| {
+            let mut writer = io::BufWriter::new(file);
+            try!(self.write_to(&mut writer));
+            try!(writer.flush());
+            match writer.into_inner() {
+                Ok(file) => file.sync_all(),
+                Err(err) => Err(err.into()),
+            }
+        });
+        match result.and_then(|_| fs::rename(&temp, path)) {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                let _ = fs::remove_file(&temp);
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Options for assembling a module.
+#[derive(Clone, Debug)]
+pub struct AssembleOptions {
+    /// Whether to write the id bound stored in the module header as is,
+    /// instead of recomputing it from all ids used in the module, like
+    /// [`mr::Module::compute_id_bound`](../mr/struct.Module.html#method.compute_id_bound).
+    /// The stored bound is also kept if no bound covers all ids.
+    pub keep_bound: bool,
+    /// The byte order of words when emitting bytes, either returned or
+    /// written to a writer. Defaults to little-endian.
+    pub endianness: Endianness,
+    /// Whether to also check the operands of each instruction against the
+    /// grammar, via
+    /// [`validate_operands`](../mr/struct.Instruction.html#method.validate_operands).
+    pub validate: bool,
+    /// Whether to sort the instructions whose order carries no meaning
+    /// before emitting them, so that the same module always assembles into
+    /// the same bytes regardless of how it was populated.
+    ///
+    /// Capabilities and extensions are sorted, and so are OpName and
+    /// OpMemberName by target id and then member index, and OpDecorate and
+    /// OpMemberDecorate by target id, member index, and then decoration.
+    /// Other debug and annotation instructions are kept in place, as are
+    /// all other sections.
+    pub deterministic: bool,
+    /// The (major, minor) version to write into the module header instead
+    /// of the one stored in it, e.g., to target drivers only accepting
+    /// older versions. Must be a version no newer than the grammar's.
+    pub version: Option<(u8, u8)>,
+    /// The instruction schema (the reserved word) to write into the module
+    /// header instead of the one stored in it.
+    pub schema: Option<u32>,
+    /// The generator magic number to write into the module header instead
+    /// of the one stored in it.
+    pub generator: Option<u32>,
+}
+
+impl Default for AssembleOptions {
+    fn default() -> AssembleOptions {
+        AssembleOptions {
+            keep_bound: false,
+            endianness: Endianness::Little,
+            validate: false,
+            deterministic: false,
+            version: None,
+            schema: None,
+            generator: None,
+        }
+    }
+}
+
+/// The section of a module an instruction is in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Section {
+    Capabilities,
+    Extensions,
+    ExtInstImports,
+    MemoryModel,
+    EntryPoints,
+    ExecutionModes,
+    Debugs,
+    Annotations,
+    TypesGlobalValues,
+    /// The function with the given index
+    Function(usize),
+}
+
+/// The location of an instruction in a module: its section and its index
+/// in the section, starting from 0.
+///
+/// Instructions in a function are indexed in order, starting from
+/// OpFunction and counting function parameters and labels.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Location {
+    pub section: Section,
+    pub index: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.section {
+            Section::Function(function) => {
+                write!(f, "instruction #{} in function #{}", self.index, function)
+            }
+            section => write!(f, "instruction #{} in {:?}", self.index, section),
+        }
+    }
+}
+
+/// Encoder error.
+#[derive(Debug)]
+pub enum Error {
+    /// The module has no OpMemoryModel
+    MemoryModelMissing,
+    /// An instruction without the result id required by its grammar
+    /// (location, opcode)
+    ResultIdMissing(Location, spirv::Op),
+    /// Id 0 used as the result type id, result id, or an operand
+    /// (location)
+    IdZero(Location),
+    /// A string operand containing a nul byte, which would terminate it
+    /// early (location, string)
+    StringNulInterior(Location, String),
+    /// An operand is not followed by all the parameters required by its
+    /// enumerant value or bits set (location, operand, number of
+    /// parameters expected, number of operands left).
+    ParametersMissing(Location, mr::Operand, usize, usize),
+    /// An instruction not matching the grammar (location, mismatch)
+    OperandsInvalid(Location, mr::OperandMismatch),
+    /// The version to write into the header is not one the grammar
+    /// supports (major, minor)
+    VersionUnsupported(u8, u8),
+    /// Errored out when writing to the writer with the given error
+    IoError(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::MemoryModelMissing => write!(f, "module has no OpMemoryModel"),
+            Error::ResultIdMissing(location, opcode) => {
+                write!(f, "{}: Op{:?} requires a result id", location, opcode)
+            }
+            Error::IdZero(location) => write!(f, "{}: id 0 is not allowed", location),
+            Error::StringNulInterior(location, ref string) => {
+                write!(f, "{}: string {:?} contains a nul byte", location, string)
+            }
+            Error::ParametersMissing(location, ref operand, expected, found) => {
+                write!(f,
+                       "{}: operand {:?} expects {} parameters but only {} operands follow",
+                       location,
+                       operand,
+                       expected,
+                       found)
+            }
+            Error::OperandsInvalid(location, ref mismatch) => {
+                write!(f, "{}: does not match the grammar: {}", location, mismatch)
+            }
+            Error::VersionUnsupported(major, minor) => {
+                write!(f,
+                       "version {}.{} not supported (grammar version {}.{})",
+                       major,
+                       minor,
+                       spirv::MAJOR_VERSION,
+                       spirv::MINOR_VERSION)
+            }
+            Error::IoError(ref err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::MemoryModelMissing => "missing OpMemoryModel",
+            Error::ResultIdMissing(..) => "missing result id",
+            Error::IdZero(_) => "id 0 used",
+            Error::StringNulInterior(..) => "string with interior nul byte",
+            Error::ParametersMissing(..) => "missing parameters for operand",
+            Error::OperandsInvalid(..) => "instruction not matching the grammar",
+            Error::VersionUnsupported(..) => "version not supported",
+            Error::IoError(_) => "I/O error",
+        }
+    }
+}
+
+/// Checks the instruction `inst` at `location` for conditions that prevent
+/// it from being encoded into a valid instruction.
+fn check_inst(location: Location, inst: &mr::Instruction) -> Result<(), Error> {
+    if inst.unknown_opcode.is_some() {
+        return Ok(());
+    }
+    let requires_id = inst.grammar().operands.iter().any(|o| o.kind == GOpKind::IdResult);
+    if requires_id && inst.result_id.is_none() {
+        return Err(Error::ResultIdMissing(location, inst.class));
+    }
+    if inst.result_type == Some(0) || inst.result_id == Some(0) {
+        return Err(Error::IdZero(location));
+    }
+    for (i, operand) in inst.operands.iter().enumerate() {
+        match *operand {
+            mr::Operand::IdMemorySemantics(0) |
+            mr::Operand::IdScope(0) |
+            mr::Operand::IdRef(0) => return Err(Error::IdZero(location)),
+            mr::Operand::LiteralString(ref v) if v.contains('\0') => {
+                return Err(Error::StringNulInterior(location, v.to_string()))
+            }
+            _ => (),
+        }
+        let expected = operand.num_parameters();
+        let found = inst.operands.len() - i - 1;
+        if expected > found {
+            return Err(Error::ParametersMissing(location, operand.clone(), expected, found));
+        }
+    }
+    Ok(())
+}
+
+impl AssembleOptions {
+    /// Assembles the given `module` and returns the binary code.
+    ///
+    /// The module is checked first for conditions preventing it from being
+    /// encoded into a valid module, e.g., a missing OpMemoryModel, id 0, or
+    /// an `ImageOperands` mask not followed by all of its parameters. If
+    /// `validate` is set, the operands of each instruction are also checked
+    /// against the grammar.
+    ///
+    /// To encode intentionally non-conforming modules, use the
+    /// [`Assemble`](trait.Assemble.html) trait instead, which encodes
+    /// anything as is.
+    pub fn assemble(&self, module: &mr::Module) -> Result<Vec<u32>, Error> {
+        try!(self.check(module));
+        let mut code = vec![];
+        self.assemble_into(module, &mut code);
+        Ok(code)
+    }
+
+    /// Assembles the given `module` and returns the binary code as bytes,
+    /// with each word in the byte order given by `endianness`.
+    ///
+    /// The module is checked as in [`assemble`](#method.assemble).
+    pub fn assemble_bytes(&self, module: &mr::Module) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![];
+        try!(self.write_to(module, &mut bytes));
+        Ok(bytes)
+    }
+
+    /// Assembles the given `module` and writes the binary code to `writer`,
+    /// with each word in the byte order given by `endianness`.
+    ///
+    /// The module is checked as in [`assemble`](#method.assemble) before
+    /// anything is written. It is then encoded and written one instruction
+    /// at a time instead of being assembled as a whole first.
+    pub fn write_to<W: io::Write>(&self, module: &mr::Module, writer: W) -> Result<(), Error> {
+        try!(self.check(module));
+        self.write_unchecked(module, writer).map_err(Error::IoError)
+    }
+
+    fn check(&self, module: &mr::Module) -> Result<(), Error> {
+        if let Some((major, minor)) = self.version {
+            let latest = (spirv::MAJOR_VERSION as u8, spirv::MINOR_VERSION as u8);
+            if major == 0 || (major, minor) > latest {
+                return Err(Error::VersionUnsupported(major, minor));
+            }
+        }
+        if module.memory_model.is_none() {
+            return Err(Error::MemoryModelMissing);
+        }
+        for (location, inst) in located_insts(module) {
+            if self.validate {
+                try!(inst.validate_operands().map_err(|e| Error::OperandsInvalid(location, e)));
+            }
+            try!(check_inst(location, inst));
+        }
+        Ok(())
+    }
+
+    fn write_unchecked<W: io::Write>(&self, module: &mr::Module, mut writer: W) -> io::Result<()> {
+        let mut code = vec![];
+        if let Some(header) = self.header(module) {
+            header.assemble_into(&mut code);
+            try!(write_words(&mut writer, &code, self.endianness));
+        }
+        for inst in self.ordered_insts(module) {
+            code.clear();
+            inst.assemble_into(&mut code);
+            try!(write_words(&mut writer, &code, self.endianness));
+        }
+        Ok(())
+    }
+
+    /// Returns all instructions in `module` in the order to emit them.
+    fn ordered_insts<'m>(&self, module: &'m mr::Module) -> Vec<&'m mr::Instruction> {
+        let mut insts: Vec<&mr::Instruction> =
+            located_insts(module).into_iter().map(|(_, inst)| inst).collect();
+        if self.deterministic {
+            let capabilities = module.capabilities.len();
+            let extensions = capabilities + module.extensions.len();
+            let debugs = extensions + module.ext_inst_imports.len() +
+                         module.memory_model.iter().count() +
+                         module.entry_points.len() +
+                         module.execution_modes.len();
+            let annotations = debugs + module.debugs.len();
+            let end = annotations + module.annotations.len();
+            sort_in_place(&mut insts[..capabilities], |inst| Some(inst.assemble()));
+            sort_in_place(&mut insts[capabilities..extensions], |inst| Some(inst.assemble()));
+            sort_in_place(&mut insts[debugs..annotations], target_key);
+            sort_in_place(&mut insts[annotations..end], target_key);
+        }
+        insts
+    }
+
+    /// Returns the module header to emit for `module`, if any, with the
+    /// fields overridden as requested.
+    fn header(&self, module: &mr::Module) -> Option<mr::ModuleHeader> {
+        module.header.as_ref().map(|h| {
+            let mut header = h.clone();
+            if !self.keep_bound {
+                // Keeps the stored bound if no bound covers all ids.
+                if let Some(bound) = module.compute_id_bound() {
+                    header.bound = bound;
+                }
+            }
+            if let Some((major, minor)) = self.version {
+                header.version = ((major as u32) << 16) | ((minor as u32) << 8);
+            }
+            if let Some(schema) = self.schema {
+                header.reserved_word = schema;
+            }
+            if let Some(generator) = self.generator {
+                header.generator = generator;
+            }
+            header
+        })
+    }
+
+    fn assemble_into(&self, module: &mr::Module, result: &mut Vec<u32>) {
+        if let Some(header) = self.header(module) {
+            header.assemble_into(result);
+        }
+        for inst in self.ordered_insts(module) {
+            inst.assemble_into(result);
+        }
+    }
+}
+
+/// Returns all instructions in `module` in module order, with their
+/// locations.
+fn located_insts(module: &mr::Module) -> Vec<(Location, &mr::Instruction)> {
+    fn push<'a, I>(insts: &mut Vec<(Location, &'a mr::Instruction)>, section: Section, iter: I)
+        where I: Iterator<Item = &'a mr::Instruction>
+    {
+        for (index, inst) in iter.enumerate() {
+            insts.push((Location { section: section, index: index }, inst));
+        }
+    }
+
+    let mut insts = vec![];
+    push(&mut insts, Section::Capabilities, module.capabilities.iter());
+    push(&mut insts, Section::Extensions, module.extensions.iter());
+    push(&mut insts, Section::ExtInstImports, module.ext_inst_imports.iter());
+    push(&mut insts, Section::MemoryModel, module.memory_model.iter());
+    push(&mut insts, Section::EntryPoints, module.entry_points.iter());
+    push(&mut insts, Section::ExecutionModes, module.execution_modes.iter());
+    push(&mut insts, Section::Debugs, module.debugs.iter());
+    push(&mut insts, Section::Annotations, module.annotations.iter());
+    push(&mut insts, Section::TypesGlobalValues, module.types_global_values.iter());
+    for (i, f) in module.functions.iter().enumerate() {
+        let mut body: Vec<&mr::Instruction> = f.def.iter().chain(f.parameters.iter()).collect();
+        for bb in &f.basic_blocks {
+            body.extend(bb.label.iter().chain(bb.instructions.iter()));
+        }
+        body.extend(f.end.iter());
+        push(&mut insts, Section::Function(i), body.into_iter());
+    }
+    insts
+}
+
+/// Sorts the instructions in `insts` for which `key` returns a key by the
+/// key, keeping the others in place.
+fn sort_in_place<'m, K, F>(insts: &mut [&'m mr::Instruction], key: F)
+    where K: Ord,
+          F: Fn(&mr::Instruction) -> Option<K>
+{
+    let positions: Vec<usize> = (0..insts.len()).filter(|&i| key(insts[i]).is_some()).collect();
+    let mut sorted: Vec<(K, &'m mr::Instruction)> =
+        insts.iter().filter_map(|&inst| key(inst).map(|k| (k, inst))).collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    for (position, (_, inst)) in positions.into_iter().zip(sorted) {
+        insts[position] = inst;
+    }
+}
+
+/// Returns the key for sorting OpName, OpMemberName, OpDecorate, and
+/// OpMemberDecorate: the target id, the member index if any, the
+/// decoration if any, and then all the words to break ties.
+fn target_key(inst: &mr::Instruction) -> Option<(spirv::Word, Option<u32>, u32, Vec<u32>)> {
+    let member = match inst.class {
+        spirv::Op::Name | spirv::Op::Decorate => None,
+        spirv::Op::MemberName | spirv::Op::MemberDecorate => {
+            match inst.operands.get(1) {
+                Some(&mr::Operand::LiteralInt32(member)) => Some(member),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    let target = match inst.operands.get(0) {
+        Some(&mr::Operand::IdRef(id)) => id,
+        _ => return None,
+    };
+    let decoration = inst.operands
+        .iter()
+        .filter_map(|o| match *o {
+            mr::Operand::Decoration(d) => Some(d as u32),
+            _ => None,
+        })
+        .next()
+        .unwrap_or(0);
+    Some((target, member, decoration, inst.assemble()))
+}
+
+/// Writes the given `words` to `writer` as bytes, with each word in the
+/// given `endianness`.
+pub fn write_words<W: io::Write>(writer: &mut W,
+                                 words: &[u32],
+                                 endianness: Endianness)
+                                 -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(words.len() * 4);
+    for &word in words {
+        let word = match endianness {
+            Endianness::Little => word,
+            Endianness::Big => word.swap_bytes(),
+        };
+        bytes.extend_from_slice(&[word as u8,
+                                  (word >> 8) as u8,
+                                  (word >> 16) as u8,
+                                  (word >> 24) as u8]);
+    }
+    writer.write_all(&bytes)
+}
+
+fn words_to_bytes(words: &[u32]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(words.len() * 4);
+    write_words(&mut bytes, words, Endianness::Little).unwrap();
+    bytes
 }
 
 impl Assemble for mr::ModuleHeader {
-    fn assemble(&self) -> Vec<u32> {
-        vec![self.magic_number, self.version, self.generator, self.bound, self.reserved_word]
+    fn assemble_into(&self, result: &mut Vec<u32>) {
+        result.extend_from_slice(&[self.magic_number,
+                                   self.version,
+                                   self.generator,
+                                   self.bound,
+                                   self.reserved_word]);
     }
 }
 
-fn assemble_str(s: &str) -> Vec<u32> {
+/// Appends the UTF-8 bytes of `s` to `result`, followed by a nul terminator
+/// and zero padding up to the word boundary. Bytes are packed into words in
+/// little-endian order. A string whose length is a multiple of four gets an
+/// extra word of zeros for the terminator.
+fn assemble_str_into(s: &str, result: &mut Vec<u32>) {
     let bytes = s.as_bytes();
     let len = (bytes.len() + 3) >> 2;
-    let mut words: Vec<u32> = (0..len).map(|i| bytes_to_u32_le(&bytes[(i << 2)..])).collect();
+    result.extend((0..len).map(|i| bytes_to_u32_le(&bytes[(i << 2)..])));
     if bytes.len() % 4 == 0 {
-        words.push(0)
+        result.push(0)
     }
-    words
+}
+
+/// Appends the given 64-bit `value` to `result` as two words, with the
+/// low-order word first.
+fn assemble_u64_into(value: u64, result: &mut Vec<u32>) {
+    result.push(value as u32);
+    result.push((value >> 32) as u32);
 }
 
 impl Assemble for mr::Operand {
-    fn assemble(&self) -> Vec<u32> {
+    fn assemble_into(&self, result: &mut Vec<u32>) {
         match *self {
-            mr::Operand::ImageOperands(v) => vec![v.bits()],
-            mr::Operand::FPFastMathMode(v) => vec![v.bits()],
-            mr::Operand::SelectionControl(v) => vec![v.bits()],
-            mr::Operand::LoopControl(v) => vec![v.bits()],
-            mr::Operand::FunctionControl(v) => vec![v.bits()],
-            mr::Operand::MemorySemantics(v) => vec![v.bits()],
-            mr::Operand::MemoryAccess(v) => vec![v.bits()],
-            mr::Operand::KernelProfilingInfo(v) => vec![v.bits()],
-            mr::Operand::SourceLanguage(v) => vec![v as u32],
-            mr::Operand::ExecutionModel(v) => vec![v as u32],
-            mr::Operand::AddressingModel(v) => vec![v as u32],
-            mr::Operand::MemoryModel(v) => vec![v as u32],
-            mr::Operand::ExecutionMode(v) => vec![v as u32],
-            mr::Operand::StorageClass(v) => vec![v as u32],
-            mr::Operand::Dim(v) => vec![v as u32],
-            mr::Operand::SamplerAddressingMode(v) => vec![v as u32],
-            mr::Operand::SamplerFilterMode(v) => vec![v as u32],
-            mr::Operand::ImageFormat(v) => vec![v as u32],
-            mr::Operand::ImageChannelOrder(v) => vec![v as u32],
-            mr::Operand::ImageChannelDataType(v) => vec![v as u32],
-            mr::Operand::FPRoundingMode(v) => vec![v as u32],
-            mr::Operand::LinkageType(v) => vec![v as u32],
-            mr::Operand::AccessQualifier(v) => vec![v as u32],
-            mr::Operand::FunctionParameterAttribute(v) => vec![v as u32],
-            mr::Operand::Decoration(v) => vec![v as u32],
-            mr::Operand::BuiltIn(v) => vec![v as u32],
-            mr::Operand::Scope(v) => vec![v as u32],
-            mr::Operand::GroupOperation(v) => vec![v as u32],
-            mr::Operand::KernelEnqueueFlags(v) => vec![v as u32],
-            mr::Operand::Capability(v) => vec![v as u32],
+            mr::Operand::ImageOperands(v) => result.push(v.bits()),
+            mr::Operand::FPFastMathMode(v) => result.push(v.bits()),
+            mr::Operand::SelectionControl(v) => result.push(v.bits()),
+            mr::Operand::LoopControl(v) => result.push(v.bits()),
+            mr::Operand::FunctionControl(v) => result.push(v.bits()),
+            mr::Operand::MemorySemantics(v) => result.push(v.bits()),
+            mr::Operand::MemoryAccess(v) => result.push(v.bits()),
+            mr::Operand::KernelProfilingInfo(v) => result.push(v.bits()),
+            mr::Operand::SourceLanguage(v) => result.push(v as u32),
+            mr::Operand::ExecutionModel(v) => result.push(v as u32),
+            mr::Operand::AddressingModel(v) => result.push(v as u32),
+            mr::Operand::MemoryModel(v) => result.push(v as u32),
+            mr::Operand::ExecutionMode(v) => result.push(v as u32),
+            mr::Operand::StorageClass(v) => result.push(v as u32),
+            mr::Operand::Dim(v) => result.push(v as u32),
+            mr::Operand::SamplerAddressingMode(v) => result.push(v as u32),
+            mr::Operand::SamplerFilterMode(v) => result.push(v as u32),
+            mr::Operand::ImageFormat(v) => result.push(v as u32),
+            mr::Operand::ImageChannelOrder(v) => result.push(v as u32),
+            mr::Operand::ImageChannelDataType(v) => result.push(v as u32),
+            mr::Operand::FPRoundingMode(v) => result.push(v as u32),
+            mr::Operand::LinkageType(v) => result.push(v as u32),
+            mr::Operand::AccessQualifier(v) => result.push(v as u32),
+            mr::Operand::FunctionParameterAttribute(v) => result.push(v as u32),
+            mr::Operand::Decoration(v) => result.push(v as u32),
+            mr::Operand::BuiltIn(v) => result.push(v as u32),
+            mr::Operand::Scope(v) => result.push(v as u32),
+            mr::Operand::GroupOperation(v) => result.push(v as u32),
+            mr::Operand::KernelEnqueueFlags(v) => result.push(v as u32),
+            mr::Operand::Capability(v) => result.push(v as u32),
             mr::Operand::IdMemorySemantics(v) |
             mr::Operand::IdScope(v) |
             mr::Operand::IdRef(v) |
             mr::Operand::LiteralInt32(v) |
-            mr::Operand::LiteralExtInstInteger(v) => vec![v],
-            mr::Operand::LiteralInt64(_) => unimplemented!(),
-            mr::Operand::LiteralFloat32(v) => vec![f32_to_u32(v)],
-            mr::Operand::LiteralFloat64(_) => unimplemented!(),
-            mr::Operand::LiteralSpecConstantOpInteger(v) => vec![v as u32],
-            mr::Operand::LiteralString(ref v) => assemble_str(v),
+            mr::Operand::LiteralExtInstInteger(v) => result.push(v),
+            mr::Operand::LiteralInt64(v) => assemble_u64_into(v, result),
+            mr::Operand::LiteralFloat32(v) => result.push(f32_to_u32(v)),
+            mr::Operand::LiteralFloat64(v) => assemble_u64_into(f64_to_u64(v), result),
+            mr::Operand::LiteralSpecConstantOpInteger(v) => result.push(v as u32),
+            mr::Operand::LiteralString(ref v) => assemble_str_into(v, result),
+            mr::Operand::RawWords(ref v) => result.extend_from_slice(v),
+            mr::Operand::UnknownEnumerant(_, v) => result.push(v),
         }
     }
 }
 
 impl Assemble for mr::Instruction {
-    fn assemble(&self) -> Vec<u32> {
-        let mut code = vec![self.class.opcode as u32];
+    fn assemble_into(&self, result: &mut Vec<u32>) {
+        let start = result.len();
+        if self.unknown_opcode.is_some() {
+            // Already contains all the words, including the first one. The
+            // grammar knows nothing about the opcode, so the word count is
+            // taken from the number of raw words kept.
+            self.operands[0].assemble_into(result);
+            result[start] = (((result.len() - start) as u32) << 16) | (result[start] & 0xffff);
+            return;
+        }
+        result.push(self.class as u32);
         if let Some(r) = self.result_type {
-            code.push(r);
+            result.push(r);
         }
         if let Some(r) = self.result_id {
-            code.push(r);
+            result.push(r);
         }
         for operand in &self.operands {
-            code.append(&mut operand.assemble());
+            operand.assemble_into(result);
         }
-        code[0] |= (code.len() as u32) << 16;
-        code
+        // Strings are of variable length, so the word count is only known
+        // after all operands are encoded.
+        result[start] |= ((result.len() - start) as u32) << 16;
     }
 }
 
 impl Assemble for mr::BasicBlock {
-    fn assemble(&self) -> Vec<u32> {
-        let mut code = vec![];
+    fn assemble_into(&self, result: &mut Vec<u32>) {
         if let Some(ref l) = self.label {
-            code.append(&mut l.assemble());
+            l.assemble_into(result);
         }
         for inst in &self.instructions {
-            code.append(&mut inst.assemble());
+            inst.assemble_into(result);
         }
-        code
     }
 }
 
 impl Assemble for mr::Function {
-    fn assemble(&self) -> Vec<u32> {
-        let mut code = vec![];
+    fn assemble_into(&self, result: &mut Vec<u32>) {
         if let Some(ref d) = self.def {
-            code.append(&mut d.assemble());
+            d.assemble_into(result);
         }
         for param in &self.parameters {
-            code.append(&mut param.assemble());
+            param.assemble_into(result);
         }
         for bb in &self.basic_blocks {
-            code.append(&mut bb.assemble());
+            bb.assemble_into(result);
         }
         if let Some(ref e) = self.end {
-            code.append(&mut e.assemble());
+            e.assemble_into(result);
         }
-        code
     }
 }
 
+/// Assembles the module with the default options; in particular, the
+/// id bound written in the header is recomputed from the module contents.
 impl Assemble for mr::Module {
-    fn assemble(&self) -> Vec<u32> {
-        let mut code = match self.header {
-            Some(ref h) => h.assemble(),
-            None => vec![],
-        };
-        for inst in self.global_inst_iter() {
-            code.append(&mut inst.assemble());
-        }
-        for f in &self.functions {
-            code.append(&mut f.assemble());
-        }
-        code
+    fn assemble_into(&self, result: &mut Vec<u32>) {
+        AssembleOptions::default().assemble_into(self, result)
+    }
+
+    fn write_to<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        AssembleOptions::default().write_unchecked(self, writer)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use grammar;
     use mr;
     use spirv;
 
-    use binary::Assemble;
-    use super::{assemble_str, bytes_to_u32_le};
+    use binary::{self, Assemble, AssembleOptions, Endianness, ParseAction};
+    use super::{Error, Location, Section};
+    use std::{env, f64, fs, i64, mem};
+    use std::io::Read;
+    use utils::num::u32_to_bytes;
+    use super::{assemble_str_into, bytes_to_u32_le};
+
+    fn assemble_str(s: &str) -> Vec<u32> {
+        let mut words = vec![];
+        assemble_str_into(s, &mut words);
+        words
+    }
 
     #[test]
     fn test_assemble_str() {
@@ -167,6 +699,89 @@ mod tests {
                    assemble_str("hello"));
     }
 
+    #[test]
+    fn test_assemble_str_lengths() {
+        let text = "abcdefghi";
+        for len in 0..(text.len() + 1) {
+            let s = &text[..len];
+            let words = assemble_str(s);
+            // Room for the nul terminator is always needed.
+            assert_eq!(len / 4 + 1, words.len());
+
+            let mut bytes = s.as_bytes().to_vec();
+            bytes.resize(words.len() * 4, 0);
+            let expected: Vec<u32> = bytes.chunks(4).map(bytes_to_u32_le).collect();
+            assert_eq!(expected, words);
+
+            let mut d = binary::Decoder::from_words(&words);
+            assert_eq!(s, d.string().unwrap());
+            assert_eq!(words.len(), d.position());
+        }
+    }
+
+    #[test]
+    fn test_assemble_str_utf8() {
+        // "aé" + "€": the two-byte 'é' ends the first word and the
+        // three-byte '€' straddles the first and second words.
+        let s = "a\u{e9}\u{20ac}";
+        assert_eq!(6, s.len());
+        let words = assemble_str(s);
+        assert_eq!(vec![bytes_to_u32_le(&[b'a', 0xc3, 0xa9, 0xe2]),
+                        bytes_to_u32_le(&[0x82, 0xac, 0, 0])],
+                   words);
+        assert_eq!(s, binary::Decoder::from_words(&words).string().unwrap());
+
+        // Four bytes exactly, so the terminator needs its own word.
+        let s = "\u{1f600}";
+        let words = assemble_str(s);
+        assert_eq!(vec![bytes_to_u32_le(&[0xf0, 0x9f, 0x98, 0x80]), 0], words);
+        assert_eq!(s, binary::Decoder::from_words(&words).string().unwrap());
+
+        // Decoding then encoding reproduces the original words.
+        let original = vec![bytes_to_u32_le(&[0xe2, 0x82, 0xac, b'x']),
+                            bytes_to_u32_le(&[0xc3, 0xa9, 0, 0])];
+        let decoded = binary::Decoder::from_words(&original).string().unwrap();
+        assert_eq!(original, assemble_str(&decoded));
+    }
+
+    #[test]
+    fn test_assemble_operand_int64() {
+        assert_eq!(vec![0x78563412, 0xefcdab90],
+                   mr::Operand::LiteralInt64(0xefcdab9078563412).assemble());
+        for &v in &[i64::MIN, i64::MAX, -1, -2, 0, 1] {
+            let words = mr::Operand::LiteralInt64(v as u64).assemble();
+            assert_eq!(vec![v as u32, ((v as u64) >> 32) as u32], words);
+            assert_eq!(v, binary::Decoder::from_words(&words).signed_int64().unwrap());
+        }
+        // -2 as a 64-bit signed integer, as spirv-as encodes it.
+        assert_eq!(vec![0xfffffffe, 0xffffffff],
+                   mr::Operand::LiteralInt64(-2i64 as u64).assemble());
+    }
+
+    #[test]
+    fn test_assemble_operand_float64() {
+        // Bit pattern for -12.34 is 0xc028ae147ae147ae.
+        assert_eq!(vec![0x7ae147ae, 0xc028ae14], mr::Operand::LiteralFloat64(-12.34).assemble());
+        let nan_payload = unsafe { mem::transmute::<u64, f64>(0x7ff4000000000123) };
+        for &v in &[-0.0f64, 0.0, f64::MIN, f64::MAX, f64::INFINITY, f64::NAN, nan_payload] {
+            let words = mr::Operand::LiteralFloat64(v).assemble();
+            let bits = unsafe { mem::transmute::<f64, u64>(v) };
+            assert_eq!(vec![bits as u32, (bits >> 32) as u32], words);
+            let decoded = binary::Decoder::from_words(&words).float64().unwrap();
+            assert_eq!(bits, unsafe { mem::transmute::<f64, u64>(decoded) });
+        }
+    }
+
+    #[test]
+    fn test_assemble_inst_constant64() {
+        let inst = mr::Instruction::new(spirv::Op::Constant,
+                                        Some(1),
+                                        Some(2),
+                                        vec![mr::Operand::LiteralInt64(i64::MIN as u64)]);
+        assert_eq!(vec![wc_op(5, spirv::Op::Constant), 1, 2, 0, 0x80000000],
+                   inst.assemble());
+    }
+
     #[test]
     fn test_assemble_operand_bitmask() {
         let v = spirv::FUNCTION_CONTROL_DONT_INLINE;
@@ -192,6 +807,12 @@ mod tests {
                    mr::Operand::BuiltIn(spirv::BuiltIn::InstanceId).assemble());
     }
 
+    #[test]
+    fn test_assemble_unknown_instruction() {
+        let inst = mr::Instruction::unknown(0xfffe, vec![7, 8, 9]);
+        assert_eq!(vec![0x0004fffe, 7, 8, 9], inst.assemble());
+    }
+
     fn wc_op(wc: u32, op: spirv::Op) -> u32 {
         (wc << 16) | op as u32
     }
@@ -338,4 +959,454 @@ mod tests {
                         wc_op(1, spirv::Op::FunctionEnd)],
                    b.module().assemble());
     }
+
+    /// Parses each instruction in `words`, re-encodes it and checks that
+    /// the same words come out.
+    fn check_round_trip(words: &[u32]) {
+        let mut offset = 5; // Skip the header.
+        binary::parse_words(words,
+                            &mut binary::on_inst(|inst| {
+                                let mut code = vec![];
+                                inst.assemble_into(&mut code);
+                                assert_eq!(&words[offset..(offset + code.len())], &code[..]);
+                                offset += code.len();
+                                ParseAction::Continue
+                            }))
+            .unwrap();
+        assert_eq!(words.len(), offset);
+    }
+
+    #[test]
+    fn test_round_trip_function_parameters() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::Simple);
+        let float = b.type_float(32);
+        let ptr = b.type_pointer(None, spirv::StorageClass::Function, float);
+        let fff = b.type_function(float, vec![float, float]);
+        b.begin_function(float, None, spirv::FUNCTION_CONTROL_CONST, fff).unwrap();
+        let param1 = b.function_parameter(ptr).unwrap();
+        let param2 = b.function_parameter(ptr).unwrap();
+        b.begin_basic_block(None).unwrap();
+        let v1 = b.load(float, None, param1, None, vec![]).unwrap();
+        let v2 = b.load(float, None, param2, None, vec![]).unwrap();
+        let v = b.fadd(float, None, v1, v2).unwrap();
+        b.ret_value(v).unwrap();
+        b.end_function().unwrap();
+
+        check_round_trip(&b.module().assemble());
+    }
+
+    #[test]
+    fn test_round_trip_strings_and_enums() {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.ext_inst_import("GLSL.std.450");
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let voidfvoid = b.type_function(void, vec![]);
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.entry_point(spirv::ExecutionModel::Fragment, f, "main", vec![]);
+        b.name(f, "main");
+        b.name(void, "void_");
+
+        check_round_trip(&b.module().assemble());
+    }
+
+    #[test]
+    fn test_assemble_module_round_trip() {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let voidfvoid = b.type_function(void, vec![]);
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.entry_point(spirv::ExecutionModel::Fragment, f, "main", vec![]);
+        let original = b.module().assemble_bytes();
+
+        let module = mr::load_bytes(&original).unwrap();
+        assert_eq!(original, module.assemble_bytes());
+        assert_eq!(module.assemble(), AssembleOptions::default().assemble(&module).unwrap());
+    }
+
+    #[test]
+    fn test_assemble_unknown_instruction_round_trip() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let voidfvoid = b.type_function(void, vec![]);
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.nop().unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.name(f, "main");
+        let mut module = b.module();
+        // Sandwich an unknown instruction between OpNop and OpReturn.
+        module.functions[0].basic_blocks[0]
+            .instructions
+            .insert(1, mr::Instruction::unknown(0xfff0, vec![7, 8, 9]));
+        let original = module.assemble();
+        assert!(original.windows(4).any(|w| w == [0x0004fff0, 7, 8, 9]));
+
+        let mut loader = mr::Loader::new();
+        let options = binary::ParserOptions { preserve_unknown_opcodes: true, ..Default::default() };
+        binary::Parser::new(&module.assemble_bytes(), &mut loader)
+            .options(options)
+            .parse()
+            .unwrap();
+        let mut parsed = loader.module();
+        assert_eq!(original, parsed.assemble());
+
+        // Edit something else; the unknown instruction is kept as is.
+        module.debugs[0].operands[1] = mr::Operand::from("mian");
+        parsed.debugs[0].operands[1] = mr::Operand::from("mian");
+        assert_eq!(module.assemble(), parsed.assemble());
+
+        // The word count follows the raw words kept.
+        if let mr::Operand::RawWords(ref mut words) =
+               parsed.functions[0].basic_blocks[0].instructions[1].operands[0] {
+            words.push(10);
+        }
+        assert!(parsed.assemble().windows(5).any(|w| w == [0x0005fff0, 7, 8, 9, 10]));
+    }
+
+    #[test]
+    fn test_assemble_module_bound() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::Simple);
+        b.type_void();
+        b.type_bool();
+        let mut module = b.module();
+        module.header.as_mut().unwrap().bound = 100;
+
+        assert_eq!(3, module.assemble()[3]);
+        let options = AssembleOptions { keep_bound: true, ..Default::default() };
+        assert_eq!(100, options.assemble(&module).unwrap()[3]);
+
+        // The assembled module parses back with the recomputed bound.
+        let mut module = mr::load_words(module.assemble()).unwrap();
+        assert_eq!(3, module.header.as_ref().unwrap().bound);
+
+        // Ids only referenced count too.
+        module.debugs.push(mr::Instruction::new(spirv::Op::Name,
+                                                None,
+                                                None,
+                                                vec![mr::Operand::IdRef(9),
+                                                     mr::Operand::from("gone")]));
+        assert_eq!(10, module.assemble()[3]);
+    }
+
+    #[test]
+    fn test_assemble_deterministic() {
+        // Builds the same module with the unordered instructions either in
+        // the sorted order or not.
+        fn build(sorted: bool) -> mr::Module {
+            let mut b = mr::Builder::new();
+            let mut capabilities = vec![spirv::Capability::Shader,
+                                        spirv::Capability::Float64,
+                                        spirv::Capability::Int64];
+            let mut extensions = vec!["SPV_KHR_shader_draw_parameters",
+                                      "SPV_KHR_storage_buffer_storage_class"];
+            if !sorted {
+                capabilities.reverse();
+                extensions.reverse();
+            }
+            for capability in capabilities {
+                b.capability(capability);
+            }
+            for extension in extensions {
+                b.extension(extension);
+            }
+            b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+            let float = b.type_float(32);
+            let st = b.type_struct(vec![float, float]);
+            b.source(spirv::SourceLanguage::GLSL, 450, None, None::<String>);
+            let offset = spirv::Decoration::Offset;
+            if sorted {
+                b.name(float, "float");
+                b.name(st, "S");
+                b.member_name(st, 0, "a");
+                b.member_name(st, 1, "b");
+                b.decorate(float, spirv::Decoration::RelaxedPrecision, vec![]).unwrap();
+                b.decorate(st, spirv::Decoration::Block, vec![]).unwrap();
+                b.member_decorate(st, 0, offset, vec![mr::Operand::LiteralInt32(0)]).unwrap();
+                b.member_decorate(st, 1, offset, vec![mr::Operand::LiteralInt32(4)]).unwrap();
+            } else {
+                b.member_name(st, 1, "b");
+                b.name(st, "S");
+                b.member_name(st, 0, "a");
+                b.name(float, "float");
+                b.member_decorate(st, 1, offset, vec![mr::Operand::LiteralInt32(4)]).unwrap();
+                b.decorate(st, spirv::Decoration::Block, vec![]).unwrap();
+                b.decorate(float, spirv::Decoration::RelaxedPrecision, vec![]).unwrap();
+                b.member_decorate(st, 0, offset, vec![mr::Operand::LiteralInt32(0)]).unwrap();
+            }
+            b.module()
+        }
+
+        let expected = build(true).assemble();
+        assert!(expected != build(false).assemble());
+
+        let options = AssembleOptions { deterministic: true, ..Default::default() };
+        assert_eq!(expected, options.assemble(&build(true)).unwrap());
+        assert_eq!(expected, options.assemble(&build(false)).unwrap());
+
+        // Shuffled differently; OpSource is kept in place.
+        let mut module = build(false);
+        module.capabilities.swap(0, 1);
+        module.debugs[1..].reverse();
+        module.annotations.reverse();
+        assert_eq!(expected, options.assemble(&module).unwrap());
+        assert_eq!(build(true).assemble_bytes(), options.assemble_bytes(&module).unwrap());
+    }
+
+    #[test]
+    fn test_assemble_generator() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::Simple);
+        b.type_void();
+        let mut module = b.module();
+        assert_eq!(spirv::GENERATOR_RSPIRV, module.assemble()[2]);
+
+        // Kept when round-tripping a parsed module.
+        let mut code = module.assemble();
+        code[2] = 0x00080003;
+        let parsed = mr::load_words(&code).unwrap();
+        assert_eq!(("Glslang Reference Front End", 3),
+                   parsed.header.as_ref().unwrap().generator());
+        assert_eq!(code, parsed.assemble());
+
+        module.header.as_mut().unwrap().set_generator(13, 7);
+        assert_eq!(0x000d0007, module.assemble()[2]);
+        assert_eq!(("Shaderc over Glslang", 7),
+                   module.header.unwrap().generator());
+    }
+
+    #[test]
+    fn test_assemble_header_overrides() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::Simple);
+        b.type_void();
+        let module = b.module();
+
+        let options = AssembleOptions {
+            version: Some((1, 0)),
+            schema: Some(0),
+            generator: Some(0x000d0007),
+            ..Default::default()
+        };
+        let code = options.assemble(&module).unwrap();
+        assert_eq!(&[0x07230203, 0x00010000, 0x000d0007, 2, 0], &code[..5]);
+        assert_eq!(module.assemble()[5..], code[5..]);
+
+        // Without overrides, the header of a parsed module is kept.
+        let mut original = module.assemble();
+        original[1] = 0x00010000;
+        original[4] = 3;
+        let parsed = mr::load_words(&original).unwrap();
+        assert_eq!(original, AssembleOptions::default().assemble(&parsed).unwrap());
+
+        for &version in &[(0, 9), (spirv::MAJOR_VERSION as u8, spirv::MINOR_VERSION as u8 + 1)] {
+            let options = AssembleOptions { version: Some(version), ..Default::default() };
+            assert_matches!(options.assemble(&module),
+                            Err(Error::VersionUnsupported(major, minor))
+                            if (major, minor) == version);
+        }
+    }
+
+    fn sampling_module(image_operands: spirv::ImageOperands,
+                       params: Vec<mr::Operand>)
+                       -> mr::Module {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let v4float = b.type_vector(float, 4);
+        let image = b.type_image(float,
+                                 spirv::Dim::Dim2D,
+                                 0,
+                                 0,
+                                 0,
+                                 1,
+                                 spirv::ImageFormat::Unknown,
+                                 None);
+        let sampled = b.type_sampled_image(image);
+        let sampled_ptr = b.type_pointer(None, spirv::StorageClass::UniformConstant, sampled);
+        let float_ptr = b.type_pointer(None, spirv::StorageClass::Function, float);
+        let voidfvoid = b.type_function(void, vec![]);
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        let sampler_param = b.function_parameter(sampled_ptr).unwrap();
+        let coord_param = b.function_parameter(float_ptr).unwrap();
+        b.begin_basic_block(None).unwrap();
+        let sampler = b.load(sampled, None, sampler_param, None, vec![]).unwrap();
+        let coord = b.load(float, None, coord_param, None, vec![]).unwrap();
+        b.image_sample_explicit_lod(v4float, None, sampler, coord, image_operands, params)
+            .unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.module()
+    }
+
+    #[test]
+    fn test_assemble_image_operands_parameters() {
+        // Lod: one id; ConstOffset: one id. Parameters of lower bits come first.
+        let module = sampling_module(spirv::IMAGE_OPERANDS_LOD |
+                                     spirv::IMAGE_OPERANDS_CONST_OFFSET,
+                                     vec![mr::Operand::IdRef(2), mr::Operand::IdRef(3)]);
+        let code = AssembleOptions::default().assemble(&module).unwrap();
+        check_round_trip(&code);
+
+        // The mask and its parameters survive parse -> encode -> parse.
+        let reloaded = mr::load_words(&code).unwrap();
+        let inst = &reloaded.functions[0].basic_blocks[0].instructions[2];
+        assert_eq!(spirv::Op::ImageSampleExplicitLod, inst.class);
+        assert_eq!(&[mr::Operand::IdRef(2), mr::Operand::IdRef(3)],
+                   inst.operand_parameters(2));
+        assert_eq!(code, reloaded.assemble());
+    }
+
+    #[test]
+    fn test_assemble_parameters_missing() {
+        let module = sampling_module(spirv::IMAGE_OPERANDS_GRAD, vec![mr::Operand::IdRef(2)]);
+        // OpFunction, two OpFunctionParameter, OpLabel, and two OpLoad
+        // precede the sampling instruction.
+        let location = Location {
+            section: Section::Function(0),
+            index: 6,
+        };
+        let err = AssembleOptions::default().assemble(&module).unwrap_err();
+        assert_matches!(err,
+                        Error::ParametersMissing(l, mr::Operand::ImageOperands(_), 2, 1)
+                            if l == location);
+        assert_eq!("instruction #6 in function #0: operand ImageOperands(IMAGE_OPERANDS_GRAD) \
+                    expects 2 parameters but only 1 operands follow",
+                   format!("{}", err));
+
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::Simple);
+        let float = b.type_float(32);
+        let ptr = b.type_pointer(None, spirv::StorageClass::Function, float);
+        let voidfvoid = b.type_function(float, vec![]);
+        b.begin_function(float, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        let param = b.function_parameter(ptr).unwrap();
+        b.begin_basic_block(None).unwrap();
+        let v = b.load(float, None, param, Some(spirv::MEMORY_ACCESS_ALIGNED), vec![]).unwrap();
+        b.ret_value(v).unwrap();
+        b.end_function().unwrap();
+        let module = b.module();
+        assert_matches!(AssembleOptions::default().assemble(&module),
+                        Err(Error::ParametersMissing(_, mr::Operand::MemoryAccess(_), 1, 0)));
+    }
+
+    #[test]
+    fn test_write_to() {
+        let module = sampling_module(spirv::IMAGE_OPERANDS_LOD, vec![mr::Operand::IdRef(2)]);
+        let words = module.assemble();
+        let expected: Vec<u8> = words.iter().flat_map(|w| u32_to_bytes(*w)).collect();
+
+        let mut sink = vec![];
+        module.write_to(&mut sink).unwrap();
+        assert_eq!(expected, sink);
+        if cfg!(target_endian = "little") {
+            assert_eq!(module.assemble_bytes(), sink);
+        }
+
+        let mut sink = vec![];
+        let inst = &module.functions[0].basic_blocks[0].instructions[2];
+        inst.write_to(&mut sink).unwrap();
+        let expected: Vec<u8> = inst.assemble().iter().flat_map(|w| u32_to_bytes(*w)).collect();
+        assert_eq!(expected, sink);
+    }
+
+    #[test]
+    fn test_write_to_file() {
+        let module = sampling_module(spirv::IMAGE_OPERANDS_LOD, vec![mr::Operand::IdRef(2)]);
+        let path = env::temp_dir().join("rspirv-test-write-to-file.spv");
+        module.write_to_file(&path).unwrap();
+
+        let mut bytes = vec![];
+        fs::File::open(&path).unwrap().read_to_end(&mut bytes).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(module.assemble(), mr::load_bytes(&bytes).unwrap().assemble());
+    }
+
+    #[test]
+    fn test_assemble_big_endian() {
+        let module = sampling_module(spirv::IMAGE_OPERANDS_LOD, vec![mr::Operand::IdRef(2)]);
+        let options = AssembleOptions { endianness: Endianness::Big, ..Default::default() };
+        let bytes = options.assemble_bytes(&module).unwrap();
+        assert_eq!(&[0x07, 0x23, 0x02, 0x03], &bytes[..4]);
+
+        let mut sink = vec![];
+        options.write_to(&module, &mut sink).unwrap();
+        assert_eq!(bytes, sink);
+
+        // The parser detects the byte order from the magic number.
+        assert_eq!(module.assemble(), mr::load_bytes(&bytes).unwrap().assemble());
+
+        let little = AssembleOptions::default().assemble_bytes(&module).unwrap();
+        assert_eq!(&[0x03, 0x02, 0x23, 0x07], &little[..4]);
+        assert_eq!(module.assemble(), mr::load_bytes(&little).unwrap().assemble());
+    }
+
+    #[test]
+    fn test_assemble_validate() {
+        let mut module = sampling_module(spirv::IMAGE_OPERANDS_LOD, vec![mr::Operand::IdRef(2)]);
+        let options = AssembleOptions { validate: true, ..Default::default() };
+        assert!(options.assemble(&module).is_ok());
+
+        // OpMemoryModel with its operands swapped.
+        module.memory_model.as_mut().unwrap().operands.reverse();
+        let location = Location {
+            section: Section::MemoryModel,
+            index: 0,
+        };
+        let mismatch =
+            mr::OperandMismatch::OperandKindMismatch(spirv::Op::MemoryModel,
+                                                     0,
+                                                     grammar::OperandKind::AddressingModel,
+                                                     mr::Operand::MemoryModel(spirv::MemoryModel::GLSL450));
+        assert_matches!(options.assemble(&module),
+                        Err(Error::OperandsInvalid(l, ref m)) if l == location && *m == mismatch);
+        // Still encoded if not validating.
+        assert!(AssembleOptions::default().assemble(&module).is_ok());
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let mut b = mr::Builder::new();
+        b.type_void();
+        let err = AssembleOptions::default().assemble(&b.module()).unwrap_err();
+        assert_matches!(err, Error::MemoryModelMissing);
+        assert_eq!("module has no OpMemoryModel", format!("{}", err));
+
+        let mut module = sampling_module(spirv::IMAGE_OPERANDS_LOD, vec![mr::Operand::IdRef(2)]);
+        module.types_global_values[1].result_id = None;
+        let err = AssembleOptions::default().assemble(&module).unwrap_err();
+        assert_eq!("instruction #1 in TypesGlobalValues: OpTypeFloat requires a result id",
+                   format!("{}", err));
+
+        let mut module = sampling_module(spirv::IMAGE_OPERANDS_LOD, vec![mr::Operand::IdRef(2)]);
+        module.functions[0].basic_blocks[0].instructions[0].operands[0] = mr::Operand::IdRef(0);
+        let err = AssembleOptions::default().assemble(&module).unwrap_err();
+        assert_eq!("instruction #4 in function #0: id 0 is not allowed", format!("{}", err));
+
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::Simple);
+        let void = b.type_void();
+        b.name(void, "vo\0id");
+        let err = AssembleOptions::default().assemble(&b.module()).unwrap_err();
+        assert_eq!(format!("instruction #0 in Debugs: string {:?} contains a nul byte", "vo\0id"),
+                   format!("{}", err));
+
+        let err = Error::IoError(::std::io::Error::new(::std::io::ErrorKind::Other, "disk full"));
+        assert_eq!("I/O error: disk full", format!("{}", err));
+    }
 }
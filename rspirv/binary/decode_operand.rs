@@ -23,7 +23,9 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V ImageOperands value.
     pub fn image_operands(&mut self) -> Result<spirv::ImageOperands> {
         if let Ok(word) = self.word() {
-            spirv::ImageOperands::from_bits(word).ok_or(Error::ImageOperandsUnknown(self.offset - WORD_NUM_BYTES, word))
+            let value = spirv::ImageOperands::from_bits_truncate(word);
+            self.check_flag_bits("ImageOperands", word, value.bits())?;
+            Ok(value)
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -33,7 +35,9 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V FPFastMathMode value.
     pub fn fpfast_math_mode(&mut self) -> Result<spirv::FPFastMathMode> {
         if let Ok(word) = self.word() {
-            spirv::FPFastMathMode::from_bits(word).ok_or(Error::FPFastMathModeUnknown(self.offset - WORD_NUM_BYTES, word))
+            let value = spirv::FPFastMathMode::from_bits_truncate(word);
+            self.check_flag_bits("FPFastMathMode", word, value.bits())?;
+            Ok(value)
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -43,7 +47,9 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V SelectionControl value.
     pub fn selection_control(&mut self) -> Result<spirv::SelectionControl> {
         if let Ok(word) = self.word() {
-            spirv::SelectionControl::from_bits(word).ok_or(Error::SelectionControlUnknown(self.offset - WORD_NUM_BYTES, word))
+            let value = spirv::SelectionControl::from_bits_truncate(word);
+            self.check_flag_bits("SelectionControl", word, value.bits())?;
+            Ok(value)
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -53,7 +59,9 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V LoopControl value.
     pub fn loop_control(&mut self) -> Result<spirv::LoopControl> {
         if let Ok(word) = self.word() {
-            spirv::LoopControl::from_bits(word).ok_or(Error::LoopControlUnknown(self.offset - WORD_NUM_BYTES, word))
+            let value = spirv::LoopControl::from_bits_truncate(word);
+            self.check_flag_bits("LoopControl", word, value.bits())?;
+            Ok(value)
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -63,7 +71,9 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V FunctionControl value.
     pub fn function_control(&mut self) -> Result<spirv::FunctionControl> {
         if let Ok(word) = self.word() {
-            spirv::FunctionControl::from_bits(word).ok_or(Error::FunctionControlUnknown(self.offset - WORD_NUM_BYTES, word))
+            let value = spirv::FunctionControl::from_bits_truncate(word);
+            self.check_flag_bits("FunctionControl", word, value.bits())?;
+            Ok(value)
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -73,7 +83,9 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V MemorySemantics value.
     pub fn memory_semantics(&mut self) -> Result<spirv::MemorySemantics> {
         if let Ok(word) = self.word() {
-            spirv::MemorySemantics::from_bits(word).ok_or(Error::MemorySemanticsUnknown(self.offset - WORD_NUM_BYTES, word))
+            let value = spirv::MemorySemantics::from_bits_truncate(word);
+            self.check_flag_bits("MemorySemantics", word, value.bits())?;
+            Ok(value)
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -83,7 +95,9 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V MemoryAccess value.
     pub fn memory_access(&mut self) -> Result<spirv::MemoryAccess> {
         if let Ok(word) = self.word() {
-            spirv::MemoryAccess::from_bits(word).ok_or(Error::MemoryAccessUnknown(self.offset - WORD_NUM_BYTES, word))
+            let value = spirv::MemoryAccess::from_bits_truncate(word);
+            self.check_flag_bits("MemoryAccess", word, value.bits())?;
+            Ok(value)
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -93,7 +107,9 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V KernelProfilingInfo value.
     pub fn kernel_profiling_info(&mut self) -> Result<spirv::KernelProfilingInfo> {
         if let Ok(word) = self.word() {
-            spirv::KernelProfilingInfo::from_bits(word).ok_or(Error::KernelProfilingInfoUnknown(self.offset - WORD_NUM_BYTES, word))
+            let value = spirv::KernelProfilingInfo::from_bits_truncate(word);
+            self.check_flag_bits("KernelProfilingInfo", word, value.bits())?;
+            Ok(value)
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -103,7 +119,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V SourceLanguage value.
     pub fn source_language(&mut self) -> Result<spirv::SourceLanguage> {
         if let Ok(word) = self.word() {
-            spirv::SourceLanguage::from_u32(word).ok_or(Error::SourceLanguageUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::SourceLanguage::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "SourceLanguage", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -113,7 +129,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V ExecutionModel value.
     pub fn execution_model(&mut self) -> Result<spirv::ExecutionModel> {
         if let Ok(word) = self.word() {
-            spirv::ExecutionModel::from_u32(word).ok_or(Error::ExecutionModelUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::ExecutionModel::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "ExecutionModel", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -123,7 +139,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V AddressingModel value.
     pub fn addressing_model(&mut self) -> Result<spirv::AddressingModel> {
         if let Ok(word) = self.word() {
-            spirv::AddressingModel::from_u32(word).ok_or(Error::AddressingModelUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::AddressingModel::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "AddressingModel", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -133,7 +149,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V MemoryModel value.
     pub fn memory_model(&mut self) -> Result<spirv::MemoryModel> {
         if let Ok(word) = self.word() {
-            spirv::MemoryModel::from_u32(word).ok_or(Error::MemoryModelUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::MemoryModel::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "MemoryModel", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -143,7 +159,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V ExecutionMode value.
     pub fn execution_mode(&mut self) -> Result<spirv::ExecutionMode> {
         if let Ok(word) = self.word() {
-            spirv::ExecutionMode::from_u32(word).ok_or(Error::ExecutionModeUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::ExecutionMode::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "ExecutionMode", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -153,7 +169,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V StorageClass value.
     pub fn storage_class(&mut self) -> Result<spirv::StorageClass> {
         if let Ok(word) = self.word() {
-            spirv::StorageClass::from_u32(word).ok_or(Error::StorageClassUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::StorageClass::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "StorageClass", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -163,7 +179,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V Dim value.
     pub fn dim(&mut self) -> Result<spirv::Dim> {
         if let Ok(word) = self.word() {
-            spirv::Dim::from_u32(word).ok_or(Error::DimUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::Dim::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "Dim", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -173,7 +189,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V SamplerAddressingMode value.
     pub fn sampler_addressing_mode(&mut self) -> Result<spirv::SamplerAddressingMode> {
         if let Ok(word) = self.word() {
-            spirv::SamplerAddressingMode::from_u32(word).ok_or(Error::SamplerAddressingModeUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::SamplerAddressingMode::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "SamplerAddressingMode", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -183,7 +199,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V SamplerFilterMode value.
     pub fn sampler_filter_mode(&mut self) -> Result<spirv::SamplerFilterMode> {
         if let Ok(word) = self.word() {
-            spirv::SamplerFilterMode::from_u32(word).ok_or(Error::SamplerFilterModeUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::SamplerFilterMode::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "SamplerFilterMode", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -193,7 +209,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V ImageFormat value.
     pub fn image_format(&mut self) -> Result<spirv::ImageFormat> {
         if let Ok(word) = self.word() {
-            spirv::ImageFormat::from_u32(word).ok_or(Error::ImageFormatUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::ImageFormat::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "ImageFormat", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -203,7 +219,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V ImageChannelOrder value.
     pub fn image_channel_order(&mut self) -> Result<spirv::ImageChannelOrder> {
         if let Ok(word) = self.word() {
-            spirv::ImageChannelOrder::from_u32(word).ok_or(Error::ImageChannelOrderUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::ImageChannelOrder::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "ImageChannelOrder", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -213,7 +229,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V ImageChannelDataType value.
     pub fn image_channel_data_type(&mut self) -> Result<spirv::ImageChannelDataType> {
         if let Ok(word) = self.word() {
-            spirv::ImageChannelDataType::from_u32(word).ok_or(Error::ImageChannelDataTypeUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::ImageChannelDataType::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "ImageChannelDataType", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -223,7 +239,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V FPRoundingMode value.
     pub fn fprounding_mode(&mut self) -> Result<spirv::FPRoundingMode> {
         if let Ok(word) = self.word() {
-            spirv::FPRoundingMode::from_u32(word).ok_or(Error::FPRoundingModeUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::FPRoundingMode::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "FPRoundingMode", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -233,7 +249,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V LinkageType value.
     pub fn linkage_type(&mut self) -> Result<spirv::LinkageType> {
         if let Ok(word) = self.word() {
-            spirv::LinkageType::from_u32(word).ok_or(Error::LinkageTypeUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::LinkageType::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "LinkageType", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -243,7 +259,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V AccessQualifier value.
     pub fn access_qualifier(&mut self) -> Result<spirv::AccessQualifier> {
         if let Ok(word) = self.word() {
-            spirv::AccessQualifier::from_u32(word).ok_or(Error::AccessQualifierUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::AccessQualifier::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "AccessQualifier", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -253,7 +269,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V FunctionParameterAttribute value.
     pub fn function_parameter_attribute(&mut self) -> Result<spirv::FunctionParameterAttribute> {
         if let Ok(word) = self.word() {
-            spirv::FunctionParameterAttribute::from_u32(word).ok_or(Error::FunctionParameterAttributeUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::FunctionParameterAttribute::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "FunctionParameterAttribute", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -263,7 +279,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V Decoration value.
     pub fn decoration(&mut self) -> Result<spirv::Decoration> {
         if let Ok(word) = self.word() {
-            spirv::Decoration::from_u32(word).ok_or(Error::DecorationUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::Decoration::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "Decoration", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -273,7 +289,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V BuiltIn value.
     pub fn built_in(&mut self) -> Result<spirv::BuiltIn> {
         if let Ok(word) = self.word() {
-            spirv::BuiltIn::from_u32(word).ok_or(Error::BuiltInUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::BuiltIn::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "BuiltIn", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -283,7 +299,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V Scope value.
     pub fn scope(&mut self) -> Result<spirv::Scope> {
         if let Ok(word) = self.word() {
-            spirv::Scope::from_u32(word).ok_or(Error::ScopeUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::Scope::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "Scope", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -293,7 +309,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V GroupOperation value.
     pub fn group_operation(&mut self) -> Result<spirv::GroupOperation> {
         if let Ok(word) = self.word() {
-            spirv::GroupOperation::from_u32(word).ok_or(Error::GroupOperationUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::GroupOperation::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "GroupOperation", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -303,7 +319,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V KernelEnqueueFlags value.
     pub fn kernel_enqueue_flags(&mut self) -> Result<spirv::KernelEnqueueFlags> {
         if let Ok(word) = self.word() {
-            spirv::KernelEnqueueFlags::from_u32(word).ok_or(Error::KernelEnqueueFlagsUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::KernelEnqueueFlags::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "KernelEnqueueFlags", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -313,7 +329,7 @@ impl<'a> Decoder<'a> {
     /// a SPIR-V Capability value.
     pub fn capability(&mut self) -> Result<spirv::Capability> {
         if let Ok(word) = self.word() {
-            spirv::Capability::from_u32(word).ok_or(Error::CapabilityUnknown(self.offset - WORD_NUM_BYTES, word))
+            spirv::Capability::from_u32(word).ok_or(Error::EnumerantUnknown(self.offset - WORD_NUM_BYTES, "Capability", word))
         } else {
             Err(Error::StreamExpected(self.offset))
         }
@@ -14,15 +14,31 @@
 
 use spirv;
 
-use std::{mem, result};
+use std::{io, mem, result};
 use super::error::Error;
 
-use utils::num::u32_to_bytes;
+use utils::num::{sign_extend_u32, u32_to_bytes};
 
 pub type Result<T> = result::Result<T, Error>;
 
 const WORD_NUM_BYTES: usize = 4;
 
+/// Byte order of the words in a SPIR-V binary.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Endianness {
+    /// The least significant byte of each word comes first.
+    Little,
+    /// The most significant byte of each word comes first.
+    Big,
+}
+
+/// The underlying data a decoder decodes from.
+enum Source<'a> {
+    Bytes(&'a [u8]),
+    Words(&'a [spirv::Word]),
+    Reader(Box<io::Read + 'a>),
+}
+
 /// The SPIR-V binary decoder.
 ///
 /// Takes in a vector of bytes, and serves requests for raw SPIR-V words
@@ -38,6 +54,20 @@ const WORD_NUM_BYTES: usize = 4;
 /// via method calls: both raw words requests and decoding the raw words
 /// into a value of a specific SPIR-V enum type.
 ///
+/// Words are decoded as little-endian by default. Use
+/// [`set_endianness`](struct.Decoder.html#method.set_endianness) to
+/// switch to big-endian decoding; literal strings are handled correctly
+/// in both cases since they are decoded from the words.
+///
+/// A decoder can also be created directly from SPIR-V words using
+/// [`from_words`](struct.Decoder.html#method.from_words). In that case,
+/// words are served as they are with `Endianness::Little`, and served
+/// byte-swapped with `Endianness::Big`. Offsets reported are still in
+/// bytes.
+///
+/// For streaming, a decoder can read words from any `std::io::Read`
+/// using [`from_reader`](struct.Decoder.html#method.from_reader).
+///
 /// It also provides a limit mechanism. Users can set a limit, and then
 /// requesting words. If that limit is reached before the end of the
 /// stream, [`State::LimitReached`](enum.ParseState.html) will be
@@ -50,8 +80,10 @@ const WORD_NUM_BYTES: usize = 4;
 /// * `Error::LimitReached(offset)` if the most recent limit has reached.
 /// * `Error::StreamExpected(offset)` if more bytes are needed to decode
 ///    the next word.
-/// * `Error::<spirv-enum>Unknown(offset, value)` if failed to decode the
-///    next word as the given `<spirv-enum>`.
+/// * `Error::EnumerantUnknown(offset, kind, value)` if failed to decode
+///    the next word as the value enum `kind`.
+/// * `Error::FlagBitsUnknown(offset, kind, bits)` if the next word decoded
+///    as the bit enum `kind` has unknown `bits` set.
 ///
 /// All errors contain the byte offset of the word failed decoding.
 ///
@@ -78,47 +110,230 @@ const WORD_NUM_BYTES: usize = 4;
 /// }
 /// ```
 pub struct Decoder<'a> {
-    /// Raw bytes to decode
-    bytes: &'a [u8],
+    /// Raw data to decode
+    source: Source<'a>,
     /// Offset for next byte to decode
     offset: usize,
     /// Remaining limit of number of words before error
     limit: Option<usize>,
+    /// Byte order of the words
+    endianness: Endianness,
+    /// The I/O error happened when reading from a reader
+    io_error: Option<io::Error>,
+    /// Number of bytes read from a reader at the end of the stream that
+    /// cannot form a whole word
+    reader_leftover: Option<usize>,
+    /// Bytes of the next word already read from a reader
+    peeked: Option<[u8; WORD_NUM_BYTES]>,
+    /// Whether to keep the known bits of bit enums with unknown bits set
+    lenient_flag_bits: bool,
+    /// The (byte offset, kind, unknown bits) of bit enums decoded leniently
+    unknown_flag_bits: Vec<(usize, &'static str, spirv::Word)>,
+}
+
+/// Assembles a word from the given four `bytes` in the given `endianness`.
+fn bytes_to_word(bytes: &[u8], endianness: Endianness) -> spirv::Word {
+    match endianness {
+        Endianness::Little => bytes.iter().rev().fold(0, |word, &b| (word << 8) | b as u32),
+        Endianness::Big => bytes.iter().fold(0, |word, &b| (word << 8) | b as u32),
+    }
 }
 
 impl<'a> Decoder<'a> {
     /// Creates a new `Decoder` instance.
     pub fn new(bytes: &'a [u8]) -> Decoder<'a> {
         Decoder {
-            bytes: bytes,
+            source: Source::Bytes(bytes),
+            offset: 0,
+            limit: None,
+            endianness: Endianness::Little,
+            io_error: None,
+            reader_leftover: None,
+            peeked: None,
+            lenient_flag_bits: false,
+            unknown_flag_bits: vec![],
+        }
+    }
+
+    /// Creates a new `Decoder` instance decoding from the given `words`.
+    pub fn from_words(words: &'a [spirv::Word]) -> Decoder<'a> {
+        Decoder {
+            source: Source::Words(words),
+            offset: 0,
+            limit: None,
+            endianness: Endianness::Little,
+            io_error: None,
+            reader_leftover: None,
+            peeked: None,
+            lenient_flag_bits: false,
+            unknown_flag_bits: vec![],
+        }
+    }
+
+    /// Creates a new `Decoder` instance decoding from the given `reader`.
+    ///
+    /// Words are read from the `reader` on demand; nothing is buffered.
+    pub fn from_reader<R: io::Read + 'a>(reader: R) -> Decoder<'a> {
+        Decoder {
+            source: Source::Reader(Box::new(reader)),
             offset: 0,
             limit: None,
+            endianness: Endianness::Little,
+            io_error: None,
+            reader_leftover: None,
+            peeked: None,
+            lenient_flag_bits: false,
+            unknown_flag_bits: vec![],
         }
     }
 
+    /// Takes the I/O error that happened when reading from the reader,
+    /// if any.
+    ///
+    /// Failing to read from the reader is reported as
+    /// `Error::StreamExpected` by the decoding methods; this method can be
+    /// used to tell real I/O errors apart from reaching the end of stream.
+    pub fn take_io_error(&mut self) -> Option<io::Error> {
+        self.io_error.take()
+    }
+
     /// Returns the offset of the byte to decode next.
     pub fn offset(&self) -> usize {
         self.offset
     }
 
+    /// Returns the position of the word to decode next, in words.
+    pub fn position(&self) -> usize {
+        self.offset / WORD_NUM_BYTES
+    }
+
+    /// Moves to the word at the given position, counting in words from
+    /// the beginning.
+    ///
+    /// Any limit set is cleared, since it is no longer meaningful at the
+    /// new position. Seeking to the end is allowed, but seeking past the
+    /// end or on a decoder reading from a reader fails with
+    /// `Error::SeekFailed` containing the requested byte offset.
+    pub fn set_position(&mut self, words: usize) -> Result<()> {
+        let offset = match words.checked_mul(WORD_NUM_BYTES) {
+            Some(offset) => offset,
+            None => return Err(Error::SeekFailed(usize::max_value())),
+        };
+        let len = match self.source {
+            Source::Bytes(data) => data.len(),
+            Source::Words(data) => data.len() * WORD_NUM_BYTES,
+            Source::Reader(_) => return Err(Error::SeekFailed(offset)),
+        };
+        if offset > len {
+            return Err(Error::SeekFailed(offset));
+        }
+        self.offset = offset;
+        self.limit = None;
+        Ok(())
+    }
+
+    /// Moves back to the beginning.
+    ///
+    /// See [`set_position`](struct.Decoder.html#method.set_position).
+    pub fn rewind(&mut self) -> Result<()> {
+        self.set_position(0)
+    }
+
+    /// Returns the number of raw bytes remaining after the current offset.
+    ///
+    /// For decoders reading from a reader, the number is only known after
+    /// failing to decode a word at the end of the stream; then it is the
+    /// number of bytes read that cannot form a whole word. `None` is
+    /// returned otherwise.
+    pub fn remaining_bytes(&self) -> Option<usize> {
+        match self.source {
+            Source::Bytes(bytes) => Some(bytes.len() - self.offset),
+            Source::Words(words) => Some(words.len() * WORD_NUM_BYTES - self.offset),
+            Source::Reader(_) => self.reader_leftover,
+        }
+    }
+
     /// Decodes and returns the next raw SPIR-V word.
     pub fn word(&mut self) -> Result<spirv::Word> {
-        if self.has_limit() {
-            if self.limit_reached() {
-                return Err(Error::LimitReached(self.offset));
-            } else {
-                *self.limit.as_mut().unwrap() -= 1
-            }
+        if self.limit_reached() {
+            return Err(Error::LimitReached(self.offset));
         }
 
-        if self.offset >= self.bytes.len() || self.offset + WORD_NUM_BYTES > self.bytes.len() {
-            Err(Error::StreamExpected(self.offset))
-        } else {
-            self.offset += WORD_NUM_BYTES;
-            Ok((0..WORD_NUM_BYTES).fold(0, |word, i| {
-                (word << 8) | (self.bytes[self.offset - i - 1]) as u32
-            }))
+        let word = self.fetch_word()?;
+        self.peeked = None;
+        // Only count words actually decoded against the limit.
+        if let Some(ref mut limit) = self.limit {
+            *limit -= 1;
+        }
+        self.offset += WORD_NUM_BYTES;
+        Ok(word)
+    }
+
+    /// Decodes and returns the next raw SPIR-V word without consuming it.
+    ///
+    /// The word is not counted against the limit. `None` is returned if
+    /// the limit has been reached or there are not enough bytes for a
+    /// whole word.
+    pub fn peek_word(&mut self) -> Option<spirv::Word> {
+        if self.limit_reached() {
+            return None;
         }
+        self.fetch_word().ok()
+    }
+
+    /// Decodes the word at the current offset without advancing.
+    ///
+    /// Words read from a reader are kept until consumed.
+    fn fetch_word(&mut self) -> Result<spirv::Word> {
+        let offset = self.offset;
+        let word = match self.source {
+            Source::Bytes(bytes) => {
+                if offset + WORD_NUM_BYTES > bytes.len() {
+                    return Err(Error::StreamExpected(offset));
+                }
+                bytes_to_word(&bytes[offset..offset + WORD_NUM_BYTES], self.endianness)
+            }
+            Source::Words(words) => {
+                if offset + WORD_NUM_BYTES > words.len() * WORD_NUM_BYTES {
+                    return Err(Error::StreamExpected(offset));
+                }
+                let word = words[offset / WORD_NUM_BYTES];
+                match self.endianness {
+                    Endianness::Little => word,
+                    Endianness::Big => word.swap_bytes(),
+                }
+            }
+            Source::Reader(ref mut reader) => {
+                if let Some(bytes) = self.peeked {
+                    return Ok(bytes_to_word(&bytes, self.endianness));
+                }
+                // Bytes after the end of the stream are already consumed.
+                if self.reader_leftover.is_some() {
+                    return Err(Error::StreamExpected(offset));
+                }
+                let mut bytes = [0u8; WORD_NUM_BYTES];
+                let mut num_read = 0;
+                while num_read < WORD_NUM_BYTES {
+                    match reader.read(&mut bytes[num_read..]) {
+                        // Reaching the end of the stream is not an I/O error.
+                        Ok(0) => break,
+                        Ok(n) => num_read += n,
+                        Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+                        Err(err) => {
+                            self.io_error = Some(err);
+                            return Err(Error::StreamExpected(offset));
+                        }
+                    }
+                }
+                if num_read < WORD_NUM_BYTES {
+                    self.reader_leftover = Some(num_read);
+                    return Err(Error::StreamExpected(offset));
+                }
+                self.peeked = Some(bytes);
+                bytes_to_word(&bytes, self.endianness)
+            }
+        };
+        Ok(word)
     }
 
     /// Decodes and returns the next `n` raw SPIR-V words.
@@ -131,6 +346,53 @@ impl<'a> Decoder<'a> {
     }
 }
 
+impl<'a> Decoder<'a> {
+    /// Sets whether to keep the known bits when decoding bit enums with
+    /// unknown bits set, instead of erroring out with
+    /// `Error::FlagBitsUnknown`.
+    ///
+    /// The unknown bits are dropped from the decoded values and recorded;
+    /// use [`take_unknown_flag_bits`](struct.Decoder.html#method.take_unknown_flag_bits)
+    /// to retrieve them.
+    pub fn set_lenient_flag_bits(&mut self, lenient: bool) {
+        self.lenient_flag_bits = lenient
+    }
+
+    /// Takes the unknown bits dropped when decoding bit enums leniently, as
+    /// (byte offset, kind, unknown bits).
+    pub fn take_unknown_flag_bits(&mut self) -> Vec<(usize, &'static str, spirv::Word)> {
+        mem::replace(&mut self.unknown_flag_bits, vec![])
+    }
+
+    /// Checks the `word` just decoded as the bit enum `kind` against the
+    /// `known` bits in it.
+    fn check_flag_bits(&mut self, kind: &'static str, word: spirv::Word, known: spirv::Word)
+                       -> Result<()> {
+        let unknown = word & !known;
+        if unknown == 0 {
+            return Ok(());
+        }
+        let offset = self.offset - WORD_NUM_BYTES;
+        if !self.lenient_flag_bits {
+            return Err(Error::FlagBitsUnknown(offset, kind, unknown));
+        }
+        self.unknown_flag_bits.push((offset, kind, unknown));
+        Ok(())
+    }
+}
+
+impl<'a> Decoder<'a> {
+    /// Sets the byte order used for decoding the following words.
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.endianness = endianness
+    }
+
+    /// Returns the byte order used for decoding words.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+}
+
 impl<'a> Decoder<'a> {
     /// Sets the limit to `num_words` words.
     ///
@@ -174,21 +436,81 @@ impl<'a> Decoder<'a> {
     /// This method will consume as many words as necessary until finding a
     /// null character (`\0`), or reaching the limit or end of the stream
     /// and erroring out.
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD REPLACEMENT
+    /// CHARACTER` instead of being treated as errors. Reaching the limit
+    /// before the nul terminator and non-zero bytes after the nul
+    /// terminator are reported as `Error::DecodeStringFailed`.
     pub fn string(&mut self) -> Result<String> {
+        if let Source::Bytes(bytes) = self.source {
+            if self.endianness == Endianness::Little {
+                return self.string_in_place(bytes);
+            }
+        }
         let start_offset = self.offset;
         let mut bytes = vec![];
         loop {
-            let word = self.word()?;
+            let word = match self.word() {
+                Ok(word) => word,
+                Err(Error::LimitReached(_)) => {
+                    return Err(Error::DecodeStringFailed(start_offset,
+                                                         "missing nul terminator".to_string()))
+                }
+                Err(err) => return Err(err),
+            };
+            let word_start = bytes.len();
             bytes.append(&mut u32_to_bytes(word));
-            if bytes.last() == Some(&0) {
+            if let Some(index) = bytes[word_start..].iter().position(|b| *b == 0) {
+                let nul = word_start + index;
+                if bytes[nul..].iter().any(|b| *b != 0) {
+                    return Err(Error::DecodeStringFailed(self.offset - WORD_NUM_BYTES,
+                                                         "found interior nul byte".to_string()));
+                }
+                bytes.truncate(nul);
+                break;
+            }
+        }
+        Ok(match String::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => String::from_utf8_lossy(&e.into_bytes()).into_owned(),
+        })
+    }
+
+    /// Decodes the next literal string directly from the given borrowed
+    /// little-endian `bytes`, which are laid out the same as the string.
+    ///
+    /// The string is only copied once, after finding the nul terminator.
+    fn string_in_place(&mut self, bytes: &[u8]) -> Result<String> {
+        let start_offset = self.offset;
+        let mut word_offset = start_offset;
+        let nul;
+        loop {
+            if let Some(limit) = self.limit {
+                if (word_offset - start_offset) / WORD_NUM_BYTES == limit {
+                    return Err(Error::DecodeStringFailed(start_offset,
+                                                         "missing nul terminator".to_string()));
+                }
+            }
+            if word_offset + WORD_NUM_BYTES > bytes.len() {
+                return Err(Error::StreamExpected(word_offset));
+            }
+            let word = &bytes[word_offset..word_offset + WORD_NUM_BYTES];
+            if let Some(index) = word.iter().position(|b| *b == 0) {
+                if word[index..].iter().any(|b| *b != 0) {
+                    return Err(Error::DecodeStringFailed(word_offset,
+                                                         "found interior nul byte".to_string()));
+                }
+                nul = word_offset + index;
                 break;
             }
+            word_offset += WORD_NUM_BYTES;
         }
-        while !bytes.is_empty() && bytes.last() == Some(&0) {
-            bytes.pop();
+        let num_words = (word_offset - start_offset) / WORD_NUM_BYTES + 1;
+        if let Some(ref mut limit) = self.limit {
+            *limit -= num_words;
         }
-        String::from_utf8(bytes)
-            .map_err(|e| Error::DecodeStringFailed(start_offset, format!("{}", e)))
+        self.offset += num_words * WORD_NUM_BYTES;
+        Ok(String::from_utf8_lossy(&bytes[start_offset..nul]).into_owned())
     }
 
     /// Decodes and returns the next SPIR-V word as a 32-bit
@@ -205,6 +527,22 @@ impl<'a> Decoder<'a> {
         Ok(((high as u64) << 32) | (low as u64))
     }
 
+    /// Decodes and returns the next SPIR-V word as a signed literal integer
+    /// of the given bit `width`, which is at most 32.
+    ///
+    /// The value is sign-extended from the lowest `width` bits; the higher
+    /// bits in the word are ignored. So a 16-bit `-1` is decoded as `-1`
+    /// regardless of whether it is stored as `0x0000ffff` or `0xffffffff`.
+    pub fn signed_int32(&mut self, width: u32) -> Result<i32> {
+        Ok(sign_extend_u32(self.word()?, width))
+    }
+
+    /// Decodes and returns the next two SPIR-V words as a 64-bit signed
+    /// literal integer.
+    pub fn signed_int64(&mut self) -> Result<i64> {
+        Ok(self.int64()? as i64)
+    }
+
     /// Decodes and returns the next SPIR-V word as a 32-bit
     /// literal floating point number.
     pub fn float32(&mut self) -> Result<f32> {
@@ -234,9 +572,11 @@ include!("decode_operand.rs");
 mod tests {
     use spirv;
 
-    use super::Decoder;
+    use super::{Decoder, Endianness};
     use binary::error::Error;
 
+    use std::io;
+
     use utils::num::f32_to_bytes;
     use utils::num::f64_to_bytes;
 
@@ -281,6 +621,91 @@ mod tests {
         assert_eq!(Ok(vec![0xbadcfe89]), d.words(1));
     }
 
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn test_decoding_big_endian_words() {
+        let b = vec![0x12, 0x34, 0x56, 0x78,
+                     0x90, 0xab, 0xcd, 0xef];
+        let mut d = Decoder::new(&b);
+        assert_eq!(Endianness::Little, d.endianness());
+        d.set_endianness(Endianness::Big);
+        assert_eq!(Endianness::Big, d.endianness());
+        assert_eq!(Ok(0x12345678), d.word());
+        assert_eq!(Ok(0x90abcdef), d.word());
+        assert_eq!(Err(Error::StreamExpected(8)), d.word());
+    }
+
+    #[test]
+    fn test_decoding_from_words() {
+        let w: Vec<u32> = vec![0x78563412, 0x02];
+        let mut d = Decoder::from_words(&w);
+        assert_eq!(Ok(0x78563412), d.word());
+        assert_eq!(4, d.offset());
+        assert_eq!(Ok(spirv::SourceLanguage::GLSL), d.source_language());
+        assert_eq!(Err(Error::StreamExpected(8)), d.word());
+
+        let mut d = Decoder::from_words(&w);
+        d.set_endianness(Endianness::Big);
+        assert_eq!(Ok(0x12345678), d.word());
+    }
+
+    #[test]
+    fn test_decoding_string_from_words() {
+        let w: Vec<u32> = vec![0x6c6c6568, 0x0000006f, 0x00006b6f];
+        let mut d = Decoder::from_words(&w);
+        assert_eq!(Ok("hello".to_string()), d.string());
+        assert_eq!(Ok("ok".to_string()), d.string());
+        assert_eq!(Err(Error::StreamExpected(12)), d.string());
+
+        let w: Vec<u32> = vec![0xffffffff; 4];
+        let mut d = Decoder::from_words(&w);
+        d.set_limit(2);
+        assert_eq!(Err(Error::DecodeStringFailed(0, "missing nul terminator".to_string())),
+                   d.string());
+    }
+
+    #[test]
+    fn test_decoding_from_reader() {
+        let b = b"ok\0\0\x12\x34\x56\x78\x90".to_vec();
+        let mut d = Decoder::from_reader(io::Cursor::new(b));
+        assert_eq!(Ok("ok".to_string()), d.string());
+        assert_eq!(Ok(0x78563412), d.word());
+        assert_eq!(8, d.offset());
+        assert_eq!(Err(Error::StreamExpected(8)), d.word());
+        assert!(d.take_io_error().is_none());
+    }
+
+    #[test]
+    fn test_remaining_bytes() {
+        let b = vec![0x12, 0x34, 0x56, 0x78, 0x90, 0xab];
+        let mut d = Decoder::new(&b);
+        assert_eq!(Some(6), d.remaining_bytes());
+        assert_eq!(Ok(0x78563412), d.word());
+        assert_eq!(Some(2), d.remaining_bytes());
+        assert_eq!(Err(Error::StreamExpected(4)), d.word());
+        assert_eq!(Some(2), d.remaining_bytes());
+
+        let w: Vec<u32> = vec![0x12345678, 0x9abcdef0];
+        let mut d = Decoder::from_words(&w);
+        assert_eq!(Ok(0x12345678), d.word());
+        assert_eq!(Some(4), d.remaining_bytes());
+
+        let mut d = Decoder::from_reader(io::Cursor::new(b.clone()));
+        assert_eq!(None, d.remaining_bytes());
+        assert_eq!(Ok(0x78563412), d.word());
+        assert_eq!(None, d.remaining_bytes());
+        assert_eq!(Err(Error::StreamExpected(4)), d.word());
+        assert_eq!(Some(2), d.remaining_bytes());
+    }
+
+    #[test]
+    fn test_decoding_big_endian_string() {
+        let b = b"lleh\0\0\0o".to_vec();
+        let mut d = Decoder::new(&b);
+        d.set_endianness(Endianness::Big);
+        assert_eq!(Ok("hello".to_string()), d.string());
+    }
+
     #[test]
     fn test_decoding_string() {
         {
@@ -312,6 +737,65 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decoding_non_utf8_string() {
+        let b = b"a\xffb\0".to_vec();
+        let mut d = Decoder::new(&b);
+        assert_eq!(Ok("a\u{fffd}b".to_string()), d.string());
+    }
+
+    #[test]
+    fn test_decoding_string_interior_nul() {
+        let b = b"rust\0a\0\0".to_vec();
+        let mut d = Decoder::new(&b);
+        assert_eq!(Err(Error::DecodeStringFailed(4, "found interior nul byte".to_string())),
+                   d.string());
+    }
+
+    #[test]
+    fn test_decoding_string_missing_terminator() {
+        let b = b"rust".to_vec();
+        let mut d = Decoder::new(&b);
+        d.set_limit(1);
+        assert_eq!(Err(Error::DecodeStringFailed(0, "missing nul terminator".to_string())),
+                   d.string());
+    }
+
+    #[test]
+    fn test_decoding_string_from_all_sources() {
+        let b = b"rust\0\0\0\0a\xffb\0ok".to_vec();
+        let w: Vec<u32> = vec![0x74737572, 0x00000000, 0x0062ff61, 0x00006b6f];
+        let mut decoders = vec![Decoder::new(&b),
+                                Decoder::from_words(&w),
+                                Decoder::from_reader(io::Cursor::new(b.clone()))];
+        for d in &mut decoders {
+            d.set_limit(3);
+            assert_eq!(Ok("rust".to_string()), d.string());
+            assert_eq!(Ok("a\u{fffd}b".to_string()), d.string());
+            assert!(d.limit_reached());
+            assert_eq!(12, d.offset());
+            assert_eq!(Err(Error::DecodeStringFailed(12, "missing nul terminator".to_string())),
+                       d.string());
+        }
+    }
+
+    #[test]
+    fn test_decoding_unknown_flag_bits() {
+        // Volatile | Aligned | 0x80000000
+        let b = vec![0x03, 0x00, 0x00, 0x80, 0x03, 0x00, 0x00, 0x80];
+        let mut d = Decoder::new(&b);
+        assert_eq!(Err(Error::FlagBitsUnknown(0, "MemoryAccess", 0x80000000)),
+                   d.memory_access());
+        assert_eq!("unknown bits 0x80000000 for operand kind MemoryAccess at word 0 (byte 0)",
+                   format!("{}", Error::FlagBitsUnknown(0, "MemoryAccess", 0x80000000)));
+
+        d.set_lenient_flag_bits(true);
+        assert_eq!(Ok(spirv::MEMORY_ACCESS_VOLATILE | spirv::MEMORY_ACCESS_ALIGNED),
+                   d.memory_access());
+        assert_eq!(vec![(4, "MemoryAccess", 0x80000000)], d.take_unknown_flag_bits());
+        assert!(d.take_unknown_flag_bits().is_empty());
+    }
+
     #[test]
     fn test_decoding_source_language() {
         let b = vec![0x02, 0x00, 0x00, 0x00];
@@ -323,7 +807,7 @@ mod tests {
     fn test_decoding_unknown_execution_model() {
         let b = vec![0xef, 0xbe, 0xad, 0xde];
         let mut d = Decoder::new(&b);
-        assert_eq!(Err(Error::ExecutionModelUnknown(0, 0xdeadbeef)),
+        assert_eq!(Err(Error::EnumerantUnknown(0, "ExecutionModel", 0xdeadbeef)),
                    d.execution_model());
     }
 
@@ -412,6 +896,109 @@ mod tests {
         assert_eq!(Err(Error::StreamExpected(12)), d.word());
     }
 
+    #[test]
+    fn test_seek() {
+        let b = b"\x11\x00\x02\x00\x01\x00\x00\x00ok\0\0\xff".to_vec();
+        let w: Vec<u32> = vec![0x00020011, 0x00000001, 0x00006b6f];
+        let mut decoders = vec![Decoder::new(&b), Decoder::from_words(&w)];
+        for d in &mut decoders {
+            assert_eq!(0, d.position());
+            d.set_limit(2);
+            let first = d.words(2);
+            assert_eq!(Ok(vec![0x00020011, 0x00000001]), first);
+            assert_eq!(2, d.position());
+            d.clear_limit();
+            assert_eq!(Ok("ok".to_string()), d.string());
+
+            // Decoding again after rewinding gives the same result.
+            d.set_limit(1);
+            assert_eq!(Ok(()), d.rewind());
+            assert!(!d.has_limit());
+            assert_eq!(first, d.words(2));
+            assert_eq!(Ok(()), d.set_position(1));
+            assert_eq!(Ok(0x00000001), d.word());
+            assert_eq!(8, d.offset());
+
+            assert_eq!(Ok(()), d.set_position(3));
+            assert_eq!(Err(Error::StreamExpected(12)), d.word());
+            assert_eq!(Err(Error::SeekFailed(16)), d.set_position(4));
+            assert_eq!(3, d.position());
+        }
+
+        let mut d = Decoder::from_reader(io::Cursor::new(b.clone()));
+        assert_eq!(Ok(0x00020011), d.word());
+        assert_eq!(1, d.position());
+        assert_eq!(Err(Error::SeekFailed(0)), d.rewind());
+        assert_eq!(Ok(0x00000001), d.word());
+    }
+
+    #[test]
+    fn test_peek_word() {
+        let b = vec![0x12, 0x34, 0x56, 0x78, 0x90, 0xab, 0xcd, 0xef, 0x01];
+        let mut d = Decoder::new(&b);
+        assert_eq!(Some(0x78563412), d.peek_word());
+        assert_eq!(Some(0x78563412), d.peek_word());
+        assert_eq!(0, d.offset());
+        assert_eq!(Ok(0x78563412), d.word());
+
+        // Peeking does not count against the limit.
+        d.set_limit(1);
+        assert_eq!(Some(0xefcdab90), d.peek_word());
+        assert!(!d.limit_reached());
+        assert_eq!(Ok(0xefcdab90), d.word());
+        assert!(d.limit_reached());
+        assert_eq!(None, d.peek_word());
+        assert_eq!(8, d.offset());
+
+        // Peeking at the end of the stream.
+        d.clear_limit();
+        assert_eq!(None, d.peek_word());
+        assert_eq!(Err(Error::StreamExpected(8)), d.word());
+    }
+
+    #[test]
+    fn test_peek_word_then_read() {
+        let b = vec![0x12, 0x34, 0x56, 0x78, 0x90, 0xab, 0xcd, 0xef, 0x01];
+        let w: Vec<u32> = vec![0x78563412, 0xefcdab90];
+        let mut decoders = vec![Decoder::new(&b),
+                                Decoder::from_words(&w),
+                                Decoder::from_reader(io::Cursor::new(b.clone()))];
+        for d in &mut decoders {
+            assert_eq!(Some(0x78563412), d.peek_word());
+            assert_eq!(Ok(0x78563412), d.word());
+            // The byte order may change between peeking and reading.
+            assert_eq!(Some(0xefcdab90), d.peek_word());
+            d.set_endianness(Endianness::Big);
+            assert_eq!(Ok(0x90abcdef), d.word());
+            assert_eq!(None, d.peek_word());
+            assert_eq!(8, d.offset());
+        }
+
+        let mut d = Decoder::from_reader(io::Cursor::new(b.clone()));
+        d.words(2).unwrap();
+        assert_eq!(None, d.peek_word());
+        assert_eq!(Err(Error::StreamExpected(8)), d.word());
+        assert_eq!(Some(1), d.remaining_bytes());
+    }
+
+    #[test]
+    fn test_decode_signed_int() {
+        let b = vec![0xff, 0xff, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00];
+        let mut d = Decoder::new(&b);
+        assert_eq!(Ok(-1), d.signed_int32(16));
+        assert_eq!(Ok(-1), d.signed_int32(16));
+        assert_eq!(Ok(-128), d.signed_int32(8));
+        d.rewind().unwrap();
+        assert_eq!(Ok(0xffff), d.signed_int32(32));
+        assert_eq!(Ok(-1), d.signed_int32(32));
+        assert_eq!(Ok(0x80), d.signed_int32(16));
+
+        let b = vec![0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let mut d = Decoder::new(&b);
+        assert_eq!(Ok(-2), d.signed_int64());
+        assert_eq!(Err(Error::StreamExpected(8)), d.signed_int64());
+    }
+
     #[test]
     fn test_decode_int64() {
         let b = vec![0x12, 0x34, 0x56, 0x78, 0x90, 0xab, 0xcd, 0xef];
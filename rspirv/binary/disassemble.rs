@@ -67,10 +67,19 @@ fn disas_join<T: Disassemble>(insts: &[T], delimiter: &str) -> String {
 
 impl Disassemble for mr::Instruction {
     fn disassemble(&self) -> String {
+        if let Some(opcode) = self.unknown_opcode {
+            if let mr::Operand::RawWords(ref words) = self.operands[0] {
+                let operands: Vec<String> = words[1..]
+                                                .iter()
+                                                .map(|w| format!("0x{:08x}", w))
+                                                .collect();
+                return format!("OpUnknown({}) [{}]", opcode, operands.join(" "));
+            }
+        }
         format!("{rid}{opcode}{rtype}{space}{operands}",
                 rid = self.result_id
                           .map_or(String::new(), |w| format!("%{} = ", w)),
-                opcode = format!("Op{}", self.class.opname),
+                opcode = format!("Op{}", self.grammar().opname),
                 // extra space both before and after the reseult type
                 rtype = self.result_type
                             .map_or(String::new(), |w| format!("  %{} ", w)),
@@ -154,7 +163,7 @@ impl Disassemble for mr::Module {
                         .as_ref()
                         .map_or(String::new(), |i| i.disassemble()));
                 for inst in &bb.instructions {
-                    match inst.class.opcode {
+                    match inst.class {
                         spirv::Op::ExtInst => {
                             push!(&mut text, disas_ext_inst(inst, &ext_inst_set_tracker))
                         }
@@ -191,7 +200,7 @@ fn disas_ext_inst(inst: &mr::Instruction,
             format!("{rid}{opcode}{rtype} {operands}",
                     rid = inst.result_id
                               .map_or(String::new(), |w| format!("%{} = ", w)),
-                    opcode = format!("Op{}", inst.class.opname),
+                    opcode = format!("Op{}", inst.grammar().opname),
                     rtype = inst.result_type
                                 .map_or(String::new(), |w| format!("  %{} ", w)),
                     operands = operands.join(" "))
@@ -210,6 +219,12 @@ mod tests {
 
     use binary::Disassemble;
 
+    #[test]
+    fn test_disassemble_unknown_instruction() {
+        let inst = mr::Instruction::unknown(0xffff, vec![0x12345678, 1]);
+        assert_eq!("OpUnknown(65535) [0x12345678 0x00000001]", inst.disassemble());
+    }
+
     #[test]
     fn test_disassemble_operand_function_control() {
         let o = mr::Operand::FunctionControl(spirv::FUNCTION_CONTROL_NONE);
@@ -257,14 +272,14 @@ mod tests {
                                  voidfvoid)
                  .unwrap();
         b.begin_basic_block(None).unwrap();
-        let var = b.variable(float32, None, spirv::StorageClass::Function, None);
+        let var = b.variable(float32, None, spirv::StorageClass::Function, None).unwrap();
         b.ret().unwrap();
         b.end_function().unwrap();
 
         b.entry_point(spirv::ExecutionModel::Fragment, f, "main", vec![]);
-        b.execution_mode(f, spirv::ExecutionMode::OriginUpperLeft, vec![]);
+        b.execution_mode(f, spirv::ExecutionMode::OriginUpperLeft, vec![]).unwrap();
         b.name(f, "main");
-        b.decorate(var, spirv::Decoration::RelaxedPrecision, vec![]);
+        b.decorate(var, spirv::Decoration::RelaxedPrecision, vec![]).unwrap();
 
         assert_eq!(b.module().disassemble(),
                    "; SPIR-V\n\
@@ -304,7 +319,7 @@ mod tests {
 
         assert!(b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).is_ok());
         b.begin_basic_block(None).unwrap();
-        let var = b.variable(float32, None, spirv::StorageClass::Function, None);
+        let var = b.variable(float32, None, spirv::StorageClass::Function, None).unwrap();
         assert!(b.ext_inst(float32, None, glsl, 6, vec![var]).is_ok());
         b.ret().unwrap();
         b.end_function().unwrap();
@@ -341,7 +356,7 @@ mod tests {
 
         assert!(b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).is_ok());
         b.begin_basic_block(None).unwrap();
-        let var = b.variable(float32, None, spirv::StorageClass::Function, None);
+        let var = b.variable(float32, None, spirv::StorageClass::Function, None).unwrap();
         assert!(b.ext_inst(float32, None, opencl, 15, vec![var]).is_ok());
         b.ret().unwrap();
         b.end_function().unwrap();
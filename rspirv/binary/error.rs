@@ -26,36 +26,13 @@ use std::{error, fmt};
 pub enum Error {
     StreamExpected(usize),
     LimitReached(usize),
-    ImageOperandsUnknown(usize, spirv::Word),
-    FPFastMathModeUnknown(usize, spirv::Word),
-    SelectionControlUnknown(usize, spirv::Word),
-    LoopControlUnknown(usize, spirv::Word),
-    FunctionControlUnknown(usize, spirv::Word),
-    MemorySemanticsUnknown(usize, spirv::Word),
-    MemoryAccessUnknown(usize, spirv::Word),
-    KernelProfilingInfoUnknown(usize, spirv::Word),
-    SourceLanguageUnknown(usize, spirv::Word),
-    ExecutionModelUnknown(usize, spirv::Word),
-    AddressingModelUnknown(usize, spirv::Word),
-    MemoryModelUnknown(usize, spirv::Word),
-    ExecutionModeUnknown(usize, spirv::Word),
-    StorageClassUnknown(usize, spirv::Word),
-    DimUnknown(usize, spirv::Word),
-    SamplerAddressingModeUnknown(usize, spirv::Word),
-    SamplerFilterModeUnknown(usize, spirv::Word),
-    ImageFormatUnknown(usize, spirv::Word),
-    ImageChannelOrderUnknown(usize, spirv::Word),
-    ImageChannelDataTypeUnknown(usize, spirv::Word),
-    FPRoundingModeUnknown(usize, spirv::Word),
-    LinkageTypeUnknown(usize, spirv::Word),
-    AccessQualifierUnknown(usize, spirv::Word),
-    FunctionParameterAttributeUnknown(usize, spirv::Word),
-    DecorationUnknown(usize, spirv::Word),
-    BuiltInUnknown(usize, spirv::Word),
-    ScopeUnknown(usize, spirv::Word),
-    GroupOperationUnknown(usize, spirv::Word),
-    KernelEnqueueFlagsUnknown(usize, spirv::Word),
-    CapabilityUnknown(usize, spirv::Word),
+    SeekFailed(usize),
+    /// Unknown value for an operand of a value enum kind
+    /// (offset, kind, value).
+    EnumerantUnknown(usize, &'static str, spirv::Word),
+    /// Unknown bits set for an operand of a bit enum kind
+    /// (offset, kind, unknown bits).
+    FlagBitsUnknown(usize, &'static str, spirv::Word),
     /// Failed to decode a string.
     ///
     /// For structured error handling, the second element could be
@@ -67,39 +44,12 @@ pub enum Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::StreamExpected(index) => write!(f, "expected more bytes in the stream at index {}", index),
-            Error::LimitReached(index) => write!(f, "reached word limit at index {}", index),
-            Error::ImageOperandsUnknown(index, word) => write!(f, "unknown value {} for operand kind ImageOperands at index {}", word, index),
-            Error::FPFastMathModeUnknown(index, word) => write!(f, "unknown value {} for operand kind FPFastMathMode at index {}", word, index),
-            Error::SelectionControlUnknown(index, word) => write!(f, "unknown value {} for operand kind SelectionControl at index {}", word, index),
-            Error::LoopControlUnknown(index, word) => write!(f, "unknown value {} for operand kind LoopControl at index {}", word, index),
-            Error::FunctionControlUnknown(index, word) => write!(f, "unknown value {} for operand kind FunctionControl at index {}", word, index),
-            Error::MemorySemanticsUnknown(index, word) => write!(f, "unknown value {} for operand kind MemorySemantics at index {}", word, index),
-            Error::MemoryAccessUnknown(index, word) => write!(f, "unknown value {} for operand kind MemoryAccess at index {}", word, index),
-            Error::KernelProfilingInfoUnknown(index, word) => write!(f, "unknown value {} for operand kind KernelProfilingInfo at index {}", word, index),
-            Error::SourceLanguageUnknown(index, word) => write!(f, "unknown value {} for operand kind SourceLanguage at index {}", word, index),
-            Error::ExecutionModelUnknown(index, word) => write!(f, "unknown value {} for operand kind ExecutionModel at index {}", word, index),
-            Error::AddressingModelUnknown(index, word) => write!(f, "unknown value {} for operand kind AddressingModel at index {}", word, index),
-            Error::MemoryModelUnknown(index, word) => write!(f, "unknown value {} for operand kind MemoryModel at index {}", word, index),
-            Error::ExecutionModeUnknown(index, word) => write!(f, "unknown value {} for operand kind ExecutionMode at index {}", word, index),
-            Error::StorageClassUnknown(index, word) => write!(f, "unknown value {} for operand kind StorageClass at index {}", word, index),
-            Error::DimUnknown(index, word) => write!(f, "unknown value {} for operand kind Dim at index {}", word, index),
-            Error::SamplerAddressingModeUnknown(index, word) => write!(f, "unknown value {} for operand kind SamplerAddressingMode at index {}", word, index),
-            Error::SamplerFilterModeUnknown(index, word) => write!(f, "unknown value {} for operand kind SamplerFilterMode at index {}", word, index),
-            Error::ImageFormatUnknown(index, word) => write!(f, "unknown value {} for operand kind ImageFormat at index {}", word, index),
-            Error::ImageChannelOrderUnknown(index, word) => write!(f, "unknown value {} for operand kind ImageChannelOrder at index {}", word, index),
-            Error::ImageChannelDataTypeUnknown(index, word) => write!(f, "unknown value {} for operand kind ImageChannelDataType at index {}", word, index),
-            Error::FPRoundingModeUnknown(index, word) => write!(f, "unknown value {} for operand kind FPRoundingMode at index {}", word, index),
-            Error::LinkageTypeUnknown(index, word) => write!(f, "unknown value {} for operand kind LinkageType at index {}", word, index),
-            Error::AccessQualifierUnknown(index, word) => write!(f, "unknown value {} for operand kind AccessQualifier at index {}", word, index),
-            Error::FunctionParameterAttributeUnknown(index, word) => write!(f, "unknown value {} for operand kind FunctionParameterAttribute at index {}", word, index),
-            Error::DecorationUnknown(index, word) => write!(f, "unknown value {} for operand kind Decoration at index {}", word, index),
-            Error::BuiltInUnknown(index, word) => write!(f, "unknown value {} for operand kind BuiltIn at index {}", word, index),
-            Error::ScopeUnknown(index, word) => write!(f, "unknown value {} for operand kind Scope at index {}", word, index),
-            Error::GroupOperationUnknown(index, word) => write!(f, "unknown value {} for operand kind GroupOperation at index {}", word, index),
-            Error::KernelEnqueueFlagsUnknown(index, word) => write!(f, "unknown value {} for operand kind KernelEnqueueFlags at index {}", word, index),
-            Error::CapabilityUnknown(index, word) => write!(f, "unknown value {} for operand kind Capability at index {}", word, index),
-            Error::DecodeStringFailed(index, ref e) => write!(f, "cannot decode string at index {}: {}", index, e),
+            Error::StreamExpected(index) => write!(f, "expected more bytes in the stream at word {} (byte {})", index / 4, index),
+            Error::LimitReached(index) => write!(f, "reached word limit at word {} (byte {})", index / 4, index),
+            Error::SeekFailed(index) => write!(f, "cannot seek to word {} (byte {})", index / 4, index),
+            Error::EnumerantUnknown(index, kind, word) => write!(f, "unknown value {} for operand kind {} at word {} (byte {})", word, kind, index / 4, index),
+            Error::FlagBitsUnknown(index, kind, bits) => write!(f, "unknown bits {:#x} for operand kind {} at word {} (byte {})", bits, kind, index / 4, index),
+            Error::DecodeStringFailed(index, ref e) => write!(f, "cannot decode string at word {} (byte {}): {}", index / 4, index, e),
         }
     }
 }
@@ -108,7 +58,30 @@ impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
             Error::StreamExpected(_) => "expected more bytes in the stream",
-            _ => "unknown operand value for the given kind",
+            Error::LimitReached(_) => "reached word limit",
+            Error::SeekFailed(_) => "cannot seek to the given position",
+            Error::EnumerantUnknown(..) => "unknown value for the given operand kind",
+            Error::FlagBitsUnknown(..) => "unknown bits for the given operand kind",
+            Error::DecodeStringFailed(..) => "cannot decode string",
         }
     }
 }
+
+impl Error {
+    /// Returns the byte offset of the word failed decoding.
+    pub fn offset(&self) -> usize {
+        match *self {
+            Error::StreamExpected(index) |
+            Error::LimitReached(index) |
+            Error::SeekFailed(index) |
+            Error::EnumerantUnknown(index, _, _) |
+            Error::FlagBitsUnknown(index, _, _) |
+            Error::DecodeStringFailed(index, _) => index,
+        }
+    }
+
+    /// Returns the offset of the word failed decoding, in words.
+    pub fn word_offset(&self) -> usize {
+        self.offset() / 4
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mr;
+use spirv;
+
+use std::{cmp, error, fmt};
+use std::collections::HashSet;
+use super::assemble::Assemble;
+use super::parser::{Action, Consumer};
+
+/// What to do with an instruction passed through a
+/// [`Filter`](struct.Filter.html).
+#[derive(Debug)]
+pub enum FilterAction {
+    /// Emit the instruction as is
+    Keep,
+    /// Emit the given instructions instead
+    Replace(Vec<mr::Instruction>),
+    /// Emit nothing for the instruction
+    Drop,
+}
+
+/// Filtering errors, reported when finalizing.
+#[derive(Debug, PartialEq)]
+pub enum FilterError {
+    /// An id defined by a dropped or replaced instruction is still
+    /// referenced by the emitted instructions but no longer defined (id),
+    /// only reported in strict mode
+    DroppedIdReferenced(spirv::Word),
+    /// An emitted id is `u32::max_value()`, which no id bound covers
+    IdOverflow,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FilterError::DroppedIdReferenced(id) => {
+                write!(f, "id %{} of a dropped instruction is still referenced", id)
+            }
+            FilterError::IdOverflow => write!(f, "emitted ids exceed any id bound"),
+        }
+    }
+}
+
+impl error::Error for FilterError {
+    fn description(&self) -> &str {
+        match *self {
+            FilterError::DroppedIdReferenced(_) => "dropped id still referenced",
+            FilterError::IdOverflow => "id bound overflow",
+        }
+    }
+}
+
+/// A consumer re-encoding the instructions as they are parsed, after
+/// passing each of them through a closure deciding whether to keep,
+/// replace, or drop it.
+///
+/// The module header is re-encoded as is, except that its id bound is
+/// recomputed from all ids used in the emitted instructions when
+/// finalizing, like
+/// [`mr::Module::compute_id_bound`](../mr/struct.Module.html#method.compute_id_bound).
+/// Instructions with unknown opcodes, reported via
+/// `consume_unknown_instruction` or preserved as raw words, are copied
+/// untouched; since the ids in them cannot be told apart, the original
+/// bound is kept if larger once any is copied. If the closure stops the
+/// parsing with `Action::Stop`, the consumer is not finalized, so the
+/// bound is left as is. Ids exceeding any bound error out with
+/// `FilterError::IdOverflow` when finalizing.
+///
+/// In strict mode, ids defined by dropped or replaced instructions, but
+/// still referenced by emitted instructions and no longer defined by any
+/// of them, error out with
+/// [`FilterError::DroppedIdReferenced`](enum.FilterError.html) when
+/// finalizing.
+///
+/// # Examples
+///
+/// ```
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::binary::{Filter, FilterAction, parse_words};
+///
+/// fn main() {
+///     let words: Vec<u32> = vec![0x07230203, 0x00010000, 0, 0, 0,
+///                                0x00020011, 0x00000001,  // OpCapability Shader
+///                                0x0003000e, 0, 1];  // OpMemoryModel Logical GLSL450
+///     let mut filter = Filter::new(|inst| match inst.class {
+///         spirv::Op::Capability => FilterAction::Drop,
+///         _ => FilterAction::Keep,
+///     });
+///     parse_words(&words, &mut filter).unwrap();
+///     assert_eq!(vec![0x07230203, 0x00010000, 0, 1, 0, 0x0003000e, 0, 1],
+///                filter.into_code());
+/// }
+/// ```
+pub struct Filter<F> {
+    inst_fn: F,
+    strict: bool,
+    code: Vec<spirv::Word>,
+    has_header: bool,
+    /// The largest id emitted
+    max_id: spirv::Word,
+    /// Whether instructions with unknown opcodes have been emitted
+    unknown_emitted: bool,
+    dropped: HashSet<spirv::Word>,
+    defined: HashSet<spirv::Word>,
+    referenced: HashSet<spirv::Word>,
+}
+
+impl<F> Filter<F>
+    where F: FnMut(&mr::Instruction) -> FilterAction
+{
+    /// Creates a new filter calling `f` for each instruction.
+    pub fn new(f: F) -> Filter<F> {
+        Filter {
+            inst_fn: f,
+            strict: false,
+            code: vec![],
+            has_header: false,
+            max_id: 0,
+            unknown_emitted: false,
+            dropped: HashSet::new(),
+            defined: HashSet::new(),
+            referenced: HashSet::new(),
+        }
+    }
+
+    /// Sets whether to check that no emitted instruction references an id
+    /// only defined by a dropped or replaced instruction.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Returns the binary code emitted so far.
+    pub fn code(&self) -> &[spirv::Word] {
+        &self.code
+    }
+
+    /// Consumes this filter and returns the binary code emitted.
+    pub fn into_code(self) -> Vec<spirv::Word> {
+        self.code
+    }
+
+    fn emit(&mut self, inst: &mr::Instruction) {
+        let ids = inst.operands.iter().filter_map(|o| o.id());
+        let max = inst.result_id.into_iter().chain(inst.result_type).chain(ids).max();
+        self.max_id = cmp::max(self.max_id, max.unwrap_or(0));
+        self.unknown_emitted |= inst.unknown_opcode.is_some();
+        if self.strict {
+            self.defined.extend(inst.result_id);
+            self.referenced.extend(inst.result_type);
+            self.referenced.extend(inst.operands.iter().filter_map(|o| o.id()));
+        }
+        inst.assemble_into(&mut self.code);
+    }
+}
+
+impl<F> Consumer for Filter<F>
+    where F: FnMut(&mr::Instruction) -> FilterAction
+{
+    fn finalize(&mut self) -> Action {
+        if self.has_header {
+            let bound = match self.max_id.checked_add(1) {
+                Some(bound) => bound,
+                None => return Action::Error(Box::new(FilterError::IdOverflow)),
+            };
+            if self.unknown_emitted {
+                self.code[3] = cmp::max(self.code[3], bound);
+            } else {
+                self.code[3] = bound;
+            }
+        }
+        if self.strict {
+            let mut ids: Vec<spirv::Word> = self.dropped
+                .iter()
+                .filter(|&id| self.referenced.contains(id) && !self.defined.contains(id))
+                .cloned()
+                .collect();
+            ids.sort();
+            if let Some(&id) = ids.first() {
+                return Action::Error(Box::new(FilterError::DroppedIdReferenced(id)));
+            }
+        }
+        Action::Continue
+    }
+
+    fn consume_header(&mut self, header: mr::ModuleHeader) -> Action {
+        header.assemble_into(&mut self.code);
+        self.has_header = true;
+        Action::Continue
+    }
+
+    fn consume_instruction(&mut self, inst: mr::Instruction) -> Action {
+        match (self.inst_fn)(&inst) {
+            FilterAction::Keep => self.emit(&inst),
+            FilterAction::Replace(insts) => {
+                self.dropped.extend(inst.result_id);
+                for inst in &insts {
+                    self.emit(inst);
+                }
+            }
+            FilterAction::Drop => {
+                self.dropped.extend(inst.result_id);
+            }
+        }
+        Action::Continue
+    }
+
+    fn consume_unknown_instruction(&mut self, _opcode: u16, words: &[spirv::Word]) -> Action {
+        self.unknown_emitted = true;
+        self.code.extend_from_slice(words);
+        Action::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mr;
+    use spirv;
+
+    use binary::{Assemble, Parser, ParserOptions, parse_words};
+    use super::{Filter, FilterAction, FilterError};
+
+    fn module() -> mr::Module {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        b.name(void, "void");
+        b.name(float, "float");
+        b.module()
+    }
+
+    #[test]
+    fn test_filter_keep() {
+        let code = module().assemble();
+        let mut filter = Filter::new(|_| FilterAction::Keep);
+        parse_words(&code, &mut filter).unwrap();
+        assert_eq!(code, filter.into_code());
+    }
+
+    #[test]
+    fn test_filter_replace_and_drop() {
+        let mut filter = Filter::new(|inst| match inst.class {
+            spirv::Op::Name => FilterAction::Drop,
+            spirv::Op::TypeFloat => {
+                FilterAction::Replace(vec![mr::Instruction::new(spirv::Op::TypeInt,
+                                                                None,
+                                                                Some(5),
+                                                                vec![mr::Operand::LiteralInt32(32),
+                                                                     mr::Operand::LiteralInt32(1)])])
+            }
+            _ => FilterAction::Keep,
+        });
+        parse_words(&module().assemble(), &mut filter).unwrap();
+
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        b.type_void();
+        let mut expected = b.module();
+        expected.types_global_values
+            .push(mr::Instruction::new(spirv::Op::TypeInt,
+                                       None,
+                                       Some(5),
+                                       vec![mr::Operand::LiteralInt32(32),
+                                            mr::Operand::LiteralInt32(1)]));
+        let expected = expected.assemble();
+        assert_eq!(6, expected[3]);
+        assert_eq!(expected, filter.into_code());
+    }
+
+    #[test]
+    fn test_filter_strict() {
+        fn drop_float(inst: &mr::Instruction) -> FilterAction {
+            match inst.class {
+                spirv::Op::TypeFloat => FilterAction::Drop,
+                _ => FilterAction::Keep,
+            }
+        }
+
+        // The OpName of the dropped type is kept.
+        let code = module().assemble();
+        assert!(parse_words(&code, &mut Filter::new(drop_float)).is_ok());
+        let err = parse_words(&code, &mut Filter::new(drop_float).strict(true)).unwrap_err();
+        assert_eq!(format!("consumer error: {}", FilterError::DroppedIdReferenced(2)),
+                   format!("{}", err));
+
+        // Redefining the dropped id is fine.
+        let mut filter = Filter::new(|inst: &mr::Instruction| match inst.class {
+                spirv::Op::TypeFloat => {
+                    FilterAction::Replace(vec![mr::Instruction::new(spirv::Op::TypeBool,
+                                                                    None,
+                                                                    inst.result_id,
+                                                                    vec![])])
+                }
+                _ => FilterAction::Keep,
+            })
+            .strict(true);
+        assert!(parse_words(&code, &mut filter).is_ok());
+    }
+
+    #[test]
+    fn test_filter_bound() {
+        // The OpName of the dropped type still references its id.
+        let code = module().assemble();
+        assert_eq!(3, code[3]);
+        let mut filter = Filter::new(|inst| match inst.class {
+            spirv::Op::TypeFloat => FilterAction::Drop,
+            _ => FilterAction::Keep,
+        });
+        parse_words(&code, &mut filter).unwrap();
+        assert_eq!(3, filter.code()[3]);
+
+        // Ids in unknown instructions cannot be told apart.
+        let mut code = code;
+        code[3] = 10;
+        code.extend_from_slice(&[0x0002fff0, 9]);
+        for &preserve in &[false, true] {
+            let options = ParserOptions {
+                ignore_unknown_opcodes: true,
+                preserve_unknown_opcodes: preserve,
+                ..Default::default()
+            };
+            let mut filter = Filter::new(|inst| match inst.class {
+                spirv::Op::Name => FilterAction::Drop,
+                _ => FilterAction::Keep,
+            });
+            Parser::from_words(&code, &mut filter).options(options).parse().unwrap();
+            assert_eq!(10, filter.code()[3]);
+            assert_eq!(&[0x0002fff0, 9], &filter.code()[filter.code().len() - 2..]);
+        }
+
+        let mut filter = Filter::new(|inst| match inst.class {
+            spirv::Op::TypeVoid => {
+                FilterAction::Replace(vec![mr::Instruction::new(spirv::Op::TypeVoid,
+                                                                None,
+                                                                Some(u32::max_value()),
+                                                                vec![])])
+            }
+            _ => FilterAction::Keep,
+        });
+        let err = parse_words(&module().assemble(), &mut filter).unwrap_err();
+        assert_eq!(format!("consumer error: {}", FilterError::IdOverflow),
+                   format!("{}", err));
+    }
+}
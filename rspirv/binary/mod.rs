@@ -24,19 +24,38 @@
 //!   [`Consumer`](trait.Consumer.html) to process a SPIR-V binary on the
 //!   instruction level.
 
-pub use self::decoder::Decoder;
+pub use self::decoder::{Decoder, Endianness};
 pub use self::error::Error as DecodeError;
-pub use self::parser::{Consumer, parse_bytes, parse_words, Parser};
+pub use self::parser::{Consumer, parse_bytes, parse_dyn, parse_header, parse_multi,
+                       parse_operand, parse_reader, parse_words, stats};
+pub use self::parser::ModuleStats;
+pub use self::parser::{IncrementalParser, Parser, ParserOptions, ParseIterator};
+pub use self::parser::{InstConsumer, InstContext, ModuleConsumer, on_header_and_inst, on_inst};
 pub use self::parser::Action as ParseAction;
+pub use self::parser::Completion as ParseCompletion;
+pub use self::parser::Limit as ParseLimit;
 pub use self::parser::Result as ParseResult;
 pub use self::parser::State as ParseState;
+pub use self::parser::Warning as ParseWarning;
+pub use self::tracker::SourceLocation;
 
 pub use self::disassemble::Disassemble;
-pub use self::assemble::Assemble;
+pub use self::assemble::{Assemble, AssembleOptions};
+pub use self::assemble::Error as EncodeError;
+pub use self::assemble::Location as EncodeLocation;
+pub use self::assemble::Section as EncodeSection;
+pub use self::filter::{Filter, FilterAction, FilterError};
+pub use self::patch::{ModuleSpans, PatchError, load_with_spans, patch_function};
+pub use self::roundtrip::{RoundtripMismatch, verify_roundtrip};
 
 mod assemble;
 mod decoder;
 mod disassemble;
 mod error;
+mod filter;
 mod parser;
+mod patch;
+mod roundtrip;
+#[cfg(test)]
+mod test_operand;
 mod tracker;
@@ -16,7 +16,7 @@
 //   external/spirv.core.grammar.json.
 // DO NOT MODIFY!
 
-impl<'c, 'd> Parser<'c, 'd> {
+impl<'d> InstParser<'d> {
     fn parse_operand(&mut self, kind: GOpKind) -> Result<Vec<mr::Operand>> {
         Ok(match kind {
             GOpKind::FPFastMathMode => vec![mr::Operand::FPFastMathMode(try_decode!(self.decoder.fpfast_math_mode()))],
@@ -48,7 +48,7 @@ impl<'c, 'd> Parser<'c, 'd> {
             GOpKind::IdScope => vec![mr::Operand::IdScope(try_decode!(self.decoder.id()))],
             GOpKind::IdRef => vec![mr::Operand::IdRef(try_decode!(self.decoder.id()))],
             GOpKind::LiteralInteger => vec![mr::Operand::LiteralInt32(try_decode!(self.decoder.int32()))],
-            GOpKind::LiteralString => vec![mr::Operand::LiteralString(try_decode!(self.decoder.string()))],
+            GOpKind::LiteralString => vec![mr::Operand::LiteralString(intern(&mut self.strings, try_decode!(self.decoder.string())))],
             GOpKind::LiteralExtInstInteger => vec![mr::Operand::LiteralExtInstInteger(try_decode!(self.decoder.ext_inst_integer()))],
             GOpKind::PairLiteralIntegerIdRef => {
                 vec![mr::Operand::LiteralInt32(try_decode!(self.decoder.int32())), mr::Operand::IdRef(try_decode!(self.decoder.id()))]
@@ -172,7 +172,7 @@ impl<'c, 'd> Parser<'c, 'd> {
             spirv::Decoration::FuncParamAttr => vec![mr::Operand::FunctionParameterAttribute(try_decode!(self.decoder.function_parameter_attribute()))],
             spirv::Decoration::FPRoundingMode => vec![mr::Operand::FPRoundingMode(try_decode!(self.decoder.fprounding_mode()))],
             spirv::Decoration::FPFastMathMode => vec![mr::Operand::FPFastMathMode(try_decode!(self.decoder.fpfast_math_mode()))],
-            spirv::Decoration::LinkageAttributes => vec![mr::Operand::LiteralString(try_decode!(self.decoder.string())), mr::Operand::LinkageType(try_decode!(self.decoder.linkage_type()))],
+            spirv::Decoration::LinkageAttributes => vec![mr::Operand::LiteralString(intern(&mut self.strings, try_decode!(self.decoder.string()))), mr::Operand::LinkageType(try_decode!(self.decoder.linkage_type()))],
             spirv::Decoration::InputAttachmentIndex => vec![mr::Operand::LiteralInt32(try_decode!(self.decoder.int32()))],
             spirv::Decoration::Alignment => vec![mr::Operand::LiteralInt32(try_decode!(self.decoder.int32()))],
             spirv::Decoration::MaxByteOffset => vec![mr::Operand::LiteralInt32(try_decode!(self.decoder.int32()))],
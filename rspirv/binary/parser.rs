@@ -16,18 +16,22 @@ use mr;
 use grammar;
 use spirv;
 
-use std::{error, fmt, result, slice};
+use std::{cmp, error, fmt, io, mem, result};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::sync::Arc;
 use super::decoder;
 use super::error::Error as DecodeError;
-use super::tracker::{Type, TypeTracker};
+use super::tracker::{ExtInstSetTracker, LineTracker, SourceLocation, Type, TypeTracker};
+use utils::num::bytes_to_u32_le;
 
 use grammar::InstructionTable as GInstTable;
 use grammar::OperandKind as GOpKind;
 use grammar::OperandQuantifier as GOpCount;
 
 type GInstRef = &'static grammar::Instruction<'static>;
-
-const WORD_NUM_BYTES: usize = 4;
+type GExtInstRef = &'static grammar::ExtendedInstruction<'static>;
 
 /// Parser State.
 ///
@@ -37,48 +41,77 @@ const WORD_NUM_BYTES: usize = 4;
 pub enum State {
     /// Parsing completed
     Complete,
-    /// Consumer requested to stop parse
-    ConsumerStopRequested,
     /// Consumer errored out with the given error
     ConsumerError(Box<error::Error>),
     /// Incomplete module header
     HeaderIncomplete(DecodeError),
     /// Incorrect module header
     HeaderIncorrect,
-    /// Unsupported endianness
-    EndiannessUnsupported,
+    /// Module version (major, minor) not supported by the grammar
+    VersionUnsupported(u8, u8),
+    /// Nonzero instruction schema in the module header (schema)
+    SchemaNonZero(spirv::Word),
     /// Zero instruction word count at (byte offset, inst number)
     WordCountZero(usize, usize),
     /// Unknown opcode at (byte offset, inst number, opcode)
     OpcodeUnknown(usize, usize, u16),
     /// Expected more operands (byte offset, inst number)
     OperandExpected(usize, usize),
+    /// Instruction word count running past the end of the binary
+    /// (byte offset, inst number)
+    InstructionIncomplete(usize, usize),
     /// found redundant operands (byte offset, inst number)
     OperandExceeded(usize, usize),
     /// Errored out when decoding operand with the given error
     OperandError(DecodeError),
+    /// Words not forming a lone operand of the requested kind, parsed
+    /// outside of any instruction (byte offset)
+    LoneOperandIncorrect(usize),
     /// Unsupported type (byte offset, inst number)
     TypeUnsupported(usize, usize),
     /// Incorrect SpecConstantOp Integer (byte offset, inst number)
     SpecConstantOpIntegerIncorrect(usize, usize),
+    /// Id zero or not less than the id bound (byte offset, inst number, id)
+    IdOutOfBounds(usize, usize, spirv::Word),
+    /// Bytes not forming a whole word after the last instruction
+    /// (byte offset, number of bytes)
+    TrailingBytes(usize, usize),
+    /// Incomplete module header when parsing multiple modules
+    /// (module number starting from 1, error)
+    ModuleHeaderIncomplete(usize, DecodeError),
+    /// Input finished in the middle of the module header or an instruction
+    /// (byte offset, number of words still expected)
+    InputIncomplete(usize, usize),
+    /// Resource limit exceeded (byte offset, limit)
+    LimitExceeded(usize, Limit),
+    /// Errored out when reading from the reader with the given error
+    IoError(io::Error),
 }
 
 impl error::Error for State {
     fn description(&self) -> &str {
         match *self {
             State::Complete => "completed parsing",
-            State::ConsumerStopRequested => "stop parsing requested by consumer",
             State::ConsumerError(_) => "consumer error",
             State::HeaderIncomplete(_) => "incomplete module header",
             State::HeaderIncorrect => "incorrect module header",
-            State::EndiannessUnsupported => "unsupported endianness",
+            State::VersionUnsupported(..) => "unsupported module version",
+            State::SchemaNonZero(_) => "nonzero instruction schema",
             State::WordCountZero(..) => "zero word count found",
             State::OpcodeUnknown(..) => "unknown opcode",
             State::OperandExpected(..) => "expected more operands",
+            State::InstructionIncomplete(..) => "incomplete instruction",
             State::OperandExceeded(..) => "found extra operands",
             State::OperandError(_) => "operand decoding error",
+            State::LoneOperandIncorrect(_) => "incorrect lone operand",
             State::TypeUnsupported(..) => "unsupported type",
             State::SpecConstantOpIntegerIncorrect(..) => "incorrect SpecConstantOp Integer",
+            State::IdOutOfBounds(..) => "id out of bounds",
+            State::TrailingBytes(..) => "found trailing bytes",
+            State::ModuleHeaderIncomplete(..) => "incomplete module header",
+            State::InputIncomplete(..) => "incomplete input",
+            State::LimitExceeded(..) => "resource limit exceeded",
+            State::IoError(_) => "I/O error",
         }
     }
 }
@@ -87,57 +120,357 @@ impl fmt::Display for State {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             State::Complete => write!(f, "completed parsing"),
-            State::ConsumerStopRequested => write!(f, "stop parsing requested by consumer"),
             State::ConsumerError(ref err) => write!(f, "consumer error: {}", err),
             State::HeaderIncomplete(ref err) => write!(f, "incomplete module header: {}", err),
             State::HeaderIncorrect => write!(f, "incorrect module header"),
-            State::EndiannessUnsupported => write!(f, "unsupported endianness"),
+            State::VersionUnsupported(major, minor) => {
+                write!(f,
+                       "unsupported module version {}.{} (grammar version {}.{})",
+                       major,
+                       minor,
+                       spirv::MAJOR_VERSION,
+                       spirv::MINOR_VERSION)
+            }
+            State::SchemaNonZero(schema) => {
+                write!(f, "nonzero instruction schema ({:#x}) in module header", schema)
+            }
             State::WordCountZero(offset, index) => {
                 write!(f,
-                       "zero word count found for instruction #{} at offset {}",
+                       "zero word count found for instruction #{} at word {} (byte {})",
                        index,
+                       offset / WORD_NUM_BYTES,
                        offset)
             }
             State::OpcodeUnknown(offset, index, opcode) => {
                 write!(f,
-                       "unknown opcode ({}) for instruction #{} at offset {}",
+                       "unknown opcode ({}) for instruction #{} at word {} (byte {})",
                        opcode,
                        index,
+                       offset / WORD_NUM_BYTES,
                        offset)
             }
             State::OperandExpected(offset, index) => {
                 write!(f,
-                       "expected more operands for instruction #{} at offset \
-                        {}",
+                       "expected more operands for instruction #{} at word {} (byte {})",
+                       index,
+                       offset / WORD_NUM_BYTES,
+                       offset)
+            }
+            State::InstructionIncomplete(offset, index) => {
+                write!(f,
+                       "incomplete instruction #{} at word {} (byte {}): word \
+                        count runs past the end of the binary",
                        index,
+                       offset / WORD_NUM_BYTES,
                        offset)
             }
             State::OperandExceeded(offset, index) => {
                 write!(f,
-                       "found extra operands for instruction #{} at offset {}",
+                       "found extra operands for instruction #{} at word {} (byte {})",
                        index,
+                       offset / WORD_NUM_BYTES,
                        offset)
             }
             State::OperandError(ref err) => write!(f, "operand decoding error: {}", err),
+            State::LoneOperandIncorrect(offset) => {
+                write!(f,
+                       "words not forming a lone operand of the requested kind at word {} \
+                        (byte {})",
+                       offset / WORD_NUM_BYTES,
+                       offset)
+            }
             State::TypeUnsupported(offset, index) => {
                 write!(f,
-                       "unsupported type for instruction #{} at offset {}",
+                       "unsupported type for instruction #{} at word {} (byte {})",
                        index,
+                       offset / WORD_NUM_BYTES,
                        offset)
             }
             State::SpecConstantOpIntegerIncorrect(offset, index) => {
                 write!(f,
-                       "incorrect SpecConstantOp number for instruction #{} at offset {}",
+                       "incorrect SpecConstantOp number for instruction #{} at word {} (byte {})",
+                       index,
+                       offset / WORD_NUM_BYTES,
+                       offset)
+            }
+            State::IdOutOfBounds(offset, index, id) => {
+                write!(f,
+                       "id ({}) out of bounds for instruction #{} at word {} (byte {})",
+                       id,
                        index,
+                       offset / WORD_NUM_BYTES,
+                       offset)
+            }
+            State::TrailingBytes(offset, count) => {
+                write!(f,
+                       "found {} trailing bytes after the last instruction at word {} (byte {})",
+                       count,
+                       offset / WORD_NUM_BYTES,
+                       offset)
+            }
+            State::ModuleHeaderIncomplete(index, ref err) => {
+                write!(f, "incomplete module header for module #{}: {}", index, err)
+            }
+            State::InputIncomplete(offset, count) => {
+                write!(f,
+                       "input finished at word {} (byte {}) with {} more words expected",
+                       offset / WORD_NUM_BYTES,
+                       offset,
+                       count)
+            }
+            State::LimitExceeded(offset, limit) => {
+                write!(f,
+                       "exceeded {} at word {} (byte {})",
+                       limit,
+                       offset / WORD_NUM_BYTES,
+                       offset)
+            }
+            State::IoError(ref err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl State {
+    /// Returns the byte offset where the error happened, if known.
+    pub fn offset(&self) -> Option<usize> {
+        match *self {
+            State::WordCountZero(offset, _) |
+            State::OpcodeUnknown(offset, _, _) |
+            State::OperandExpected(offset, _) |
+            State::InstructionIncomplete(offset, _) |
+            State::OperandExceeded(offset, _) |
+            State::TypeUnsupported(offset, _) |
+            State::SpecConstantOpIntegerIncorrect(offset, _) |
+            State::IdOutOfBounds(offset, _, _) |
+            State::LoneOperandIncorrect(offset) |
+            State::TrailingBytes(offset, _) |
+            State::InputIncomplete(offset, _) |
+            State::LimitExceeded(offset, _) => Some(offset),
+            State::HeaderIncomplete(ref err) |
+            State::OperandError(ref err) |
+            State::ModuleHeaderIncomplete(_, ref err) => Some(err.offset()),
+            State::Complete |
+            State::ConsumerError(_) |
+            State::HeaderIncorrect |
+            State::VersionUnsupported(..) |
+            State::SchemaNonZero(_) |
+            State::IoError(_) => None,
+        }
+    }
+
+    /// Returns the offset where the error happened in words, if known.
+    ///
+    /// Offsets count from the beginning of the binary, including the
+    /// module header.
+    pub fn word_offset(&self) -> Option<usize> {
+        self.offset().map(|offset| offset / WORD_NUM_BYTES)
+    }
+
+    /// Returns the number of the instruction where the error happened,
+    /// starting from 1, if known.
+    pub fn inst_index(&self) -> Option<usize> {
+        match *self {
+            State::WordCountZero(_, index) |
+            State::OpcodeUnknown(_, index, _) |
+            State::OperandExpected(_, index) |
+            State::InstructionIncomplete(_, index) |
+            State::OperandExceeded(_, index) |
+            State::TypeUnsupported(_, index) |
+            State::SpecConstantOpIntegerIncorrect(_, index) |
+            State::IdOutOfBounds(_, index, _) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this error is local to a single instruction, whose
+    /// word count can still be trusted to find the next instruction.
+    fn is_recoverable(&self) -> bool {
+        match *self {
+            State::OpcodeUnknown(..) |
+            State::OperandExpected(..) |
+            State::OperandExceeded(..) |
+            State::OperandError(_) |
+            State::TypeUnsupported(..) |
+            State::SpecConstantOpIntegerIncorrect(..) |
+            State::IdOutOfBounds(..) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Conditions tolerated by the parser because of the options set, reported
+/// to the consumer via [`Consumer::warn`](trait.Consumer.html#method.warn).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Warning {
+    /// Module version (major, minor) not supported by the grammar
+    VersionUnsupported(u8, u8),
+    /// Unknown bits dropped from a bit enum operand
+    /// (byte offset, operand kind, bits)
+    UnknownFlagBitsDropped(usize, &'static str, spirv::Word),
+    /// Unknown value kept for an enumerant operand
+    /// (byte offset, operand kind, value)
+    UnknownEnumerantPreserved(usize, &'static str, spirv::Word),
+    /// Bytes not forming a whole word ignored after the last instruction
+    /// (byte offset, number of bytes)
+    TrailingBytesIgnored(usize, usize),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::VersionUnsupported(major, minor) => {
+                write!(f,
+                       "module version {}.{} is not supported by grammar {}.{}",
+                       major,
+                       minor,
+                       spirv::MAJOR_VERSION,
+                       spirv::MINOR_VERSION)
+            }
+            Warning::UnknownFlagBitsDropped(offset, kind, bits) => {
+                write!(f,
+                       "dropped unknown bits {:#x} for operand kind {} at word {} (byte {})",
+                       bits,
+                       kind,
+                       offset / WORD_NUM_BYTES,
+                       offset)
+            }
+            Warning::UnknownEnumerantPreserved(offset, kind, value) => {
+                write!(f,
+                       "preserved unknown value {} for operand kind {} at word {} (byte {})",
+                       value,
+                       kind,
+                       offset / WORD_NUM_BYTES,
+                       offset)
+            }
+            Warning::TrailingBytesIgnored(offset, count) => {
+                write!(f,
+                       "ignored {} trailing bytes after the last instruction at word {} (byte {})",
+                       count,
+                       offset / WORD_NUM_BYTES,
                        offset)
             }
         }
     }
 }
 
+/// Resource limits that can be enforced when parsing untrusted modules.
+///
+/// All limits are unlimited by default.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Limit {
+    /// The id bound in the module header
+    IdBound(spirv::Word),
+    /// The number of instructions in a module
+    InstructionCount(usize),
+    /// The number of functions in a module
+    FunctionCount(usize),
+    /// The size of the binary in words
+    BinaryWords(usize),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Limit::IdBound(max) => write!(f, "the maximal id bound ({})", max),
+            Limit::InstructionCount(max) => {
+                write!(f, "the maximal number of instructions ({})", max)
+            }
+            Limit::FunctionCount(max) => write!(f, "the maximal number of functions ({})", max),
+            Limit::BinaryWords(max) => write!(f, "the maximal number of words ({})", max),
+        }
+    }
+}
+
+/// Options for parsing a module, accepted by all the parser front-ends:
+/// [`Parser`](struct.Parser.html),
+/// [`ParseIterator`](struct.ParseIterator.html), and
+/// [`IncrementalParser`](struct.IncrementalParser.html).
+///
+/// By default, everything not conforming to the grammar is an error and
+/// no limits are enforced. Conditions tolerated because of the options set
+/// are reported as [`Warning`](enum.Warning.html)s, via
+/// [`Consumer::warn`](trait.Consumer.html#method.warn) or
+/// [`ParseIterator::take_warnings`](struct.ParseIterator.html#method.take_warnings).
+#[derive(Clone, Debug, Default)]
+pub struct ParserOptions {
+    /// Whether to skip instructions with opcodes unknown to the grammar
+    /// instead of erroring out with `State::OpcodeUnknown`.
+    ///
+    /// Skipped instructions are sent to the consumer via
+    /// `consume_unknown_instruction`, or silently dropped by
+    /// `ParseIterator`. Instructions with zero word count or truncated
+    /// instructions are still reported as errors.
+    pub ignore_unknown_opcodes: bool,
+    /// Whether to preserve instructions with opcodes unknown to the grammar
+    /// instead of erroring out with `State::OpcodeUnknown`.
+    ///
+    /// Preserved instructions are created via
+    /// [`mr::Instruction::unknown`](../mr/struct.Instruction.html#method.unknown)
+    /// and handled like normal instructions, so they can be assembled back
+    /// verbatim. This takes precedence over `ignore_unknown_opcodes`.
+    pub preserve_unknown_opcodes: bool,
+    /// Whether to preserve operands with enumerant values unknown to the
+    /// grammar instead of erroring out with
+    /// `State::OperandError(Error::EnumerantUnknown(..))`.
+    ///
+    /// The unknown value is kept as `mr::Operand::UnknownEnumerant`. Since
+    /// the operands it implies cannot be known, all the remaining words of
+    /// the instruction are kept as `mr::Operand::LiteralInt32` operands.
+    /// Unknown values nested in the parameters of another enumerant are
+    /// still errors. Warned as `Warning::UnknownEnumerantPreserved`.
+    pub preserve_unknown_enumerants: bool,
+    /// Whether to keep the known bits of bit enum operands with unknown
+    /// bits set instead of erroring out with
+    /// `State::OperandError(DecodeError::FlagBitsUnknown(..))`.
+    ///
+    /// Parameters required by the unknown bits cannot be parsed, so such
+    /// instructions are likely to be reported as `State::OperandExceeded`.
+    /// Warned as `Warning::UnknownFlagBitsDropped`.
+    pub allow_unknown_flag_bits: bool,
+    /// Whether to continue parsing modules with versions not supported by
+    /// the grammar instead of erroring out with `State::VersionUnsupported`.
+    ///
+    /// Version words with nonzero reserved bytes are still reported as
+    /// `State::HeaderIncorrect`. Warned as `Warning::VersionUnsupported`.
+    pub allow_unsupported_version: bool,
+    /// Whether to ignore bytes not forming a whole word after the last
+    /// instruction instead of erroring out with `State::TrailingBytes`.
+    ///
+    /// Warned as `Warning::TrailingBytesIgnored`.
+    pub allow_trailing_bytes: bool,
+    /// Whether to check that all ids are non-zero and less than the id
+    /// bound in the module header.
+    ///
+    /// Violations are reported as `State::IdOutOfBounds`, with the byte
+    /// offset of the instruction containing the id.
+    pub check_id_bound: bool,
+    /// Whether to error out with `State::SchemaNonZero` if the instruction
+    /// schema in the module header is not zero, as the spec requires.
+    ///
+    /// Otherwise, the schema is kept in `ModuleHeader::reserved_word`
+    /// whatever it is.
+    pub reject_nonzero_schema: bool,
+    /// The maximal id bound allowed in the module header.
+    ///
+    /// Larger bounds are reported as `State::LimitExceeded` when parsing
+    /// the header, like exceeding the other limits.
+    pub max_id_bound: Option<spirv::Word>,
+    /// The maximal number of instructions allowed in the module.
+    pub max_instruction_count: Option<usize>,
+    /// The maximal number of functions allowed in the module.
+    pub max_function_count: Option<usize>,
+    /// The maximal size of the binary in words, including the module
+    /// header.
+    ///
+    /// If the size of the binary is known up front, it is checked when
+    /// parsing the header; otherwise, it is checked for each instruction.
+    pub max_binary_words: Option<usize>,
+}
+
 pub type Result<T> = result::Result<T, State>;
 
 const HEADER_NUM_WORDS: usize = 5;
+const WORD_NUM_BYTES: usize = 4;
 
 /// Orders consumer sent to the parser after each consuming call.
 #[derive(Debug)]
@@ -145,11 +478,38 @@ pub enum Action {
     /// Continue the parsing
     Continue,
     /// Normally stop the parsing
+    ///
+    /// This is not an error; the parser will stop and report success
+    /// without calling `finalize` on the consumer.
     Stop,
     /// Error out with the given error
     Error(Box<error::Error>),
 }
 
+/// How a successful parsing finished.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Completion {
+    /// The whole binary was parsed
+    Complete,
+    /// The consumer requested to stop before reaching the end
+    StoppedEarly,
+}
+
+/// Where an instruction sent to a consumer is in the module.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InstContext {
+    /// The byte offset of the instruction, starting from 0
+    pub offset: usize,
+    /// The number of bytes of the instruction, from the word encoding the
+    /// word count and opcode to the last operand word
+    pub len: usize,
+    /// The index of the instruction, starting from 1
+    pub index: usize,
+    /// The source location declared by `OpLine` in effect for the
+    /// instruction, if any
+    pub location: Option<SourceLocation>,
+}
+
 /// The binary consumer trait.
 ///
 /// The parser will call `initialize` before parsing the SPIR-V binary and
@@ -160,31 +520,384 @@ pub enum Action {
 /// will be called.
 ///
 /// The consumer can use [`Action`](enum.ParseAction.html) to control the
-/// parsing process.
+/// parsing process. Returning `Action::Stop` from any method makes the
+/// parser stop immediately and report success.
+///
+/// Only `consume_instruction` is required to be implemented. By default,
+/// `initialize`, `finalize`, and `consume_header` do nothing but return
+/// `Action::Continue`; the module header is just dropped.
+///
+/// The parser actually calls `consume_instruction_span`, or
+/// `consume_instruction_ref` if it lends instructions, which by default
+/// forward to `consume_instruction`. Override `consume_instruction_span` if
+/// the position of each instruction in the binary is needed.
 pub trait Consumer {
     /// Intialize the consumer.
-    fn initialize(&mut self) -> Action;
+    ///
+    /// Returns `Action::Continue` by default.
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
     /// Finalize the consumer.
-    fn finalize(&mut self) -> Action;
+    ///
+    /// Returns `Action::Continue` by default.
+    fn finalize(&mut self) -> Action {
+        Action::Continue
+    }
 
     /// Consume the module header.
-    fn consume_header(&mut self, module: mr::ModuleHeader) -> Action;
+    ///
+    /// Drops the header and returns `Action::Continue` by default.
+    fn consume_header(&mut self, _module: mr::ModuleHeader) -> Action {
+        Action::Continue
+    }
     /// Consume the given instruction.
     fn consume_instruction(&mut self, inst: mr::Instruction) -> Action;
+
+    /// Consume the given borrowed instruction, described by `context`.
+    ///
+    /// Only called, instead of `consume_instruction_span`, if the parser is
+    /// configured to lend instructions.
+    ///
+    /// Clones the instruction and forwards to `consume_instruction_span` by
+    /// default.
+    fn consume_instruction_ref(&mut self,
+                               context: InstContext,
+                               inst: &mr::Instruction)
+                               -> Action {
+        self.consume_instruction_span(context, inst.clone())
+    }
+
+    /// Consume the given instruction, described by `context`.
+    ///
+    /// Forwards to `consume_instruction` by default.
+    fn consume_instruction_span(&mut self,
+                                context: InstContext,
+                                inst: mr::Instruction)
+                                -> Action {
+        let _ = context;
+        self.consume_instruction(inst)
+    }
+
+    /// Warns about the given condition tolerated because of the options
+    /// set on the parser.
+    ///
+    /// Warnings about the operands of an instruction are given before
+    /// consuming the instruction.
+    ///
+    /// Returns `Action::Continue` by default.
+    fn warn(&mut self, warning: Warning) -> Action {
+        let _ = warning;
+        Action::Continue
+    }
+
+    /// Consume an instruction with an opcode unknown to the grammar.
+    ///
+    /// Only called if the parser is configured to ignore unknown opcodes.
+    /// `words` contains all the words of the instruction, including the
+    /// first word encoding the word count and opcode.
+    ///
+    /// Skips the instruction and returns `Action::Continue` by default.
+    fn consume_unknown_instruction(&mut self, opcode: u16, words: &[spirv::Word]) -> Action {
+        let _ = (opcode, words);
+        Action::Continue
+    }
+}
+
+/// A consumer calling a closure for each instruction.
+///
+/// Created by [`on_inst`](fn.on_inst.html).
+pub struct InstConsumer<F> {
+    inst_fn: F,
+}
+
+/// A consumer calling a closure for the module header and another closure
+/// for each instruction.
+///
+/// Created by [`on_header_and_inst`](fn.on_header_and_inst.html).
+pub struct ModuleConsumer<H, I> {
+    header_fn: H,
+    inst_fn: I,
+}
+
+/// Returns a consumer calling the given closure `f` for each instruction.
+///
+/// # Examples
+///
+/// ```
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::binary::{on_inst, parse_words, ParseAction};
+///
+/// fn main() {
+///     let words: Vec<u32> = vec![0x07230203, 0x00010000, 0, 0, 0,
+///                                0x00020011, 0x00000001,  // OpCapability Shader
+///                                0x0003000e, 0, 1];  // OpMemoryModel Logical GLSL450
+///     let mut count = 0;
+///     parse_words(&words,
+///                 &mut on_inst(|_| {
+///                     count += 1;
+///                     ParseAction::Continue
+///                 }))
+///         .unwrap();
+///     assert_eq!(2, count);
+/// }
+/// ```
+pub fn on_inst<F>(f: F) -> InstConsumer<F>
+    where F: FnMut(mr::Instruction) -> Action
+{
+    InstConsumer { inst_fn: f }
+}
+
+/// Returns a consumer calling the closure `h` for the module header and the
+/// closure `i` for each instruction.
+pub fn on_header_and_inst<H, I>(h: H, i: I) -> ModuleConsumer<H, I>
+    where H: FnMut(mr::ModuleHeader) -> Action,
+          I: FnMut(mr::Instruction) -> Action
+{
+    ModuleConsumer {
+        header_fn: h,
+        inst_fn: i,
+    }
+}
+
+impl<F> Consumer for InstConsumer<F>
+    where F: FnMut(mr::Instruction) -> Action
+{
+    fn consume_instruction(&mut self, inst: mr::Instruction) -> Action {
+        (self.inst_fn)(inst)
+    }
+}
+
+impl<H, I> Consumer for ModuleConsumer<H, I>
+    where H: FnMut(mr::ModuleHeader) -> Action,
+          I: FnMut(mr::Instruction) -> Action
+{
+    fn consume_header(&mut self, header: mr::ModuleHeader) -> Action {
+        (self.header_fn)(header)
+    }
+    fn consume_instruction(&mut self, inst: mr::Instruction) -> Action {
+        (self.inst_fn)(inst)
+    }
 }
 
 /// Parses the given `binary` and consumes the module using the given
 /// `consumer`.
-pub fn parse_bytes<T: AsRef<[u8]>>(binary: T, consumer: &mut Consumer) -> Result<()> {
+///
+/// The `binary` can be either owned (e.g., `Vec<u8>`) or borrowed (e.g.,
+/// `&[u8]` from `include_bytes!` or a memory-mapped file); borrowed bytes
+/// are parsed in place without copying.
+pub fn parse_bytes<T, C>(binary: T, consumer: &mut C) -> Result<()>
+    where T: AsRef<[u8]>,
+          C: Consumer + ?Sized
+{
     Parser::new(binary.as_ref(), consumer).parse()
 }
 
 /// Parses the given `binary` and consumes the module using the given
 /// `consumer`.
-pub fn parse_words<T: AsRef<[u32]>>(binary: T, consumer: &mut Consumer) -> Result<()> {
-    let len = binary.as_ref().len() * 4;
-    let buf = unsafe { slice::from_raw_parts(binary.as_ref().as_ptr() as *const u8, len) };
-    Parser::new(buf, consumer).parse()
+///
+/// The words are decoded directly without being converted into bytes
+/// first.
+pub fn parse_words<T, C>(binary: T, consumer: &mut C) -> Result<()>
+    where T: AsRef<[u32]>,
+          C: Consumer + ?Sized
+{
+    Parser::from_words(binary.as_ref(), consumer).parse()
+}
+
+/// Parses the SPIR-V binary read from the given `reader` and consumes the
+/// module using the given `consumer`.
+///
+/// The binary is read word by word on demand; it is never buffered as a
+/// whole.
+pub fn parse_reader<'r, R, C>(reader: R, consumer: &mut C) -> Result<()>
+    where R: io::Read + 'r,
+          C: Consumer + ?Sized
+{
+    Parser::from_reader(reader, consumer).parse()
+}
+
+/// Parses the given `binary` and consumes the module using the given
+/// `consumer` trait object.
+///
+/// Unlike [`parse_bytes`](fn.parse_bytes.html), this function is not
+/// generic; the parser is compiled only once and dispatches to the
+/// `consumer` dynamically.
+pub fn parse_dyn(binary: &[u8], consumer: &mut Consumer) -> Result<()> {
+    Parser::new(binary, consumer).parse()
+}
+
+/// Parses all the SPIR-V modules concatenated in the given `binary` and
+/// consumes them using the given `consumer`.
+///
+/// Each module starts with its own module header; the end of a module is
+/// detected by finding the magic number of the next header where an
+/// instruction is expected. For each module, the consumer is called in the
+/// same way as parsing a single module, from `initialize` to `finalize`.
+/// Byte offsets in errors are counted from the start of the `binary`.
+///
+/// Returns the number of modules parsed. An incomplete module header is
+/// reported as `State::ModuleHeaderIncomplete` with the number of the
+/// module it belongs to.
+pub fn parse_multi<T, C>(binary: T, consumer: &mut C) -> Result<usize>
+    where T: AsRef<[u8]>,
+          C: Consumer + ?Sized
+{
+    let mut parser = Parser::new(binary.as_ref(), consumer);
+    parser.inst_parser.multi_module = true;
+    let mut count = 0;
+    loop {
+        count += 1;
+        match parser.parse_module() {
+            Ok(Completion::Complete) => (),
+            Ok(Completion::StoppedEarly) => return Ok(count),
+            Err(State::HeaderIncomplete(err)) => {
+                return Err(State::ModuleHeaderIncomplete(count, err))
+            }
+            Err(error) => return Err(error),
+        }
+        if parser.inst_parser.next_magic.is_none() {
+            return Ok(count);
+        }
+        parser.inst_parser.reset_module();
+    }
+}
+
+/// Parses only the module header at the beginning of the given `binary`.
+///
+/// Returns the module header together with the byte order detected from the
+/// magic number. The version, generator, bound, and reserved word are
+/// returned as they are in the binary. Only the first five words are
+/// looked at; nothing is allocated.
+///
+/// This is useful for cheaply checking whether some bytes are a SPIR-V
+/// binary and which version and generator it has before parsing the whole.
+///
+/// # Examples
+///
+/// ```
+/// use rspirv::binary::{Endianness, parse_header};
+///
+/// let bin: Vec<u8> = vec![
+///     // Magic number.           Version number: 1.0.
+///     0x03, 0x02, 0x23, 0x07,    0x00, 0x00, 0x01, 0x00,
+///     // Generator number: 8.    Bound: 42.
+///     0x00, 0x00, 0x08, 0x00,    0x2a, 0x00, 0x00, 0x00,
+///     // Reserved word: 0.
+///     0x00, 0x00, 0x00, 0x00];
+/// let (header, endianness) = parse_header(&bin).unwrap();
+/// assert_eq!(Endianness::Little, endianness);
+/// assert_eq!((1, 0), header.version());
+/// assert_eq!(("Glslang Reference Front End", 0), header.generator());
+/// assert_eq!(42, header.bound);
+/// ```
+pub fn parse_header(binary: &[u8]) -> Result<(mr::ModuleHeader, decoder::Endianness)> {
+    let mut words = [0; HEADER_NUM_WORDS];
+    for (i, word) in words.iter_mut().enumerate() {
+        let offset = i * WORD_NUM_BYTES;
+        if offset + WORD_NUM_BYTES > binary.len() {
+            return Err(State::HeaderIncomplete(DecodeError::StreamExpected(offset)));
+        }
+        *word = bytes_to_u32_le(&binary[offset..offset + WORD_NUM_BYTES]);
+    }
+    let endianness = if words[0] == spirv::MAGIC_NUMBER {
+        decoder::Endianness::Little
+    } else if words[0] == spirv::MAGIC_NUMBER.swap_bytes() {
+        for word in words.iter_mut() {
+            *word = word.swap_bytes();
+        }
+        decoder::Endianness::Big
+    } else {
+        return Err(State::HeaderIncorrect);
+    };
+    let header = mr::ModuleHeader {
+        magic_number: words[0],
+        version: words[1],
+        generator: words[2],
+        bound: words[3],
+        reserved_word: words[4],
+    };
+    Ok((header, endianness))
+}
+
+/// Statistics of a SPIR-V module collected by [`stats`](fn.stats.html).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ModuleStats {
+    /// The id bound in the module header
+    pub bound: spirv::Word,
+    /// The size of the module in words, including the module header
+    pub num_words: usize,
+    /// The number of instructions, including those with unknown opcodes
+    pub num_insts: usize,
+    /// The number of instructions of each opcode
+    pub op_counts: HashMap<spirv::Op, usize>,
+    /// The number of instructions with opcodes unknown to the grammar
+    pub unknown: usize,
+    /// The number of functions
+    pub num_functions: usize,
+}
+
+/// Collects statistics of the SPIR-V module in the given `binary`.
+///
+/// Only the first word of each instruction is decoded; operands are
+/// skipped entirely. So this is a fast way to sanity check the instruction
+/// stream, and instructions with unknown opcodes are counted instead of
+/// being errors.
+///
+/// # Examples
+///
+/// ```
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::binary::stats;
+///
+/// fn main() {
+///     let bin: Vec<u8> = vec![
+///         // Magic number.           Version number: 1.0.
+///         0x03, 0x02, 0x23, 0x07,    0x00, 0x00, 0x01, 0x00,
+///         // Generator number: 0.    Bound: 0.
+///         0x00, 0x00, 0x00, 0x00,    0x00, 0x00, 0x00, 0x00,
+///         // Reserved word: 0.
+///         0x00, 0x00, 0x00, 0x00,
+///         // OpMemoryModel.          Logical.
+///         0x0e, 0x00, 0x03, 0x00,    0x00, 0x00, 0x00, 0x00,
+///         // GLSL450.
+///         0x01, 0x00, 0x00, 0x00];
+///     let s = stats(&bin).unwrap();
+///     assert_eq!(8, s.num_words);
+///     assert_eq!(1, s.num_insts);
+///     assert_eq!(Some(&1), s.op_counts.get(&spirv::Op::MemoryModel));
+/// }
+/// ```
+pub fn stats(binary: &[u8]) -> Result<ModuleStats> {
+    let mut inst_parser = InstParser::new(decoder::Decoder::new(binary));
+    let header = inst_parser.parse_header()?;
+    let mut stats = ModuleStats {
+        bound: header.bound,
+        num_words: HEADER_NUM_WORDS,
+        ..Default::default()
+    };
+    loop {
+        let (opcode, num_words) = match inst_parser.skip_next_inst() {
+            Ok(inst) => inst,
+            Err(State::Complete) => return Ok(stats),
+            Err(err) => return Err(err),
+        };
+        stats.num_insts += 1;
+        stats.num_words += num_words;
+        match GInstTable::lookup_opcode(opcode) {
+            Some(grammar) => {
+                if grammar.opcode == spirv::Op::Function {
+                    stats.num_functions += 1;
+                }
+                *stats.op_counts.entry(grammar.opcode).or_insert(0) += 1;
+            }
+            None => stats.unknown += 1,
+        }
+    }
 }
 
 /// The SPIR-V binary parser.
@@ -193,10 +906,22 @@ pub fn parse_words<T: AsRef<[u32]>>(binary: T, consumer: &mut Consumer) -> Resul
 /// consume methods on the consumer for the module header and each
 /// instruction parsed.
 ///
+/// Both little-endian and big-endian binaries are supported; the byte order
+/// is detected from the magic number in the module header.
+///
+/// The parser is generic over the consumer type so that calls into the
+/// consumer can be inlined; trait objects (`Parser<Consumer>`) are also
+/// accepted.
+///
 /// Different from the [`Decoder`](struct.Decoder.html),
 /// this parser is high-level; it has knowlege of the SPIR-V grammar.
 /// It will parse instructions according to SPIR-V grammar.
 ///
+/// Instead of letting [`parse`](#method.parse) drive the consumer, the
+/// caller can also step through the binary with
+/// [`parse_header`](#method.parse_header) and
+/// [`next_instruction`](#method.next_instruction).
+///
 /// # Examples
 ///
 /// ```
@@ -226,7 +951,7 @@ pub fn parse_words<T: AsRef<[u32]>>(binary: T, consumer: &mut Consumer) -> Resul
 ///     }
 ///     let module = loader.module();
 ///
-///     assert_eq!((1, 1), module.header.unwrap().version());
+///     assert_eq!((1, 0), module.header.unwrap().version());
 ///     let m = module.memory_model.as_ref().unwrap();
 ///     assert_eq!(Operand::AddressingModel(AddressingModel::Logical),
 ///                m.operands[0]);
@@ -234,14 +959,53 @@ pub fn parse_words<T: AsRef<[u32]>>(binary: T, consumer: &mut Consumer) -> Resul
 ///                m.operands[1]);
 /// }
 /// ```
-pub struct Parser<'c, 'd> {
-    decoder: decoder::Decoder<'d>,
-    consumer: &'c mut Consumer,
-    type_tracker: TypeTracker,
-    /// The index of the current instructions
-    ///
-    /// Starting from 1, 0 means invalid
-    inst_index: usize,
+///
+/// Stepping through the instructions manually:
+///
+/// ```
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::binary::{Parser, on_inst, ParseAction};
+///
+/// fn main() {
+///     let words: Vec<u32> = vec![0x07230203, 0x00010000, 0, 0, 0,
+///                                0x00020011, 0x00000001,  // OpCapability Shader
+///                                0x0003000e, 0, 1];  // OpMemoryModel Logical GLSL450
+///     // The consumer is not used when stepping.
+///     let mut consumer = on_inst(|_| ParseAction::Continue);
+///     let mut p = Parser::from_words(&words, &mut consumer);
+///
+///     let header = p.parse_header().unwrap();
+///     assert_eq!(0, header.bound);
+///     let mut opcodes = vec![];
+///     while let Some(inst) = p.next_instruction().unwrap() {
+///         opcodes.push(inst.class);
+///         assert!(p.last_span().is_some());
+///     }
+///     assert_eq!(vec![spirv::Op::Capability, spirv::Op::MemoryModel], opcodes);
+/// }
+/// ```
+pub struct Parser<'c, 'd, C: 'c + ?Sized> {
+    inst_parser: InstParser<'d>,
+    consumer: &'c mut C,
+    /// Whether the module header has been parsed
+    header_parsed: bool,
+    /// Recoverable errors collected so far, if collecting them
+    errors: Option<Vec<State>>,
+    /// Whether to lend instructions to the consumer
+    lend_instructions: bool,
+}
+
+/// Returns the copy of the given string `s` in `strings`, adding it there if
+/// absent, so that equal strings share their memory.
+fn intern(strings: &mut HashSet<Arc<str>>, s: String) -> Arc<str> {
+    if let Some(shared) = strings.get(s.as_str()) {
+        return shared.clone();
+    }
+    let shared: Arc<str> = s.into();
+    strings.insert(shared.clone());
+    shared
 }
 
 /// Tries to decode `$e` and returns the error if errored out.
@@ -252,914 +1016,3678 @@ macro_rules! try_decode {
     });
 }
 
-impl<'c, 'd> Parser<'c, 'd> {
+/// Parses the given `words` as a lone operand of the given `kind`, outside
+/// of any instruction, and returns it together with its parameters, if any.
+///
+/// All of the `words` must be consumed. Since there is no instruction to
+/// provide the type, an operand of kind `LiteralContextDependentNumber` is
+/// parsed as `LiteralInt32` if it has one word and as `LiteralInt64` if
+/// it has two. Operands of kind `LiteralSpecConstantOpInteger` are parsed
+/// without the ids following, and `IdResultType` and `IdResult` as `IdRef`.
+///
+/// Words not forming an operand of the given `kind`, or left over after
+/// it, are reported as `State::LoneOperandIncorrect`, without an
+/// instruction number.
+///
+/// # Examples
+///
+/// ```
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::binary::parse_operand;
+/// use rspirv::grammar::OperandKind;
+/// use rspirv::mr::Operand;
+///
+/// fn main() {
+///     assert_eq!(vec![Operand::MemoryAccess(spirv::MEMORY_ACCESS_ALIGNED),
+///                     Operand::LiteralInt32(4)],
+///                parse_operand(OperandKind::MemoryAccess, &[0x2, 4]).unwrap());
+/// }
+/// ```
+pub fn parse_operand(kind: GOpKind, words: &[spirv::Word]) -> Result<Vec<mr::Operand>> {
+    let mut parser = InstParser::new(decoder::Decoder::from_words(words));
+    let operands = match kind {
+        GOpKind::IdResultType | GOpKind::IdResult => {
+            vec![mr::Operand::IdRef(try_decode!(parser.decoder.id()))]
+        }
+        GOpKind::LiteralContextDependentNumber => {
+            if words.len() == 2 {
+                vec![mr::Operand::LiteralInt64(try_decode!(parser.decoder.int64()))]
+            } else {
+                vec![mr::Operand::LiteralInt32(try_decode!(parser.decoder.int32()))]
+            }
+        }
+        GOpKind::LiteralSpecConstantOpInteger => {
+            let number = try_decode!(parser.decoder.int32());
+            match GInstTable::lookup_opcode(number as u16) {
+                Some(g) => vec![mr::Operand::LiteralSpecConstantOpInteger(g.opcode)],
+                None => return Err(State::LoneOperandIncorrect(0)),
+            }
+        }
+        _ => try!(parser.parse_operand(kind)),
+    };
+    if parser.decoder.offset() != words.len() * WORD_NUM_BYTES {
+        return Err(State::LoneOperandIncorrect(parser.decoder.offset()));
+    }
+    Ok(operands)
+}
+
+impl<'c, 'd, C: Consumer + ?Sized> Parser<'c, 'd, C> {
     /// Creates a new parser to parse the given `binary` and send the module
     /// header and instructions to the given `consumer`.
-    pub fn new(binary: &'d [u8], consumer: &'c mut Consumer) -> Parser<'c, 'd> {
+    pub fn new(binary: &'d [u8], consumer: &'c mut C) -> Parser<'c, 'd, C> {
         Parser {
-            decoder: decoder::Decoder::new(binary),
+            inst_parser: InstParser::new(decoder::Decoder::new(binary)),
             consumer: consumer,
-            type_tracker: TypeTracker::new(),
-            inst_index: 0,
+            header_parsed: false,
+            errors: None,
+            lend_instructions: false,
         }
     }
 
-    /// Does the parsing.
-    pub fn parse(mut self) -> Result<()> {
-        match self.consumer.initialize() {
-            Action::Continue => (),
-            Action::Stop => return Err(State::ConsumerStopRequested),
-            Action::Error(err) => return Err(State::ConsumerError(err)),
+    /// Creates a new parser to parse the given `binary` words and send the
+    /// module header and instructions to the given `consumer`.
+    pub fn from_words(binary: &'d [spirv::Word], consumer: &'c mut C) -> Parser<'c, 'd, C> {
+        Parser {
+            inst_parser: InstParser::new(decoder::Decoder::from_words(binary)),
+            consumer: consumer,
+            header_parsed: false,
+            errors: None,
+            lend_instructions: false,
         }
-        let header = self.parse_header()?;
-        match self.consumer.consume_header(header) {
-            Action::Continue => (),
-            Action::Stop => return Err(State::ConsumerStopRequested),
-            Action::Error(err) => return Err(State::ConsumerError(err)),
+    }
+
+    /// Creates a new parser to parse the binary read from the given `reader`
+    /// and send the module header and instructions to the given `consumer`.
+    ///
+    /// Words are read from the `reader` as they are needed. Reaching the
+    /// end of the stream in the middle of the header or an instruction is
+    /// reported the same way as parsing a truncated binary; other I/O
+    /// errors are reported as `State::IoError`.
+    pub fn from_reader<R: io::Read + 'd>(reader: R, consumer: &'c mut C) -> Parser<'c, 'd, C> {
+        Parser {
+            inst_parser: InstParser::new(decoder::Decoder::from_reader(reader)),
+            consumer: consumer,
+            header_parsed: false,
+            errors: None,
+            lend_instructions: false,
         }
+    }
+
+    /// Sets the options to parse with.
+    pub fn options(mut self, options: ParserOptions) -> Parser<'c, 'd, C> {
+        self.inst_parser.set_options(options);
+        self
+    }
+
+    /// Sets whether to lend instructions to the consumer via
+    /// `consume_instruction_ref` instead of giving them away.
+    ///
+    /// The parser then reuses the memory of each instruction for parsing
+    /// the next, which saves allocations for consumers only inspecting
+    /// instructions. Consumers not overriding `consume_instruction_ref`
+    /// get clones as usual.
+    pub fn lend_instructions(mut self, lend: bool) -> Parser<'c, 'd, C> {
+        self.lend_instructions = lend;
+        self
+    }
+
+    /// Does the parsing.
+    ///
+    /// A stop requested by the consumer via `Action::Stop` is treated as
+    /// success. Use [`parse_to_completion`](#method.parse_to_completion)
+    /// to tell it apart from parsing the whole binary.
+    pub fn parse(self) -> Result<()> {
+        self.parse_to_completion().map(|_| ())
+    }
+
+    /// Does the parsing and returns whether the whole binary was parsed or
+    /// the consumer requested to stop early.
+    pub fn parse_to_completion(mut self) -> Result<Completion> {
+        self.parse_module()
+    }
+
+    /// Does the parsing, skipping instructions with errors local to them
+    /// instead of erroring out, and returns all such errors in the order
+    /// they are found.
+    ///
+    /// Recoverable errors are `State::OpcodeUnknown`,
+    /// `State::OperandExpected`, `State::OperandExceeded`,
+    /// `State::OperandError`, `State::TypeUnsupported`,
+    /// `State::SpecConstantOpIntegerIncorrect` and `State::IdOutOfBounds`.
+    /// The word count of the erroneous instruction is used to resume
+    /// parsing at the next instruction. All other errors still abort the
+    /// parsing. The consumer only receives instructions parsed without
+    /// errors.
+    pub fn parse_collecting_errors(mut self) -> Result<Vec<State>> {
+        self.errors = Some(vec![]);
+        self.parse_module()?;
+        Ok(self.errors.take().unwrap_or_else(Vec::new))
+    }
 
+    /// Parses the module header and returns it.
+    ///
+    /// This should be called once before stepping through instructions
+    /// with [`next_instruction`](#method.next_instruction). The consumer is
+    /// not called.
+    pub fn parse_header(&mut self) -> Result<mr::ModuleHeader> {
+        let header = self.inst_parser.parse_header()?;
+        self.header_parsed = true;
+        Ok(header)
+    }
+
+    /// Parses the next instruction and returns it, or `None` if the end of
+    /// the binary is reached.
+    ///
+    /// The module header is parsed and discarded first if
+    /// [`parse_header`](#method.parse_header) has not been called.
+    /// Instructions skipped via `ignore_unknown_opcodes` are not returned.
+    /// The consumer is not called.
+    pub fn next_instruction(&mut self) -> Result<Option<mr::Instruction>> {
         loop {
-            let result = self.parse_inst();
-            match result {
-                Ok(inst) => {
-                    self.type_tracker.track(&inst);
-                    match self.consumer.consume_instruction(inst) {
-                        Action::Continue => (),
-                        Action::Stop => return Err(State::ConsumerStopRequested),
-                        Action::Error(err) => return Err(State::ConsumerError(err)),
-                    }
-                }
-                Err(State::Complete) => break,
-                Err(error) => return Err(error),
-            };
+            match self.next_parsed_inst()? {
+                Some(ParsedInst::Known(inst)) => return Ok(Some(inst)),
+                Some(ParsedInst::Unknown(..)) => continue,
+                None => return Ok(None),
+            }
         }
-        match self.consumer.finalize() {
-            Action::Continue => (),
-            Action::Stop => return Err(State::ConsumerStopRequested),
-            Action::Error(err) => return Err(State::ConsumerError(err)),
+    }
+
+    /// Returns the span of the instruction last parsed as (start byte
+    /// offset, length in bytes).
+    ///
+    /// This will return `None` before parsing the first instruction.
+    pub fn last_span(&self) -> Option<(usize, usize)> {
+        if self.inst_parser.inst_index == 0 {
+            None
+        } else {
+            Some(self.inst_parser.inst_span())
         }
-        Ok(())
     }
 
-    fn split_into_word_count_and_opcode(word: spirv::Word) -> (u16, u16) {
-        ((word >> 16) as u16, (word & 0xffff) as u16)
+    /// Returns the source location declared by `OpLine` in effect for the
+    /// instruction last parsed, if any.
+    pub fn last_location(&self) -> Option<SourceLocation> {
+        self.inst_parser.inst_location
     }
 
-    fn parse_header(&mut self) -> Result<mr::ModuleHeader> {
-        match self.decoder.words(HEADER_NUM_WORDS) {
-            Ok(words) => {
-                if words[0] != spirv::MAGIC_NUMBER {
-                    if words[0] == spirv::MAGIC_NUMBER.swap_bytes() {
-                        return Err(State::EndiannessUnsupported);
-                    } else {
-                        return Err(State::HeaderIncorrect);
-                    }
-                }
-                Ok(mr::ModuleHeader::new(words[3]))
-            }
-            Err(err) => Err(State::HeaderIncomplete(err)),
+    fn next_parsed_inst(&mut self) -> Result<Option<ParsedInst>> {
+        if !self.header_parsed {
+            self.parse_header()?;
+        }
+        match self.inst_parser.parse_inst() {
+            Ok(inst) => Ok(Some(inst)),
+            Err(State::Complete) => Ok(None),
+            Err(error) => Err(error),
         }
     }
 
-    fn parse_inst(&mut self) -> Result<mr::Instruction> {
-        self.inst_index += 1;
-        if let Ok(word) = self.decoder.word() {
-            let (wc, opcode) = Parser::split_into_word_count_and_opcode(word);
-            if wc == 0 {
-                return Err(State::WordCountZero(self.decoder.offset() - WORD_NUM_BYTES,
-                                                self.inst_index));
+    fn parse_module(&mut self) -> Result<Completion> {
+        if let Some(completion) = self.start_module()? {
+            return Ok(completion);
+        }
+        loop {
+            if let Some(completion) = self.consume_next_inst()? {
+                return Ok(completion);
             }
-            if let Some(grammar) = GInstTable::lookup_opcode(opcode) {
-                self.decoder.set_limit((wc - 1) as usize);
-                let result = self.parse_operands(grammar);
-                if !self.decoder.limit_reached() {
-                    return Err(State::OperandExceeded(self.decoder.offset(), self.inst_index));
-                }
-                self.decoder.clear_limit();
-                result
-            } else {
-                Err(State::OpcodeUnknown(self.decoder.offset() - WORD_NUM_BYTES,
-                                         self.inst_index,
-                                         opcode))
+        }
+    }
+
+    /// Initializes the consumer and parses the module header.
+    ///
+    /// Returns `Some` if the consumer stops the parsing.
+    fn start_module(&mut self) -> Result<Option<Completion>> {
+        match self.consumer.initialize() {
+            Action::Continue => (),
+            Action::Stop => return Ok(Some(Completion::StoppedEarly)),
+            Action::Error(err) => return Err(State::ConsumerError(err)),
+        }
+        let header = self.parse_header()?;
+        if let Some((major, minor)) = self.inst_parser.unsupported_version {
+            if let Some(completion) = self.warn(Warning::VersionUnsupported(major, minor))? {
+                return Ok(Some(completion));
             }
-        } else {
-            Err(State::Complete)
+        }
+        match self.consumer.consume_header(header) {
+            Action::Continue => Ok(None),
+            Action::Stop => Ok(Some(Completion::StoppedEarly)),
+            Action::Error(err) => Err(State::ConsumerError(err)),
         }
     }
 
-    fn parse_literal(&mut self, type_id: spirv::Word) -> Result<mr::Operand> {
-        let tracked_type = self.type_tracker.resolve(type_id);
-        match tracked_type {
-            Some(t) => {
-                match t {
-                    Type::Integer(size, _) => {
-                        match size {
-                            32 => Ok(mr::Operand::LiteralInt32(try_decode!(self.decoder.int32()))),
-                            64 => Ok(mr::Operand::LiteralInt64(try_decode!(self.decoder.int64()))),
-                            _ => {
-                                Err(State::TypeUnsupported(self.decoder.offset(), self.inst_index))
-                            }
-                        }
-                    }
-                    Type::Float(size) => {
-                        match size {
-                            32 => {
-                                Ok(mr::Operand::LiteralFloat32(try_decode!(self.decoder.float32())))
-                            }
-                            64 => {
-                                Ok(mr::Operand::LiteralFloat64(try_decode!(self.decoder.float64())))
-                            }
-                            _ => {
-                                Err(State::TypeUnsupported(self.decoder.offset(), self.inst_index))
-                            }
-                        }
+    /// Parses the next instruction and sends it to the consumer.
+    ///
+    /// Finalizes the consumer if there are no more instructions. Returns
+    /// `Some` if the parsing is over.
+    fn consume_next_inst(&mut self) -> Result<Option<Completion>> {
+        let result = self.next_parsed_inst();
+        let action = match result {
+            Ok(Some(ParsedInst::Known(inst))) => {
+                for warning in self.inst_parser.take_inst_warnings() {
+                    if let Some(completion) = self.warn(warning)? {
+                        return Ok(Some(completion));
                     }
                 }
+                let context = self.inst_parser.inst_context();
+                if self.lend_instructions {
+                    let action = self.consumer.consume_instruction_ref(context, &inst);
+                    self.inst_parser.operand_buffer = inst.operands;
+                    action
+                } else {
+                    self.consumer.consume_instruction_span(context, inst)
+                }
             }
-            // Treat as a normal SPIR-V word if we don't know the type.
-            // TODO: find a better way to handle this.
-            None => Ok(mr::Operand::LiteralInt32(try_decode!(self.decoder.int32()))),
+            Ok(Some(ParsedInst::Unknown(opcode, words))) => {
+                self.consumer.consume_unknown_instruction(opcode, &words)
+            }
+            Ok(None) => return self.finish_module().map(Some),
+            Err(error) => {
+                if self.errors.is_none() || !error.is_recoverable() {
+                    return Err(error);
+                }
+                self.inst_parser.skip_rest_of_inst()?;
+                if let Some(ref mut errors) = self.errors {
+                    errors.push(error);
+                }
+                return Ok(None);
+            }
+        };
+        match action {
+            Action::Continue => Ok(None),
+            Action::Stop => Ok(Some(Completion::StoppedEarly)),
+            Action::Error(err) => Err(State::ConsumerError(err)),
         }
     }
 
-    fn parse_spec_constant_op(&mut self) -> Result<Vec<mr::Operand>> {
-        let mut operands = vec![];
+    /// Sends the given `warning` to the consumer.
+    ///
+    /// Returns `Some` if the consumer stops the parsing.
+    fn warn(&mut self, warning: Warning) -> Result<Option<Completion>> {
+        match self.consumer.warn(warning) {
+            Action::Continue => Ok(None),
+            Action::Stop => Ok(Some(Completion::StoppedEarly)),
+            Action::Error(err) => Err(State::ConsumerError(err)),
+        }
+    }
 
-        let number = try_decode!(self.decoder.int32());
-        if let Some(g) = GInstTable::lookup_opcode(number as u16) {
-            // TODO: check whether this opcode is allowed here.
-            operands.push(mr::Operand::LiteralSpecConstantOpInteger(g.opcode));
-            // We need id parameters to this SpecConstantOp.
-            for operand in g.operands {
-                if operand.kind == GOpKind::IdRef {
-                    operands.push(mr::Operand::IdRef(try_decode!(self.decoder.id())))
-                }
+    /// Reports the trailing bytes if any and finalizes the consumer.
+    fn finish_module(&mut self) -> Result<Completion> {
+        if let Some((offset, count)) = self.inst_parser.trailing_bytes {
+            if let Some(completion) = self.warn(Warning::TrailingBytesIgnored(offset, count))? {
+                return Ok(completion);
             }
-            Ok(operands)
-        } else {
-            Err(State::SpecConstantOpIntegerIncorrect(self.decoder.offset(), self.inst_index))
+        }
+        match self.consumer.finalize() {
+            Action::Continue | Action::Stop => Ok(Completion::Complete),
+            Action::Error(err) => Err(State::ConsumerError(err)),
         }
     }
+}
 
-    fn parse_operands(&mut self, grammar: GInstRef) -> Result<mr::Instruction> {
-        let mut rtype = None;
-        let mut rid = None;
-        let mut coperands = vec![]; // concrete operands
+/// A reader over the bytes fed to an `IncrementalParser` but not parsed
+/// yet.
+struct FeedBuffer(Rc<RefCell<VecDeque<u8>>>);
 
-        let mut loperand_index: usize = 0; // logical operand index
-        while loperand_index < grammar.operands.len() {
-            let loperand = &grammar.operands[loperand_index];
-            let has_more_coperands = !self.decoder.limit_reached();
-            if has_more_coperands {
-                match loperand.kind {
-                    GOpKind::IdResultType => rtype = Some(try_decode!(self.decoder.id())),
-                    GOpKind::IdResult => rid = Some(try_decode!(self.decoder.id())),
-                    GOpKind::LiteralContextDependentNumber => {
-                        // Only constant defining instructions use this kind.
-                        // If it is not true, that means the grammar is wrong
-                        // or has changed.
-                        assert!(grammar.opcode == spirv::Op::Constant ||
-                                grammar.opcode == spirv::Op::SpecConstant);
-                        let id = rtype.expect("internal error: \
-                            should already decoded result type id before context dependent number");
-                        coperands.push(self.parse_literal(id)?)
-                    }
-                    GOpKind::LiteralSpecConstantOpInteger => {
-                        coperands.append(&mut self.parse_spec_constant_op()?)
-                    }
-                    _ => coperands.append(&mut self.parse_operand(loperand.kind)?),
-                }
-                match loperand.quantifier {
-                    GOpCount::One | GOpCount::ZeroOrOne => loperand_index += 1,
-                    GOpCount::ZeroOrMore => continue,
+impl io::Read for FeedBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut bytes = self.0.borrow_mut();
+        let count = cmp::min(buf.len(), bytes.len());
+        for (dst, src) in buf.iter_mut().zip(bytes.drain(..count)) {
+            *dst = src;
+        }
+        Ok(count)
+    }
+}
+
+/// A push-style parser for SPIR-V binaries arriving in chunks.
+///
+/// Bytes are given to the parser via [`feed`](#method.feed) as they
+/// arrive, in chunks of arbitrary sizes. The consumer is invoked for each
+/// instruction as soon as all its words are available; only the bytes of
+/// the current incomplete instruction are kept in between.
+/// [`finish`](#method.finish) must be called after the last chunk to
+/// finalize the consumer.
+///
+/// After an error or a stop requested by the consumer, further input is
+/// ignored.
+///
+/// # Examples
+///
+/// ```
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::binary::IncrementalParser;
+/// use rspirv::mr::Loader;
+///
+/// fn main() {
+///     let bin: Vec<u8> = vec![
+///         // Magic number.           Version number: 1.0.
+///         0x03, 0x02, 0x23, 0x07,    0x00, 0x00, 0x01, 0x00,
+///         // Generator number: 0.    Bound: 0.
+///         0x00, 0x00, 0x00, 0x00,    0x00, 0x00, 0x00, 0x00,
+///         // Reserved word: 0.
+///         0x00, 0x00, 0x00, 0x00,
+///         // OpMemoryModel.          Logical.
+///         0x0e, 0x00, 0x03, 0x00,    0x00, 0x00, 0x00, 0x00,
+///         // GLSL450.
+///         0x01, 0x00, 0x00, 0x00];
+///     let mut loader = Loader::new();
+///     {
+///         let mut p = IncrementalParser::new(&mut loader);
+///         for chunk in bin.chunks(7) {
+///             p.feed(chunk).unwrap();
+///         }
+///         p.finish().unwrap();
+///     }
+///     assert!(loader.module().memory_model.is_some());
+/// }
+/// ```
+pub struct IncrementalParser<'c, C: 'c + ?Sized> {
+    parser: Parser<'c, 'static, C>,
+    /// Bytes fed but not parsed yet
+    buffer: Rc<RefCell<VecDeque<u8>>>,
+    /// Whether the parsing is over because of an error or the consumer
+    done: bool,
+}
+
+impl<'c, C: Consumer + ?Sized> IncrementalParser<'c, C> {
+    /// Creates a new incremental parser sending the module to the given
+    /// `consumer`.
+    pub fn new(consumer: &'c mut C) -> IncrementalParser<'c, C> {
+        let buffer = Rc::new(RefCell::new(VecDeque::new()));
+        IncrementalParser {
+            parser: Parser::from_reader(FeedBuffer(buffer.clone()), consumer),
+            buffer: buffer,
+            done: false,
+        }
+    }
+
+    /// Sets the options to parse with.
+    pub fn options(mut self, options: ParserOptions) -> IncrementalParser<'c, C> {
+        self.parser.inst_parser.set_options(options);
+        self
+    }
+
+    /// Feeds the next chunk of the binary to the parser.
+    ///
+    /// The module header and all the instructions completed by this chunk
+    /// are parsed and sent to the consumer.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.done {
+            return Ok(());
+        }
+        self.buffer.borrow_mut().extend(bytes.iter().cloned());
+        match self.consume_available() {
+            Ok(None) => Ok(()),
+            Ok(Some(_)) => {
+                self.done = true;
+                Ok(())
+            }
+            Err(err) => {
+                self.done = true;
+                Err(err)
+            }
+        }
+    }
+
+    /// Finishes the parsing and finalizes the consumer.
+    ///
+    /// Returns `State::InputIncomplete` if the input finished in the middle
+    /// of the module header or an instruction, unless only bytes not
+    /// forming a whole word are left and the `allow_trailing_bytes` option
+    /// is set. Does nothing if the parsing is already over.
+    pub fn finish(mut self) -> Result<()> {
+        if self.done {
+            return Ok(());
+        }
+        let missing = self.missing_bytes();
+        if missing > 0 {
+            let offset = self.parser.inst_parser.decoder.offset();
+            let left = self.buffer.borrow().len();
+            if self.parser.header_parsed && left < WORD_NUM_BYTES {
+                if !self.parser.inst_parser.options.allow_trailing_bytes {
+                    return Err(State::TrailingBytes(offset, left));
                 }
+                self.parser.inst_parser.trailing_bytes = Some((offset, left));
             } else {
-                // We still have logical operands to match but no no more words.
-                match loperand.quantifier {
-                    GOpCount::One => {
-                        return Err(State::OperandExpected(self.decoder.offset(), self.inst_index))
-                    }
-                    GOpCount::ZeroOrOne | GOpCount::ZeroOrMore => break,
-                }
+                return Err(State::InputIncomplete(offset,
+                                                  (missing + WORD_NUM_BYTES - 1) /
+                                                  WORD_NUM_BYTES));
+            }
+        }
+        self.parser.finish_module().map(|_| ())
+    }
+
+    /// Parses and consumes as much as the buffered bytes allow.
+    ///
+    /// Returns `Some` if the parsing is over.
+    fn consume_available(&mut self) -> Result<Option<Completion>> {
+        loop {
+            if self.buffer.borrow().is_empty() || self.missing_bytes() > 0 {
+                return Ok(None);
+            }
+            let completion = if self.parser.header_parsed {
+                self.parser.consume_next_inst()?
+            } else {
+                self.parser.start_module()?
+            };
+            if completion.is_some() {
+                return Ok(completion);
             }
         }
-        Ok(mr::Instruction::new(grammar.opcode, rtype, rid, coperands))
+    }
+
+    /// Returns the number of bytes still missing to complete the module
+    /// header or the next instruction.
+    ///
+    /// Returns zero if they are all buffered, or if nothing is expected
+    /// after the last instruction.
+    fn missing_bytes(&self) -> usize {
+        let buffer = self.buffer.borrow();
+        let needed = if !self.parser.header_parsed {
+            HEADER_NUM_WORDS * WORD_NUM_BYTES
+        } else if buffer.is_empty() {
+            0
+        } else if buffer.len() < WORD_NUM_BYTES {
+            WORD_NUM_BYTES
+        } else {
+            let bytes: Vec<u32> = buffer.iter().take(WORD_NUM_BYTES).map(|b| *b as u32).collect();
+            let word = match self.parser.inst_parser.decoder.endianness() {
+                decoder::Endianness::Little => {
+                    bytes[0] | (bytes[1] << 8) | (bytes[2] << 16) | (bytes[3] << 24)
+                }
+                decoder::Endianness::Big => {
+                    (bytes[0] << 24) | (bytes[1] << 16) | (bytes[2] << 8) | bytes[3]
+                }
+            };
+            // Zero word counts are reported when parsing the first word.
+            cmp::max(word >> 16, 1) as usize * WORD_NUM_BYTES
+        };
+        needed.saturating_sub(buffer.len())
     }
 }
 
-include!("parse_operand.rs");
+/// An iterator over the instructions in a SPIR-V binary.
+///
+/// This is an alternative to the [`Parser`](struct.Parser.html) for cases
+/// where implementing a [`Consumer`](trait.Consumer.html) is overkill.
+/// It parses lazily: each call to `next()` parses exactly one instruction.
+/// The module header is parsed when pulling the first item and is
+/// available via [`header()`](struct.ParseIterator.html#method.header)
+/// afterwards.
+///
+/// Errors are yielded once; the iteration terminates after that.
+///
+/// # Examples
+///
+/// ```
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::binary::ParseIterator;
+///
+/// fn main() {
+///     let words: Vec<u32> = vec![0x07230203, 0x00010000, 0, 0, 0,
+///                                0x00020011, 0x00000001,  // OpCapability Shader
+///                                0x0003000e, 0, 1];  // OpMemoryModel Logical GLSL450
+///     let mut iter = ParseIterator::from_words(&words);
+///     assert!(iter.header().is_none());
+///
+///     let opcodes: Vec<spirv::Op> = iter.by_ref()
+///                                       .map(|inst| inst.unwrap().class)
+///                                       .collect();
+///     assert_eq!(vec![spirv::Op::Capability, spirv::Op::MemoryModel], opcodes);
+///     assert_eq!(0, iter.header().unwrap().bound);
+/// }
+/// ```
+pub struct ParseIterator<'d> {
+    inst_parser: InstParser<'d>,
+    header: Option<mr::ModuleHeader>,
+    finished: bool,
+    /// Warnings not taken yet
+    warnings: Vec<Warning>,
+}
 
-#[cfg(test)]
-mod tests {
-    use mr;
-    use spirv;
+impl<'d> ParseIterator<'d> {
+    /// Creates a new iterator over the instructions in the given `binary`.
+    pub fn new(binary: &'d [u8]) -> ParseIterator<'d> {
+        ParseIterator::with_decoder(decoder::Decoder::new(binary))
+    }
 
-    use binary::error::Error;
-    use std::{error, fmt};
-    use super::{Action, Consumer, parse_words, Parser, State, WORD_NUM_BYTES};
+    /// Creates a new iterator over the instructions in the given `binary`
+    /// words.
+    pub fn from_words(binary: &'d [spirv::Word]) -> ParseIterator<'d> {
+        ParseIterator::with_decoder(decoder::Decoder::from_words(binary))
+    }
 
-    use utils::num::f32_to_bytes;
-    use utils::num::f64_to_bytes;
+    /// Creates a new iterator over the instructions read from the given
+    /// `reader`.
+    pub fn from_reader<R: io::Read + 'd>(reader: R) -> ParseIterator<'d> {
+        ParseIterator::with_decoder(decoder::Decoder::from_reader(reader))
+    }
 
-    // TODO: It's unfortunate that we have these numbers directly coded here
-    // and repeat them in the following tests. Should have a better way.
-    #[cfg_attr(rustfmt, rustfmt_skip)]
-    static ZERO_BOUND_HEADER: &'static [u8] = &[
-        // Magic number.           Version number: 1.0.
-        0x03, 0x02, 0x23, 0x07,    0x00, 0x00, 0x01, 0x00,
-        // Generator number: 0.    Bound: 0.
+    fn with_decoder(decoder: decoder::Decoder<'d>) -> ParseIterator<'d> {
+        ParseIterator {
+            inst_parser: InstParser::new(decoder),
+            header: None,
+            finished: false,
+            warnings: vec![],
+        }
+    }
+
+    /// Sets the options to parse with.
+    ///
+    /// Instructions skipped via `ignore_unknown_opcodes` are not yielded.
+    pub fn options(mut self, options: ParserOptions) -> ParseIterator<'d> {
+        self.inst_parser.set_options(options);
+        self
+    }
+
+    /// Takes the warnings about the conditions tolerated so far because of
+    /// the options set, in the order they are found.
+    ///
+    /// Warnings about the operands of an instruction are available once
+    /// the instruction is yielded.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        mem::replace(&mut self.warnings, vec![])
+    }
+
+    /// Returns the span of the instruction last yielded by this iterator as
+    /// (start byte offset, length in bytes).
+    ///
+    /// This will return `None` before yielding the first instruction.
+    pub fn last_span(&self) -> Option<(usize, usize)> {
+        if self.inst_parser.inst_index == 0 {
+            None
+        } else {
+            Some(self.inst_parser.inst_span())
+        }
+    }
+
+    /// Returns the source location declared by `OpLine` in effect for the
+    /// instruction last yielded by this iterator, if any.
+    pub fn last_location(&self) -> Option<SourceLocation> {
+        self.inst_parser.inst_location
+    }
+
+    /// Returns the module header.
+    ///
+    /// This will return `None` before pulling the first item from this
+    /// iterator or if the module header fails parsing.
+    pub fn header(&self) -> Option<&mr::ModuleHeader> {
+        self.header.as_ref()
+    }
+}
+
+impl<'d> Iterator for ParseIterator<'d> {
+    type Item = Result<mr::Instruction>;
+
+    fn next(&mut self) -> Option<Result<mr::Instruction>> {
+        if self.finished {
+            return None;
+        }
+        if self.header.is_none() {
+            match self.inst_parser.parse_header() {
+                Ok(header) => {
+                    if let Some((major, minor)) = self.inst_parser.unsupported_version {
+                        self.warnings.push(Warning::VersionUnsupported(major, minor));
+                    }
+                    self.header = Some(header)
+                }
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+        loop {
+            match self.inst_parser.parse_inst() {
+                Ok(ParsedInst::Known(inst)) => {
+                    self.warnings.extend(self.inst_parser.take_inst_warnings());
+                    return Some(Ok(inst));
+                }
+                Ok(ParsedInst::Unknown(..)) => continue,
+                Err(State::Complete) => {
+                    if let Some((offset, count)) = self.inst_parser.trailing_bytes {
+                        self.warnings.push(Warning::TrailingBytesIgnored(offset, count));
+                    }
+                    self.finished = true;
+                    return None;
+                }
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+/// An instruction parsed by the `InstParser`.
+enum ParsedInst {
+    /// An instruction known to the grammar
+    Known(mr::Instruction),
+    /// An instruction with unknown opcode, as (opcode, all words)
+    Unknown(u16, Vec<spirv::Word>),
+}
+
+/// The instruction parser shared by `Parser` and `ParseIterator`.
+///
+/// It parses the module header and instructions one by one out of the
+/// decoder, according to the SPIR-V grammar.
+struct InstParser<'d> {
+    decoder: decoder::Decoder<'d>,
+    type_tracker: TypeTracker,
+    ext_inst_set_tracker: ExtInstSetTracker,
+    line_tracker: LineTracker,
+    /// The source location in effect for the current instruction
+    inst_location: Option<SourceLocation>,
+    /// The operand vector of the last instruction lent to the consumer,
+    /// kept for reuse
+    operand_buffer: Vec<mr::Operand>,
+    /// The index of the current instructions
+    ///
+    /// Starting from 1, 0 means invalid
+    inst_index: usize,
+    /// The byte offset of the first word of the current instruction
+    inst_offset: usize,
+    /// The word count of the current instruction
+    inst_num_words: usize,
+    /// The options to parse with
+    options: ParserOptions,
+    /// The (byte offset, kind, value) of unknown enumerants kept for the
+    /// current instruction
+    preserved_enumerants: Vec<(usize, &'static str, spirv::Word)>,
+    /// The (major, minor) version of the module if unsupported
+    unsupported_version: Option<(u8, u8)>,
+    /// The id bound in the module header
+    bound: spirv::Word,
+    /// The (byte offset, number) of trailing bytes if ignored
+    trailing_bytes: Option<(usize, usize)>,
+    /// Whether to treat a magic number as the start of the next module
+    multi_module: bool,
+    /// The magic number of the next module if already decoded
+    next_magic: Option<spirv::Word>,
+    /// The number of functions seen in the current module
+    num_functions: usize,
+    /// The strings decoded in the current module, shared among all
+    /// operands with the same value
+    strings: HashSet<Arc<str>>,
+}
+
+impl<'d> InstParser<'d> {
+    fn new(decoder: decoder::Decoder<'d>) -> InstParser<'d> {
+        InstParser {
+            decoder: decoder,
+            type_tracker: TypeTracker::new(),
+            ext_inst_set_tracker: ExtInstSetTracker::new(),
+            line_tracker: LineTracker::new(),
+            inst_location: None,
+            operand_buffer: vec![],
+            inst_index: 0,
+            inst_offset: 0,
+            inst_num_words: 0,
+            options: ParserOptions::default(),
+            preserved_enumerants: vec![],
+            unsupported_version: None,
+            bound: 0,
+            trailing_bytes: None,
+            multi_module: false,
+            next_magic: None,
+            num_functions: 0,
+            strings: HashSet::new(),
+        }
+    }
+
+    fn set_options(&mut self, options: ParserOptions) {
+        self.decoder.set_lenient_flag_bits(options.allow_unknown_flag_bits);
+        self.options = options;
+    }
+
+    /// Takes the warnings about the operands of the current instruction.
+    fn take_inst_warnings(&mut self) -> Vec<Warning> {
+        let preserved = mem::replace(&mut self.preserved_enumerants, vec![]);
+        let dropped = self.decoder.take_unknown_flag_bits();
+        preserved.into_iter()
+            .map(|(offset, kind, value)| Warning::UnknownEnumerantPreserved(offset, kind, value))
+            .chain(dropped.into_iter().map(|(offset, kind, bits)| {
+                Warning::UnknownFlagBitsDropped(offset, kind, bits)
+            }))
+            .collect()
+    }
+
+    /// Resets the states tracked for the current module, so that the next
+    /// module can be parsed.
+    fn reset_module(&mut self) {
+        self.type_tracker = TypeTracker::new();
+        self.ext_inst_set_tracker = ExtInstSetTracker::new();
+        self.line_tracker = LineTracker::new();
+        self.inst_location = None;
+        self.inst_index = 0;
+        self.inst_num_words = 0;
+        self.unsupported_version = None;
+        self.bound = 0;
+        self.trailing_bytes = None;
+        self.num_functions = 0;
+        self.strings.clear();
+    }
+
+    /// Returns the I/O error recorded by the decoder if any; otherwise,
+    /// returns the given `state`.
+    fn io_error_or(&mut self, state: State) -> State {
+        match self.decoder.take_io_error() {
+            Some(err) => State::IoError(err),
+            None => state,
+        }
+    }
+
+    /// Skips the words of the current instruction not decoded yet, so that
+    /// parsing can resume at the next instruction.
+    fn skip_rest_of_inst(&mut self) -> Result<()> {
+        let end = self.inst_offset + self.inst_num_words * WORD_NUM_BYTES;
+        let offset = self.decoder.offset();
+        if offset < end {
+            if let Err(error) = self.rest_inst_words((end - offset) / WORD_NUM_BYTES) {
+                return Err(self.io_error_or(error));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the span of the current instruction as (start byte offset,
+    /// length in bytes).
+    fn inst_span(&self) -> (usize, usize) {
+        (self.inst_offset, self.inst_num_words * WORD_NUM_BYTES)
+    }
+
+    /// Returns the context of the current instruction.
+    fn inst_context(&self) -> InstContext {
+        let (offset, len) = self.inst_span();
+        InstContext {
+            offset: offset,
+            len: len,
+            index: self.inst_index,
+            location: self.inst_location,
+        }
+    }
+
+    fn split_into_word_count_and_opcode(word: spirv::Word) -> (u16, u16) {
+        ((word >> 16) as u16, (word & 0xffff) as u16)
+    }
+
+    /// Parses the module header.
+    fn parse_header(&mut self) -> Result<mr::ModuleHeader> {
+        let magic = match self.next_magic.take() {
+            Some(magic) => Ok(magic),
+            None => self.decoder.word(),
+        };
+        let words = match magic {
+            Ok(magic) => {
+                self.decoder.words(HEADER_NUM_WORDS - 1).map(|mut words| {
+                    words.insert(0, magic);
+                    words
+                })
+            }
+            Err(err) => Err(err),
+        };
+        match words {
+            Ok(mut words) => {
+                if words[0] != spirv::MAGIC_NUMBER {
+                    if words[0] == spirv::MAGIC_NUMBER.swap_bytes() {
+                        // The binary has the opposite byte order; decode
+                        // all following words accordingly.
+                        let endianness = match self.decoder.endianness() {
+                            decoder::Endianness::Little => decoder::Endianness::Big,
+                            decoder::Endianness::Big => decoder::Endianness::Little,
+                        };
+                        self.decoder.set_endianness(endianness);
+                        words = words.iter().map(|w| w.swap_bytes()).collect();
+                    } else {
+                        return Err(State::HeaderIncorrect);
+                    }
+                }
+                self.check_version(words[1])?;
+                if self.options.reject_nonzero_schema && words[4] != 0 {
+                    return Err(State::SchemaNonZero(words[4]));
+                }
+                self.bound = words[3];
+                self.check_header_limits()?;
+                // Keep all the words as they are so that they can be
+                // emitted again.
+                Ok(mr::ModuleHeader {
+                    magic_number: words[0],
+                    version: words[1],
+                    generator: words[2],
+                    bound: words[3],
+                    reserved_word: words[4],
+                })
+            }
+            Err(err) => Err(self.io_error_or(State::HeaderIncomplete(err))),
+        }
+    }
+
+    /// Checks the id bound and, if the size of the binary is known, the
+    /// number of words against the limits.
+    fn check_header_limits(&self) -> Result<()> {
+        let header_offset = self.decoder.offset() - HEADER_NUM_WORDS * WORD_NUM_BYTES;
+        if let Some(max) = self.options.max_id_bound {
+            if self.bound > max {
+                // Point to the bound word.
+                return Err(State::LimitExceeded(header_offset + 3 * WORD_NUM_BYTES,
+                                                Limit::IdBound(max)));
+            }
+        }
+        if let (Some(max), Some(remaining)) = (self.options.max_binary_words, self.decoder.remaining_bytes()) {
+            if (self.decoder.offset() + remaining) / WORD_NUM_BYTES > max {
+                return Err(State::LimitExceeded(header_offset, Limit::BinaryWords(max)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the current instruction with the given word count `wc` and
+    /// `opcode` against the limits.
+    fn check_inst_limits(&mut self, wc: u16, opcode: u16) -> Result<()> {
+        let offset = self.inst_offset;
+        if let Some(max) = self.options.max_binary_words {
+            if offset / WORD_NUM_BYTES + wc as usize > max {
+                return Err(State::LimitExceeded(offset, Limit::BinaryWords(max)));
+            }
+        }
+        if let Some(max) = self.options.max_instruction_count {
+            if self.inst_index > max {
+                return Err(State::LimitExceeded(offset, Limit::InstructionCount(max)));
+            }
+        }
+        if opcode == spirv::Op::Function as u16 {
+            self.num_functions += 1;
+            if let Some(max) = self.options.max_function_count {
+                if self.num_functions > max {
+                    return Err(State::LimitExceeded(offset, Limit::FunctionCount(max)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the given `version` word against the grammar version.
+    ///
+    /// Modules with the same major version and a minor version not newer
+    /// than the grammar's are supported.
+    fn check_version(&mut self, version: spirv::Word) -> Result<()> {
+        // The version word is laid out as 0 | major | minor | 0.
+        if version & 0xff0000ff != 0 {
+            return Err(State::HeaderIncorrect);
+        }
+        let major = ((version >> 16) & 0xff) as u8;
+        let minor = ((version >> 8) & 0xff) as u8;
+        if major as u32 == spirv::MAJOR_VERSION && minor as u32 <= spirv::MINOR_VERSION {
+            return Ok(());
+        }
+        if !self.options.allow_unsupported_version {
+            return Err(State::VersionUnsupported(major, minor));
+        }
+        self.unsupported_version = Some((major, minor));
+        Ok(())
+    }
+
+    /// Checks that all ids in the given `inst` are non-zero and less than
+    /// the id bound.
+    fn check_ids(&self, inst: &mr::Instruction) -> Result<()> {
+        let operand_ids = inst.operands.iter().filter_map(|o| o.id());
+        for id in inst.result_type.into_iter().chain(inst.result_id).chain(operand_ids) {
+            if id == 0 || id >= self.bound {
+                return Err(State::IdOutOfBounds(self.inst_offset, self.inst_index, id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the next instruction.
+    ///
+    /// Returns `State::Complete` if there are no more instructions.
+    fn parse_inst(&mut self) -> Result<ParsedInst> {
+        self.inst_location = self.line_tracker.location();
+        let result = self.parse_next_inst();
+        match result {
+            Ok(ParsedInst::Known(inst)) => {
+                self.type_tracker.track(&inst);
+                self.ext_inst_set_tracker.track(&inst);
+                self.line_tracker.track(&inst);
+                Ok(ParsedInst::Known(inst))
+            }
+            Ok(unknown) => Ok(unknown),
+            Err(error) => Err(self.io_error_or(error)),
+        }
+    }
+
+    fn parse_next_inst(&mut self) -> Result<ParsedInst> {
+        // Drop unknown flag bits and enumerants not taken for the previous
+        // instruction.
+        self.decoder.take_unknown_flag_bits();
+        self.preserved_enumerants.clear();
+        self.inst_index += 1;
+        self.inst_offset = self.decoder.offset();
+        if let Ok(word) = self.decoder.word() {
+            if self.multi_module &&
+               (word == spirv::MAGIC_NUMBER || word == spirv::MAGIC_NUMBER.swap_bytes()) {
+                // The header of the next module.
+                self.next_magic = Some(word);
+                return Err(State::Complete);
+            }
+            let (wc, opcode) = InstParser::split_into_word_count_and_opcode(word);
+            self.inst_num_words = wc as usize;
+            if wc == 0 {
+                return Err(State::WordCountZero(self.inst_offset, self.inst_index));
+            }
+            self.check_inst_limits(wc, opcode)?;
+            // Widen before subtracting so that no word count can wrap.
+            let num_operand_words = wc as usize - 1;
+            if let Some(remaining) = self.decoder.remaining_bytes() {
+                if remaining < num_operand_words * WORD_NUM_BYTES {
+                    return Err(State::InstructionIncomplete(self.inst_offset, self.inst_index));
+                }
+            }
+            if let Some(grammar) = GInstTable::lookup_opcode(opcode) {
+                self.decoder.set_limit(num_operand_words);
+                let result = self.parse_operands(grammar);
+                let limit_reached = self.decoder.limit_reached();
+                self.decoder.clear_limit();
+                // Errors from decoding the operands take precedence over
+                // the leftover operand words they caused.
+                let inst = match result {
+                    // Only a reader can run out of words before the limit.
+                    Err(State::OperandError(DecodeError::StreamExpected(_))) if !limit_reached => {
+                        return Err(State::InstructionIncomplete(self.inst_offset,
+                                                                self.inst_index))
+                    }
+                    // The word count is too small for the parameters
+                    // required by some enumerant or opcode.
+                    Err(State::OperandError(DecodeError::LimitReached(offset))) => {
+                        return Err(State::OperandExpected(offset, self.inst_index))
+                    }
+                    result => result?,
+                };
+                if !limit_reached {
+                    return Err(State::OperandExceeded(self.decoder.offset(), self.inst_index));
+                }
+                if self.options.check_id_bound {
+                    self.check_ids(&inst)?;
+                }
+                Ok(ParsedInst::Known(inst))
+            } else if self.options.preserve_unknown_opcodes {
+                let operands = self.rest_inst_words(num_operand_words)?;
+                Ok(ParsedInst::Known(mr::Instruction::unknown(opcode, operands)))
+            } else if self.options.ignore_unknown_opcodes {
+                let mut words = vec![word];
+                words.append(&mut self.rest_inst_words(num_operand_words)?);
+                Ok(ParsedInst::Unknown(opcode, words))
+            } else {
+                Err(State::OpcodeUnknown(self.inst_offset, self.inst_index, opcode))
+            }
+        } else {
+            Err(self.end_of_insts())
+        }
+    }
+
+    /// Returns the state for running out of instructions, checking for
+    /// trailing bytes.
+    fn end_of_insts(&mut self) -> State {
+        match self.decoder.remaining_bytes() {
+            Some(count) if count > 0 => {
+                let offset = self.decoder.offset();
+                if !self.options.allow_trailing_bytes {
+                    return State::TrailingBytes(offset, count);
+                }
+                self.trailing_bytes = Some((offset, count));
+                State::Complete
+            }
+            _ => State::Complete,
+        }
+    }
+
+    /// Skips the next instruction without decoding any of its operands.
+    ///
+    /// Returns the opcode and the word count of the skipped instruction, or
+    /// `State::Complete` if there are no more instructions.
+    fn skip_next_inst(&mut self) -> Result<(u16, usize)> {
+        self.inst_index += 1;
+        self.inst_offset = self.decoder.offset();
+        let word = match self.decoder.word() {
+            Ok(word) => word,
+            Err(_) => return Err(self.end_of_insts()),
+        };
+        let (wc, opcode) = InstParser::split_into_word_count_and_opcode(word);
+        self.inst_num_words = wc as usize;
+        if wc == 0 {
+            return Err(State::WordCountZero(self.inst_offset, self.inst_index));
+        }
+        let end = self.decoder.position() + wc as usize - 1;
+        let (offset, index) = (self.inst_offset, self.inst_index);
+        self.decoder.set_position(end).map_err(|_| State::InstructionIncomplete(offset, index))?;
+        Ok((opcode, wc as usize))
+    }
+
+    /// Decodes the given number of remaining words of the current
+    /// instruction.
+    fn rest_inst_words(&mut self, count: usize) -> Result<Vec<spirv::Word>> {
+        let (offset, index) = (self.inst_offset, self.inst_index);
+        self.decoder.words(count).map_err(|_| State::InstructionIncomplete(offset, index))
+    }
+
+    /// Parses a literal number whose width is decided by the type of the
+    /// given `id`, which can be either a type id or a value id.
+    fn parse_literal(&mut self, id: spirv::Word) -> Result<mr::Operand> {
+        let tracked_type = self.type_tracker.resolve(id);
+        match tracked_type {
+            Some(t) => {
+                match t {
+                    Type::Integer(size, _) => {
+                        match size {
+                            // Integers narrower than 32 bits still take
+                            // a whole word.
+                            8 | 16 | 32 => Ok(mr::Operand::LiteralInt32(try_decode!(self.decoder.int32()))),
+                            64 => Ok(mr::Operand::LiteralInt64(try_decode!(self.decoder.int64()))),
+                            _ => {
+                                Err(State::TypeUnsupported(self.decoder.offset(), self.inst_index))
+                            }
+                        }
+                    }
+                    Type::Float(size) => {
+                        match size {
+                            32 => {
+                                Ok(mr::Operand::LiteralFloat32(try_decode!(self.decoder.float32())))
+                            }
+                            64 => {
+                                Ok(mr::Operand::LiteralFloat64(try_decode!(self.decoder.float64())))
+                            }
+                            _ => {
+                                Err(State::TypeUnsupported(self.decoder.offset(), self.inst_index))
+                            }
+                        }
+                    }
+                }
+            }
+            // Treat as a normal SPIR-V word if we don't know the type.
+            // TODO: find a better way to handle this.
+            None => Ok(mr::Operand::LiteralInt32(try_decode!(self.decoder.int32()))),
+        }
+    }
+
+    fn parse_spec_constant_op(&mut self) -> Result<Vec<mr::Operand>> {
+        let mut operands = vec![];
+
+        let number = try_decode!(self.decoder.int32());
+        if let Some(g) = GInstTable::lookup_opcode(number as u16) {
+            // TODO: check whether this opcode is allowed here.
+            operands.push(mr::Operand::LiteralSpecConstantOpInteger(g.opcode));
+            // We need id parameters to this SpecConstantOp.
+            for operand in g.operands {
+                if operand.kind == GOpKind::IdRef {
+                    operands.push(mr::Operand::IdRef(try_decode!(self.decoder.id())))
+                }
+            }
+            Ok(operands)
+        } else {
+            Err(State::SpecConstantOpIntegerIncorrect(self.decoder.offset(), self.inst_index))
+        }
+    }
+
+    /// Parses the operands of an OpExtInst instruction following the
+    /// extended instruction number according to the given `grammar`.
+    fn parse_ext_inst_operands(&mut self, grammar: GExtInstRef) -> Result<Vec<mr::Operand>> {
+        let mut operands = vec![];
+        for loperand in grammar.operands {
+            match loperand.quantifier {
+                GOpCount::One => {
+                    if self.decoder.limit_reached() {
+                        return Err(State::OperandExpected(self.decoder.offset(), self.inst_index));
+                    }
+                    operands.append(&mut self.parse_operand(loperand.kind)?)
+                }
+                GOpCount::ZeroOrOne => {
+                    if !self.decoder.limit_reached() {
+                        operands.append(&mut self.parse_operand(loperand.kind)?)
+                    }
+                }
+                GOpCount::ZeroOrMore => {
+                    while !self.decoder.limit_reached() {
+                        operands.append(&mut self.parse_operand(loperand.kind)?)
+                    }
+                }
+            }
+        }
+        Ok(operands)
+    }
+
+    fn parse_operands(&mut self, grammar: GInstRef) -> Result<mr::Instruction> {
+        let mut rtype = None;
+        let mut rid = None;
+        // Concrete operands, reusing the buffer given back by the consumer
+        // if any.
+        let mut coperands = mem::replace(&mut self.operand_buffer, vec![]);
+        coperands.clear();
+
+        let mut loperand_index: usize = 0; // logical operand index
+        while loperand_index < grammar.operands.len() {
+            let loperand = &grammar.operands[loperand_index];
+            let has_more_coperands = !self.decoder.limit_reached();
+            if has_more_coperands {
+                match loperand.kind {
+                    GOpKind::IdResultType => rtype = Some(try_decode!(self.decoder.id())),
+                    GOpKind::IdResult => rid = Some(try_decode!(self.decoder.id())),
+                    GOpKind::LiteralContextDependentNumber => {
+                        // Only constant defining instructions use this kind.
+                        // If it is not true, that means the grammar is wrong
+                        // or has changed.
+                        assert!(grammar.opcode == spirv::Op::Constant ||
+                                grammar.opcode == spirv::Op::SpecConstant);
+                        let id = rtype.expect("internal error: \
+                            should already decoded result type id before context dependent number");
+                        coperands.push(self.parse_literal(id)?)
+                    }
+                    GOpKind::LiteralSpecConstantOpInteger => {
+                        coperands.append(&mut self.parse_spec_constant_op()?)
+                    }
+                    GOpKind::PairLiteralIntegerIdRef if grammar.opcode == spirv::Op::Switch => {
+                        // The width of the literal is decided by the
+                        // selector, which is the first operand.
+                        let selector = match coperands.first() {
+                            Some(&mr::Operand::IdRef(id)) => id,
+                            _ => unreachable!(),
+                        };
+                        coperands.push(self.parse_literal(selector)?);
+                        coperands.push(mr::Operand::IdRef(try_decode!(self.decoder.id())))
+                    }
+                    GOpKind::LiteralExtInstInteger => {
+                        let number = try_decode!(self.decoder.ext_inst_integer());
+                        let set = match coperands.last() {
+                            Some(&mr::Operand::IdRef(set)) => Some(set),
+                            _ => None,
+                        };
+                        coperands.push(mr::Operand::LiteralExtInstInteger(number));
+                        // Parse the remaining operands according to the
+                        // extended instruction set grammar if we know it;
+                        // otherwise, fall back to the generic id list.
+                        let ext_grammar = match set {
+                            Some(set) => self.ext_inst_set_tracker.resolve(set, number),
+                            None => None,
+                        };
+                        if let Some(g) = ext_grammar {
+                            coperands.append(&mut self.parse_ext_inst_operands(g)?);
+                            break;
+                        }
+                    }
+                    _ => {
+                        let start = self.decoder.offset();
+                        match self.parse_operand(loperand.kind) {
+                            Ok(mut operands) => coperands.append(&mut operands),
+                            Err(State::OperandError(DecodeError::EnumerantUnknown(offset, kind, value)))
+                                if self.options.preserve_unknown_enumerants && offset == start => {
+                                coperands.push(mr::Operand::UnknownEnumerant(kind, value));
+                                self.preserved_enumerants.push((offset, kind, value));
+                                // We cannot know which operands the unknown
+                                // value implies, so keep the remaining words
+                                // as they are.
+                                while !self.decoder.limit_reached() {
+                                    coperands.push(mr::Operand::LiteralInt32(try_decode!(self.decoder.int32())));
+                                }
+                                break;
+                            }
+                            Err(err) => return Err(err),
+                        }
+                    }
+                }
+                match loperand.quantifier {
+                    GOpCount::One | GOpCount::ZeroOrOne => loperand_index += 1,
+                    GOpCount::ZeroOrMore => continue,
+                }
+            } else {
+                // We still have logical operands to match but no no more words.
+                match loperand.quantifier {
+                    GOpCount::One => {
+                        return Err(State::OperandExpected(self.decoder.offset(), self.inst_index))
+                    }
+                    GOpCount::ZeroOrOne | GOpCount::ZeroOrMore => break,
+                }
+            }
+        }
+        Ok(mr::Instruction::new(grammar.opcode, rtype, rid, coperands))
+    }
+}
+
+include!("parse_operand.rs");
+
+#[cfg(test)]
+mod tests {
+    use mr;
+    use spirv;
+
+    use binary::Assemble;
+    use binary::error::Error;
+    use grammar::OperandKind as GOpKind;
+    use std::{cmp, error, fmt, io};
+    use binary::SourceLocation;
+    use binary::decoder::Endianness;
+    use super::{Action, Completion, Consumer, IncrementalParser, InstContext, Limit,
+                on_header_and_inst, on_inst, parse_bytes, parse_dyn, parse_header, parse_multi,
+                parse_operand, parse_reader, parse_words, stats, Parser, ParserOptions,
+                ParseIterator, State, Warning, WORD_NUM_BYTES};
+
+    use utils::num::f32_to_bytes;
+    use utils::num::f64_to_bytes;
+
+    // TODO: It's unfortunate that we have these numbers directly coded here
+    // and repeat them in the following tests. Should have a better way.
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    static ZERO_BOUND_HEADER: &'static [u8] = &[
+        // Magic number.           Version number: 1.0.
+        0x03, 0x02, 0x23, 0x07,    0x00, 0x00, 0x01, 0x00,
+        // Generator number: 0.    Bound: 0.
         0x00, 0x00, 0x00, 0x00,    0x00, 0x00, 0x00, 0x00,
         // Reserved word: 0.
         0x00, 0x00, 0x00, 0x00];
 
-    struct RetainingConsumer {
-        pub header: Option<mr::ModuleHeader>,
-        pub insts: Vec<mr::Instruction>,
+    /// Returns the module header in `ZERO_BOUND_HEADER`.
+    fn zero_bound_header() -> mr::ModuleHeader {
+        mr::ModuleHeader {
+            magic_number: spirv::MAGIC_NUMBER,
+            version: 0x00010000,
+            generator: 0,
+            bound: 0,
+            reserved_word: 0,
+        }
+    }
+
+    struct RetainingConsumer {
+        pub header: Option<mr::ModuleHeader>,
+        pub insts: Vec<mr::Instruction>,
+    }
+    impl RetainingConsumer {
+        fn new() -> RetainingConsumer {
+            RetainingConsumer {
+                header: None,
+                insts: vec![],
+            }
+        }
+    }
+    impl Consumer for RetainingConsumer {
+        fn consume_header(&mut self, header: mr::ModuleHeader) -> Action {
+            self.header = Some(header);
+            Action::Continue
+        }
+        fn consume_instruction(&mut self, inst: mr::Instruction) -> Action {
+            self.insts.push(inst);
+            Action::Continue
+        }
+    }
+
+    // TODO: Should put this function and its duplicate in the decoder in
+    // a utility module.
+    fn w2b(word: spirv::Word) -> Vec<u8> {
+        (0..WORD_NUM_BYTES)
+            .map(|i| ((word >> (8 * i)) & 0xff) as u8)
+            .collect()
+    }
+
+    /// A simple module builder for testing purpose.
+    struct ModuleBuilder {
+        insts: Vec<u8>,
+    }
+    impl ModuleBuilder {
+        fn new() -> ModuleBuilder {
+            ModuleBuilder { insts: ZERO_BOUND_HEADER.to_vec() }
+        }
+
+        /// Appends an instruction with the given `opcode` and `operands` into
+        /// the module.
+        fn inst(&mut self, opcode: spirv::Op, operands: Vec<u32>) {
+            let count: u32 = operands.len() as u32 + 1;
+            self.insts.append(&mut w2b((count << 16) | (opcode as u32)));
+            for o in operands {
+                self.insts.append(&mut w2b(o));
+            }
+        }
+
+        /// Returns the module being constructed.
+        fn get(&self) -> &[u8] {
+            &self.insts
+        }
+    }
+
+    #[test]
+    fn test_module_builder() {
+        let mut b = ModuleBuilder::new();
+        // OpNop
+        b.inst(spirv::Op::Nop, vec![]);
+        // OpCapability Int16
+        b.inst(spirv::Op::Capability, vec![22]);
+        // OpMemoryModel Logical GLSL450
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        let mut module = ZERO_BOUND_HEADER.to_vec();
+        module.append(&mut vec![0x00, 0x00, 0x01, 0x00]); // OpNop
+        module.append(&mut vec![0x11, 0x00, 0x02, 0x00]); // OpCapability
+        module.append(&mut vec![0x16, 0x00, 0x00, 0x00]); // Int16
+        module.append(&mut vec![0x0e, 0x00, 0x03, 0x00]); // OpMemoryModel
+        module.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // Logical
+        module.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // GLSL450
+        assert_eq!(module, b.get());
+    }
+
+    #[test]
+    fn test_parsing_empty_binary() {
+        let v = vec![];
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&v, &mut c);
+        assert_matches!(p.parse(),
+                        Err(State::HeaderIncomplete(Error::StreamExpected(0))));
+    }
+
+    #[test]
+    fn test_parsing_incomplete_header() {
+        let v = vec![0x03, 0x02, 0x23, 0x07];
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&v, &mut c);
+        assert_matches!(p.parse(),
+                        Err(State::HeaderIncomplete(Error::StreamExpected(4))));
+    }
+
+    /// Swaps the byte order of every word in the given little-endian `bytes`.
+    fn swap_word_bytes(bytes: &[u8]) -> Vec<u8> {
+        bytes.chunks(WORD_NUM_BYTES)
+            .flat_map(|w| w.iter().rev().cloned().collect::<Vec<u8>>())
+            .collect()
+    }
+
+    #[test]
+    fn test_parsing_big_endian_header() {
+        let module = swap_word_bytes(ZERO_BOUND_HEADER);
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&module, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(Some(zero_bound_header()), c.header);
+    }
+
+    #[test]
+    fn test_parsing_big_endian_module() {
+        let mut b = ModuleBuilder::new();
+        // OpCapability Int16
+        b.inst(spirv::Op::Capability, vec![22]);
+        // OpMemoryModel Logical GLSL450
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        let mut little = b.get().to_vec();
+        // OpSource GLSL 450 %6 "wow"
+        little.append(&mut vec![0x03, 0x00, 0x05, 0x00]);
+        little.append(&mut vec![0x02, 0x00, 0x00, 0x00]);
+        little.append(&mut vec![0xc2, 0x01, 0x00, 0x00]);
+        little.append(&mut vec![0x06, 0x00, 0x00, 0x00]);
+        little.append(&mut b"wow\0".to_vec());
+        let big = swap_word_bytes(&little);
+
+        let mut lc = RetainingConsumer::new();
+        {
+            let p = Parser::new(&little, &mut lc);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        let mut bc = RetainingConsumer::new();
+        {
+            let p = Parser::new(&big, &mut bc);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(lc.header, bc.header);
+        assert_eq!(3, bc.insts.len());
+        assert_eq!(lc.insts.len(), bc.insts.len());
+        for (l, b) in lc.insts.iter().zip(bc.insts.iter()) {
+            assert_eq!(l.class, b.class);
+            assert_eq!(l.result_type, b.result_type);
+            assert_eq!(l.result_id, b.result_id);
+            assert_eq!(l.operands, b.operands);
+        }
+        assert_eq!(mr::Operand::from("wow"), bc.insts[2].operands[3]);
+    }
+
+    #[test]
+    fn test_parsing_wrong_magic_number() {
+        let mut module = ZERO_BOUND_HEADER.to_vec();
+        module[0] = 0x00;
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&module, &mut c);
+        assert_matches!(p.parse(), Err(State::HeaderIncorrect));
+    }
+
+    #[test]
+    fn test_parsing_complete_header() {
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(ZERO_BOUND_HEADER, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(Some(zero_bound_header()), c.header);
+    }
+
+    #[test]
+    fn test_parsing_one_inst() {
+        let mut c = RetainingConsumer::new();
+        {
+            let mut b = ModuleBuilder::new();
+            // OpMemoryModel Logical GLSL450
+            b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+            let p = Parser::new(b.get(), &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("MemoryModel", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::AddressingModel(spirv::AddressingModel::Logical),
+                        mr::Operand::MemoryModel(spirv::MemoryModel::GLSL450)],
+                   inst.operands);
+    }
+
+    #[test]
+    fn test_parsing_zero_word_count() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // OpNop with word count 0
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&v, &mut c);
+        // The first instruction starts at byte offset 20.
+        assert_matches!(p.parse(), Err(State::WordCountZero(20, 1)));
+    }
+
+    #[test]
+    fn test_parsing_extra_operand() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x00, 0x00, 0x01, 0x00]); // OpNop with word count 1
+        v.append(&mut vec![0x00, 0x00, 0x02, 0x00]); // OpNop with word count 2
+        v.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // A bogus operand
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&v, &mut c);
+        // The bogus operand to the second OpNop instruction starts at
+        // byte offset (20 + 4 + 4).
+        assert_matches!(p.parse(), Err(State::OperandExceeded(28, 2)));
+    }
+
+    #[test]
+    fn test_parsing_missing_operand() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x00, 0x00, 0x01, 0x00]); // OpNop with word count 1
+        v.append(&mut vec![0x0e, 0x00, 0x03, 0x00]); // OpMemoryModel
+        v.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // Logical
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&v, &mut c);
+        // The incomplete OpMemoryModel instruction starts at byte offset
+        // (20 + 4).
+        assert_matches!(p.parse(), Err(State::InstructionIncomplete(24, 2)));
+    }
+
+    #[test]
+    fn test_parsing_operand_parameters() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x47, 0x00, 0x04, 0x00]); // OpDecorate
+        v.append(&mut vec![0x05, 0x00, 0x00, 0x00]); // id 5
+        v.append(&mut vec![0x0b, 0x00, 0x00, 0x00]); // BuiltIn
+        v.append(&mut vec![0x06, 0x00, 0x00, 0x00]); // InstanceId
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("Decorate", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::IdRef(5),
+                        mr::Operand::Decoration(spirv::Decoration::BuiltIn),
+                        mr::Operand::BuiltIn(spirv::BuiltIn::InstanceId)],
+                   inst.operands);
+    }
+
+    #[test]
+    fn test_parsing_missing_operand_parameters() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x47, 0x00, 0x03, 0x00]); // OpDecorate
+        v.append(&mut vec![0x05, 0x00, 0x00, 0x00]); // id 5
+        v.append(&mut vec![0x0b, 0x00, 0x00, 0x00]); // BuiltIn
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&v, &mut c);
+        // The word count is fully present, but the parameter to BuiltIn
+        // is missing within it.
+        assert_matches!(p.parse(),
+                        Err(State::OperandError(Error::StreamExpected(32))));
+    }
+
+    #[test]
+    fn test_parsing_with_all_optional_operands() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x03, 0x00, 0x05, 0x00]); // OpSource
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // GLSL
+        v.append(&mut vec![0xc2, 0x01, 0x00, 0x00]); // 450 (0x1c2)
+        v.append(&mut vec![0x06, 0x00, 0x00, 0x00]); // File id
+        v.append(&mut b"wow".to_vec());              // Source
+        v.push(0x00);                                // EOS
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("Source", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL),
+                        mr::Operand::LiteralInt32(450),
+                        mr::Operand::IdRef(6),
+                        mr::Operand::from("wow")],
+                   inst.operands);
+    }
+
+    #[test]
+    fn test_parsing_missing_one_optional_operand() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x03, 0x00, 0x04, 0x00]); // OpSource
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // GLSL
+        v.append(&mut vec![0xc2, 0x01, 0x00, 0x00]); // 450 (0x1c2)
+        v.append(&mut vec![0x06, 0x00, 0x00, 0x00]); // File id
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("Source", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL),
+                        mr::Operand::LiteralInt32(450),
+                        mr::Operand::IdRef(6)],
+                   inst.operands);
+    }
+
+    #[test]
+    fn test_parsing_missing_two_optional_operands() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x03, 0x00, 0x03, 0x00]); // OpSource
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // GLSL
+        v.append(&mut vec![0xc2, 0x01, 0x00, 0x00]); // 450 (0x1c2)
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("Source", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL),
+                        mr::Operand::LiteralInt32(450)],
+                   inst.operands);
+    }
+
+    #[derive(Debug)]
+    struct ErrorString(&'static str);
+    impl error::Error for ErrorString {
+        fn description(&self) -> &str {
+            "consumer error"
+        }
+    }
+    impl fmt::Display for ErrorString {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let &ErrorString(ref s) = self;
+            write!(f, "{}", s)
+        }
+    }
+
+    struct InitializeErrorConsumer;
+    impl Consumer for InitializeErrorConsumer {
+        fn initialize(&mut self) -> Action {
+            Action::Error(Box::new(ErrorString("init error")))
+        }
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            Action::Continue
+        }
+    }
+
+    #[test]
+    fn test_consumer_initialize_error() {
+        let v = vec![];
+        let mut c = InitializeErrorConsumer {};
+        let p = Parser::new(&v, &mut c);
+        let ret = p.parse();
+        assert_matches!(ret, Err(State::ConsumerError(_)));
+        if let Err(State::ConsumerError(err)) = ret {
+            assert_eq!("consumer error", err.description());
+            assert_eq!("init error", format!("{}", err));
+        } else {
+            assert!(false);
+        }
+    }
+
+    struct FinalizeErrorConsumer;
+    impl Consumer for FinalizeErrorConsumer {
+        fn finalize(&mut self) -> Action {
+            Action::Error(Box::new(ErrorString("fin error")))
+        }
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            Action::Continue
+        }
+    }
+
+    #[test]
+    fn test_consumer_finalize_error() {
+        let mut c = FinalizeErrorConsumer {};
+        let p = Parser::new(ZERO_BOUND_HEADER, &mut c);
+        let ret = p.parse();
+        assert_matches!(ret, Err(State::ConsumerError(_)));
+        if let Err(State::ConsumerError(err)) = ret {
+            assert_eq!("consumer error", err.description());
+            assert_eq!("fin error", format!("{}", err));
+        } else {
+            assert!(false);
+        }
+    }
+
+    struct ParseHeaderErrorConsumer;
+    impl Consumer for ParseHeaderErrorConsumer {
+        fn consume_header(&mut self, _: mr::ModuleHeader) -> Action {
+            Action::Error(Box::new(ErrorString("parse header error")))
+        }
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            Action::Continue
+        }
+    }
+
+    #[test]
+    fn test_consumer_parse_header_error() {
+        let mut c = ParseHeaderErrorConsumer {};
+        let p = Parser::new(ZERO_BOUND_HEADER, &mut c);
+        let ret = p.parse();
+        assert_matches!(ret, Err(State::ConsumerError(_)));
+        if let Err(State::ConsumerError(err)) = ret {
+            assert_eq!("consumer error", err.description());
+            assert_eq!("parse header error", format!("{}", err));
+        } else {
+            assert!(false);
+        }
+    }
+
+    struct ParseInstErrorConsumer;
+    impl Consumer for ParseInstErrorConsumer {
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            Action::Error(Box::new(ErrorString("parse inst error")))
+        }
+    }
+
+    #[test]
+    fn test_consumer_parse_inst_error() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut c = ParseInstErrorConsumer {};
+        let p = Parser::new(b.get(), &mut c);
+        let ret = p.parse();
+        assert_matches!(ret, Err(State::ConsumerError(_)));
+        if let Err(State::ConsumerError(err)) = ret {
+            assert_eq!("consumer error", err.description());
+            assert_eq!("parse inst error", format!("{}", err));
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn test_parsing_int32() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x15, 0x00, 0x04, 0x00]); // OpTypeInt
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result id: 1
+        v.append(&mut vec![0x20, 0x00, 0x00, 0x00]); // 32
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // 1 (signed)
+
+        v.append(&mut vec![0x2b, 0x00, 0x04, 0x00]); // OpConstant
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
+        v.append(&mut vec![0x12, 0x34, 0x56, 0x78]);
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(2, c.insts.len());
+        let inst = &c.insts[1];
+        assert_eq!("Constant", inst.grammar().opname);
+        assert_eq!(Some(1), inst.result_type);
+        assert_eq!(Some(2), inst.result_id);
+        assert_eq!(vec![mr::Operand::LiteralInt32(0x78563412)], inst.operands);
+    }
+
+    #[test]
+    fn test_parsing_int64() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x15, 0x00, 0x04, 0x00]); // OpTypeInt
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result id: 1
+        v.append(&mut vec![0x40, 0x00, 0x00, 0x00]); // 64
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // 1 (signed)
+
+        v.append(&mut vec![0x2b, 0x00, 0x05, 0x00]); // OpConstant
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
+        v.append(&mut vec![0x12, 0x34, 0x56, 0x78]);
+        v.append(&mut vec![0x90, 0xab, 0xcd, 0xef]);
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(2, c.insts.len());
+        let inst = &c.insts[1];
+        assert_eq!("Constant", inst.grammar().opname);
+        assert_eq!(Some(1), inst.result_type);
+        assert_eq!(Some(2), inst.result_id);
+        assert_eq!(vec![mr::Operand::LiteralInt64(0xefcdab9078563412)],
+                   inst.operands);
+    }
+
+    #[test]
+    fn test_parsing_float32() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x16, 0x00, 0x03, 0x00]); // OpTypeFloat
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result id: 1
+        v.append(&mut vec![0x20, 0x00, 0x00, 0x00]); // 32
+
+        v.append(&mut vec![0x2b, 0x00, 0x04, 0x00]); // OpConstant
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
+        v.append(&mut f32_to_bytes(42.42));
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(2, c.insts.len());
+        let inst = &c.insts[1];
+        assert_eq!("Constant", inst.grammar().opname);
+        assert_eq!(Some(1), inst.result_type);
+        assert_eq!(Some(2), inst.result_id);
+        assert_eq!(vec![mr::Operand::LiteralFloat32(42.42)], inst.operands);
+    }
+
+    #[test]
+    fn test_parsing_float64() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x16, 0x00, 0x03, 0x00]); // OpTypeFloat
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result id: 1
+        v.append(&mut vec![0x40, 0x00, 0x00, 0x00]); // 64
+
+        v.append(&mut vec![0x2b, 0x00, 0x05, 0x00]); // OpConstant
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
+        v.append(&mut f64_to_bytes(-12.34));
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(2, c.insts.len());
+        let inst = &c.insts[1];
+        assert_eq!("Constant", inst.grammar().opname);
+        assert_eq!(Some(1), inst.result_type);
+        assert_eq!(Some(2), inst.result_id);
+        assert_eq!(vec![mr::Operand::LiteralFloat64(-12.34)], inst.operands);
+    }
+
+    #[test]
+    fn test_parsing_spec_constant_op() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x34, 0x00, 0x05, 0x00]); // OpTypeFloat
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
+        v.append(&mut vec![0x7e, 0x00, 0x00, 0x00]); // OpSNegate
+        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // id ref: 3
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("SpecConstantOp", inst.grammar().opname);
+        assert_eq!(Some(1), inst.result_type);
+        assert_eq!(Some(2), inst.result_id);
+        assert_eq!(vec![mr::Operand::LiteralSpecConstantOpInteger(spirv::Op::SNegate),
+                        mr::Operand::IdRef(3)],
+                   inst.operands);
+    }
+
+    #[test]
+    fn test_parsing_spec_constant_op_missing_parameter() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x34, 0x00, 0x05, 0x00]); // OpTypeFloat
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
+        v.append(&mut vec![0x80, 0x00, 0x00, 0x00]); // OpIAdd
+        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // id ref: 3
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&v, &mut c);
+        assert_matches!(p.parse(),
+                        // The header has 5 words, the above instruction has 5 words,
+                        // so in total 40 bytes.
+                        Err(State::OperandExpected(40, 1)));
+    }
+
+    #[test]
+    fn test_parsing_bitmasks_requiring_params_no_mem_access() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x3e, 0x00, 0x03, 0x00]); // OpStore
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // pointer: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // object: 2
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("Store", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::IdRef(1), mr::Operand::IdRef(2)],
+                   inst.operands);
+    }
+    #[test]
+    fn test_parsing_bitmasks_requiring_params_mem_access_no_param() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x3e, 0x00, 0x04, 0x00]); // OpStore
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // pointer: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // object: 2
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // Volatile
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("Store", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::IdRef(1),
+                        mr::Operand::IdRef(2),
+                        mr::Operand::MemoryAccess(spirv::MEMORY_ACCESS_VOLATILE)],
+                   inst.operands);
+    }
+    #[test]
+    fn test_parsing_bitmasks_requiring_params_mem_access_with_param() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x3e, 0x00, 0x05, 0x00]); // OpStore
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // pointer: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // object: 2
+        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // Volatile & Aligned
+        v.append(&mut vec![0x04, 0x00, 0x00, 0x00]); // alignment
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("Store", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::IdRef(1),
+                        mr::Operand::IdRef(2),
+                        mr::Operand::MemoryAccess(spirv::MemoryAccess::from_bits(3).unwrap()),
+                        mr::Operand::LiteralInt32(4)],
+                   inst.operands);
+    }
+    #[test]
+    fn test_parsing_bitmasks_requiring_params_mem_access_missing_param() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x3e, 0x00, 0x04, 0x00]); // OpStore
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // pointer: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // object: 2
+        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // Volatile & Aligned
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&v, &mut c);
+        assert_matches!(p.parse(),
+                        // The header has 5 words, the above instruction has 4 words,
+                        // so in total 36 bytes.
+                        Err(State::OperandExpected(36, 1)));
+    }
+    #[test]
+    fn test_parsing_bitmasks_requiring_params_img_operands_param_order() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x63, 0x00, 0x08, 0x00]); // OpStore
+        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // image: 1
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // coordinate: 2
+        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // texel: 3
+        v.append(&mut vec![0x05, 0x00, 0x00, 0x00]); // Bias & GRAD
+        v.append(&mut vec![0xaa, 0x00, 0x00, 0x00]); // bias
+        v.append(&mut vec![0xbb, 0x00, 0x00, 0x00]); // dx
+        v.append(&mut vec![0xcc, 0x00, 0x00, 0x00]); // dy
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(&v, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("ImageWrite", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::IdRef(1),
+                        mr::Operand::IdRef(2),
+                        mr::Operand::IdRef(3),
+                        mr::Operand::ImageOperands(spirv::ImageOperands::from_bits(5).unwrap()),
+                        mr::Operand::IdRef(0xaa),
+                        mr::Operand::IdRef(0xbb),
+                        mr::Operand::IdRef(0xcc)],
+                   inst.operands);
+        assert_eq!(3, inst.operands[3].num_parameters());
+        assert_eq!(&inst.operands[4..], inst.operand_parameters(3));
+
+        // Missing the dy parameter.
+        v[22] = 0x07;
+        v.truncate(v.len() - WORD_NUM_BYTES);
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&v, &mut c);
+        assert_matches!(p.parse(), Err(State::OperandExpected(48, 1)));
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let mut b = ModuleBuilder::new();
+        // OpCapability Int16
+        b.inst(spirv::Op::Capability, vec![22]);
+        let bytes: &[u8] = b.get();
+
+        // Borrowed bytes
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_bytes(bytes, &mut c), Ok(()));
+        assert_eq!(1, c.insts.len());
+        assert_eq!(vec![mr::Operand::Capability(spirv::Capability::Int16)],
+                   c.insts[0].operands);
+
+        // Owned bytes
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_bytes(bytes.to_vec(), &mut c), Ok(()));
+        assert_eq!(1, c.insts.len());
+        assert_eq!(vec![mr::Operand::Capability(spirv::Capability::Int16)],
+                   c.insts[0].operands);
+    }
+
+    #[test]
+    fn test_parse_words() {
+        let words = vec![0x07230203, 0x00010000, 0, 0, 0, 0x00020011, 0x00000016];
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_words(&words, &mut c), Ok(()));
+        assert_eq!(1, c.insts.len());
+        let inst = &c.insts[0];
+        assert_eq!("Capability", inst.grammar().opname);
+        assert_eq!(None, inst.result_type);
+        assert_eq!(None, inst.result_id);
+        assert_eq!(vec![mr::Operand::Capability(spirv::Capability::Int16)],
+                   inst.operands);
+    }
+
+    #[test]
+    fn test_parse_words_string() {
+        let words = vec![0x07230203, 0x00010000, 0, 0, 0,
+                         0x0004000a, 0x5f565053, 0x5f52484b, 0x00000000]; // OpExtension
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_words(&words, &mut c), Ok(()));
+        assert_eq!(1, c.insts.len());
+        assert_eq!(vec![mr::Operand::from("SPV_KHR_")], c.insts[0].operands);
+    }
+
+    #[test]
+    fn test_parse_words_byte_swapped() {
+        let words: Vec<u32> = vec![0x07230203, 0x00010000, 0, 0, 0, 0x00020011, 0x00000016]
+            .into_iter()
+            .map(|w: u32| w.swap_bytes())
+            .collect();
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_words(&words, &mut c), Ok(()));
+        assert_eq!(1, c.insts.len());
+        assert_eq!(vec![mr::Operand::Capability(spirv::Capability::Int16)],
+                   c.insts[0].operands);
+    }
+
+    #[test]
+    fn test_parse_words_missing_operand() {
+        let words = vec![0x07230203, 0x00010000, 0, 0, 0, 0x0003000e, 0];
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_words(&words, &mut c),
+                        Err(State::InstructionIncomplete(20, 1)));
+    }
+
+    /// A reader serving at most `chunk` bytes per read from the inner reader.
+    struct ChunkedReader<R: io::Read> {
+        inner: R,
+        chunk: usize,
+    }
+    impl<R: io::Read> io::Read for ChunkedReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = cmp::min(self.chunk, buf.len());
+            self.inner.read(&mut buf[..len])
+        }
+    }
+
+    /// A reader erroring out after serving all the given bytes.
+    struct BrokenReader {
+        inner: io::Cursor<Vec<u8>>,
+    }
+    impl io::Read for BrokenReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.inner.read(buf) {
+                Ok(0) => Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken")),
+                result => result,
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let mut b = ModuleBuilder::new();
+        // OpCapability Int16
+        b.inst(spirv::Op::Capability, vec![22]);
+        let mut v = b.get().to_vec();
+        v.append(&mut vec![0x03, 0x00, 0x05, 0x00]); // OpSource
+        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // GLSL
+        v.append(&mut vec![0xc2, 0x01, 0x00, 0x00]); // 450 (0x1c2)
+        v.append(&mut vec![0x06, 0x00, 0x00, 0x00]); // File id
+        v.append(&mut b"wow".to_vec());              // Source
+        v.push(0x00);                                // EOS
+
+        for chunk in 1..6 {
+            let reader = ChunkedReader {
+                inner: io::Cursor::new(v.clone()),
+                chunk: chunk,
+            };
+            let mut c = RetainingConsumer::new();
+            assert_matches!(parse_reader(reader, &mut c), Ok(()));
+            assert_eq!(Some(zero_bound_header()), c.header);
+            assert_eq!(2, c.insts.len());
+            assert_eq!(vec![mr::Operand::Capability(spirv::Capability::Int16)],
+                       c.insts[0].operands);
+            assert_eq!(vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL),
+                            mr::Operand::LiteralInt32(450),
+                            mr::Operand::IdRef(6),
+                            mr::Operand::from("wow")],
+                       c.insts[1].operands);
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_truncated() {
+        let mut c = RetainingConsumer::new();
+        let reader = io::Cursor::new(ZERO_BOUND_HEADER[..10].to_vec());
+        assert_matches!(Parser::from_reader(reader, &mut c).parse(),
+                        Err(State::HeaderIncomplete(Error::StreamExpected(8))));
+
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x0e, 0x00, 0x03, 0x00]); // OpMemoryModel
+        v.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // Logical
+        v.append(&mut vec![0x01, 0x00]); // Half of GLSL450
+        let mut c = RetainingConsumer::new();
+        let reader = ChunkedReader {
+            inner: io::Cursor::new(v),
+            chunk: 3,
+        };
+        assert_matches!(Parser::from_reader(reader, &mut c).parse(),
+                        Err(State::InstructionIncomplete(20, 1)));
+    }
+
+    #[test]
+    fn test_parse_reader_io_error() {
+        let mut b = ModuleBuilder::new();
+        // OpMemoryModel Logical GLSL450
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+
+        // Erroring out at instruction boundary.
+        let reader = BrokenReader { inner: io::Cursor::new(b.get().to_vec()) };
+        let mut c = RetainingConsumer::new();
+        let ret = parse_reader(reader, &mut c);
+        assert_matches!(ret, Err(State::IoError(_)));
+        if let Err(State::IoError(err)) = ret {
+            assert_eq!(io::ErrorKind::BrokenPipe, err.kind());
+        }
+        assert_eq!(1, c.insts.len());
+
+        // Erroring out in the middle of an instruction.
+        let reader = BrokenReader { inner: io::Cursor::new(b.get()[..28].to_vec()) };
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_reader(reader, &mut c), Err(State::IoError(_)));
+        assert_eq!(0, c.insts.len());
+
+        // Erroring out in the header.
+        let reader = BrokenReader { inner: io::Cursor::new(b.get()[..8].to_vec()) };
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_reader(reader, &mut c), Err(State::IoError(_)));
+        assert!(c.header.is_none());
+    }
+
+    #[test]
+    fn test_parse_iterator() {
+        let mut b = ModuleBuilder::new();
+        // OpCapability Int16
+        b.inst(spirv::Op::Capability, vec![22]);
+        // OpMemoryModel Logical GLSL450
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        let mut iter = ParseIterator::new(b.get());
+        assert!(iter.header().is_none());
+
+        let inst = iter.next().unwrap().unwrap();
+        assert_eq!(Some(&zero_bound_header()), iter.header());
+        assert_eq!(spirv::Op::Capability, inst.class);
+        assert_eq!(vec![mr::Operand::Capability(spirv::Capability::Int16)],
+                   inst.operands);
+
+        let inst = iter.next().unwrap().unwrap();
+        assert_eq!(spirv::Op::MemoryModel, inst.class);
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_parse_iterator_is_lazy() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x00, 0x00, 0x01, 0x00]); // OpNop
+        v.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // OpNop with word count 0
+        let mut iter = ParseIterator::new(&v);
+        // The first instruction is fine; the second one is never touched.
+        let inst = iter.next().unwrap().unwrap();
+        assert_eq!(spirv::Op::Nop, inst.class);
+    }
+
+    #[test]
+    fn test_parse_iterator_errors() {
+        let mut v = ZERO_BOUND_HEADER.to_vec();
+        v.append(&mut vec![0x00, 0x00, 0x01, 0x00]); // OpNop
+        v.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // OpNop with word count 0
+        v.append(&mut vec![0x00, 0x00, 0x01, 0x00]); // OpNop
+        let mut iter = ParseIterator::new(&v);
+        assert_matches!(iter.next(), Some(Ok(_)));
+        assert_matches!(iter.next(), Some(Err(State::WordCountZero(24, 2))));
+        assert!(iter.next().is_none());
+
+        let v = vec![0x03, 0x02, 0x23, 0x07];
+        let mut iter = ParseIterator::new(&v);
+        assert_matches!(iter.next(),
+                        Some(Err(State::HeaderIncomplete(Error::StreamExpected(4)))));
+        assert!(iter.header().is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_closure_consumer() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        // OpCapability Int16
+        b.inst(spirv::Op::Capability, vec![22]);
+        let mut opcodes = vec![];
+        assert_matches!(parse_bytes(b.get(),
+                                    &mut on_inst(|inst| {
+                                        opcodes.push(inst.class);
+                                        Action::Continue
+                                    })),
+                        Ok(()));
+        assert_eq!(vec![spirv::Op::Nop, spirv::Op::Capability], opcodes);
+    }
+
+    #[test]
+    fn test_closure_consumer_stop() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        b.inst(spirv::Op::Nop, vec![]);
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut count = 0;
+        assert_matches!(parse_bytes(b.get(),
+                                    &mut on_inst(|_| {
+                                        count += 1;
+                                        if count == 2 {
+                                            Action::Stop
+                                        } else {
+                                            Action::Continue
+                                        }
+                                    })),
+                        Ok(()));
+        assert_eq!(2, count);
+    }
+
+    #[test]
+    fn test_closure_consumer_error() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        let ret = parse_bytes(b.get(),
+                              &mut on_inst(|_| Action::Error(Box::new(ErrorString("closure")))));
+        assert_matches!(ret, Err(State::ConsumerError(_)));
+        if let Err(State::ConsumerError(err)) = ret {
+            assert_eq!("closure", format!("{}", err));
+        }
+    }
+
+    #[test]
+    fn test_header_and_inst_closure_consumer() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut header = None;
+        let mut count = 0;
+        assert_matches!(parse_bytes(b.get(),
+                                    &mut on_header_and_inst(|h| {
+                                                                header = Some(h);
+                                                                Action::Continue
+                                                            },
+                                                            |_| {
+                                                                count += 1;
+                                                                Action::Continue
+                                                            })),
+                        Ok(()));
+        assert_eq!(Some(zero_bound_header()), header);
+        assert_eq!(1, count);
+
+        let ret = parse_bytes(b.get(),
+                              &mut on_header_and_inst(|_| Action::Stop, |_| Action::Continue));
+        assert_matches!(ret, Ok(()));
+    }
+
+    struct CountingConsumer {
+        count: usize,
+    }
+    impl Consumer for CountingConsumer {
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            self.count += 1;
+            Action::Continue
+        }
+    }
+
+    #[test]
+    fn test_consumer_default_methods() {
+        let mut c = CountingConsumer { count: 0 };
+        assert_matches!(c.initialize(), Action::Continue);
+        assert_matches!(c.consume_header(mr::ModuleHeader::new(0)), Action::Continue);
+        assert_matches!(c.finalize(), Action::Continue);
+
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        b.inst(spirv::Op::Nop, vec![]);
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(2, c.count);
+    }
+
+    struct PositionConsumer {
+        positions: Vec<(usize, usize, spirv::Op)>,
+    }
+    impl Consumer for PositionConsumer {
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            unreachable!()
+        }
+        fn consume_instruction_span(&mut self,
+                                    context: InstContext,
+                                    inst: mr::Instruction)
+                                    -> Action {
+            self.positions.push((context.offset, context.index, inst.class));
+            Action::Continue
+        }
+    }
+
+    #[test]
+    fn test_consume_instruction_positions() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Capability, vec![1]); // bytes 20..28
+        b.inst(spirv::Op::Nop, vec![]); // bytes 28..32
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]); // bytes 32..44
+        b.inst(spirv::Op::Nop, vec![]); // bytes 44..48
+        let mut c = PositionConsumer { positions: vec![] };
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(vec![(20, 1, spirv::Op::Capability),
+                        (28, 2, spirv::Op::Nop),
+                        (32, 3, spirv::Op::MemoryModel),
+                        (44, 4, spirv::Op::Nop)],
+                   c.positions);
+    }
+
+    #[test]
+    fn test_consume_instruction_span_forwarding() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut c = RetainingConsumer::new();
+        let context = InstContext {
+            offset: 20,
+            len: 4,
+            index: 1,
+            location: None,
+        };
+        assert_matches!(c.consume_instruction_span(context,
+                                                   mr::Instruction::new(spirv::Op::Nop,
+                                                                        None,
+                                                                        None,
+                                                                        vec![])),
+                        Action::Continue);
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(2, c.insts.len());
+    }
+
+    struct StopConsumer {
+        stop_at_init: bool,
+        stop_at_header: bool,
+        stop_at_inst: usize,
+        insts: usize,
+        finalized: bool,
     }
-    impl RetainingConsumer {
-        fn new() -> RetainingConsumer {
-            RetainingConsumer {
-                header: None,
-                insts: vec![],
+    impl StopConsumer {
+        fn new() -> StopConsumer {
+            StopConsumer {
+                stop_at_init: false,
+                stop_at_header: false,
+                stop_at_inst: 0,
+                insts: 0,
+                finalized: false,
             }
         }
     }
-    impl Consumer for RetainingConsumer {
+    impl Consumer for StopConsumer {
         fn initialize(&mut self) -> Action {
-            Action::Continue
+            if self.stop_at_init { Action::Stop } else { Action::Continue }
         }
         fn finalize(&mut self) -> Action {
+            self.finalized = true;
             Action::Continue
         }
+        fn consume_header(&mut self, _: mr::ModuleHeader) -> Action {
+            if self.stop_at_header { Action::Stop } else { Action::Continue }
+        }
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            self.insts += 1;
+            if self.insts == self.stop_at_inst { Action::Stop } else { Action::Continue }
+        }
+    }
 
-        fn consume_header(&mut self, header: mr::ModuleHeader) -> Action {
-            self.header = Some(header);
+    #[test]
+    fn test_parse_to_completion() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        b.inst(spirv::Op::Nop, vec![]);
+        b.inst(spirv::Op::Nop, vec![]);
+
+        let mut c = StopConsumer::new();
+        assert_matches!(Parser::new(b.get(), &mut c).parse_to_completion(),
+                        Ok(Completion::Complete));
+        assert_eq!(3, c.insts);
+        assert!(c.finalized);
+
+        let mut c = StopConsumer::new();
+        c.stop_at_inst = 2;
+        assert_matches!(Parser::new(b.get(), &mut c).parse_to_completion(),
+                        Ok(Completion::StoppedEarly));
+        assert_eq!(2, c.insts);
+        assert!(!c.finalized);
+
+        let mut c = StopConsumer::new();
+        c.stop_at_header = true;
+        assert_matches!(Parser::new(b.get(), &mut c).parse_to_completion(),
+                        Ok(Completion::StoppedEarly));
+        assert_eq!(0, c.insts);
+
+        let mut c = StopConsumer::new();
+        c.stop_at_init = true;
+        // The header is not even looked at.
+        assert_matches!(Parser::new(&[], &mut c).parse_to_completion(),
+                        Ok(Completion::StoppedEarly));
+    }
+
+    #[test]
+    fn test_stop_is_success() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut c = StopConsumer::new();
+        c.stop_at_inst = 1;
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(1, c.insts);
+    }
+
+    struct UnknownRetainingConsumer {
+        insts: Vec<spirv::Op>,
+        unknowns: Vec<(u16, Vec<spirv::Word>)>,
+    }
+    impl Consumer for UnknownRetainingConsumer {
+        fn consume_instruction(&mut self, inst: mr::Instruction) -> Action {
+            self.insts.push(inst.class);
             Action::Continue
         }
-        fn consume_instruction(&mut self, inst: mr::Instruction) -> Action {
-            self.insts.push(inst);
+        fn consume_unknown_instruction(&mut self, opcode: u16, words: &[spirv::Word]) -> Action {
+            self.unknowns.push((opcode, words.to_vec()));
             Action::Continue
         }
     }
 
-    // TODO: Should put this function and its duplicate in the decoder in
-    // a utility module.
-    fn w2b(word: spirv::Word) -> Vec<u8> {
-        (0..WORD_NUM_BYTES)
-            .map(|i| ((word >> (8 * i)) & 0xff) as u8)
-            .collect()
+    fn module_with_unknown_opcode() -> ModuleBuilder {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Capability, vec![1]);
+        b.insts.append(&mut w2b(0x0003ffff));
+        b.insts.append(&mut w2b(0x12345678));
+        b.insts.append(&mut w2b(0x9abcdef0));
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        b
     }
 
-    /// A simple module builder for testing purpose.
-    struct ModuleBuilder {
-        insts: Vec<u8>,
+    #[test]
+    fn test_stats() {
+        let mut b = module_with_unknown_opcode();
+        b.inst(spirv::Op::Function, vec![1, 2, 0, 3]);
+        b.inst(spirv::Op::FunctionEnd, vec![]);
+        let s = stats(b.get()).unwrap();
+        assert_eq!(0, s.bound);
+        assert_eq!(19, s.num_words);
+        assert_eq!(5, s.num_insts);
+        assert_eq!(1, s.unknown);
+        assert_eq!(1, s.num_functions);
+        assert_eq!(4, s.op_counts.len());
+        assert_eq!(Some(&1), s.op_counts.get(&spirv::Op::Capability));
+        assert_eq!(Some(&1), s.op_counts.get(&spirv::Op::FunctionEnd));
+        assert_eq!(None, s.op_counts.get(&spirv::Op::Nop));
+
+        // Instructions are still checked to be complete.
+        b.insts.append(&mut w2b(0x00030011));
+        assert_matches!(stats(b.get()), Err(State::InstructionIncomplete(76, 6)));
+        assert_matches!(stats(&b.get()[..10]), Err(State::HeaderIncomplete(_)));
     }
-    impl ModuleBuilder {
-        fn new() -> ModuleBuilder {
-            ModuleBuilder { insts: ZERO_BOUND_HEADER.to_vec() }
+
+    #[test]
+    fn test_parsing_unknown_opcode() {
+        let b = module_with_unknown_opcode();
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_bytes(b.get(), &mut c),
+                        Err(State::OpcodeUnknown(28, 2, 0xffff)));
+    }
+
+    #[test]
+    fn test_ignoring_unknown_opcode() {
+        let b = module_with_unknown_opcode();
+        let mut c = UnknownRetainingConsumer {
+            insts: vec![],
+            unknowns: vec![],
+        };
+        {
+            let options = ParserOptions { ignore_unknown_opcodes: true, ..Default::default() };
+            let p = Parser::new(b.get(), &mut c).options(options);
+            assert_matches!(p.parse(), Ok(()));
         }
+        assert_eq!(vec![spirv::Op::Capability, spirv::Op::MemoryModel], c.insts);
+        assert_eq!(vec![(0xffff, vec![0x0003ffff, 0x12345678, 0x9abcdef0])],
+                   c.unknowns);
 
-        /// Appends an instruction with the given `opcode` and `operands` into
-        /// the module.
-        fn inst(&mut self, opcode: spirv::Op, operands: Vec<u32>) {
-            let count: u32 = operands.len() as u32 + 1;
-            self.insts.append(&mut w2b((count << 16) | (opcode as u32)));
-            for o in operands {
-                self.insts.append(&mut w2b(o));
-            }
+        // The default implementation just skips.
+        let mut c = RetainingConsumer::new();
+        {
+            let options = ParserOptions { ignore_unknown_opcodes: true, ..Default::default() };
+            let p = Parser::new(b.get(), &mut c).options(options);
+            assert_matches!(p.parse(), Ok(()));
         }
+        assert_eq!(2, c.insts.len());
 
-        /// Returns the module being constructed.
-        fn get(&self) -> &[u8] {
-            &self.insts
+        let options = ParserOptions { ignore_unknown_opcodes: true, ..Default::default() };
+        let opcodes: Vec<spirv::Op> = ParseIterator::new(b.get())
+                                          .options(options)
+                                          .map(|inst| inst.unwrap().class)
+                                          .collect();
+        assert_eq!(vec![spirv::Op::Capability, spirv::Op::MemoryModel], opcodes);
+    }
+
+    #[test]
+    fn test_preserving_unknown_opcode() {
+        let b = module_with_unknown_opcode();
+        let mut c = RetainingConsumer::new();
+        {
+            let options = ParserOptions { preserve_unknown_opcodes: true, ..Default::default() };
+            let p = Parser::new(b.get(), &mut c).options(options);
+            assert_matches!(p.parse(), Ok(()));
         }
+        assert_eq!(3, c.insts.len());
+        assert_eq!(Some(0xffff), c.insts[1].unknown_opcode);
+        assert_eq!(vec![0x0003ffff, 0x12345678, 0x9abcdef0],
+                   c.insts[1].assemble());
+
+        let options = ParserOptions { preserve_unknown_opcodes: true, ..Default::default() };
+        let mut insts = ParseIterator::new(b.get()).options(options);
+        assert_eq!(spirv::Op::Capability, insts.next().unwrap().unwrap().class);
+        assert_eq!(Some(0xffff), insts.next().unwrap().unwrap().unknown_opcode);
+        assert_eq!(spirv::Op::MemoryModel, insts.next().unwrap().unwrap().class);
+        assert!(insts.next().is_none());
     }
 
     #[test]
-    fn test_module_builder() {
+    fn test_preserving_unknown_enumerants() {
         let mut b = ModuleBuilder::new();
-        // OpNop
-        b.inst(spirv::Op::Nop, vec![]);
-        // OpCapability Int16
-        b.inst(spirv::Op::Capability, vec![22]);
-        // OpMemoryModel Logical GLSL450
-        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
-        let mut module = ZERO_BOUND_HEADER.to_vec();
-        module.append(&mut vec![0x00, 0x00, 0x01, 0x00]); // OpNop
-        module.append(&mut vec![0x11, 0x00, 0x02, 0x00]); // OpCapability
-        module.append(&mut vec![0x16, 0x00, 0x00, 0x00]); // Int16
-        module.append(&mut vec![0x0e, 0x00, 0x03, 0x00]); // OpMemoryModel
-        module.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // Logical
-        module.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // GLSL450
-        assert_eq!(module, b.get());
+        // OpCapability <unknown>
+        b.inst(spirv::Op::Capability, vec![0xdeadbeef]);
+        // OpExecutionMode %1 <unknown> 7 8
+        b.inst(spirv::Op::ExecutionMode, vec![1, 0x9999, 7, 8]);
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_bytes(b.get(), &mut c),
+                        Err(State::OperandError(Error::EnumerantUnknown(24, "Capability", 0xdeadbeef))));
+
+        let mut c = RetainingConsumer::new();
+        {
+            let options = ParserOptions { preserve_unknown_enumerants: true, ..Default::default() };
+            let p = Parser::new(b.get(), &mut c).options(options);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(2, c.insts.len());
+        assert_eq!(vec![mr::Operand::UnknownEnumerant("Capability", 0xdeadbeef)],
+                   c.insts[0].operands);
+        assert_eq!(vec![mr::Operand::IdRef(1),
+                        mr::Operand::UnknownEnumerant("ExecutionMode", 0x9999),
+                        mr::Operand::LiteralInt32(7),
+                        mr::Operand::LiteralInt32(8)],
+                   c.insts[1].operands);
+        assert_eq!(vec![0x00020011, 0xdeadbeef], c.insts[0].assemble());
+        assert_eq!(vec![0x00050010, 1, 0x9999, 7, 8], c.insts[1].assemble());
+
+        {
+            let options = ParserOptions { preserve_unknown_enumerants: true, ..Default::default() };
+            let mut insts = ParseIterator::new(b.get()).options(options);
+            assert_eq!(spirv::Op::Capability, insts.next().unwrap().unwrap().class);
+            assert_eq!(spirv::Op::ExecutionMode, insts.next().unwrap().unwrap().class);
+            assert!(insts.next().is_none());
+        }
+
+        // Unknown values nested in enumerant parameters are still errors.
+        // OpDecorate %1 BuiltIn <unknown>
+        b.inst(spirv::Op::Decorate, vec![1, 11, 0xdead]);
+        let mut c = RetainingConsumer::new();
+        let options = ParserOptions { preserve_unknown_enumerants: true, ..Default::default() };
+        let p = Parser::new(b.get(), &mut c).options(options);
+        assert_matches!(p.parse(),
+                        Err(State::OperandError(Error::EnumerantUnknown(60, "BuiltIn", 0xdead))));
     }
 
     #[test]
-    fn test_parsing_empty_binary() {
-        let v = vec![];
+    fn test_loading_unknown_opcode() {
+        let b = module_with_unknown_opcode();
+        let mut loader = mr::Loader::new();
+        {
+            let options = ParserOptions { preserve_unknown_opcodes: true, ..Default::default() };
+            let p = Parser::new(b.get(), &mut loader).options(options);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        let module = loader.module();
+        assert_eq!(1, module.capabilities.len());
+        assert!(module.memory_model.is_some());
+        assert_eq!(1, module.types_global_values.len());
+        assert_eq!(Some(0xffff), module.types_global_values[0].unknown_opcode);
+    }
+
+    #[test]
+    fn test_ignoring_unknown_opcode_still_errors() {
+        let mut b = ModuleBuilder::new();
+        b.insts.append(&mut w2b(0x0000ffff));
         let mut c = RetainingConsumer::new();
-        let p = Parser::new(&v, &mut c);
-        assert_matches!(p.parse(),
-                        Err(State::HeaderIncomplete(Error::StreamExpected(0))));
+        {
+            let options = ParserOptions { ignore_unknown_opcodes: true, ..Default::default() };
+            let p = Parser::new(b.get(), &mut c).options(options);
+            assert_matches!(p.parse(), Err(State::WordCountZero(20, 1)));
+        }
+
+        let mut b = ModuleBuilder::new();
+        b.insts.append(&mut w2b(0x0003ffff));
+        b.insts.append(&mut w2b(0x12345678));
+        let options = ParserOptions { ignore_unknown_opcodes: true, ..Default::default() };
+        let p = Parser::new(b.get(), &mut c).options(options);
+        assert_matches!(p.parse(), Err(State::InstructionIncomplete(20, 1)));
     }
 
     #[test]
-    fn test_parsing_incomplete_header() {
-        let v = vec![0x03, 0x02, 0x23, 0x07];
+    fn test_parse_header() {
+        let mut bin = ZERO_BOUND_HEADER.to_vec();
+        // Generator: 8.
+        bin[10] = 8;
+        // Bound: 0x0102.
+        bin[12] = 2;
+        bin[13] = 1;
+        // Anything after the header is not looked at.
+        bin.append(&mut vec![0xde, 0xad]);
+        let (header, endianness) = parse_header(&bin).unwrap();
+        assert_eq!(Endianness::Little, endianness);
+        assert_eq!(spirv::MAGIC_NUMBER, header.magic_number);
+        assert_eq!((1, 0), header.version());
+        assert_eq!(("Glslang Reference Front End", 0), header.generator());
+        assert_eq!(0x0102, header.bound);
+        assert_eq!(0, header.reserved_word);
+    }
+
+    #[test]
+    fn test_parsing_header_words_preserved() {
+        let mut bin = ZERO_BOUND_HEADER.to_vec();
+        // Generator: vendor 8, version 3.
+        bin[8] = 3;
+        bin[10] = 8;
+        // Schema: 0x1234.
+        bin[16] = 0x34;
+        bin[17] = 0x12;
         let mut c = RetainingConsumer::new();
-        let p = Parser::new(&v, &mut c);
-        assert_matches!(p.parse(),
-                        Err(State::HeaderIncomplete(Error::StreamExpected(4))));
+        {
+            let p = Parser::new(&bin, &mut c);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        let header = c.header.unwrap();
+        assert_eq!(0x00080003, header.generator);
+        assert_eq!(8, header.generator_vendor());
+        assert_eq!(("Glslang Reference Front End", 3), header.generator());
+        assert_eq!(0x1234, header.reserved_word);
+        assert_eq!(vec![spirv::MAGIC_NUMBER, 0x00010000, 0x00080003, 0, 0x1234],
+                   header.assemble());
+
+        let mut c = RetainingConsumer::new();
+        {
+            let options = ParserOptions { reject_nonzero_schema: true, ..Default::default() };
+            let p = Parser::new(&bin, &mut c).options(options);
+            assert_matches!(p.parse(), Err(State::SchemaNonZero(0x1234)));
+        }
+        let options = ParserOptions { reject_nonzero_schema: true, ..Default::default() };
+        let mut insts = ParseIterator::new(&bin).options(options);
+        assert_matches!(insts.next(), Some(Err(State::SchemaNonZero(0x1234))));
+
+        let mut c = RetainingConsumer::new();
+        {
+            let options = ParserOptions { reject_nonzero_schema: true, ..Default::default() };
+            let p = Parser::new(ZERO_BOUND_HEADER, &mut c).options(options);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(Some(zero_bound_header()), c.header);
     }
 
     #[test]
-    fn test_parsing_unsupported_endianness() {
-        let mut module = ZERO_BOUND_HEADER.to_vec();
-        module.as_mut_slice().swap(0, 3);
-        module.as_mut_slice().swap(1, 2);
+    fn test_parse_header_big_endian() {
+        let bin: Vec<u8> = ZERO_BOUND_HEADER.chunks(WORD_NUM_BYTES)
+                                            .flat_map(|w| w.iter().rev().cloned())
+                                            .collect();
+        let (header, endianness) = parse_header(&bin).unwrap();
+        assert_eq!(Endianness::Big, endianness);
+        assert_eq!(spirv::MAGIC_NUMBER, header.magic_number);
+        assert_eq!((1, 0), header.version());
+    }
+
+    #[test]
+    fn test_parse_header_errors() {
+        assert_matches!(parse_header(&[]),
+                        Err(State::HeaderIncomplete(Error::StreamExpected(0))));
+        assert_matches!(parse_header(&ZERO_BOUND_HEADER[..19]),
+                        Err(State::HeaderIncomplete(Error::StreamExpected(16))));
+        let mut bin = ZERO_BOUND_HEADER.to_vec();
+        bin[0] = 0;
+        assert_matches!(parse_header(&bin), Err(State::HeaderIncorrect));
+    }
+
+    struct VersionWarningConsumer {
+        warnings: Vec<(u8, u8)>,
+        insts: usize,
+    }
+    impl Consumer for VersionWarningConsumer {
+        fn warn(&mut self, warning: Warning) -> Action {
+            if let Warning::VersionUnsupported(major, minor) = warning {
+                self.warnings.push((major, minor));
+            }
+            Action::Continue
+        }
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            self.insts += 1;
+            Action::Continue
+        }
+    }
+
+    fn module_with_version(version: spirv::Word) -> Vec<u32> {
+        vec![0x07230203, version, 0, 0, 0, 0x00020011, 0x00000001]
+    }
+
+    #[test]
+    fn test_version_1_0() {
         let mut c = RetainingConsumer::new();
-        let p = Parser::new(&module, &mut c);
-        assert_matches!(p.parse(), Err(State::EndiannessUnsupported));
+        assert_matches!(parse_words(module_with_version(0x00010000), &mut c), Ok(()));
+        assert_eq!(1, c.insts.len());
     }
 
     #[test]
-    fn test_parsing_wrong_magic_number() {
-        let mut module = ZERO_BOUND_HEADER.to_vec();
-        module[0] = 0x00;
+    fn test_version_matching_grammar() {
+        let version = (spirv::MAJOR_VERSION << 16) | (spirv::MINOR_VERSION << 8);
         let mut c = RetainingConsumer::new();
-        let p = Parser::new(&module, &mut c);
-        assert_matches!(p.parse(), Err(State::HeaderIncorrect));
+        assert_matches!(parse_words(module_with_version(version), &mut c), Ok(()));
+        assert_eq!(1, c.insts.len());
     }
 
     #[test]
-    fn test_parsing_complete_header() {
+    fn test_version_future() {
+        let future = module_with_version(0x00090900);
         let mut c = RetainingConsumer::new();
+        assert_matches!(parse_words(&future, &mut c),
+                        Err(State::VersionUnsupported(9, 9)));
+        let newer_minor = (spirv::MAJOR_VERSION << 16) | ((spirv::MINOR_VERSION + 1) << 8);
+        assert_matches!(parse_words(module_with_version(newer_minor), &mut c),
+                        Err(State::VersionUnsupported(..)));
+        assert!(ParseIterator::from_words(&future).next().unwrap().is_err());
+
+        let mut c = VersionWarningConsumer {
+            warnings: vec![],
+            insts: 0,
+        };
         {
-            let p = Parser::new(ZERO_BOUND_HEADER, &mut c);
+            let options = ParserOptions { allow_unsupported_version: true, ..Default::default() };
+            let p = Parser::from_words(&future, &mut c).options(options);
             assert_matches!(p.parse(), Ok(()));
         }
-        assert_eq!(Some(mr::ModuleHeader::new(0)), c.header);
+        assert_eq!(vec![(9, 9)], c.warnings);
+        assert_eq!(1, c.insts);
+
+        let options = ParserOptions { allow_unsupported_version: true, ..Default::default() };
+        let insts: Vec<_> = ParseIterator::from_words(&future)
+                                .options(options)
+                                .collect();
+        assert_eq!(1, insts.len());
     }
 
     #[test]
-    fn test_parsing_one_inst() {
+    fn test_version_malformed() {
         let mut c = RetainingConsumer::new();
+        for &version in &[0x01010000, 0x00010001] {
+            let words = module_with_version(version);
+            assert_matches!(parse_words(&words, &mut c), Err(State::HeaderIncorrect));
+            let options = ParserOptions { allow_unsupported_version: true, ..Default::default() };
+            let p = Parser::from_words(&words, &mut c).options(options);
+            assert_matches!(p.parse(), Err(State::HeaderIncorrect));
+        }
+    }
+
+    #[test]
+    fn test_parse_dyn() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Nop, vec![]);
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut c = CountingConsumer { count: 0 };
         {
-            let mut b = ModuleBuilder::new();
-            // OpMemoryModel Logical GLSL450
-            b.inst(spirv::Op::MemoryModel, vec![0, 1]);
-            let p = Parser::new(b.get(), &mut c);
-            assert_matches!(p.parse(), Ok(()));
+            let consumer: &mut Consumer = &mut c;
+            assert_matches!(parse_dyn(b.get(), &mut *consumer), Ok(()));
+            // Trait objects also work with the generic functions.
+            assert_matches!(parse_bytes(b.get(), &mut *consumer), Ok(()));
+            assert_matches!(Parser::new(b.get(), consumer).parse(), Ok(()));
         }
-        assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("MemoryModel", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::AddressingModel(spirv::AddressingModel::Logical),
-                        mr::Operand::MemoryModel(spirv::MemoryModel::GLSL450)],
-                   inst.operands);
+        assert_eq!(6, c.count);
     }
 
     #[test]
-    fn test_parsing_zero_word_count() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // OpNop with word count 0
+    fn test_parsing_ext_inst() {
+        let mut b = ModuleBuilder::new();
+        // %1 = OpExtInstImport "GLSL.std.450"
+        b.inst(spirv::Op::ExtInstImport,
+               vec![1, 0x4c534c47, 0x6474732e, 0x3035342e, 0x00000000]);
+        // %2 = OpExtInstImport "OpenCL.std"
+        b.inst(spirv::Op::ExtInstImport,
+               vec![2, 0x6e65704f, 0x732e4c43, 0x00006474]);
+        // %5 = OpExtInst %3 %1 FMax %4 %4
+        b.inst(spirv::Op::ExtInst, vec![3, 5, 1, 40, 4, 4]);
+        // %6 = OpExtInst %3 %2 vstore_half_r %4 %4 %4 RTZ
+        b.inst(spirv::Op::ExtInst, vec![3, 6, 2, 176, 4, 4, 4, 1]);
+        // %7 = OpExtInst %3 %8 42 %4 %4 %4 (unknown set)
+        b.inst(spirv::Op::ExtInst, vec![3, 7, 8, 42, 4, 4, 4]);
+        // %9 = OpExtInst %3 %1 1000 %4 (unknown number)
+        b.inst(spirv::Op::ExtInst, vec![3, 9, 1, 1000, 4]);
         let mut c = RetainingConsumer::new();
-        let p = Parser::new(&v, &mut c);
-        // The first instruction starts at byte offset 20.
-        assert_matches!(p.parse(), Err(State::WordCountZero(20, 1)));
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(6, c.insts.len());
+
+        assert_eq!(vec![mr::Operand::IdRef(1),
+                        mr::Operand::LiteralExtInstInteger(40),
+                        mr::Operand::IdRef(4),
+                        mr::Operand::IdRef(4)],
+                   c.insts[2].operands);
+        assert_eq!(vec![mr::Operand::IdRef(2),
+                        mr::Operand::LiteralExtInstInteger(176),
+                        mr::Operand::IdRef(4),
+                        mr::Operand::IdRef(4),
+                        mr::Operand::IdRef(4),
+                        mr::Operand::FPRoundingMode(spirv::FPRoundingMode::RTZ)],
+                   c.insts[3].operands);
+        assert_eq!(vec![mr::Operand::IdRef(8),
+                        mr::Operand::LiteralExtInstInteger(42),
+                        mr::Operand::IdRef(4),
+                        mr::Operand::IdRef(4),
+                        mr::Operand::IdRef(4)],
+                   c.insts[4].operands);
+        assert_eq!(vec![mr::Operand::IdRef(1),
+                        mr::Operand::LiteralExtInstInteger(1000),
+                        mr::Operand::IdRef(4)],
+                   c.insts[5].operands);
     }
 
     #[test]
-    fn test_parsing_extra_operand() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x00, 0x00, 0x01, 0x00]); // OpNop with word count 1
-        v.append(&mut vec![0x00, 0x00, 0x02, 0x00]); // OpNop with word count 2
-        v.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // A bogus operand
+    fn test_parsing_ext_inst_wrong_operands() {
+        let mut b = ModuleBuilder::new();
+        // %1 = OpExtInstImport "GLSL.std.450"
+        b.inst(spirv::Op::ExtInstImport,
+               vec![1, 0x4c534c47, 0x6474732e, 0x3035342e, 0x00000000]);
+        // %5 = OpExtInst %3 %1 FMax %4
+        b.inst(spirv::Op::ExtInst, vec![3, 5, 1, 40, 4]);
         let mut c = RetainingConsumer::new();
-        let p = Parser::new(&v, &mut c);
-        // The bogus operand to the second OpNop instruction starts at
-        // byte offset (20 + 4 + 4).
-        assert_matches!(p.parse(), Err(State::OperandExceeded(28, 2)));
+        assert_matches!(parse_bytes(b.get(), &mut c),
+                        Err(State::OperandExpected(68, 2)));
     }
 
     #[test]
-    fn test_parsing_missing_operand() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x00, 0x00, 0x01, 0x00]); // OpNop with word count 1
-        v.append(&mut vec![0x0e, 0x00, 0x03, 0x00]); // OpMemoryModel
-        v.append(&mut vec![0x00, 0x00, 0x00, 0x00]); // Logical
+    fn test_parsing_int16_constant() {
+        let mut b = ModuleBuilder::new();
+        // %1 = OpTypeInt 16 1
+        b.inst(spirv::Op::TypeInt, vec![1, 16, 1]);
+        // %2 = OpConstant %1 0xffff8000
+        b.inst(spirv::Op::Constant, vec![1, 2, 0xffff8000]);
         let mut c = RetainingConsumer::new();
-        let p = Parser::new(&v, &mut c);
-        // The missing operand to the OpMemoryModel instruction starts at
-        // byte offset (20 + 4 + 4 + 4).
-        assert_matches!(p.parse(),
-                        Err(State::OperandError(Error::StreamExpected(32))));
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(vec![mr::Operand::LiteralInt32(0xffff8000)], c.insts[1].operands);
     }
 
     #[test]
-    fn test_parsing_operand_parameters() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x47, 0x00, 0x04, 0x00]); // OpDecorate
-        v.append(&mut vec![0x05, 0x00, 0x00, 0x00]); // id 5
-        v.append(&mut vec![0x0b, 0x00, 0x00, 0x00]); // BuiltIn
-        v.append(&mut vec![0x06, 0x00, 0x00, 0x00]); // InstanceId
+    fn test_parsing_switch_literals() {
+        let mut b = ModuleBuilder::new();
+        // %1 = OpTypeInt 64 0
+        b.inst(spirv::Op::TypeInt, vec![1, 64, 0]);
+        // %2 = OpUndef %1
+        b.inst(spirv::Op::Undef, vec![1, 2]);
+        // %3 = OpTypeInt 32 0
+        b.inst(spirv::Op::TypeInt, vec![3, 32, 0]);
+        // %4 = OpUndef %3
+        b.inst(spirv::Op::Undef, vec![3, 4]);
+        // OpSwitch %2 %5 0x100000002 %6 7 %8
+        b.inst(spirv::Op::Switch, vec![2, 5, 2, 1, 6, 7, 0, 8]);
+        // OpSwitch %4 %5 2 %6 7 %8
+        b.inst(spirv::Op::Switch, vec![4, 5, 2, 6, 7, 8]);
         let mut c = RetainingConsumer::new();
-        {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
-        }
-        assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("Decorate", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::IdRef(5),
-                        mr::Operand::Decoration(spirv::Decoration::BuiltIn),
-                        mr::Operand::BuiltIn(spirv::BuiltIn::InstanceId)],
-                   inst.operands);
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(6, c.insts.len());
+        assert_eq!(vec![mr::Operand::IdRef(2),
+                        mr::Operand::IdRef(5),
+                        mr::Operand::LiteralInt64(0x100000002),
+                        mr::Operand::IdRef(6),
+                        mr::Operand::LiteralInt64(7),
+                        mr::Operand::IdRef(8)],
+                   c.insts[4].operands);
+        assert_eq!(vec![mr::Operand::IdRef(4),
+                        mr::Operand::IdRef(5),
+                        mr::Operand::LiteralInt32(2),
+                        mr::Operand::IdRef(6),
+                        mr::Operand::LiteralInt32(7),
+                        mr::Operand::IdRef(8)],
+                   c.insts[5].operands);
+    }
+
+    #[test]
+    fn test_parsing_non_utf8_string() {
+        let mut b = ModuleBuilder::new();
+        // OpName %1 "\xff\xfe"
+        b.inst(spirv::Op::Name, vec![1, 0x0000feff]);
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(2, c.insts.len());
+        assert_eq!(vec![mr::Operand::IdRef(1), mr::Operand::from("\u{fffd}\u{fffd}")],
+                   c.insts[0].operands);
     }
 
     #[test]
-    fn test_parsing_missing_operand_parameters() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x47, 0x00, 0x03, 0x00]); // OpDecorate
-        v.append(&mut vec![0x05, 0x00, 0x00, 0x00]); // id 5
-        v.append(&mut vec![0x0b, 0x00, 0x00, 0x00]); // BuiltIn
+    fn test_parsing_unterminated_string() {
+        let mut b = ModuleBuilder::new();
+        // OpName %1 "rust" without nul terminator
+        b.inst(spirv::Op::Name, vec![1, 0x74737572]);
+        b.inst(spirv::Op::Nop, vec![]);
         let mut c = RetainingConsumer::new();
-        let p = Parser::new(&v, &mut c);
-        assert_matches!(p.parse(),
-                        Err(State::OperandError(Error::StreamExpected(32))));
+        assert_matches!(parse_bytes(b.get(), &mut c),
+                        Err(State::OperandError(Error::DecodeStringFailed(28, _))));
+    }
+
+    struct SpanConsumer {
+        spans: Vec<(usize, usize)>,
+    }
+    impl Consumer for SpanConsumer {
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            unreachable!()
+        }
+        fn consume_instruction_span(&mut self,
+                                    context: InstContext,
+                                    _: mr::Instruction)
+                                    -> Action {
+            self.spans.push((context.offset, context.len));
+            Action::Continue
+        }
     }
 
     #[test]
-    fn test_parsing_with_all_optional_operands() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x03, 0x00, 0x05, 0x00]); // OpSource
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // GLSL
-        v.append(&mut vec![0xc2, 0x01, 0x00, 0x00]); // 450 (0x1c2)
-        v.append(&mut vec![0x06, 0x00, 0x00, 0x00]); // File id
-        v.append(&mut b"wow".to_vec());              // Source
-        v.push(0x00);                                // EOS
-        let mut c = RetainingConsumer::new();
-        {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
+    fn test_instruction_spans() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Capability, vec![1]);
+        // OpMemoryModel Logical GLSL450
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        // OpName %1 "main"
+        b.inst(spirv::Op::Name, vec![1, 0x6e69616d, 0]);
+        // %3 = OpLoad %1 %2, without the optional memory access
+        b.inst(spirv::Op::Load, vec![1, 3, 2]);
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut c = SpanConsumer { spans: vec![] };
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(vec![(20, 8), (28, 12), (40, 16), (56, 16), (72, 4)], c.spans);
+
+        // The spans tile the binary exactly after the header.
+        let mut end = 20;
+        for &(start, len) in &c.spans {
+            assert_eq!(end, start);
+            end = start + len;
         }
-        assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("Source", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL),
-                        mr::Operand::LiteralInt32(450),
-                        mr::Operand::IdRef(6),
-                        mr::Operand::from("wow")],
-                   inst.operands);
+        assert_eq!(b.get().len(), end);
+
+        let mut insts = ParseIterator::new(b.get());
+        assert_eq!(None, insts.last_span());
+        insts.next();
+        assert_eq!(Some((20, 8)), insts.last_span());
+        insts.next();
+        assert_eq!(Some((28, 12)), insts.last_span());
+    }
+
+    fn module_with_bound(bound: spirv::Word) -> ModuleBuilder {
+        let mut b = ModuleBuilder::new();
+        b.insts[12..16].copy_from_slice(&w2b(bound));
+        // %1 = OpTypeInt 32 0
+        b.inst(spirv::Op::TypeInt, vec![1, 32, 0]);
+        // %2 = OpUndef %1
+        b.inst(spirv::Op::Undef, vec![1, 2]);
+        // %3 = OpIAdd %1 %2 %2
+        b.inst(spirv::Op::IAdd, vec![1, 3, 2, 2]);
+        b
     }
 
     #[test]
-    fn test_parsing_missing_one_optional_operand() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x03, 0x00, 0x04, 0x00]); // OpSource
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // GLSL
-        v.append(&mut vec![0xc2, 0x01, 0x00, 0x00]); // 450 (0x1c2)
-        v.append(&mut vec![0x06, 0x00, 0x00, 0x00]); // File id
+    fn test_id_bound() {
+        let b = module_with_bound(4);
         let mut c = RetainingConsumer::new();
         {
-            let p = Parser::new(&v, &mut c);
+            let options = ParserOptions { check_id_bound: true, ..Default::default() };
+            let p = Parser::new(b.get(), &mut c).options(options);
             assert_matches!(p.parse(), Ok(()));
         }
-        assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("Source", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL),
-                        mr::Operand::LiteralInt32(450),
-                        mr::Operand::IdRef(6)],
-                   inst.operands);
+        assert_eq!(3, c.insts.len());
     }
 
     #[test]
-    fn test_parsing_missing_two_optional_operands() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x03, 0x00, 0x03, 0x00]); // OpSource
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // GLSL
-        v.append(&mut vec![0xc2, 0x01, 0x00, 0x00]); // 450 (0x1c2)
+    fn test_id_bound_too_small() {
+        let b = module_with_bound(3);
         let mut c = RetainingConsumer::new();
+        // Not checked by default.
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
         {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
+            let options = ParserOptions { check_id_bound: true, ..Default::default() };
+            let p = Parser::new(b.get(), &mut c).options(options);
+            assert_matches!(p.parse(), Err(State::IdOutOfBounds(48, 3, 3)));
         }
-        assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("Source", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL),
-                        mr::Operand::LiteralInt32(450)],
-                   inst.operands);
+        let options = ParserOptions { check_id_bound: true, ..Default::default() };
+        let errors: Vec<_> = ParseIterator::new(b.get())
+                                 .options(options)
+                                 .filter_map(|i| i.err())
+                                 .collect();
+        assert_eq!(1, errors.len());
+        assert_matches!(errors[0], State::IdOutOfBounds(48, 3, 3));
     }
 
-    #[derive(Debug)]
-    struct ErrorString(&'static str);
-    impl error::Error for ErrorString {
-        fn description(&self) -> &str {
-            "consumer error"
-        }
-    }
-    impl fmt::Display for ErrorString {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            let &ErrorString(ref s) = self;
-            write!(f, "{}", s)
-        }
+    #[test]
+    fn test_id_bound_zero_id() {
+        let mut b = module_with_bound(10);
+        // OpName %0 "" (the string is a single zero word)
+        b.inst(spirv::Op::Name, vec![0, 0]);
+        let mut c = RetainingConsumer::new();
+        let options = ParserOptions { check_id_bound: true, ..Default::default() };
+        let p = Parser::new(b.get(), &mut c).options(options);
+        assert_matches!(p.parse(), Err(State::IdOutOfBounds(68, 4, 0)));
     }
 
-    struct InitializeErrorConsumer;
-    impl Consumer for InitializeErrorConsumer {
-        fn initialize(&mut self) -> Action {
-            Action::Error(Box::new(ErrorString("init error")))
-        }
-        fn finalize(&mut self) -> Action {
-            Action::Continue
-        }
-        fn consume_header(&mut self, _: mr::ModuleHeader) -> Action {
+    struct TrailingBytesConsumer {
+        warnings: Vec<(usize, usize)>,
+        insts: usize,
+    }
+    impl Consumer for TrailingBytesConsumer {
+        fn warn(&mut self, warning: Warning) -> Action {
+            if let Warning::TrailingBytesIgnored(offset, count) = warning {
+                self.warnings.push((offset, count));
+            }
             Action::Continue
         }
         fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            self.insts += 1;
             Action::Continue
         }
     }
 
     #[test]
-    fn test_consumer_initialize_error() {
-        let v = vec![];
-        let mut c = InitializeErrorConsumer {};
-        let p = Parser::new(&v, &mut c);
-        let ret = p.parse();
-        assert_matches!(ret, Err(State::ConsumerError(_)));
-        if let Err(State::ConsumerError(err)) = ret {
-            assert_eq!("consumer error", err.description());
-            assert_eq!("init error", format!("{}", err));
-        } else {
-            assert!(false);
+    fn test_trailing_bytes() {
+        let mut b = ModuleBuilder::new();
+        // OpMemoryModel Logical GLSL450
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+
+        let mut v = b.get().to_vec();
+        v.append(&mut vec![0xde, 0xad]);
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_bytes(&v, &mut c), Err(State::TrailingBytes(32, 2)));
+        assert_eq!(1, c.insts.len());
+        let reader = ChunkedReader {
+            inner: io::Cursor::new(v.clone()),
+            chunk: 3,
+        };
+        assert_matches!(parse_reader(reader, &mut c), Err(State::TrailingBytes(32, 2)));
+        let mut insts = ParseIterator::new(&v);
+        assert!(insts.next().unwrap().is_ok());
+        assert_matches!(insts.next(), Some(Err(State::TrailingBytes(32, 2))));
+
+        let mut c = TrailingBytesConsumer {
+            warnings: vec![],
+            insts: 0,
+        };
+        {
+            let options = ParserOptions { allow_trailing_bytes: true, ..Default::default() };
+            let p = Parser::new(&v, &mut c).options(options);
+            assert_matches!(p.parse(), Ok(()));
         }
+        assert_eq!(vec![(32, 2)], c.warnings);
+        assert_eq!(1, c.insts);
+        let options = ParserOptions { allow_trailing_bytes: true, ..Default::default() };
+        assert_eq!(1,
+                   ParseIterator::new(&v)
+                       .options(options)
+                       .map(|i| i.unwrap())
+                       .count());
     }
 
-    struct FinalizeErrorConsumer;
-    impl Consumer for FinalizeErrorConsumer {
-        fn initialize(&mut self) -> Action {
-            Action::Continue
-        }
-        fn finalize(&mut self) -> Action {
-            Action::Error(Box::new(ErrorString("fin error")))
-        }
-        fn consume_header(&mut self, _: mr::ModuleHeader) -> Action {
-            Action::Continue
-        }
-        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
-            Action::Continue
+    #[test]
+    fn test_malformed_word_counts() {
+        fn parse(insts: Vec<spirv::Word>) -> super::Result<()> {
+            let mut b = ModuleBuilder::new();
+            for word in insts {
+                b.insts.append(&mut w2b(word));
+            }
+            let mut c = RetainingConsumer::new();
+            parse_bytes(b.get(), &mut c)
         }
+
+        // OpCapability with no operand words
+        assert_matches!(parse(vec![0x00010011]), Err(State::OperandExpected(24, 1)));
+        // OpCapability declaring far more words than exist
+        assert_matches!(parse(vec![0xffff0011, 0x00000001]),
+                        Err(State::InstructionIncomplete(20, 1)));
+        assert_matches!(parse(vec![0x00020011]), Err(State::InstructionIncomplete(20, 1)));
+        assert_matches!(parse(vec![0x00020011, 0x00000001, 0xfffe0011]),
+                        Err(State::InstructionIncomplete(28, 2)));
+        // OpCapability with one extra word
+        assert_matches!(parse(vec![0x00030011, 0x00000001, 0x00000001]),
+                        Err(State::OperandExceeded(28, 1)));
+        // Bad operands are reported instead of the words left behind them
+        assert_matches!(parse(vec![0x00030011, 0xdeadbeef, 0x00000001]),
+                        Err(State::OperandError(Error::EnumerantUnknown(24, "Capability", 0xdeadbeef))));
+        // The word count is checked before looking up the opcode
+        assert_matches!(parse(vec![0xffffffff]), Err(State::InstructionIncomplete(20, 1)));
+        assert_matches!(parse(vec![0x0001ffff]), Err(State::OpcodeUnknown(20, 1, 0xffff)));
+
+        let mut b = ModuleBuilder::new();
+        b.insts.append(&mut w2b(0xffffffff));
+        let options = ParserOptions { preserve_unknown_opcodes: true, ..Default::default() };
+        let mut insts = ParseIterator::new(b.get()).options(options);
+        assert_matches!(insts.next(), Some(Err(State::InstructionIncomplete(20, 1))));
+        let words: Vec<spirv::Word> = vec![0x07230203, 0x00010000, 0, 0, 0, 0xffff0000];
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_words(&words, &mut c), Err(State::InstructionIncomplete(20, 1)));
     }
 
     #[test]
-    fn test_consumer_finalize_error() {
-        let mut c = FinalizeErrorConsumer {};
-        let p = Parser::new(ZERO_BOUND_HEADER, &mut c);
-        let ret = p.parse();
-        assert_matches!(ret, Err(State::ConsumerError(_)));
-        if let Err(State::ConsumerError(err)) = ret {
-            assert_eq!("consumer error", err.description());
-            assert_eq!("fin error", format!("{}", err));
-        } else {
-            assert!(false);
+    fn test_parse_collecting_errors() {
+        let mut b = ModuleBuilder::new();
+        // OpCapability <unknown>
+        b.insts.append(&mut w2b(0x00020011));
+        b.insts.append(&mut w2b(0xdeadbeef));
+        // OpCapability Shader
+        b.inst(spirv::Op::Capability, vec![1]);
+        // Unknown opcode with one operand
+        b.insts.append(&mut w2b(0x0002ffff));
+        b.insts.append(&mut w2b(0x00000001));
+        // OpMemoryModel Logical GLSL450 <extra>
+        b.inst(spirv::Op::MemoryModel, vec![0, 1, 2]);
+        // OpNop
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(b.get(), &mut c);
+            let errors = p.parse_collecting_errors().unwrap();
+            assert_eq!(3, errors.len());
+            assert_matches!(errors[0],
+                            State::OperandError(Error::EnumerantUnknown(24, "Capability", 0xdeadbeef)));
+            assert_matches!(errors[1], State::OpcodeUnknown(36, 3, 0xffff));
+            assert_matches!(errors[2], State::OperandExceeded(56, 4));
         }
+        assert!(c.header.is_some());
+        assert_eq!(2, c.insts.len());
+        assert_eq!(spirv::Op::Capability, c.insts[0].class);
+        assert_eq!(spirv::Op::Nop, c.insts[1].class);
+
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(b.get(), &mut c);
+        assert_matches!(p.parse(),
+                        Err(State::OperandError(Error::EnumerantUnknown(24, "Capability", 0xdeadbeef))));
     }
 
-    struct ParseHeaderErrorConsumer;
-    impl Consumer for ParseHeaderErrorConsumer {
-        fn initialize(&mut self) -> Action {
-            Action::Continue
-        }
-        fn finalize(&mut self) -> Action {
-            Action::Continue
-        }
-        fn consume_header(&mut self, _: mr::ModuleHeader) -> Action {
-            Action::Error(Box::new(ErrorString("parse header error")))
-        }
-        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
-            Action::Continue
+    #[test]
+    fn test_parse_collecting_errors_still_aborts() {
+        let mut b = ModuleBuilder::new();
+        // OpCapability <unknown>
+        b.insts.append(&mut w2b(0x00020011));
+        b.insts.append(&mut w2b(0xdeadbeef));
+        // Zero word count
+        b.insts.append(&mut w2b(0x00000000));
+        // OpNop
+        b.inst(spirv::Op::Nop, vec![]);
+        let mut c = RetainingConsumer::new();
+        {
+            let p = Parser::new(b.get(), &mut c);
+            assert_matches!(p.parse_collecting_errors(), Err(State::WordCountZero(28, 2)));
         }
+        assert_eq!(0, c.insts.len());
+
+        let mut c = RetainingConsumer::new();
+        let p = Parser::new(&ZERO_BOUND_HEADER[..8], &mut c);
+        assert_matches!(p.parse_collecting_errors(),
+                        Err(State::HeaderIncomplete(Error::StreamExpected(8))));
     }
 
     #[test]
-    fn test_consumer_parse_header_error() {
-        let mut c = ParseHeaderErrorConsumer {};
-        let p = Parser::new(ZERO_BOUND_HEADER, &mut c);
-        let ret = p.parse();
-        assert_matches!(ret, Err(State::ConsumerError(_)));
-        if let Err(State::ConsumerError(err)) = ret {
-            assert_eq!("consumer error", err.description());
-            assert_eq!("parse header error", format!("{}", err));
-        } else {
-            assert!(false);
+    fn test_parser_stepping() {
+        let mut b = ModuleBuilder::new();
+        // OpCapability Shader
+        b.inst(spirv::Op::Capability, vec![1]);
+        // Unknown opcode
+        b.insts.append(&mut w2b(0x0001ffff));
+        // OpMemoryModel Logical GLSL450
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        let mut c = RetainingConsumer::new();
+        {
+            let options = ParserOptions { ignore_unknown_opcodes: true, ..Default::default() };
+            let mut p = Parser::new(b.get(), &mut c).options(options);
+            assert_eq!(None, p.last_span());
+            assert_eq!(0, p.parse_header().unwrap().bound);
+            let inst = p.next_instruction().unwrap().unwrap();
+            assert_eq!(spirv::Op::Capability, inst.class);
+            assert_eq!(Some((20, 8)), p.last_span());
+            let inst = p.next_instruction().unwrap().unwrap();
+            assert_eq!(spirv::Op::MemoryModel, inst.class);
+            assert_eq!(Some((32, 12)), p.last_span());
+            assert_matches!(p.next_instruction(), Ok(None));
         }
-    }
+        assert!(c.header.is_none());
+        assert_eq!(0, c.insts.len());
 
-    struct ParseInstErrorConsumer;
-    impl Consumer for ParseInstErrorConsumer {
-        fn initialize(&mut self) -> Action {
-            Action::Continue
+        {
+            // The header is parsed implicitly.
+            let mut p = Parser::new(b.get(), &mut c);
+            assert!(p.next_instruction().unwrap().is_some());
+            assert_matches!(p.next_instruction(), Err(State::OpcodeUnknown(28, 2, 0xffff)));
         }
-        fn finalize(&mut self) -> Action {
-            Action::Continue
+
+        let mut p = Parser::new(&ZERO_BOUND_HEADER[..4], &mut c);
+        assert_matches!(p.next_instruction(),
+                        Err(State::HeaderIncomplete(Error::StreamExpected(4))));
+    }
+
+    struct LocationConsumer {
+        locations: Vec<Option<SourceLocation>>,
+    }
+    impl Consumer for LocationConsumer {
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            panic!("should be called with the location")
         }
-        fn consume_header(&mut self, _: mr::ModuleHeader) -> Action {
+        fn consume_instruction_span(&mut self,
+                                    context: InstContext,
+                                    _: mr::Instruction)
+                                    -> Action {
+            self.locations.push(context.location);
             Action::Continue
         }
-        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
-            Action::Error(Box::new(ErrorString("parse inst error")))
-        }
     }
 
     #[test]
-    fn test_consumer_parse_inst_error() {
+    fn test_source_locations() {
         let mut b = ModuleBuilder::new();
-        b.inst(spirv::Op::Nop, vec![]);
-        let mut c = ParseInstErrorConsumer {};
-        let p = Parser::new(b.get(), &mut c);
-        let ret = p.parse();
-        assert_matches!(ret, Err(State::ConsumerError(_)));
-        if let Err(State::ConsumerError(err)) = ret {
-            assert_eq!("consumer error", err.description());
-            assert_eq!("parse inst error", format!("{}", err));
-        } else {
-            assert!(false);
-        }
-    }
+        b.inst(spirv::Op::String, vec![1, 0x00000061]);
+        b.inst(spirv::Op::Line, vec![1, 10, 2]);
+        b.inst(spirv::Op::Function, vec![3, 4, 0, 5]);
+        b.inst(spirv::Op::Label, vec![6]);
+        b.inst(spirv::Op::Line, vec![1, 20, 3]);
+        b.inst(spirv::Op::Branch, vec![7]);
+        b.inst(spirv::Op::Label, vec![7]);
+        b.inst(spirv::Op::Line, vec![1, 30, 4]);
+        b.inst(spirv::Op::NoLine, vec![]);
+        b.inst(spirv::Op::Line, vec![1, 40, 5]);
+        b.inst(spirv::Op::Return, vec![]);
+        b.inst(spirv::Op::Line, vec![1, 50, 6]);
+        b.inst(spirv::Op::FunctionEnd, vec![]);
+        let loc = |line, column| {
+            Some(SourceLocation {
+                file: 1,
+                line: line,
+                column: column,
+            })
+        };
+        let expected = vec![None, None, loc(10, 2), loc(10, 2), loc(10, 2), loc(20, 3), None,
+                            None, loc(30, 4), None, loc(40, 5), None, loc(50, 6)];
 
-    #[test]
-    fn test_parsing_int32() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x15, 0x00, 0x04, 0x00]); // OpTypeInt
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result id: 1
-        v.append(&mut vec![0x20, 0x00, 0x00, 0x00]); // 32
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // 1 (signed)
+        let mut c = LocationConsumer { locations: vec![] };
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        assert_eq!(expected, c.locations);
 
-        v.append(&mut vec![0x2b, 0x00, 0x04, 0x00]); // OpConstant
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
-        v.append(&mut vec![0x12, 0x34, 0x56, 0x78]);
-        let mut c = RetainingConsumer::new();
-        {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
+        let mut insts = ParseIterator::new(b.get());
+        assert_eq!(None, insts.last_location());
+        let mut locations = vec![];
+        while let Some(inst) = insts.next() {
+            inst.unwrap();
+            locations.push(insts.last_location());
+        }
+        assert_eq!(expected, locations);
+    }
+
+    struct BorrowingConsumer {
+        opcodes: Vec<(usize, spirv::Op)>,
+    }
+    impl Consumer for BorrowingConsumer {
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            panic!("should be lent the instruction")
+        }
+        fn consume_instruction_ref(&mut self,
+                                   context: InstContext,
+                                   inst: &mr::Instruction)
+                                   -> Action {
+            self.opcodes.push((context.offset, inst.class));
+            Action::Continue
         }
-        assert_eq!(2, c.insts.len());
-        let inst = &c.insts[1];
-        assert_eq!("Constant", inst.class.opname);
-        assert_eq!(Some(1), inst.result_type);
-        assert_eq!(Some(2), inst.result_id);
-        assert_eq!(vec![mr::Operand::LiteralInt32(0x78563412)], inst.operands);
     }
 
     #[test]
-    fn test_parsing_int64() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x15, 0x00, 0x04, 0x00]); // OpTypeInt
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result id: 1
-        v.append(&mut vec![0x40, 0x00, 0x00, 0x00]); // 64
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // 1 (signed)
+    fn test_lending_instructions() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Capability, vec![1]);
+        b.inst(spirv::Op::Decorate, vec![1, 30, 2]);
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
 
-        v.append(&mut vec![0x2b, 0x00, 0x05, 0x00]); // OpConstant
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
-        v.append(&mut vec![0x12, 0x34, 0x56, 0x78]);
-        v.append(&mut vec![0x90, 0xab, 0xcd, 0xef]);
-        let mut c = RetainingConsumer::new();
+        let mut c = BorrowingConsumer { opcodes: vec![] };
         {
-            let p = Parser::new(&v, &mut c);
+            let p = Parser::new(b.get(), &mut c).lend_instructions(true);
             assert_matches!(p.parse(), Ok(()));
         }
-        assert_eq!(2, c.insts.len());
-        let inst = &c.insts[1];
-        assert_eq!("Constant", inst.class.opname);
-        assert_eq!(Some(1), inst.result_type);
-        assert_eq!(Some(2), inst.result_id);
-        assert_eq!(vec![mr::Operand::LiteralInt64(0xefcdab9078563412)],
-                   inst.operands);
-    }
+        assert_eq!(vec![(20, spirv::Op::Capability),
+                        (28, spirv::Op::Decorate),
+                        (44, spirv::Op::MemoryModel)],
+                   c.opcodes);
 
-    #[test]
-    fn test_parsing_float32() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x16, 0x00, 0x03, 0x00]); // OpTypeFloat
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result id: 1
-        v.append(&mut vec![0x20, 0x00, 0x00, 0x00]); // 32
+        // Consumers taking instructions get clones, with their positions.
+        let mut c = SpanConsumer { spans: vec![] };
+        {
+            let p = Parser::new(b.get(), &mut c).lend_instructions(true);
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(vec![(20, 8), (28, 16), (44, 12)], c.spans);
 
-        v.append(&mut vec![0x2b, 0x00, 0x04, 0x00]); // OpConstant
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
-        v.append(&mut f32_to_bytes(42.42));
+        // Consumers only taking instructions get clones.
         let mut c = RetainingConsumer::new();
         {
-            let p = Parser::new(&v, &mut c);
+            let p = Parser::new(b.get(), &mut c).lend_instructions(true);
             assert_matches!(p.parse(), Ok(()));
         }
-        assert_eq!(2, c.insts.len());
-        let inst = &c.insts[1];
-        assert_eq!("Constant", inst.class.opname);
-        assert_eq!(Some(1), inst.result_type);
-        assert_eq!(Some(2), inst.result_id);
-        assert_eq!(vec![mr::Operand::LiteralFloat32(42.42)], inst.operands);
+        assert_eq!(3, c.insts.len());
+        assert_eq!(vec![mr::Operand::IdRef(1),
+                        mr::Operand::Decoration(spirv::Decoration::Location),
+                        mr::Operand::LiteralInt32(2)],
+                   c.insts[1].operands);
+        assert_eq!(vec![mr::Operand::AddressingModel(spirv::AddressingModel::Logical),
+                        mr::Operand::MemoryModel(spirv::MemoryModel::GLSL450)],
+                   c.insts[2].operands);
     }
 
     #[test]
-    fn test_parsing_float64() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x16, 0x00, 0x03, 0x00]); // OpTypeFloat
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result id: 1
-        v.append(&mut vec![0x40, 0x00, 0x00, 0x00]); // 64
+    fn test_limits() {
+        fn parse(p: Parser<RetainingConsumer>) -> super::Result<()> {
+            p.parse()
+        }
+
+        let mut b = ModuleBuilder::new();
+        b.insts[12] = 5; // Bound
+        b.inst(spirv::Op::Capability, vec![1]);
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        b.inst(spirv::Op::Function, vec![1, 2, 0, 3]);
+        b.inst(spirv::Op::FunctionEnd, vec![]);
 
-        v.append(&mut vec![0x2b, 0x00, 0x05, 0x00]); // OpConstant
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
-        v.append(&mut f64_to_bytes(-12.34));
         let mut c = RetainingConsumer::new();
-        {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
-        }
-        assert_eq!(2, c.insts.len());
-        let inst = &c.insts[1];
-        assert_eq!("Constant", inst.class.opname);
-        assert_eq!(Some(1), inst.result_type);
-        assert_eq!(Some(2), inst.result_id);
-        assert_eq!(vec![mr::Operand::LiteralFloat64(-12.34)], inst.operands);
+        assert_matches!(parse_bytes(b.get(), &mut c), Ok(()));
+        let options = ParserOptions {
+            max_id_bound: Some(5),
+            max_instruction_count: Some(4),
+            max_function_count: Some(1),
+            max_binary_words: Some(16),
+            ..Default::default()
+        };
+        assert_matches!(parse(Parser::new(b.get(), &mut c).options(options)),
+                        Ok(()));
+        let options = ParserOptions { max_id_bound: Some(4), ..Default::default() };
+        assert_matches!(parse(Parser::new(b.get(), &mut c).options(options)),
+                        Err(State::LimitExceeded(12, Limit::IdBound(4))));
+        let options = ParserOptions { max_instruction_count: Some(3), ..Default::default() };
+        assert_matches!(parse(Parser::new(b.get(), &mut c).options(options)),
+                        Err(State::LimitExceeded(60, Limit::InstructionCount(3))));
+        let options = ParserOptions { max_function_count: Some(0), ..Default::default() };
+        assert_matches!(parse(Parser::new(b.get(), &mut c).options(options)),
+                        Err(State::LimitExceeded(40, Limit::FunctionCount(0))));
+        let options = ParserOptions { max_binary_words: Some(15), ..Default::default() };
+        assert_matches!(parse(Parser::new(b.get(), &mut c).options(options)),
+                        Err(State::LimitExceeded(0, Limit::BinaryWords(15))));
+        // The size of the binary is not known up front for readers.
+        let options = ParserOptions { max_binary_words: Some(15), ..Default::default() };
+        assert_matches!(parse(Parser::from_reader(b.get(), &mut c).options(options)),
+                        Err(State::LimitExceeded(60, Limit::BinaryWords(15))));
+
+        let options = ParserOptions { max_instruction_count: Some(1), ..Default::default() };
+        let mut insts = ParseIterator::new(b.get()).options(options);
+        assert_matches!(insts.next(), Some(Ok(_)));
+        assert_matches!(insts.next(),
+                        Some(Err(State::LimitExceeded(28, Limit::InstructionCount(1)))));
     }
 
     #[test]
-    fn test_parsing_spec_constant_op() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x34, 0x00, 0x05, 0x00]); // OpTypeFloat
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
-        v.append(&mut vec![0x7e, 0x00, 0x00, 0x00]); // OpSNegate
-        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // id ref: 3
+    fn test_incremental_parser() {
+        let mut b = ModuleBuilder::new();
+        b.inst(spirv::Op::Capability, vec![1]);
+        b.inst(spirv::Op::Source, vec![2, 450, 0x006c6966]);
+        b.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        let mut expected = RetainingConsumer::new();
+        parse_bytes(b.get(), &mut expected).unwrap();
+        let expected: Vec<Vec<spirv::Word>> = expected.insts.iter().map(|i| i.assemble()).collect();
+
+        for size in vec![1, 3, 4, 7, 100] {
+            let mut c = RetainingConsumer::new();
+            {
+                let mut p = IncrementalParser::new(&mut c);
+                for chunk in b.get().chunks(size) {
+                    assert_matches!(p.feed(chunk), Ok(()));
+                }
+                assert_matches!(p.finish(), Ok(()));
+            }
+            assert_eq!(0, c.header.unwrap().bound);
+            let insts: Vec<Vec<spirv::Word>> = c.insts.iter().map(|i| i.assemble()).collect();
+            assert_eq!(expected, insts);
+        }
+
+        // Instructions are consumed as soon as they are complete.
         let mut c = RetainingConsumer::new();
         {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
+            let mut p = IncrementalParser::new(&mut c);
+            assert_matches!(p.feed(&b.get()[..32]), Ok(()));
+            assert_matches!(p.finish(), Err(State::InputIncomplete(28, 3)));
         }
         assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("SpecConstantOp", inst.class.opname);
-        assert_eq!(Some(1), inst.result_type);
-        assert_eq!(Some(2), inst.result_id);
-        assert_eq!(vec![mr::Operand::LiteralSpecConstantOpInteger(spirv::Op::SNegate),
-                        mr::Operand::IdRef(3)],
-                   inst.operands);
+
+        let mut c = RetainingConsumer::new();
+        {
+            let mut p = IncrementalParser::new(&mut c);
+            assert_matches!(p.feed(&b.get()[..6]), Ok(()));
+            assert_matches!(p.finish(), Err(State::InputIncomplete(0, 4)));
+        }
+        let p = IncrementalParser::new(&mut c);
+        assert_matches!(p.finish(), Err(State::InputIncomplete(0, 5)));
     }
 
     #[test]
-    fn test_parsing_spec_constant_op_missing_parameter() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x34, 0x00, 0x05, 0x00]); // OpTypeFloat
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // result type: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // result id: 2
-        v.append(&mut vec![0x80, 0x00, 0x00, 0x00]); // OpIAdd
-        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // id ref: 3
+    fn test_incremental_parser_errors() {
+        let mut b = ModuleBuilder::new();
+        b.insts.append(&mut w2b(0x00000011));
         let mut c = RetainingConsumer::new();
-        let p = Parser::new(&v, &mut c);
-        assert_matches!(p.parse(),
-                        // The header has 5 words, the above instruction has 5 words,
-                        // so in total 40 bytes.
-                        Err(State::OperandError(Error::LimitReached(40))));
+        let mut p = IncrementalParser::new(&mut c);
+        assert_matches!(p.feed(&b.get()[..22]), Ok(()));
+        assert_matches!(p.feed(&b.get()[22..]), Err(State::WordCountZero(20, 1)));
+        // Further input is ignored.
+        assert_matches!(p.feed(&w2b(0x00020011)), Ok(()));
+        assert_matches!(p.finish(), Ok(()));
     }
 
     #[test]
-    fn test_parsing_bitmasks_requiring_params_no_mem_access() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x3e, 0x00, 0x03, 0x00]); // OpStore
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // pointer: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // object: 2
-        let mut c = RetainingConsumer::new();
+    fn test_parse_multi() {
+        let mut first = ModuleBuilder::new();
+        // OpCapability Shader
+        first.inst(spirv::Op::Capability, vec![1]);
+        let mut second = ModuleBuilder::new();
+        // OpMemoryModel Logical GLSL450
+        second.inst(spirv::Op::MemoryModel, vec![0, 1]);
+        // OpNop
+        second.inst(spirv::Op::Nop, vec![]);
+        let mut v = first.get().to_vec();
+        v.extend_from_slice(second.get());
+        // The third module is byte-swapped.
+        for chunk in first.get().chunks(WORD_NUM_BYTES) {
+            v.extend(chunk.iter().rev());
+        }
+
+        let mut headers = 0;
+        let mut opcodes = vec![];
         {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
+            let mut c = on_header_and_inst(|_| {
+                                               headers += 1;
+                                               Action::Continue
+                                           },
+                                           |inst| {
+                                               opcodes.push(inst.class);
+                                               Action::Continue
+                                           });
+            assert_matches!(parse_multi(&v, &mut c), Ok(3));
         }
+        assert_eq!(3, headers);
+        assert_eq!(vec![spirv::Op::Capability,
+                        spirv::Op::MemoryModel,
+                        spirv::Op::Nop,
+                        spirv::Op::Capability],
+                   opcodes);
+
+        // A single module is parsed as is.
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_multi(first.get(), &mut c), Ok(1));
         assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("Store", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::IdRef(1), mr::Operand::IdRef(2)],
-                   inst.operands);
+        // Without parse_multi, the next header is taken as an instruction.
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_bytes(&v, &mut c), Err(State::InstructionIncomplete(28, 2)));
     }
+
     #[test]
-    fn test_parsing_bitmasks_requiring_params_mem_access_no_param() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x3e, 0x00, 0x04, 0x00]); // OpStore
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // pointer: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // object: 2
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // Volatile
+    fn test_parse_multi_errors() {
+        let mut b = ModuleBuilder::new();
+        // OpCapability Shader
+        b.inst(spirv::Op::Capability, vec![1]);
+        let mut v = b.get().to_vec();
+        // Dangling magic number and version.
+        v.extend_from_slice(&ZERO_BOUND_HEADER[..8]);
         let mut c = RetainingConsumer::new();
-        {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
-        }
+        assert_matches!(parse_multi(&v, &mut c),
+                        Err(State::ModuleHeaderIncomplete(2, Error::StreamExpected(36))));
         assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("Store", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::IdRef(1),
-                        mr::Operand::IdRef(2),
-                        mr::Operand::MemoryAccess(spirv::MEMORY_ACCESS_VOLATILE)],
-                   inst.operands);
+
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_multi(&ZERO_BOUND_HEADER[..4], &mut c),
+                        Err(State::ModuleHeaderIncomplete(1, Error::StreamExpected(4))));
+
+        // Errors in later modules are reported with offsets from the start.
+        let mut v = b.get().to_vec();
+        v.extend_from_slice(ZERO_BOUND_HEADER);
+        v.append(&mut w2b(0x00000000));
+        let mut c = RetainingConsumer::new();
+        assert_matches!(parse_multi(&v, &mut c), Err(State::WordCountZero(48, 1)));
     }
+
     #[test]
-    fn test_parsing_bitmasks_requiring_params_mem_access_with_param() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x3e, 0x00, 0x05, 0x00]); // OpStore
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // pointer: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // object: 2
-        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // Volatile & Aligned
-        v.append(&mut vec![0x04, 0x00, 0x00, 0x00]); // alignment
-        let mut c = RetainingConsumer::new();
-        {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
-        }
-        assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("Store", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::IdRef(1),
-                        mr::Operand::IdRef(2),
-                        mr::Operand::MemoryAccess(spirv::MemoryAccess::from_bits(3).unwrap()),
-                        mr::Operand::LiteralInt32(4)],
-                   inst.operands);
+    fn test_error_offsets() {
+        let e = State::OperandExceeded(148, 3);
+        assert_eq!(Some(148), e.offset());
+        assert_eq!(Some(37), e.word_offset());
+        assert_eq!(Some(3), e.inst_index());
+        assert_eq!("found extra operands for instruction #3 at word 37 (byte 148)",
+                   format!("{}", e));
+
+        let e = State::OperandError(Error::EnumerantUnknown(24, "Capability", 0xdeadbeef));
+        assert_eq!(Some(24), e.offset());
+        assert_eq!(Some(6), e.word_offset());
+        assert_eq!(None, e.inst_index());
+        assert_eq!("operand decoding error: unknown value 3735928559 for operand kind \
+                    Capability at word 6 (byte 24)",
+                   format!("{}", e));
+
+        let e = State::HeaderIncomplete(Error::StreamExpected(8));
+        assert_eq!(Some(2), e.word_offset());
+        assert_eq!(None, State::HeaderIncorrect.offset());
+        assert_eq!(None, State::Complete.inst_index());
     }
+
     #[test]
-    fn test_parsing_bitmasks_requiring_params_mem_access_missing_param() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x3e, 0x00, 0x04, 0x00]); // OpStore
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // pointer: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // object: 2
-        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // Volatile & Aligned
-        let mut c = RetainingConsumer::new();
-        let p = Parser::new(&v, &mut c);
-        assert_matches!(p.parse(),
-                        // The header has 5 words, the above instruction has 4 words,
-                        // so in total 36 bytes.
-                        Err(State::OperandError(Error::LimitReached(36))));
+    fn test_parse_operand_errors() {
+        let e = parse_operand(GOpKind::IdRef, &[1, 2]).unwrap_err();
+        assert_matches!(e, State::LoneOperandIncorrect(4));
+        assert_eq!(None, e.inst_index());
+        assert_eq!("words not forming a lone operand of the requested kind at word 1 (byte 4)",
+                   format!("{}", e));
+        assert_matches!(parse_operand(GOpKind::LiteralSpecConstantOpInteger, &[0xfff0]),
+                        Err(State::LoneOperandIncorrect(0)));
+    }
+
+    struct FlagBitsConsumer {
+        warnings: Vec<(usize, &'static str, spirv::Word)>,
+        insts: Vec<mr::Instruction>,
+    }
+    impl Consumer for FlagBitsConsumer {
+        fn warn(&mut self, warning: Warning) -> Action {
+            if let Warning::UnknownFlagBitsDropped(offset, kind, bits) = warning {
+                self.warnings.push((offset, kind, bits));
+            }
+            Action::Continue
+        }
+        fn consume_instruction(&mut self, inst: mr::Instruction) -> Action {
+            self.insts.push(inst);
+            Action::Continue
+        }
     }
+
     #[test]
-    fn test_parsing_bitmasks_requiring_params_img_operands_param_order() {
-        let mut v = ZERO_BOUND_HEADER.to_vec();
-        v.append(&mut vec![0x63, 0x00, 0x08, 0x00]); // OpStore
-        v.append(&mut vec![0x01, 0x00, 0x00, 0x00]); // image: 1
-        v.append(&mut vec![0x02, 0x00, 0x00, 0x00]); // coordinate: 2
-        v.append(&mut vec![0x03, 0x00, 0x00, 0x00]); // texel: 3
-        v.append(&mut vec![0x05, 0x00, 0x00, 0x00]); // Bias & GRAD
-        v.append(&mut vec![0xaa, 0x00, 0x00, 0x00]); // bias
-        v.append(&mut vec![0xbb, 0x00, 0x00, 0x00]); // dx
-        v.append(&mut vec![0xcc, 0x00, 0x00, 0x00]); // dy
+    fn test_unknown_flag_bits() {
+        let mut b = ModuleBuilder::new();
+        // OpLoopMerge %1 %2 Unroll|0x100
+        b.inst(spirv::Op::LoopMerge, vec![1, 2, 0x00000101]);
+        // OpCapability <unknown>
+        b.inst(spirv::Op::Capability, vec![0xdeadbeef]);
         let mut c = RetainingConsumer::new();
         {
-            let p = Parser::new(&v, &mut c);
-            assert_matches!(p.parse(), Ok(()));
+            let p = Parser::new(b.get(), &mut c);
+            assert_matches!(p.parse(),
+                            Err(State::OperandError(Error::FlagBitsUnknown(32,
+                                                                           "LoopControl",
+                                                                           0x100))));
+        }
+
+        let mut c = FlagBitsConsumer {
+            warnings: vec![],
+            insts: vec![],
+        };
+        {
+            let options = ParserOptions { allow_unknown_flag_bits: true, ..Default::default() };
+            let p = Parser::new(b.get(), &mut c).options(options);
+            assert_matches!(p.parse(),
+                            Err(State::OperandError(Error::EnumerantUnknown(40, "Capability",
+                                                                            0xdeadbeef))));
         }
+        assert_eq!(vec![(32, "LoopControl", 0x100)], c.warnings);
         assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("ImageWrite", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::IdRef(1),
-                        mr::Operand::IdRef(2),
-                        mr::Operand::IdRef(3),
-                        mr::Operand::ImageOperands(spirv::ImageOperands::from_bits(5).unwrap()),
-                        mr::Operand::IdRef(0xaa),
-                        mr::Operand::IdRef(0xbb),
-                        mr::Operand::IdRef(0xcc)],
-                   inst.operands);
+        assert_eq!(mr::Operand::LoopControl(spirv::LOOP_CONTROL_UNROLL),
+                   c.insts[0].operands[2]);
+
+        let options = ParserOptions { allow_unknown_flag_bits: true, ..Default::default() };
+        let mut insts = ParseIterator::new(b.get()).options(options);
+        assert!(insts.next().unwrap().is_ok());
+    }
+    struct WarningConsumer {
+        warnings: Vec<Warning>,
+        insts: usize,
+    }
+    impl Consumer for WarningConsumer {
+        fn warn(&mut self, warning: Warning) -> Action {
+            self.warnings.push(warning);
+            Action::Continue
+        }
+        fn consume_instruction(&mut self, _: mr::Instruction) -> Action {
+            self.insts += 1;
+            Action::Continue
+        }
     }
 
     #[test]
-    fn test_parse_words() {
-        let words = vec![0x07230203, 0x01000000, 0, 0, 0, 0x00020011, 0x00000016];
-        let mut c = RetainingConsumer::new();
-        assert_matches!(parse_words(&words, &mut c), Ok(()));
-        assert_eq!(1, c.insts.len());
-        let inst = &c.insts[0];
-        assert_eq!("Capability", inst.class.opname);
-        assert_eq!(None, inst.result_type);
-        assert_eq!(None, inst.result_id);
-        assert_eq!(vec![mr::Operand::Capability(spirv::Capability::Int16)],
-                   inst.operands);
+    fn test_warnings() {
+        let mut b = ModuleBuilder::new();
+        b.insts[5] = 9; // Version 1.9
+        // OpLoopMerge %1 %2 Unroll|0x100
+        b.inst(spirv::Op::LoopMerge, vec![1, 2, 0x00000101]);
+        // OpCapability <unknown>
+        b.inst(spirv::Op::Capability, vec![0xdeadbeef]);
+        b.insts.append(&mut vec![0xde, 0xad]);
+
+        let options = ParserOptions {
+            allow_unsupported_version: true,
+            allow_unknown_flag_bits: true,
+            preserve_unknown_enumerants: true,
+            allow_trailing_bytes: true,
+            ..Default::default()
+        };
+        let expected = vec![Warning::VersionUnsupported(1, 9),
+                            Warning::UnknownFlagBitsDropped(32, "LoopControl", 0x100),
+                            Warning::UnknownEnumerantPreserved(40, "Capability", 0xdeadbeef),
+                            Warning::TrailingBytesIgnored(44, 2)];
+
+        let mut c = WarningConsumer {
+            warnings: vec![],
+            insts: 0,
+        };
+        {
+            let p = Parser::new(b.get(), &mut c).options(options.clone());
+            assert_matches!(p.parse(), Ok(()));
+        }
+        assert_eq!(expected, c.warnings);
+        assert_eq!(2, c.insts);
+        assert_eq!("module version 1.9 is not supported by grammar 1.1",
+                   format!("{}", c.warnings[0]));
+
+        let mut c = WarningConsumer {
+            warnings: vec![],
+            insts: 0,
+        };
+        {
+            let mut p = IncrementalParser::new(&mut c).options(options.clone());
+            for chunk in b.get().chunks(5) {
+                p.feed(chunk).unwrap();
+            }
+            p.finish().unwrap();
+        }
+        assert_eq!(expected, c.warnings);
+
+        let mut insts = ParseIterator::new(b.get()).options(options);
+        assert!(insts.take_warnings().is_empty());
+        assert!(insts.next().unwrap().is_ok());
+        assert_eq!(&expected[..2], &insts.take_warnings()[..]);
+        assert!(insts.next().unwrap().is_ok());
+        assert!(insts.next().is_none());
+        assert_eq!(&expected[2..], &insts.take_warnings()[..]);
     }
-}
+}
\ No newline at end of file
@@ -0,0 +1,379 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mr;
+use spirv;
+
+use std::{cmp, error, fmt};
+use std::ops::Range;
+use super::assemble::{Assemble, write_words};
+use super::decoder::Endianness;
+use super::parser::{self, Action, Consumer, InstContext, Parser};
+
+const HEADER_NUM_BYTES: usize = 20;
+
+/// Byte spans of the parts of a SPIR-V binary, recorded by
+/// [`load_with_spans`](fn.load_with_spans.html) and kept up to date by
+/// [`patch_function`](fn.patch_function.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModuleSpans {
+    /// The byte order of the binary
+    pub endianness: Endianness,
+    /// The id bound in the module header
+    pub bound: spirv::Word,
+    /// The span of all instructions between the module header and the
+    /// first function
+    pub globals: Range<usize>,
+    /// The span of each function, from its OpFunction to its OpFunctionEnd
+    pub functions: Vec<Range<usize>>,
+}
+
+/// Errors from [`patch_function`](fn.patch_function.html), for which the
+/// module has to be reassembled as a whole instead.
+#[derive(Debug, PartialEq)]
+pub enum PatchError {
+    /// No function at the given index (index, number of functions)
+    FunctionMissing(usize, usize),
+    /// The number of functions differs from the one recorded
+    /// (recorded, found)
+    FunctionCountChanged(usize, usize),
+    /// The global section does not encode into the recorded bytes anymore
+    GlobalsChanged,
+    /// The spans do not fit into the binary (binary size in bytes)
+    SpansInvalid(usize),
+    /// A result id is too large for any id bound (id)
+    IdOverflow(spirv::Word),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PatchError::FunctionMissing(index, count) => {
+                write!(f, "no function #{} in a module of {} functions", index, count)
+            }
+            PatchError::FunctionCountChanged(recorded, found) => {
+                write!(f,
+                       "number of functions changed from {} to {}",
+                       recorded,
+                       found)
+            }
+            PatchError::GlobalsChanged => write!(f, "global section changed"),
+            PatchError::SpansInvalid(len) => {
+                write!(f, "spans do not fit into the binary of {} bytes", len)
+            }
+            PatchError::IdOverflow(id) => write!(f, "result id {} exceeds any id bound", id),
+        }
+    }
+}
+
+impl error::Error for PatchError {
+    fn description(&self) -> &str {
+        match *self {
+            PatchError::FunctionMissing(..) => "function missing",
+            PatchError::FunctionCountChanged(..) => "number of functions changed",
+            PatchError::GlobalsChanged => "global section changed",
+            PatchError::SpansInvalid(_) => "spans not fitting into the binary",
+            PatchError::IdOverflow(_) => "result id exceeding any id bound",
+        }
+    }
+}
+
+/// A loader also recording the byte spans of the global section and each
+/// function.
+struct SpanLoader {
+    loader: mr::Loader,
+    globals_end: Option<usize>,
+    function_start: usize,
+    functions: Vec<Range<usize>>,
+    end: usize,
+}
+
+impl Consumer for SpanLoader {
+    fn initialize(&mut self) -> Action {
+        self.loader.initialize()
+    }
+
+    fn finalize(&mut self) -> Action {
+        self.loader.finalize()
+    }
+
+    fn consume_header(&mut self, header: mr::ModuleHeader) -> Action {
+        self.loader.consume_header(header)
+    }
+
+    fn consume_instruction(&mut self, inst: mr::Instruction) -> Action {
+        self.loader.consume_instruction(inst)
+    }
+
+    fn consume_instruction_span(&mut self,
+                                context: InstContext,
+                                inst: mr::Instruction)
+                                -> Action {
+        let (start, end) = (context.offset, context.offset + context.len);
+        match inst.class {
+            spirv::Op::Function => {
+                if self.globals_end.is_none() {
+                    self.globals_end = Some(start);
+                }
+                self.function_start = start;
+            }
+            spirv::Op::FunctionEnd => self.functions.push(self.function_start..end),
+            _ => (),
+        }
+        self.end = end;
+        self.loader.consume_instruction_span(context, inst)
+    }
+}
+
+/// Loads the SPIR-V `binary` into memory like
+/// [`mr::load_bytes`](../mr/fn.load_bytes.html), and also returns the byte
+/// spans of its global section and functions, for patching the binary
+/// later with [`patch_function`](fn.patch_function.html).
+pub fn load_with_spans(binary: &[u8]) -> parser::Result<(mr::Module, ModuleSpans)> {
+    let (header, endianness) = try!(parser::parse_header(binary));
+    let mut consumer = SpanLoader {
+        loader: mr::Loader::new(),
+        globals_end: None,
+        function_start: 0,
+        functions: vec![],
+        end: HEADER_NUM_BYTES,
+    };
+    try!(Parser::new(binary, &mut consumer).parse());
+    let spans = ModuleSpans {
+        endianness: endianness,
+        bound: header.bound,
+        globals: HEADER_NUM_BYTES..consumer.globals_end.unwrap_or(consumer.end),
+        functions: consumer.functions,
+    };
+    Ok((consumer.loader.module(), spans))
+}
+
+/// Reassembles `module`, of which only the function at `index` has changed
+/// since it was loaded from `original` by
+/// [`load_with_spans`](fn.load_with_spans.html), by encoding only that
+/// function and splicing it into a copy of `original`.
+///
+/// Nothing else in `original` is touched, except the id bound in the
+/// module header if the function now defines ids not below it. The
+/// `spans` are updated to describe the returned binary, so that it can be
+/// patched again.
+///
+/// The global section is encoded instruction by instruction to check
+/// that it still gives the original bytes; functions other than the one
+/// at `index` are not checked. If an error is returned, the module needs
+/// to be reassembled as a whole instead. This includes global sections
+/// not in the order the loader keeps instructions in, e.g., with OpLine
+/// instructions, and `spans` not describing `original`, which are
+/// reported as `PatchError::SpansInvalid`.
+pub fn patch_function(original: &[u8],
+                      spans: &mut ModuleSpans,
+                      module: &mr::Module,
+                      index: usize)
+                      -> Result<Vec<u8>, PatchError> {
+    if spans.functions.len() != module.functions.len() {
+        return Err(PatchError::FunctionCountChanged(spans.functions.len(),
+                                                    module.functions.len()));
+    }
+    let span = match spans.functions.get(index) {
+        Some(span) => span.clone(),
+        None => return Err(PatchError::FunctionMissing(index, spans.functions.len())),
+    };
+    if !spans_fit(spans, original.len()) {
+        return Err(PatchError::SpansInvalid(original.len()));
+    }
+
+    let mut words = vec![];
+    let mut bytes = Vec::with_capacity(original.len());
+    let mut offset = spans.globals.start;
+    for inst in module.global_inst_iter() {
+        words.clear();
+        bytes.clear();
+        inst.assemble_into(&mut words);
+        write_words(&mut bytes, &words, spans.endianness).unwrap();
+        let end = offset + bytes.len();
+        if end > spans.globals.end || bytes[..] != original[offset..end] {
+            return Err(PatchError::GlobalsChanged);
+        }
+        offset = end;
+    }
+    if offset != spans.globals.end {
+        return Err(PatchError::GlobalsChanged);
+    }
+
+    let function = &module.functions[index];
+    let mut bound = spans.bound;
+    {
+        let params = function.parameters.iter();
+        let outer = function.def.iter().chain(params).chain(function.end.iter());
+        let blocks = function.basic_blocks
+            .iter()
+            .flat_map(|bb| bb.label.iter().chain(bb.instructions.iter()));
+        for id in outer.chain(blocks).filter_map(|inst| inst.result_id) {
+            bound = match id.checked_add(1) {
+                Some(next) => cmp::max(bound, next),
+                None => return Err(PatchError::IdOverflow(id)),
+            };
+        }
+    }
+
+    bytes.clear();
+    bytes.extend_from_slice(&original[..span.start]);
+    write_words(&mut bytes, &function.assemble(), spans.endianness).unwrap();
+    let end = bytes.len();
+    bytes.extend_from_slice(&original[span.end..]);
+    if bound != spans.bound {
+        let mut word = vec![];
+        write_words(&mut word, &[bound], spans.endianness).unwrap();
+        bytes[12..16].copy_from_slice(&word);
+        spans.bound = bound;
+    }
+
+    // Shift the spans of the following functions.
+    spans.functions[index] = span.start..end;
+    for following in &mut spans.functions[index + 1..] {
+        *following = following.start + end - span.end..following.end + end - span.end;
+    }
+    Ok(bytes)
+}
+
+/// Returns whether `spans` fit into a binary of `len` bytes: the global
+/// section right after the module header, followed by the functions in
+/// order, without overlapping.
+fn spans_fit(spans: &ModuleSpans, len: usize) -> bool {
+    if spans.globals.start != HEADER_NUM_BYTES || spans.globals.start > spans.globals.end {
+        return false;
+    }
+    let mut end = spans.globals.end;
+    for span in &spans.functions {
+        if span.start < end || span.start > span.end {
+            return false;
+        }
+        end = span.end;
+    }
+    end <= len
+}
+
+#[cfg(test)]
+mod tests {
+    use mr;
+    use spirv;
+
+    use binary::{Assemble, AssembleOptions, Endianness};
+    use super::{PatchError, load_with_spans, patch_function};
+
+    fn module() -> mr::Module {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let voidfvoid = b.type_function(void, vec![]);
+        for _ in 0..3 {
+            b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+            b.begin_basic_block(None).unwrap();
+            b.undef(float, None);
+            b.ret().unwrap();
+            b.end_function().unwrap();
+        }
+        b.module()
+    }
+
+    #[test]
+    fn test_patch_function() {
+        let original = module().assemble_bytes();
+        let (mut module, mut spans) = load_with_spans(&original).unwrap();
+        assert_eq!(3, spans.functions.len());
+        assert_eq!(original.len(), spans.functions[2].end);
+
+        // Grow the first function with a new id.
+        let bound = spans.bound;
+        let mut grown = module.functions[0].basic_blocks[0].instructions[0].clone();
+        grown.result_id = Some(bound);
+        module.functions[0].basic_blocks[0].instructions.insert(1, grown);
+        let patched = patch_function(&original, &mut spans, &module, 0).unwrap();
+        assert_eq!(AssembleOptions::default().assemble_bytes(&module).unwrap(), patched);
+        assert_eq!(bound + 1, spans.bound);
+        assert_eq!(load_with_spans(&patched).unwrap().1, spans);
+
+        // Shrink the last function; the bound is kept.
+        module.functions[2].basic_blocks[0].instructions.remove(0);
+        let repatched = patch_function(&patched, &mut spans, &module, 2).unwrap();
+        assert_eq!(&patched[..spans.functions[2].start],
+                   &repatched[..spans.functions[2].start]);
+        assert_eq!(load_with_spans(&repatched).unwrap().1, spans);
+        assert_eq!(bound + 1, spans.bound);
+    }
+
+    #[test]
+    fn test_patch_function_big_endian() {
+        let options = AssembleOptions { endianness: Endianness::Big, ..Default::default() };
+        let original = options.assemble_bytes(&module()).unwrap();
+        let (mut module, mut spans) = load_with_spans(&original).unwrap();
+        assert_eq!(Endianness::Big, spans.endianness);
+        let mut grown = module.functions[1].basic_blocks[0].instructions[0].clone();
+        grown.result_id = Some(spans.bound);
+        module.functions[1].basic_blocks[0].instructions.push(grown);
+        let patched = patch_function(&original, &mut spans, &module, 1).unwrap();
+        assert_eq!(options.assemble_bytes(&module).unwrap(), patched);
+    }
+
+    #[test]
+    fn test_patch_function_refused() {
+        let original = module().assemble_bytes();
+        let (mut module, mut spans) = load_with_spans(&original).unwrap();
+        assert_eq!(Err(PatchError::FunctionMissing(3, 3)),
+                   patch_function(&original, &mut spans, &module, 3));
+
+        module.debugs.push(mr::Instruction::new(spirv::Op::Name,
+                                                None,
+                                                None,
+                                                vec![mr::Operand::IdRef(1),
+                                                     mr::Operand::from("void")]));
+        assert_eq!(Err(PatchError::GlobalsChanged),
+                   patch_function(&original, &mut spans, &module, 0));
+
+        let mut overflowing = module.functions[1].basic_blocks[0].instructions[0].clone();
+        overflowing.result_id = Some(u32::max_value());
+        module.functions[1].basic_blocks[0].instructions.push(overflowing);
+        module.debugs.clear();
+        assert_eq!(Err(PatchError::IdOverflow(u32::max_value())),
+                   patch_function(&original, &mut spans, &module, 1));
+
+        module.functions.pop();
+        assert_eq!(Err(PatchError::FunctionCountChanged(3, 2)),
+                   patch_function(&original, &mut spans, &module, 0));
+    }
+
+    #[test]
+    fn test_patch_function_spans_invalid() {
+        let original = module().assemble_bytes();
+        let (module, spans) = load_with_spans(&original).unwrap();
+        let len = original.len();
+
+        let mut reversed = spans.clone();
+        reversed.functions[1] = reversed.functions[1].end..reversed.functions[1].start;
+        let mut overlapping = spans.clone();
+        overlapping.functions[1].start = overlapping.functions[0].start;
+        let mut truncated = spans.clone();
+        truncated.functions[2].end = len + 4;
+        let mut misplaced = spans.clone();
+        misplaced.globals.start = 0;
+        for mut spans in vec![reversed, overlapping, truncated, misplaced] {
+            assert_eq!(Err(PatchError::SpansInvalid(len)),
+                       patch_function(&original, &mut spans, &module, 1));
+        }
+        let mut spans = spans;
+        assert_eq!(Err(PatchError::SpansInvalid(8)),
+                   patch_function(&original[..8], &mut spans, &module, 0));
+    }
+}
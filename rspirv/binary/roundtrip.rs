@@ -0,0 +1,319 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mr;
+use spirv;
+
+use std::{cmp, error, fmt};
+use super::{Assemble, Disassemble};
+use super::decoder::Endianness;
+use super::parser::{self, Action, Consumer, Parser, ParserOptions, State, Warning};
+use utils::num::bytes_to_u32_le;
+
+const HEADER_NUM_WORDS: usize = 5;
+
+/// The first difference found by [`verify_roundtrip`](fn.verify_roundtrip.html).
+#[derive(Debug)]
+pub enum RoundtripMismatch {
+    /// Parsing failed with the given state
+    ParseFailed(State),
+    /// Unknown bits were dropped from a bit enum operand of an instruction,
+    /// so it cannot reassemble the same (instruction index, warning)
+    FlagBitsDropped(usize, Warning),
+    /// The reassembled instruction differs from the original one
+    WordsDiffer {
+        /// The index of the instruction, starting from 0
+        index: usize,
+        /// The position of the first differing word in the binary, in words
+        word: usize,
+        /// The original words of the instruction
+        original: Vec<spirv::Word>,
+        /// The words of the instruction reassembled
+        reassembled: Vec<spirv::Word>,
+        /// The disassembly of the instruction parsed
+        disassembly: String,
+    },
+}
+
+fn format_words(words: &[spirv::Word]) -> String {
+    let words: Vec<String> = words.iter().map(|w| format!("{:#010x}", w)).collect();
+    words.join(" ")
+}
+
+impl fmt::Display for RoundtripMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RoundtripMismatch::ParseFailed(ref state) => write!(f, "parsing failed: {}", state),
+            RoundtripMismatch::FlagBitsDropped(index, ref warning) => {
+                write!(f, "instruction #{} cannot be reassembled: {}", index, warning)
+            }
+            RoundtripMismatch::WordsDiffer { index,
+                                             word,
+                                             ref original,
+                                             ref reassembled,
+                                             ref disassembly } => {
+                write!(f,
+                       "instruction #{} `{}` differs at word {} after reassembling:\n  \
+                        original:    {}\n  reassembled: {}",
+                       index,
+                       disassembly,
+                       word,
+                       format_words(original),
+                       format_words(reassembled))
+            }
+        }
+    }
+}
+
+impl error::Error for RoundtripMismatch {
+    fn description(&self) -> &str {
+        match *self {
+            RoundtripMismatch::ParseFailed(_) => "parsing failed",
+            RoundtripMismatch::FlagBitsDropped(..) => "unknown flag bits dropped",
+            RoundtripMismatch::WordsDiffer { .. } => "reassembled instruction differs",
+        }
+    }
+}
+
+/// Parses the given SPIR-V `binary` instruction by instruction, reassembles
+/// each instruction, and checks that the exact same words come out.
+///
+/// Instructions with unknown opcodes and operands with unknown enumerant
+/// values are preserved by the parser, so they are expected to reassemble
+/// the same. Unknown bits in bit enum operands cannot be preserved; the
+/// instruction carrying them is reported as
+/// [`FlagBitsDropped`](enum.RoundtripMismatch.html#variant.FlagBitsDropped).
+/// The module header is kept as is by the parser and not checked.
+pub fn verify_roundtrip(binary: &[u8]) -> Result<(), RoundtripMismatch> {
+    let endianness = match parser::parse_header(binary) {
+        Ok((_, endianness)) => endianness,
+        Err(state) => return Err(RoundtripMismatch::ParseFailed(state)),
+    };
+    let words: Vec<spirv::Word> = binary.chunks(4)
+        .filter(|c| c.len() == 4)
+        .map(|c| match endianness {
+            Endianness::Little => bytes_to_u32_le(c),
+            Endianness::Big => bytes_to_u32_le(c).swap_bytes(),
+        })
+        .collect();
+
+    let mut verifier = Verifier {
+        words: &words,
+        offset: HEADER_NUM_WORDS,
+        index: 0,
+        code: vec![],
+        dropped: None,
+        mismatch: None,
+    };
+    let options = ParserOptions {
+        preserve_unknown_opcodes: true,
+        preserve_unknown_enumerants: true,
+        allow_unknown_flag_bits: true,
+        ..Default::default()
+    };
+    let result = Parser::new(binary, &mut verifier).options(options).parse();
+    match verifier.mismatch {
+        Some(mismatch) => Err(mismatch),
+        None => result.map_err(RoundtripMismatch::ParseFailed),
+    }
+}
+
+/// A consumer reassembling each instruction and comparing the words with
+/// the original ones.
+struct Verifier<'w> {
+    words: &'w [spirv::Word],
+    /// The position of the current instruction in `words`
+    offset: usize,
+    index: usize,
+    code: Vec<spirv::Word>,
+    /// The warning about unknown bits dropped from the current instruction
+    dropped: Option<Warning>,
+    mismatch: Option<RoundtripMismatch>,
+}
+
+impl<'w> Consumer for Verifier<'w> {
+    fn warn(&mut self, warning: Warning) -> Action {
+        if let Warning::UnknownFlagBitsDropped(..) = warning {
+            if self.dropped.is_none() {
+                self.dropped = Some(warning);
+            }
+        }
+        Action::Continue
+    }
+
+    fn consume_instruction(&mut self, inst: mr::Instruction) -> Action {
+        let words = self.words;
+        let count = (words[self.offset] >> 16) as usize;
+        let original = &words[self.offset..cmp::min(self.offset + count, words.len())];
+        self.code.clear();
+        inst.assemble_into(&mut self.code);
+        let dropped = self.dropped.take();
+        if original != &self.code[..] {
+            self.mismatch = Some(match dropped {
+                Some(warning) => RoundtripMismatch::FlagBitsDropped(self.index, warning),
+                None => {
+                    let differ = original.iter()
+                        .zip(self.code.iter())
+                        .position(|(a, b)| a != b)
+                        .unwrap_or(cmp::min(original.len(), self.code.len()));
+                    RoundtripMismatch::WordsDiffer {
+                        index: self.index,
+                        word: self.offset + differ,
+                        original: original.to_vec(),
+                        reassembled: self.code.clone(),
+                        disassembly: inst.disassemble(),
+                    }
+                }
+            });
+            return Action::Stop;
+        }
+        self.offset += count;
+        self.index += 1;
+        Action::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mr;
+    use spirv;
+
+    use binary::{Assemble, AssembleOptions, Endianness};
+    use std::i64;
+    use super::{RoundtripMismatch, verify_roundtrip};
+    use utils::num::u32_to_bytes;
+
+    fn w2b(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| u32_to_bytes(*w)).collect()
+    }
+
+    /// Builds a module exercising each category of operand kinds.
+    fn corpus() -> mr::Module {
+        let mut b = mr::Builder::new();
+        // Value enums
+        b.capability(spirv::Capability::Shader);
+        b.capability(spirv::Capability::Int64);
+        b.capability(spirv::Capability::Float64);
+        b.extension("SPV_KHR_storage_buffer_storage_class");
+        let glsl = b.ext_inst_import("GLSL.std.450");
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let int = b.type_int(32, 1);
+        let long = b.type_int(64, 1);
+        let double = b.type_float(64);
+        let ptr = b.type_pointer(None, spirv::StorageClass::Function, float);
+        let voidfvoid = b.type_function(void, vec![]);
+        // Literal numbers
+        let one = b.constant_f32(float, 1.0);
+        b.constant_u32(int, 0xffffffff);
+        let spec = b.spec_constant_u32(int, 3);
+        // Bit enums
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_INLINE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        let var = b.variable(ptr, None, spirv::StorageClass::Function, None).unwrap();
+        // Bit enum with parameters
+        let v = b.load(float,
+                       None,
+                       var,
+                       Some(spirv::MEMORY_ACCESS_ALIGNED),
+                       vec![mr::Operand::LiteralInt32(4)])
+            .unwrap();
+        // LiteralExtInstInteger
+        b.ext_inst(float, None, glsl, 4, vec![v]).unwrap();
+        b.store(var, one, None, vec![]).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        // Strings
+        b.entry_point(spirv::ExecutionModel::Fragment, f, "main", vec![]);
+        b.execution_mode(f, spirv::ExecutionMode::OriginUpperLeft, vec![]).unwrap();
+        b.name(f, "main");
+        b.name(var, "four");
+        // Value enum with parameters
+        b.decorate(var, spirv::Decoration::Location, vec![mr::Operand::LiteralInt32(2)]).unwrap();
+        let mut module = b.module();
+        // LiteralSpecConstantOpInteger
+        let id = module.compute_id_bound().unwrap();
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::SpecConstantOp,
+                                       Some(int),
+                                       Some(id),
+                                       vec![mr::Operand::LiteralSpecConstantOpInteger(spirv::Op::IAdd),
+                                            mr::Operand::IdRef(spec),
+                                            mr::Operand::IdRef(spec)]));
+        // 64-bit literals
+        let id = module.compute_id_bound().unwrap();
+        let min = mr::Operand::LiteralInt64(i64::MIN as u64);
+        let zero = mr::Operand::LiteralFloat64(-0.0);
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::Constant, Some(long), Some(id), vec![min]));
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::Constant, Some(double), Some(id + 1), vec![zero]));
+        // Unknown opcode and unknown enumerant
+        module.annotations.push(mr::Instruction::unknown(0xfffe, vec![1, 2]));
+        module.annotations.push(mr::Instruction::new(spirv::Op::Decorate,
+                                                     None,
+                                                     None,
+                                                     vec![mr::Operand::IdRef(var),
+                                                          mr::Operand::UnknownEnumerant("Decoration",
+                                                                                        0xfff0)]));
+        module
+    }
+
+    #[test]
+    fn test_verify_roundtrip_corpus() {
+        let module = corpus();
+        assert_matches!(verify_roundtrip(&module.assemble_bytes()), Ok(()));
+
+        let options = AssembleOptions { endianness: Endianness::Big, ..Default::default() };
+        assert_matches!(verify_roundtrip(&options.assemble_bytes(&module).unwrap()), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_roundtrip_flag_bits_dropped() {
+        let mut words = vec![spirv::MAGIC_NUMBER, 0x00010000, 0, 4, 0];
+        // OpTypeVoid %1
+        words.extend_from_slice(&[0x00020013, 1]);
+        // OpTypeFunction %2 %1
+        words.extend_from_slice(&[0x00030021, 2, 1]);
+        // OpFunction %1 %3 <unknown bit> %2
+        words.extend_from_slice(&[0x00050036, 1, 3, 0x8000, 2]);
+        match verify_roundtrip(&w2b(&words)) {
+            Err(RoundtripMismatch::FlagBitsDropped(2, _)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_verify_roundtrip_parse_failed() {
+        let words = vec![spirv::MAGIC_NUMBER, 0x00010000, 0, 4, 0, 0x00000000];
+        assert_matches!(verify_roundtrip(&w2b(&words)),
+                        Err(RoundtripMismatch::ParseFailed(_)));
+    }
+
+    #[test]
+    fn test_roundtrip_mismatch_display() {
+        let mismatch = RoundtripMismatch::WordsDiffer {
+            index: 3,
+            word: 12,
+            original: vec![0x00020013, 1],
+            reassembled: vec![0x00020013, 2],
+            disassembly: "%1 = OpTypeVoid".to_string(),
+        };
+        assert_eq!("instruction #3 `%1 = OpTypeVoid` differs at word 12 after reassembling:\n  \
+                    original:    0x00020013 0x00000001\n  \
+                    reassembled: 0x00020013 0x00000002",
+                   format!("{}", mismatch));
+    }
+}
@@ -0,0 +1,616 @@
+// Copyright 2016 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// AUTOMATICALLY GENERATED from the SPIR-V JSON grammar:
+//   external/spirv.core.grammar.json.
+// DO NOT MODIFY!
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+
+use mr;
+use spirv;
+
+use binary::{Assemble, parse_operand};
+use grammar::OperandKind as GOpKind;
+
+/// Encodes the given `operands` and checks that parsing the words as a
+/// lone operand of the given `kind` gives them back.
+fn check(kind: GOpKind, operands: Vec<mr::Operand>) {
+    let mut words = vec![];
+    for operand in &operands {
+        operand.assemble_into(&mut words);
+    }
+    match parse_operand(kind, &words) {
+        Ok(parsed) => assert_eq!(operands, parsed),
+        Err(err) => panic!("failed to parse {:?} from {:?}: {}", kind, words, err),
+    }
+}
+
+#[test]
+fn test_image_operands() {
+    check(GOpKind::ImageOperands, vec![mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_NONE)]);
+    check(GOpKind::ImageOperands, vec![mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_BIAS), mr::Operand::IdRef(1)]);
+    check(GOpKind::ImageOperands, vec![mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_LOD), mr::Operand::IdRef(1)]);
+    check(GOpKind::ImageOperands, vec![mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_GRAD), mr::Operand::IdRef(1), mr::Operand::IdRef(1)]);
+    check(GOpKind::ImageOperands, vec![mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_CONST_OFFSET), mr::Operand::IdRef(1)]);
+    check(GOpKind::ImageOperands, vec![mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_OFFSET), mr::Operand::IdRef(1)]);
+    check(GOpKind::ImageOperands, vec![mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_CONST_OFFSETS), mr::Operand::IdRef(1)]);
+    check(GOpKind::ImageOperands, vec![mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_SAMPLE), mr::Operand::IdRef(1)]);
+    check(GOpKind::ImageOperands, vec![mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_MIN_LOD), mr::Operand::IdRef(1)]);
+}
+
+#[test]
+fn test_fpfast_math_mode() {
+    check(GOpKind::FPFastMathMode, vec![mr::Operand::FPFastMathMode(spirv::FPFAST_MATH_MODE_NONE)]);
+    check(GOpKind::FPFastMathMode, vec![mr::Operand::FPFastMathMode(spirv::FPFAST_MATH_MODE_NOT_NAN)]);
+    check(GOpKind::FPFastMathMode, vec![mr::Operand::FPFastMathMode(spirv::FPFAST_MATH_MODE_NOT_INF)]);
+    check(GOpKind::FPFastMathMode, vec![mr::Operand::FPFastMathMode(spirv::FPFAST_MATH_MODE_NSZ)]);
+    check(GOpKind::FPFastMathMode, vec![mr::Operand::FPFastMathMode(spirv::FPFAST_MATH_MODE_ALLOW_RECIP)]);
+    check(GOpKind::FPFastMathMode, vec![mr::Operand::FPFastMathMode(spirv::FPFAST_MATH_MODE_FAST)]);
+}
+
+#[test]
+fn test_selection_control() {
+    check(GOpKind::SelectionControl, vec![mr::Operand::SelectionControl(spirv::SELECTION_CONTROL_NONE)]);
+    check(GOpKind::SelectionControl, vec![mr::Operand::SelectionControl(spirv::SELECTION_CONTROL_FLATTEN)]);
+    check(GOpKind::SelectionControl, vec![mr::Operand::SelectionControl(spirv::SELECTION_CONTROL_DONT_FLATTEN)]);
+}
+
+#[test]
+fn test_loop_control() {
+    check(GOpKind::LoopControl, vec![mr::Operand::LoopControl(spirv::LOOP_CONTROL_NONE)]);
+    check(GOpKind::LoopControl, vec![mr::Operand::LoopControl(spirv::LOOP_CONTROL_UNROLL)]);
+    check(GOpKind::LoopControl, vec![mr::Operand::LoopControl(spirv::LOOP_CONTROL_DONT_UNROLL)]);
+    check(GOpKind::LoopControl, vec![mr::Operand::LoopControl(spirv::LOOP_CONTROL_DEPENDENCY_INFINITE)]);
+    check(GOpKind::LoopControl, vec![mr::Operand::LoopControl(spirv::LOOP_CONTROL_DEPENDENCY_LENGTH), mr::Operand::LiteralInt32(1)]);
+}
+
+#[test]
+fn test_function_control() {
+    check(GOpKind::FunctionControl, vec![mr::Operand::FunctionControl(spirv::FUNCTION_CONTROL_NONE)]);
+    check(GOpKind::FunctionControl, vec![mr::Operand::FunctionControl(spirv::FUNCTION_CONTROL_INLINE)]);
+    check(GOpKind::FunctionControl, vec![mr::Operand::FunctionControl(spirv::FUNCTION_CONTROL_DONT_INLINE)]);
+    check(GOpKind::FunctionControl, vec![mr::Operand::FunctionControl(spirv::FUNCTION_CONTROL_PURE)]);
+    check(GOpKind::FunctionControl, vec![mr::Operand::FunctionControl(spirv::FUNCTION_CONTROL_CONST)]);
+}
+
+#[test]
+fn test_memory_semantics() {
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_RELAXED)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_NONE)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_ACQUIRE)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_RELEASE)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_ACQUIRE_RELEASE)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_SEQUENTIALLY_CONSISTENT)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_UNIFORM_MEMORY)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_SUBGROUP_MEMORY)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_WORKGROUP_MEMORY)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_CROSS_WORKGROUP_MEMORY)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_ATOMIC_COUNTER_MEMORY)]);
+    check(GOpKind::MemorySemantics, vec![mr::Operand::MemorySemantics(spirv::MEMORY_SEMANTICS_IMAGE_MEMORY)]);
+}
+
+#[test]
+fn test_memory_access() {
+    check(GOpKind::MemoryAccess, vec![mr::Operand::MemoryAccess(spirv::MEMORY_ACCESS_NONE)]);
+    check(GOpKind::MemoryAccess, vec![mr::Operand::MemoryAccess(spirv::MEMORY_ACCESS_VOLATILE)]);
+    check(GOpKind::MemoryAccess, vec![mr::Operand::MemoryAccess(spirv::MEMORY_ACCESS_ALIGNED), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::MemoryAccess, vec![mr::Operand::MemoryAccess(spirv::MEMORY_ACCESS_NONTEMPORAL)]);
+}
+
+#[test]
+fn test_kernel_profiling_info() {
+    check(GOpKind::KernelProfilingInfo, vec![mr::Operand::KernelProfilingInfo(spirv::KERNEL_PROFILING_INFO_NONE)]);
+    check(GOpKind::KernelProfilingInfo, vec![mr::Operand::KernelProfilingInfo(spirv::KERNEL_PROFILING_INFO_CMD_EXEC_TIME)]);
+}
+
+#[test]
+fn test_source_language() {
+    check(GOpKind::SourceLanguage, vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::Unknown)]);
+    check(GOpKind::SourceLanguage, vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::ESSL)]);
+    check(GOpKind::SourceLanguage, vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL)]);
+    check(GOpKind::SourceLanguage, vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::OpenCL_C)]);
+    check(GOpKind::SourceLanguage, vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::OpenCL_CPP)]);
+}
+
+#[test]
+fn test_execution_model() {
+    check(GOpKind::ExecutionModel, vec![mr::Operand::ExecutionModel(spirv::ExecutionModel::Vertex)]);
+    check(GOpKind::ExecutionModel, vec![mr::Operand::ExecutionModel(spirv::ExecutionModel::TessellationControl)]);
+    check(GOpKind::ExecutionModel, vec![mr::Operand::ExecutionModel(spirv::ExecutionModel::TessellationEvaluation)]);
+    check(GOpKind::ExecutionModel, vec![mr::Operand::ExecutionModel(spirv::ExecutionModel::Geometry)]);
+    check(GOpKind::ExecutionModel, vec![mr::Operand::ExecutionModel(spirv::ExecutionModel::Fragment)]);
+    check(GOpKind::ExecutionModel, vec![mr::Operand::ExecutionModel(spirv::ExecutionModel::GLCompute)]);
+    check(GOpKind::ExecutionModel, vec![mr::Operand::ExecutionModel(spirv::ExecutionModel::Kernel)]);
+}
+
+#[test]
+fn test_addressing_model() {
+    check(GOpKind::AddressingModel, vec![mr::Operand::AddressingModel(spirv::AddressingModel::Logical)]);
+    check(GOpKind::AddressingModel, vec![mr::Operand::AddressingModel(spirv::AddressingModel::Physical32)]);
+    check(GOpKind::AddressingModel, vec![mr::Operand::AddressingModel(spirv::AddressingModel::Physical64)]);
+}
+
+#[test]
+fn test_memory_model() {
+    check(GOpKind::MemoryModel, vec![mr::Operand::MemoryModel(spirv::MemoryModel::Simple)]);
+    check(GOpKind::MemoryModel, vec![mr::Operand::MemoryModel(spirv::MemoryModel::GLSL450)]);
+    check(GOpKind::MemoryModel, vec![mr::Operand::MemoryModel(spirv::MemoryModel::OpenCL)]);
+}
+
+#[test]
+fn test_execution_mode() {
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::Invocations), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::SpacingEqual)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::SpacingFractionalEven)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::SpacingFractionalOdd)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::VertexOrderCw)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::VertexOrderCcw)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::PixelCenterInteger)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::OriginUpperLeft)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::OriginLowerLeft)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::EarlyFragmentTests)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::PointMode)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::Xfb)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::DepthReplacing)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::DepthGreater)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::DepthLess)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::DepthUnchanged)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::LocalSize), mr::Operand::LiteralInt32(1), mr::Operand::LiteralInt32(1), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::LocalSizeHint), mr::Operand::LiteralInt32(1), mr::Operand::LiteralInt32(1), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::InputPoints)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::InputLines)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::InputLinesAdjacency)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::Triangles)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::InputTrianglesAdjacency)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::Quads)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::Isolines)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::OutputVertices), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::OutputPoints)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::OutputLineStrip)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::OutputTriangleStrip)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::VecTypeHint), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::ContractionOff)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::Initializer)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::Finalizer)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::SubgroupSize), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::ExecutionMode, vec![mr::Operand::ExecutionMode(spirv::ExecutionMode::SubgroupsPerWorkgroup), mr::Operand::LiteralInt32(1)]);
+}
+
+#[test]
+fn test_storage_class() {
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::UniformConstant)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::Input)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::Uniform)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::Output)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::Workgroup)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::CrossWorkgroup)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::Private)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::Function)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::Generic)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::PushConstant)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::AtomicCounter)]);
+    check(GOpKind::StorageClass, vec![mr::Operand::StorageClass(spirv::StorageClass::Image)]);
+}
+
+#[test]
+fn test_dim() {
+    check(GOpKind::Dim, vec![mr::Operand::Dim(spirv::Dim::Dim1D)]);
+    check(GOpKind::Dim, vec![mr::Operand::Dim(spirv::Dim::Dim2D)]);
+    check(GOpKind::Dim, vec![mr::Operand::Dim(spirv::Dim::Dim3D)]);
+    check(GOpKind::Dim, vec![mr::Operand::Dim(spirv::Dim::DimCube)]);
+    check(GOpKind::Dim, vec![mr::Operand::Dim(spirv::Dim::DimRect)]);
+    check(GOpKind::Dim, vec![mr::Operand::Dim(spirv::Dim::DimBuffer)]);
+    check(GOpKind::Dim, vec![mr::Operand::Dim(spirv::Dim::DimSubpassData)]);
+}
+
+#[test]
+fn test_sampler_addressing_mode() {
+    check(GOpKind::SamplerAddressingMode, vec![mr::Operand::SamplerAddressingMode(spirv::SamplerAddressingMode::None)]);
+    check(GOpKind::SamplerAddressingMode, vec![mr::Operand::SamplerAddressingMode(spirv::SamplerAddressingMode::ClampToEdge)]);
+    check(GOpKind::SamplerAddressingMode, vec![mr::Operand::SamplerAddressingMode(spirv::SamplerAddressingMode::Clamp)]);
+    check(GOpKind::SamplerAddressingMode, vec![mr::Operand::SamplerAddressingMode(spirv::SamplerAddressingMode::Repeat)]);
+    check(GOpKind::SamplerAddressingMode, vec![mr::Operand::SamplerAddressingMode(spirv::SamplerAddressingMode::RepeatMirrored)]);
+}
+
+#[test]
+fn test_sampler_filter_mode() {
+    check(GOpKind::SamplerFilterMode, vec![mr::Operand::SamplerFilterMode(spirv::SamplerFilterMode::Nearest)]);
+    check(GOpKind::SamplerFilterMode, vec![mr::Operand::SamplerFilterMode(spirv::SamplerFilterMode::Linear)]);
+}
+
+#[test]
+fn test_image_format() {
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Unknown)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba32f)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba16f)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R32f)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba8)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba8Snorm)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg32f)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg16f)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R11fG11fB10f)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R16f)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba16)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgb10A2)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg16)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg8)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R16)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R8)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba16Snorm)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg16Snorm)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg8Snorm)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R16Snorm)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R8Snorm)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba32i)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba16i)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba8i)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R32i)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg32i)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg16i)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg8i)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R16i)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R8i)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba32ui)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba16ui)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgba8ui)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R32ui)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rgb10a2ui)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg32ui)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg16ui)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::Rg8ui)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R16ui)]);
+    check(GOpKind::ImageFormat, vec![mr::Operand::ImageFormat(spirv::ImageFormat::R8ui)]);
+}
+
+#[test]
+fn test_image_channel_order() {
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::R)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::A)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::RG)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::RA)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::RGB)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::RGBA)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::BGRA)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::ARGB)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::Intensity)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::Luminance)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::Rx)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::RGx)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::RGBx)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::Depth)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::DepthStencil)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::sRGB)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::sRGBx)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::sRGBA)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::sBGRA)]);
+    check(GOpKind::ImageChannelOrder, vec![mr::Operand::ImageChannelOrder(spirv::ImageChannelOrder::ABGR)]);
+}
+
+#[test]
+fn test_image_channel_data_type() {
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::SnormInt8)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::SnormInt16)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnormInt8)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnormInt16)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnormShort565)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnormShort555)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnormInt101010)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::SignedInt8)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::SignedInt16)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::SignedInt32)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnsignedInt8)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnsignedInt16)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnsignedInt32)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::HalfFloat)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::Float)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnormInt24)]);
+    check(GOpKind::ImageChannelDataType, vec![mr::Operand::ImageChannelDataType(spirv::ImageChannelDataType::UnormInt101010_2)]);
+}
+
+#[test]
+fn test_fprounding_mode() {
+    check(GOpKind::FPRoundingMode, vec![mr::Operand::FPRoundingMode(spirv::FPRoundingMode::RTE)]);
+    check(GOpKind::FPRoundingMode, vec![mr::Operand::FPRoundingMode(spirv::FPRoundingMode::RTZ)]);
+    check(GOpKind::FPRoundingMode, vec![mr::Operand::FPRoundingMode(spirv::FPRoundingMode::RTP)]);
+    check(GOpKind::FPRoundingMode, vec![mr::Operand::FPRoundingMode(spirv::FPRoundingMode::RTN)]);
+}
+
+#[test]
+fn test_linkage_type() {
+    check(GOpKind::LinkageType, vec![mr::Operand::LinkageType(spirv::LinkageType::Export)]);
+    check(GOpKind::LinkageType, vec![mr::Operand::LinkageType(spirv::LinkageType::Import)]);
+}
+
+#[test]
+fn test_access_qualifier() {
+    check(GOpKind::AccessQualifier, vec![mr::Operand::AccessQualifier(spirv::AccessQualifier::ReadOnly)]);
+    check(GOpKind::AccessQualifier, vec![mr::Operand::AccessQualifier(spirv::AccessQualifier::WriteOnly)]);
+    check(GOpKind::AccessQualifier, vec![mr::Operand::AccessQualifier(spirv::AccessQualifier::ReadWrite)]);
+}
+
+#[test]
+fn test_function_parameter_attribute() {
+    check(GOpKind::FunctionParameterAttribute, vec![mr::Operand::FunctionParameterAttribute(spirv::FunctionParameterAttribute::Zext)]);
+    check(GOpKind::FunctionParameterAttribute, vec![mr::Operand::FunctionParameterAttribute(spirv::FunctionParameterAttribute::Sext)]);
+    check(GOpKind::FunctionParameterAttribute, vec![mr::Operand::FunctionParameterAttribute(spirv::FunctionParameterAttribute::ByVal)]);
+    check(GOpKind::FunctionParameterAttribute, vec![mr::Operand::FunctionParameterAttribute(spirv::FunctionParameterAttribute::Sret)]);
+    check(GOpKind::FunctionParameterAttribute, vec![mr::Operand::FunctionParameterAttribute(spirv::FunctionParameterAttribute::NoAlias)]);
+    check(GOpKind::FunctionParameterAttribute, vec![mr::Operand::FunctionParameterAttribute(spirv::FunctionParameterAttribute::NoCapture)]);
+    check(GOpKind::FunctionParameterAttribute, vec![mr::Operand::FunctionParameterAttribute(spirv::FunctionParameterAttribute::NoWrite)]);
+    check(GOpKind::FunctionParameterAttribute, vec![mr::Operand::FunctionParameterAttribute(spirv::FunctionParameterAttribute::NoReadWrite)]);
+}
+
+#[test]
+fn test_decoration() {
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::RelaxedPrecision)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::SpecId), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Block)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::BufferBlock)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::RowMajor)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::ColMajor)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::ArrayStride), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::MatrixStride), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::GLSLShared)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::GLSLPacked)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::CPacked)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::BuiltIn), mr::Operand::BuiltIn(spirv::BuiltIn::DrawIndex)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::NoPerspective)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Flat)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Patch)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Centroid)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Sample)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Invariant)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Restrict)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Aliased)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Volatile)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Constant)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Coherent)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::NonWritable)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::NonReadable)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Uniform)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::SaturatedConversion)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Stream), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Location), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Component), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Index), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Binding), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::DescriptorSet), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Offset), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::XfbBuffer), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::XfbStride), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::FuncParamAttr), mr::Operand::FunctionParameterAttribute(spirv::FunctionParameterAttribute::NoReadWrite)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::FPRoundingMode), mr::Operand::FPRoundingMode(spirv::FPRoundingMode::RTN)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::FPFastMathMode), mr::Operand::FPFastMathMode(spirv::FPFAST_MATH_MODE_FAST)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::LinkageAttributes), mr::Operand::from("param"), mr::Operand::LinkageType(spirv::LinkageType::Import)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::NoContraction)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::InputAttachmentIndex), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::Alignment), mr::Operand::LiteralInt32(1)]);
+    check(GOpKind::Decoration, vec![mr::Operand::Decoration(spirv::Decoration::MaxByteOffset), mr::Operand::LiteralInt32(1)]);
+}
+
+#[test]
+fn test_built_in() {
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::Position)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::PointSize)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::ClipDistance)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::CullDistance)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::VertexId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::InstanceId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::PrimitiveId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::InvocationId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::Layer)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::ViewportIndex)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::TessLevelOuter)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::TessLevelInner)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::TessCoord)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::PatchVertices)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::FragCoord)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::PointCoord)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::FrontFacing)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SampleId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SamplePosition)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SampleMask)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::FragDepth)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::HelperInvocation)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::NumWorkgroups)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::WorkgroupSize)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::WorkgroupId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::LocalInvocationId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::GlobalInvocationId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::LocalInvocationIndex)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::WorkDim)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::GlobalSize)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::EnqueuedWorkgroupSize)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::GlobalOffset)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::GlobalLinearId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SubgroupSize)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SubgroupMaxSize)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::NumSubgroups)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::NumEnqueuedSubgroups)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SubgroupId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SubgroupLocalInvocationId)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::VertexIndex)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::InstanceIndex)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SubgroupEqMaskKHR)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SubgroupGeMaskKHR)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SubgroupGtMaskKHR)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SubgroupLeMaskKHR)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::SubgroupLtMaskKHR)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::BaseVertex)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::BaseInstance)]);
+    check(GOpKind::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::DrawIndex)]);
+}
+
+#[test]
+fn test_scope() {
+    check(GOpKind::Scope, vec![mr::Operand::Scope(spirv::Scope::CrossDevice)]);
+    check(GOpKind::Scope, vec![mr::Operand::Scope(spirv::Scope::Device)]);
+    check(GOpKind::Scope, vec![mr::Operand::Scope(spirv::Scope::Workgroup)]);
+    check(GOpKind::Scope, vec![mr::Operand::Scope(spirv::Scope::Subgroup)]);
+    check(GOpKind::Scope, vec![mr::Operand::Scope(spirv::Scope::Invocation)]);
+}
+
+#[test]
+fn test_group_operation() {
+    check(GOpKind::GroupOperation, vec![mr::Operand::GroupOperation(spirv::GroupOperation::Reduce)]);
+    check(GOpKind::GroupOperation, vec![mr::Operand::GroupOperation(spirv::GroupOperation::InclusiveScan)]);
+    check(GOpKind::GroupOperation, vec![mr::Operand::GroupOperation(spirv::GroupOperation::ExclusiveScan)]);
+}
+
+#[test]
+fn test_kernel_enqueue_flags() {
+    check(GOpKind::KernelEnqueueFlags, vec![mr::Operand::KernelEnqueueFlags(spirv::KernelEnqueueFlags::NoWait)]);
+    check(GOpKind::KernelEnqueueFlags, vec![mr::Operand::KernelEnqueueFlags(spirv::KernelEnqueueFlags::WaitKernel)]);
+    check(GOpKind::KernelEnqueueFlags, vec![mr::Operand::KernelEnqueueFlags(spirv::KernelEnqueueFlags::WaitWorkGroup)]);
+}
+
+#[test]
+fn test_capability() {
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Matrix)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Shader)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Geometry)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Tessellation)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Addresses)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Linkage)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Kernel)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Vector16)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Float16Buffer)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Float16)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Float64)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Int64)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Int64Atomics)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ImageBasic)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ImageReadWrite)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ImageMipmap)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Pipes)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Groups)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::DeviceEnqueue)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::LiteralSampler)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::AtomicStorage)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Int16)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::TessellationPointSize)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::GeometryPointSize)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ImageGatherExtended)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::StorageImageMultisample)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::UniformBufferArrayDynamicIndexing)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::SampledImageArrayDynamicIndexing)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::StorageBufferArrayDynamicIndexing)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::StorageImageArrayDynamicIndexing)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ClipDistance)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::CullDistance)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ImageCubeArray)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::SampleRateShading)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ImageRect)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::SampledRect)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::GenericPointer)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Int8)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::InputAttachment)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::SparseResidency)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::MinLod)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Sampled1D)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::Image1D)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::SampledCubeArray)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::SampledBuffer)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ImageBuffer)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ImageMSArray)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::StorageImageExtendedFormats)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::ImageQuery)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::DerivativeControl)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::InterpolationFunction)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::TransformFeedback)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::GeometryStreams)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::StorageImageReadWithoutFormat)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::StorageImageWriteWithoutFormat)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::MultiViewport)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::SubgroupDispatch)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::NamedBarrier)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::PipeStorage)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::SubgroupBallotKHR)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::DrawParameters)]);
+    check(GOpKind::Capability, vec![mr::Operand::Capability(spirv::Capability::SubgroupVoteKHR)]);
+}
+
+#[test]
+fn test_id_result_type() {
+    check(GOpKind::IdResultType, vec![mr::Operand::IdRef(42)]);
+}
+
+#[test]
+fn test_id_result() {
+    check(GOpKind::IdResult, vec![mr::Operand::IdRef(42)]);
+}
+
+#[test]
+fn test_id_memory_semantics() {
+    check(GOpKind::IdMemorySemantics, vec![mr::Operand::IdMemorySemantics(42)]);
+}
+
+#[test]
+fn test_id_scope() {
+    check(GOpKind::IdScope, vec![mr::Operand::IdScope(42)]);
+}
+
+#[test]
+fn test_id_ref() {
+    check(GOpKind::IdRef, vec![mr::Operand::IdRef(1)]);
+    check(GOpKind::IdRef, vec![mr::Operand::IdRef(0xffffffff)]);
+}
+
+#[test]
+fn test_literal_integer() {
+    check(GOpKind::LiteralInteger, vec![mr::Operand::LiteralInt32(0)]);
+    check(GOpKind::LiteralInteger, vec![mr::Operand::LiteralInt32(0xffffffff)]);
+}
+
+#[test]
+fn test_literal_string() {
+    check(GOpKind::LiteralString, vec![mr::Operand::from("")]);
+    check(GOpKind::LiteralString, vec![mr::Operand::from("a")]);
+    check(GOpKind::LiteralString, vec![mr::Operand::from("ab")]);
+    check(GOpKind::LiteralString, vec![mr::Operand::from("abc")]);
+    check(GOpKind::LiteralString, vec![mr::Operand::from("abcd")]);
+    check(GOpKind::LiteralString, vec![mr::Operand::from("UTF-8: \u{e9}")]);
+}
+
+#[test]
+fn test_literal_context_dependent_number() {
+    check(GOpKind::LiteralContextDependentNumber, vec![mr::Operand::LiteralInt32(0xdeadbeef)]);
+    check(GOpKind::LiteralContextDependentNumber, vec![mr::Operand::LiteralInt64(0x0123456789abcdef)]);
+}
+
+#[test]
+fn test_literal_ext_inst_integer() {
+    check(GOpKind::LiteralExtInstInteger, vec![mr::Operand::LiteralExtInstInteger(7)]);
+}
+
+#[test]
+fn test_literal_spec_constant_op_integer() {
+    check(GOpKind::LiteralSpecConstantOpInteger, vec![mr::Operand::LiteralSpecConstantOpInteger(spirv::Op::IAdd)]);
+}
+
+#[test]
+fn test_pair_literal_integer_id_ref() {
+    check(GOpKind::PairLiteralIntegerIdRef, vec![mr::Operand::LiteralInt32(1), mr::Operand::IdRef(2)]);
+}
+
+#[test]
+fn test_pair_id_ref_literal_integer() {
+    check(GOpKind::PairIdRefLiteralInteger, vec![mr::Operand::IdRef(1), mr::Operand::LiteralInt32(2)]);
+}
+
+#[test]
+fn test_pair_id_ref_id_ref() {
+    check(GOpKind::PairIdRefIdRef, vec![mr::Operand::IdRef(1), mr::Operand::IdRef(2)]);
+}
@@ -50,8 +50,8 @@ impl TypeTracker {
 
     pub fn track(&mut self, inst: &mr::Instruction) {
         if let Some(rid) = inst.result_id {
-            if grammar::reflect::is_type(inst.class.opcode) {
-                match inst.class.opcode {
+            if grammar::reflect::is_type(inst.class) {
+                match inst.class {
                     spirv::Op::TypeInt => {
                         if let (&mr::Operand::LiteralInt32(bits),
                                 &mr::Operand::LiteralInt32(sign)) = (&inst.operands[0],
@@ -104,15 +104,15 @@ impl ExtInstSetTracker {
     /// If the given extended instruction set is not recognized, it will
     /// be silently ignored.
     pub fn track(&mut self, inst: &mr::Instruction) {
-        if inst.class.opcode != spirv::Op::ExtInstImport || inst.result_id.is_none() ||
+        if inst.class != spirv::Op::ExtInstImport || inst.result_id.is_none() ||
            inst.operands.is_empty() {
             return;
         }
         if let mr::Operand::LiteralString(ref s) = inst.operands[0] {
-            if s == "GLSL.std.450" {
+            if &**s == "GLSL.std.450" {
                 self.sets
                     .insert(inst.result_id.unwrap(), ExtInstSet::GlslStd450);
-            } else if s == "OpenCL.std" {
+            } else if &**s == "OpenCL.std" {
                 self.sets
                     .insert(inst.result_id.unwrap(), ExtInstSet::OpenCLStd100);
             }
@@ -140,3 +140,64 @@ impl ExtInstSetTracker {
         }
     }
 }
+
+/// A source location declared by `OpLine`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SourceLocation {
+    /// The id of the `OpString` holding the file name
+    pub file: spirv::Word,
+    /// The line number
+    pub line: u32,
+    /// The column number
+    pub column: u32,
+}
+
+/// Tracks the source location declared by `OpLine` for the instructions
+/// following it.
+///
+/// The location is cleared by `OpNoLine` and at the end of each block and
+/// function, as required by the spec.
+#[derive(Debug)]
+pub struct LineTracker {
+    current: Option<SourceLocation>,
+}
+
+impl LineTracker {
+    pub fn new() -> LineTracker {
+        LineTracker { current: None }
+    }
+
+    /// Tracks the source location changed by the given `inst`.
+    pub fn track(&mut self, inst: &mr::Instruction) {
+        match inst.class {
+            spirv::Op::Line => {
+                if let (Some(&mr::Operand::IdRef(file)),
+                        Some(&mr::Operand::LiteralInt32(line)),
+                        Some(&mr::Operand::LiteralInt32(column))) = (inst.operands.get(0),
+                                                                     inst.operands.get(1),
+                                                                     inst.operands.get(2)) {
+                    self.current = Some(SourceLocation {
+                        file: file,
+                        line: line,
+                        column: column,
+                    });
+                }
+            }
+            spirv::Op::NoLine |
+            spirv::Op::Branch |
+            spirv::Op::BranchConditional |
+            spirv::Op::Switch |
+            spirv::Op::Return |
+            spirv::Op::ReturnValue |
+            spirv::Op::Kill |
+            spirv::Op::Unreachable |
+            spirv::Op::FunctionEnd => self.current = None,
+            _ => (),
+        }
+    }
+
+    /// Returns the source location in effect for the next instruction.
+    pub fn location(&self) -> Option<SourceLocation> {
+        self.current
+    }
+}
@@ -103,6 +103,15 @@ extern crate assert_matches;
 #[macro_use]
 extern crate derive_more;
 extern crate num;
+#[cfg(feature = "serialize")]
+extern crate serde;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(all(test, feature = "serialize"))]
+extern crate bincode;
+#[cfg(all(test, feature = "serialize"))]
+extern crate serde_json;
 extern crate spirv_headers as spirv;
 
 pub mod binary;
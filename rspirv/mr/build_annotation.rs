@@ -18,17 +18,33 @@
 
 impl Builder {
     /// Appends an OpDecorate instruction.
-    pub fn decorate(&mut self, target: spirv::Word, decoration: spirv::Decoration, mut additional_params: Vec<mr::Operand>) {
+    ///
+    /// Returns `Error::WrongDecorationOperand` if the number of operands
+    /// in `additional_params` is not the number of parameters expected by
+    /// the grammar for `decoration`.
+    pub fn decorate(&mut self, target: spirv::Word, decoration: spirv::Decoration, mut additional_params: Vec<mr::Operand>) -> BuildResult<()> {
+        if additional_params.len() != mr::Operand::Decoration(decoration).num_parameters() {
+            return Err(Error::WrongDecorationOperand);
+        }
         let mut inst = mr::Instruction::new(spirv::Op::Decorate, None, None, vec![mr::Operand::IdRef(target), mr::Operand::Decoration(decoration)]);
         inst.operands.append(&mut additional_params);
         self.module.annotations.push(inst);
+        Ok(())
     }
 
     /// Appends an OpMemberDecorate instruction.
-    pub fn member_decorate(&mut self, structure_type: spirv::Word, member: u32, decoration: spirv::Decoration, mut additional_params: Vec<mr::Operand>) {
+    ///
+    /// Returns `Error::WrongDecorationOperand` if the number of operands
+    /// in `additional_params` is not the number of parameters expected by
+    /// the grammar for `decoration`.
+    pub fn member_decorate(&mut self, structure_type: spirv::Word, member: u32, decoration: spirv::Decoration, mut additional_params: Vec<mr::Operand>) -> BuildResult<()> {
+        if additional_params.len() != mr::Operand::Decoration(decoration).num_parameters() {
+            return Err(Error::WrongDecorationOperand);
+        }
         let mut inst = mr::Instruction::new(spirv::Op::MemberDecorate, None, None, vec![mr::Operand::IdRef(structure_type), mr::Operand::LiteralInt32(member), mr::Operand::Decoration(decoration)]);
         inst.operands.append(&mut additional_params);
         self.module.annotations.push(inst);
+        Ok(())
     }
 
     /// Appends an OpGroupDecorate instruction.
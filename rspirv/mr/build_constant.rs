@@ -17,47 +17,42 @@
 // DO NOT MODIFY!
 
 impl Builder {
-    /// Appends an OpConstantTrue instruction.
+    /// Appends an OpConstantTrue instruction and returns the result id, or
+    /// returns the result id of the same constant appended before.
     pub fn constant_true(&mut self, result_type: spirv::Word) -> spirv::Word {
-        let id = self.id();
-        let inst = mr::Instruction::new(spirv::Op::ConstantTrue, Some(result_type), Some(id), vec![]);
-        self.module.types_global_values.push(inst);
-        id
+        let inst = mr::Instruction::new(spirv::Op::ConstantTrue, Some(result_type), None, vec![]);
+        self.declare_constant(inst)
     }
 
-    /// Appends an OpConstantFalse instruction.
+    /// Appends an OpConstantFalse instruction and returns the result id, or
+    /// returns the result id of the same constant appended before.
     pub fn constant_false(&mut self, result_type: spirv::Word) -> spirv::Word {
-        let id = self.id();
-        let inst = mr::Instruction::new(spirv::Op::ConstantFalse, Some(result_type), Some(id), vec![]);
-        self.module.types_global_values.push(inst);
-        id
+        let inst = mr::Instruction::new(spirv::Op::ConstantFalse, Some(result_type), None, vec![]);
+        self.declare_constant(inst)
     }
 
-    /// Appends an OpConstantComposite instruction.
+    /// Appends an OpConstantComposite instruction and returns the result id, or
+    /// returns the result id of the same constant appended before.
     pub fn constant_composite(&mut self, result_type: spirv::Word, constituents: Vec<spirv::Word>) -> spirv::Word {
-        let id = self.id();
-        let mut inst = mr::Instruction::new(spirv::Op::ConstantComposite, Some(result_type), Some(id), vec![]);
+        let mut inst = mr::Instruction::new(spirv::Op::ConstantComposite, Some(result_type), None, vec![]);
         for v in constituents {
             inst.operands.push(mr::Operand::IdRef(v))
         };
-        self.module.types_global_values.push(inst);
-        id
+        self.declare_constant(inst)
     }
 
-    /// Appends an OpConstantSampler instruction.
+    /// Appends an OpConstantSampler instruction and returns the result id, or
+    /// returns the result id of the same constant appended before.
     pub fn constant_sampler(&mut self, result_type: spirv::Word, sampler_addressing_mode: spirv::SamplerAddressingMode, param: u32, sampler_filter_mode: spirv::SamplerFilterMode) -> spirv::Word {
-        let id = self.id();
-        let inst = mr::Instruction::new(spirv::Op::ConstantSampler, Some(result_type), Some(id), vec![mr::Operand::SamplerAddressingMode(sampler_addressing_mode), mr::Operand::LiteralInt32(param), mr::Operand::SamplerFilterMode(sampler_filter_mode)]);
-        self.module.types_global_values.push(inst);
-        id
+        let inst = mr::Instruction::new(spirv::Op::ConstantSampler, Some(result_type), None, vec![mr::Operand::SamplerAddressingMode(sampler_addressing_mode), mr::Operand::LiteralInt32(param), mr::Operand::SamplerFilterMode(sampler_filter_mode)]);
+        self.declare_constant(inst)
     }
 
-    /// Appends an OpConstantNull instruction.
+    /// Appends an OpConstantNull instruction and returns the result id, or
+    /// returns the result id of the same constant appended before.
     pub fn constant_null(&mut self, result_type: spirv::Word) -> spirv::Word {
-        let id = self.id();
-        let inst = mr::Instruction::new(spirv::Op::ConstantNull, Some(result_type), Some(id), vec![]);
-        self.module.types_global_values.push(inst);
-        id
+        let inst = mr::Instruction::new(spirv::Op::ConstantNull, Some(result_type), None, vec![]);
+        self.declare_constant(inst)
     }
 
     /// Appends an OpSpecConstantTrue instruction.
@@ -95,11 +90,10 @@ impl Builder {
         id
     }
 
-    /// Appends an OpConstantPipeStorage instruction.
+    /// Appends an OpConstantPipeStorage instruction and returns the result id, or
+    /// returns the result id of the same constant appended before.
     pub fn constant_pipe_storage(&mut self, result_type: spirv::Word, packet_size: u32, packet_alignment: u32, capacity: u32) -> spirv::Word {
-        let id = self.id();
-        let inst = mr::Instruction::new(spirv::Op::ConstantPipeStorage, Some(result_type), Some(id), vec![mr::Operand::LiteralInt32(packet_size), mr::Operand::LiteralInt32(packet_alignment), mr::Operand::LiteralInt32(capacity)]);
-        self.module.types_global_values.push(inst);
-        id
+        let inst = mr::Instruction::new(spirv::Op::ConstantPipeStorage, Some(result_type), None, vec![mr::Operand::LiteralInt32(packet_size), mr::Operand::LiteralInt32(packet_alignment), mr::Operand::LiteralInt32(capacity)]);
+        self.declare_constant(inst)
     }
 }
\ No newline at end of file
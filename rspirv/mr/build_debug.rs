@@ -19,7 +19,7 @@
 impl Builder {
     /// Appends an OpSourceContinued instruction.
     pub fn source_continued<T: Into<String>>(&mut self, continued_source: T) {
-        let inst = mr::Instruction::new(spirv::Op::SourceContinued, None, None, vec![mr::Operand::LiteralString(continued_source.into())]);
+        let inst = mr::Instruction::new(spirv::Op::SourceContinued, None, None, vec![mr::Operand::from(continued_source.into())]);
         self.module.debugs.push(inst);
     }
 
@@ -30,32 +30,32 @@ impl Builder {
             inst.operands.push(mr::Operand::IdRef(v));
         };
         if let Some(v) = source {
-            inst.operands.push(mr::Operand::LiteralString(v.into()));
+            inst.operands.push(mr::Operand::from(v.into()));
         };
         self.module.debugs.push(inst);
     }
 
     /// Appends an OpSourceExtension instruction.
     pub fn source_extension<T: Into<String>>(&mut self, extension: T) {
-        let inst = mr::Instruction::new(spirv::Op::SourceExtension, None, None, vec![mr::Operand::LiteralString(extension.into())]);
+        let inst = mr::Instruction::new(spirv::Op::SourceExtension, None, None, vec![mr::Operand::from(extension.into())]);
         self.module.debugs.push(inst);
     }
 
     /// Appends an OpName instruction.
     pub fn name<T: Into<String>>(&mut self, target: spirv::Word, name: T) {
-        let inst = mr::Instruction::new(spirv::Op::Name, None, None, vec![mr::Operand::IdRef(target), mr::Operand::LiteralString(name.into())]);
+        let inst = mr::Instruction::new(spirv::Op::Name, None, None, vec![mr::Operand::IdRef(target), mr::Operand::from(name.into())]);
         self.module.debugs.push(inst);
     }
 
     /// Appends an OpMemberName instruction.
     pub fn member_name<T: Into<String>>(&mut self, target_type: spirv::Word, member: u32, name: T) {
-        let inst = mr::Instruction::new(spirv::Op::MemberName, None, None, vec![mr::Operand::IdRef(target_type), mr::Operand::LiteralInt32(member), mr::Operand::LiteralString(name.into())]);
+        let inst = mr::Instruction::new(spirv::Op::MemberName, None, None, vec![mr::Operand::IdRef(target_type), mr::Operand::LiteralInt32(member), mr::Operand::from(name.into())]);
         self.module.debugs.push(inst);
     }
 
     /// Appends an OpModuleProcessed instruction.
     pub fn module_processed<T: Into<String>>(&mut self, process: T) {
-        let inst = mr::Instruction::new(spirv::Op::ModuleProcessed, None, None, vec![mr::Operand::LiteralString(process.into())]);
+        let inst = mr::Instruction::new(spirv::Op::ModuleProcessed, None, None, vec![mr::Operand::from(process.into())]);
         self.module.debugs.push(inst);
     }
 }
\ No newline at end of file
@@ -17,7 +17,8 @@
 use mr;
 use spirv;
 
-use std::result;
+use std::{cmp, result};
+use std::collections::HashMap;
 use super::Error;
 
 type BuildResult<T> = result::Result<T, Error>;
@@ -52,6 +53,17 @@ type BuildResult<T> = result::Result<T, Error>;
 /// (e.g., `OpVariable`) will be inserted to the current basic block under
 /// construction first, if any.
 ///
+/// Type declarations are deduplicated, as required by the specification:
+/// the build methods for types return the result id of the same type if
+/// appended before. This also applies to OpTypeStruct, although structures
+/// with the same members can be distinct types by their decorations; use
+/// [`type_struct_unique`](#method.type_struct_unique) for them.
+///
+/// Constants are deduplicated likewise, with floating-point values compared
+/// by their bit patterns, so `0.0` and `-0.0` remain distinct constants.
+/// Specialization constants are never deduplicated, since they can be
+/// decorated with different `SpecId`s.
+///
 /// # Errors
 ///
 /// Methods in the builder implement little sanity check; only appending
@@ -99,12 +111,61 @@ type BuildResult<T> = result::Result<T, Error>;
 ///                 OpFunctionEnd");
 /// }
 /// ```
+///
+/// A minimal vertex shader, writing a constant position:
+///
+/// ```
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::mr::Operand;
+///
+/// fn main() {
+///     let mut b = rspirv::mr::Builder::new();
+///     b.capability(spirv::Capability::Shader);
+///     b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+///
+///     let void = b.type_void();
+///     let float = b.type_float(32);
+///     let vec4 = b.type_vector(float, 4);
+///     let output = b.type_pointer(None, spirv::StorageClass::Output, vec4);
+///     let position = b.variable(output, None, spirv::StorageClass::Output, None).unwrap();
+///     b.decorate(position,
+///                spirv::Decoration::BuiltIn,
+///                vec![Operand::BuiltIn(spirv::BuiltIn::Position)]).unwrap();
+///     let zero = b.constant_f32(float, 0.0);
+///     let one = b.constant_f32(float, 1.0);
+///     let origin = b.constant_composite(vec4, vec![zero, zero, zero, one]);
+///
+///     let voidfvoid = b.type_function(void, vec![]);
+///     let main = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid)
+///                 .unwrap();
+///     b.begin_basic_block(None).unwrap();
+///     b.store(position, origin, None, vec![]).unwrap();
+///     b.ret().unwrap();
+///     b.end_function().unwrap();
+///     b.entry_point(spirv::ExecutionModel::Vertex, main, "main", vec![position]);
+///
+///     let module = b.module();
+///     assert_eq!(Ok(()), module.validate_layout());
+///     assert_eq!(Some(main), module.entry_point_by_name("main").map(|e| e.function_id));
+///     assert_eq!(module.compute_id_bound(), module.header.map(|h| h.bound));
+/// }
+/// ```
 #[derive(Default)]
 pub struct Builder {
     module: mr::Module,
     next_id: u32,
     function: Option<mr::Function>,
     basic_block: Option<mr::BasicBlock>,
+    /// The result ids of the types appended, by their instructions without
+    /// result ids
+    types: HashMap<mr::Instruction, spirv::Word>,
+    /// The result ids of the constants appended, by their instructions
+    /// without result ids
+    constants: HashMap<mr::Instruction, spirv::Word>,
+    /// Whether to hoist function variables to the entry block
+    hoist_variables: bool,
 }
 
 impl Builder {
@@ -115,16 +176,74 @@ impl Builder {
             next_id: 1,
             function: None,
             basic_block: None,
+            types: HashMap::new(),
+            constants: HashMap::new(),
+            hoist_variables: false,
         }
     }
 
+    /// Sets whether [`variable`](#method.variable) hoists variables of the
+    /// `Function` storage class to the beginning of the entry block of the
+    /// current function, instead of rejecting them with
+    /// `Error::MisplacedVariable` when they come after other instructions.
+    ///
+    /// Hoisting is off by default.
+    pub fn hoist_variables(&mut self, hoist: bool) {
+        self.hoist_variables = hoist;
+    }
+
     /// Returns the `Module` under construction.
+    ///
+    /// The id bound in its header covers both the ids allocated by this
+    /// builder and those given explicitly as `result_id`s. No bound covers
+    /// an id of `u32::max_value()`; the bound is `u32::max_value()` then,
+    /// which [`checked_module`](#method.checked_module) rejects.
     pub fn module(self) -> mr::Module {
         let mut module = self.module;
-        module.header = Some(mr::ModuleHeader::new(self.next_id));
+        let bound = module.compute_id_bound().unwrap_or(u32::max_value());
+        let bound = cmp::max(self.next_id, bound);
+        module.header = Some(mr::ModuleHeader::new(bound));
         module
     }
 
+    /// Returns the `Module` under construction like
+    /// [`module`](#method.module), after checking that its construction
+    /// is complete.
+    ///
+    /// Returns `Error::UnclosedBasicBlock` or `Error::UnclosedFunction` if
+    /// a basic block or function is still under construction,
+    /// `Error::UndefinedEntryPoint` if an OpEntryPoint instruction refers
+    /// to a function not defined in the module, and `Error::IdOverflow` if
+    /// no id bound covers all ids in the module.
+    pub fn checked_module(self) -> BuildResult<mr::Module> {
+        if self.basic_block.is_some() {
+            return Err(Error::UnclosedBasicBlock);
+        }
+        if self.function.is_some() {
+            return Err(Error::UnclosedFunction);
+        }
+        if self.module.compute_id_bound().is_none() {
+            return Err(Error::IdOverflow);
+        }
+        {
+            let functions: Vec<Option<spirv::Word>> = self.module
+                .functions
+                .iter()
+                .map(|f| f.def.as_ref().and_then(|def| def.result_id))
+                .collect();
+            for inst in &self.module.entry_points {
+                let defined = match inst.operands.get(1) {
+                    Some(&mr::Operand::IdRef(id)) => functions.contains(&Some(id)),
+                    _ => false,
+                };
+                if !defined {
+                    return Err(Error::UndefinedEntryPoint);
+                }
+            }
+        }
+        Ok(self.module())
+    }
+
     /// Returns the next unused id.
     pub fn id(&mut self) -> spirv::Word {
         let id = self.next_id;
@@ -163,10 +282,17 @@ impl Builder {
     }
 
     /// Ends building of the current function.
+    ///
+    /// The basic block under construction, if any, must be terminated
+    /// first; otherwise `Error::UnclosedBasicBlock` is returned and the
+    /// function is kept open.
     pub fn end_function(&mut self) -> BuildResult<()> {
         if self.function.is_none() {
             return Err(Error::MismatchedFunctionEnd);
         }
+        if self.basic_block.is_some() {
+            return Err(Error::UnclosedBasicBlock);
+        }
 
         let mut f = self.function.take().unwrap();
         f.end = Some(mr::Instruction::new(spirv::Op::FunctionEnd, None, None, vec![]));
@@ -216,11 +342,56 @@ impl Builder {
         if self.basic_block.is_none() {
             return Err(Error::MismatchedTerminator);
         }
+        if !is_merge_valid(self.basic_block.as_ref().unwrap(), inst.class) {
+            return Err(Error::MisplacedMergeInstruction);
+        }
 
         self.basic_block.as_mut().unwrap().instructions.push(inst);
         Ok(self.function.as_mut().unwrap().basic_blocks.push(self.basic_block.take().unwrap()))
     }
 
+    /// Builds an if-else construct branching on the boolean `condition`,
+    /// and begins its merge block, returning the label of the merge block.
+    ///
+    /// The current basic block is terminated by an OpSelectionMerge and an
+    /// OpBranchConditional to two new basic blocks, whose contents are built
+    /// by `then_body` and `else_body` respectively. After each body returns,
+    /// the basic block under construction is terminated with an OpBranch to
+    /// the merge block, unless the body has terminated it already (e.g.,
+    /// with `ret()`).
+    pub fn build_if<T, E>(&mut self,
+                          condition: spirv::Word,
+                          then_body: T,
+                          else_body: E)
+                          -> BuildResult<spirv::Word>
+        where T: FnOnce(&mut Builder) -> BuildResult<()>,
+              E: FnOnce(&mut Builder) -> BuildResult<()>
+    {
+        if self.basic_block.is_none() {
+            return Err(Error::DetachedInstruction);
+        }
+        let (then_label, else_label, merge) = (self.id(), self.id(), self.id());
+        self.selection_merge(merge, spirv::SELECTION_CONTROL_NONE)?;
+        self.branch_conditional(condition, then_label, else_label, vec![])?;
+
+        self.begin_basic_block(Some(then_label))?;
+        then_body(self)?;
+        self.branch_if_unterminated(merge)?;
+
+        self.begin_basic_block(Some(else_label))?;
+        else_body(self)?;
+        self.branch_if_unterminated(merge)?;
+
+        self.begin_basic_block(Some(merge))
+    }
+
+    fn branch_if_unterminated(&mut self, target_label: spirv::Word) -> BuildResult<()> {
+        if self.basic_block.is_none() {
+            return Ok(());
+        }
+        self.branch(target_label)
+    }
+
     /// Appends an OpCapability instruction.
     pub fn capability(&mut self, capability: spirv::Capability) {
         let inst = mr::Instruction::new(spirv::Op::Capability,
@@ -235,7 +406,7 @@ impl Builder {
         let inst = mr::Instruction::new(spirv::Op::Extension,
                                         None,
                                         None,
-                                        vec![mr::Operand::LiteralString(extension.into())]);
+                                        vec![mr::Operand::from(extension.into())]);
         self.module.extensions.push(inst);
     }
 
@@ -245,7 +416,7 @@ impl Builder {
         let inst = mr::Instruction::new(spirv::Op::ExtInstImport,
                                         None,
                                         Some(id),
-                                        vec![mr::Operand::LiteralString(extended_inst_set.into())]);
+                                        vec![mr::Operand::from(extended_inst_set.into())]);
         self.module.ext_inst_imports.push(inst);
         id
     }
@@ -270,7 +441,7 @@ impl Builder {
                                         interface: Vec<spirv::Word>) {
         let mut operands = vec![mr::Operand::ExecutionModel(execution_model),
                                 mr::Operand::IdRef(entry_point),
-                                mr::Operand::LiteralString(name.into())];
+                                mr::Operand::from(name.into())];
         for v in interface {
             operands.push(mr::Operand::IdRef(v));
         }
@@ -280,21 +451,74 @@ impl Builder {
     }
 
     /// Appends an OpExecutionMode instruction.
+    ///
+    /// Returns `Error::WrongOpExecutionModeOperand` if the number of
+    /// literals in `params` is not the number expected by the grammar for
+    /// `execution_mode`, e.g., three for `LocalSize` and none for
+    /// `OriginUpperLeft`.
     pub fn execution_mode(&mut self,
                           entry_point: spirv::Word,
                           execution_mode: spirv::ExecutionMode,
-                          params: Vec<u32>) {
-        let mut operands = vec![mr::Operand::IdRef(entry_point),
-                                mr::Operand::ExecutionMode(execution_mode)];
+                          params: Vec<u32>)
+                          -> BuildResult<()> {
+        let mode = mr::Operand::ExecutionMode(execution_mode);
+        if params.len() != mode.num_parameters() {
+            return Err(Error::WrongOpExecutionModeOperand);
+        }
+        let mut operands = vec![mr::Operand::IdRef(entry_point), mode];
         for v in params {
             operands.push(mr::Operand::LiteralInt32(v));
         }
 
         let inst = mr::Instruction::new(spirv::Op::ExecutionMode, None, None, operands);
         self.module.execution_modes.push(inst);
+        Ok(())
+    }
+
+    /// Appends the global variable `variable` to the interface of all
+    /// OpEntryPoint instructions for the function `entry_point`, unless
+    /// already there.
+    ///
+    /// Returns `Error::MissingEntryPoint` if there are no OpEntryPoint
+    /// instructions for `entry_point`.
+    pub fn add_to_entry_point_interface(&mut self,
+                                        entry_point: spirv::Word,
+                                        variable: spirv::Word)
+                                        -> BuildResult<()> {
+        let mut found = false;
+        for inst in &mut self.module.entry_points {
+            if inst.operands.get(1) != Some(&mr::Operand::IdRef(entry_point)) {
+                continue;
+            }
+            found = true;
+            let operand = mr::Operand::IdRef(variable);
+            if !inst.operands[3..].contains(&operand) {
+                inst.operands.push(operand);
+            }
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(Error::MissingEntryPoint)
+        }
     }
 }
 
+/// Returns whether the merge instruction in `block`, if any, is its last
+/// instruction and can be followed by the terminator `opcode`.
+fn is_merge_valid(block: &mr::BasicBlock, opcode: spirv::Op) -> bool {
+    let last = block.instructions.len().wrapping_sub(1);
+    block.instructions.iter().enumerate().all(|(index, inst)| match inst.class {
+        spirv::Op::SelectionMerge => {
+            index == last && (opcode == spirv::Op::BranchConditional || opcode == spirv::Op::Switch)
+        }
+        spirv::Op::LoopMerge => {
+            index == last && (opcode == spirv::Op::Branch || opcode == spirv::Op::BranchConditional)
+        }
+        _ => true,
+    })
+}
+
 include!("build_type.rs");
 include!("build_constant.rs");
 include!("build_annotation.rs");
@@ -311,12 +535,13 @@ impl Builder {
         id
     }
 
+    /// Appends an OpString instruction and returns the result id.
     pub fn string<T: Into<String>>(&mut self, s: T) -> spirv::Word {
         let id = self.id();
         self.module.debugs.push(mr::Instruction::new(spirv::Op::String,
                                                      None,
                                                      Some(id),
-                                                     vec![mr::Operand::LiteralString(s.into())]));
+                                                     vec![mr::Operand::from(s.into())]));
         id
     }
 
@@ -343,23 +568,55 @@ impl Builder {
                                             mr::Operand::StorageClass(storage_class)]));
     }
 
+    /// Appends the given type declaration `inst` without result id and
+    /// returns its result id, or returns the result id of the same type
+    /// appended before.
+    fn declare_type(&mut self, inst: mr::Instruction) -> spirv::Word {
+        if let Some(&id) = self.types.get(&inst) {
+            return id;
+        }
+        let id = self.id();
+        let mut decl = inst.clone();
+        decl.result_id = Some(id);
+        self.module.types_global_values.push(decl);
+        self.types.insert(inst, id);
+        id
+    }
+
     /// Appends an OpTypePointer instruction and returns the result id.
+    ///
+    /// If `result_id` is `None`, returns the result id of the same pointer
+    /// type if appended before. Otherwise, the given `result_id`, which may
+    /// be forward declared by OpTypeForwardPointer, is always used.
     pub fn type_pointer(&mut self,
                         result_id: Option<spirv::Word>,
                         storage_class: spirv::StorageClass,
                         pointee_type: spirv::Word)
                         -> spirv::Word {
+        let inst = mr::Instruction::new(spirv::Op::TypePointer,
+                                        None,
+                                        None,
+                                        vec![mr::Operand::StorageClass(storage_class),
+                                             mr::Operand::IdRef(pointee_type)]);
         let id = match result_id {
             Some(v) => v,
-            None => self.id(),
+            None => return self.declare_type(inst),
         };
+        let mut decl = inst.clone();
+        decl.result_id = Some(id);
+        self.module.types_global_values.push(decl);
+        self.types.entry(inst).or_insert(id);
+        id
+    }
+
+    /// Appends an OpTypeStruct instruction and returns the result id, even
+    /// if the same structure type was appended before.
+    pub fn type_struct_unique(&mut self, field_types: Vec<spirv::Word>) -> spirv::Word {
+        let id = self.id();
+        let operands = field_types.into_iter().map(mr::Operand::IdRef).collect();
         self.module
             .types_global_values
-            .push(mr::Instruction::new(spirv::Op::TypePointer,
-                                       None,
-                                       Some(id),
-                                       vec![mr::Operand::StorageClass(storage_class),
-                                            mr::Operand::IdRef(pointee_type)]));
+            .push(mr::Instruction::new(spirv::Op::TypeStruct, None, Some(id), operands));
         id
     }
 
@@ -371,32 +628,74 @@ impl Builder {
             .push(mr::Instruction::new(spirv::Op::TypeOpaque,
                                        None,
                                        Some(id),
-                                       vec![mr::Operand::LiteralString(type_name.into())]));
+                                       vec![mr::Operand::from(type_name.into())]));
         id
     }
 
-    /// Appends an OpConstant instruction with the given 32-bit float `value`.
-    /// or the module if no basic block is under construction.
-    pub fn constant_f32(&mut self, result_type: spirv::Word, value: f32) -> spirv::Word {
+    /// Appends the given constant declaration `inst` without result id and
+    /// returns its result id, or returns the result id of the same constant
+    /// appended before.
+    fn declare_constant(&mut self, inst: mr::Instruction) -> spirv::Word {
+        if let Some(&id) = self.constants.get(&inst) {
+            return id;
+        }
         let id = self.id();
-        let inst = mr::Instruction::new(spirv::Op::Constant,
-                                        Some(result_type),
-                                        Some(id),
-                                        vec![mr::Operand::LiteralFloat32(value)]);
-        self.module.types_global_values.push(inst);
+        let mut decl = inst.clone();
+        decl.result_id = Some(id);
+        self.module.types_global_values.push(decl);
+        self.constants.insert(inst, id);
         id
     }
 
-    /// Appends an OpConstant instruction with the given 32-bit integer `value`.
-    /// or the module if no basic block is under construction.
+    /// Appends an OpConstant instruction with the given 32-bit float `value`
+    /// and returns the result id, or returns the result id of the same
+    /// constant appended before.
+    pub fn constant_f32(&mut self, result_type: spirv::Word, value: f32) -> spirv::Word {
+        self.declare_constant(mr::Instruction::new(spirv::Op::Constant,
+                                                   Some(result_type),
+                                                   None,
+                                                   vec![mr::Operand::LiteralFloat32(value)]))
+    }
+
+    /// Appends an OpConstant instruction with the given 64-bit float `value`
+    /// and returns the result id, or returns the result id of the same
+    /// constant appended before.
+    pub fn constant_f64(&mut self, result_type: spirv::Word, value: f64) -> spirv::Word {
+        self.declare_constant(mr::Instruction::new(spirv::Op::Constant,
+                                                   Some(result_type),
+                                                   None,
+                                                   vec![mr::Operand::LiteralFloat64(value)]))
+    }
+
+    /// Appends an OpConstant instruction with the given 32-bit integer `value`
+    /// and returns the result id, or returns the result id of the same
+    /// constant appended before.
     pub fn constant_u32(&mut self, result_type: spirv::Word, value: u32) -> spirv::Word {
-        let id = self.id();
+        self.declare_constant(mr::Instruction::new(spirv::Op::Constant,
+                                                   Some(result_type),
+                                                   None,
+                                                   vec![mr::Operand::LiteralInt32(value)]))
+    }
+
+    /// Appends an OpConstant instruction with the given 64-bit integer `value`
+    /// and returns the result id, or returns the result id of the same
+    /// constant appended before.
+    pub fn constant_u64(&mut self, result_type: spirv::Word, value: u64) -> spirv::Word {
+        self.declare_constant(mr::Instruction::new(spirv::Op::Constant,
+                                                   Some(result_type),
+                                                   None,
+                                                   vec![mr::Operand::LiteralInt64(value)]))
+    }
+
+    /// Returns the result id of the OpConstant instruction of type
+    /// `result_type` with the given 32-bit integer `value` appended before
+    /// by [`constant_u32`](#method.constant_u32), if any.
+    pub fn lookup_constant_u32(&self, result_type: spirv::Word, value: u32) -> Option<spirv::Word> {
         let inst = mr::Instruction::new(spirv::Op::Constant,
                                         Some(result_type),
-                                        Some(id),
+                                        None,
                                         vec![mr::Operand::LiteralInt32(value)]);
-        self.module.types_global_values.push(inst);
-        id
+        self.constants.get(&inst).cloned()
     }
 
     /// Appends an OpSpecConstant instruction with the given 32-bit float `value`.
@@ -423,14 +722,43 @@ impl Builder {
         id
     }
 
-    /// Appends an OpVariable instruction to either the current basic block
-    /// or the module if no basic block is under construction.
+    /// Appends an OpVariable instruction and returns the result id.
+    ///
+    /// Variables of other storage classes than `Function` are appended to
+    /// the module.
+    ///
+    /// Variables of the `Function` storage class are appended to the
+    /// current basic block, which has to be the entry block of the current
+    /// function with only OpVariable instructions in it so far, as the
+    /// spec requires. `Error::DetachedInstruction` is returned if there is
+    /// no current basic block, and `Error::MisplacedVariable` if it is not
+    /// the right place. With [`hoist_variables`](#method.hoist_variables)
+    /// set, they are instead inserted into the entry block after the
+    /// OpVariable instructions at its beginning, wherever the builder is in
+    /// the current function; `Error::DetachedInstruction` is then only
+    /// returned if no basic block of the current function has begun.
     pub fn variable(&mut self,
                     result_type: spirv::Word,
                     result_id: Option<spirv::Word>,
                     storage_class: spirv::StorageClass,
                     initializer: Option<spirv::Word>)
-                    -> spirv::Word {
+                    -> BuildResult<spirv::Word> {
+        let is_local = storage_class == spirv::StorageClass::Function;
+        if is_local {
+            let in_entry = self.function.as_ref().map_or(true, |f| f.basic_blocks.is_empty());
+            match self.basic_block {
+                None if self.hoist_variables && !in_entry => (),
+                None => return Err(Error::DetachedInstruction),
+                Some(_) if self.hoist_variables => (),
+                Some(ref bb) => {
+                    let after_others =
+                        bb.instructions.iter().any(|inst| inst.class != spirv::Op::Variable);
+                    if !in_entry || after_others {
+                        return Err(Error::MisplacedVariable);
+                    }
+                }
+            }
+        }
         let id = match result_id {
             Some(v) => v,
             None => self.id(),
@@ -441,11 +769,20 @@ impl Builder {
         }
         let inst = mr::Instruction::new(spirv::Op::Variable, Some(result_type), Some(id), operands);
 
-        match self.basic_block {
-            Some(ref mut bb) => bb.instructions.push(inst),
-            None => self.module.types_global_values.push(inst),
+        if !is_local {
+            self.module.types_global_values.push(inst);
+            return Ok(id);
         }
-        id
+        let entry = match self.function.as_mut().and_then(|f| f.basic_blocks.first_mut()) {
+            Some(bb) => bb,
+            None => self.basic_block.as_mut().unwrap(),
+        };
+        let index = entry.instructions
+            .iter()
+            .take_while(|inst| inst.class == spirv::Op::Variable)
+            .count();
+        entry.instructions.insert(index, inst);
+        Ok(id)
     }
 
     /// Appends an OpUndef instruction to either the current basic block
@@ -476,9 +813,9 @@ mod tests {
     use spirv;
 
     use std::f32;
-    use super::Builder;
+    use super::{Builder, Error};
 
-    use binary::Disassemble;
+    use binary::{Assemble, Disassemble};
 
     fn has_only_one_global_inst(module: &mr::Module) -> bool {
         if !module.functions.is_empty() {
@@ -503,7 +840,7 @@ mod tests {
         assert!(m.memory_model.is_some());
         let inst = m.memory_model.as_ref().unwrap();
         assert!(has_only_one_global_inst(&m));
-        assert_eq!("MemoryModel", inst.class.opname);
+        assert_eq!("MemoryModel", inst.grammar().opname);
         assert_eq!(2, inst.operands.len());
         assert_eq!(mr::Operand::from(spirv::AddressingModel::Logical),
                    inst.operands[0]);
@@ -511,14 +848,90 @@ mod tests {
                    inst.operands[1]);
     }
 
+    #[test]
+    fn test_execution_mode_params() {
+        let mut b = Builder::new();
+        assert_eq!(Ok(()),
+                   b.execution_mode(1, spirv::ExecutionMode::LocalSize, vec![8, 8, 1]));
+        assert_eq!(Ok(()),
+                   b.execution_mode(1, spirv::ExecutionMode::OriginUpperLeft, vec![]));
+        assert_eq!(Err(Error::WrongOpExecutionModeOperand),
+                   b.execution_mode(1, spirv::ExecutionMode::LocalSize, vec![8, 8]));
+        assert_eq!(Err(Error::WrongOpExecutionModeOperand),
+                   b.execution_mode(1, spirv::ExecutionMode::OriginUpperLeft, vec![0]));
+
+        let m = b.module();
+        assert_eq!(2, m.execution_modes.len());
+        assert_eq!(vec![mr::Operand::IdRef(1),
+                        mr::Operand::ExecutionMode(spirv::ExecutionMode::LocalSize),
+                        mr::Operand::LiteralInt32(8),
+                        mr::Operand::LiteralInt32(8),
+                        mr::Operand::LiteralInt32(1)],
+                   m.execution_modes[0].operands);
+    }
+
+    #[test]
+    fn test_add_to_entry_point_interface() {
+        let mut b = Builder::new();
+        b.entry_point(spirv::ExecutionModel::Vertex, 1, "main", vec![2]);
+        b.entry_point(spirv::ExecutionModel::Fragment, 1, "main", vec![]);
+        b.entry_point(spirv::ExecutionModel::Vertex, 5, "other", vec![]);
+        assert_eq!(Ok(()), b.add_to_entry_point_interface(1, 3));
+        assert_eq!(Ok(()), b.add_to_entry_point_interface(1, 2));
+        assert_eq!(Err(Error::MissingEntryPoint), b.add_to_entry_point_interface(2, 3));
+
+        let m = b.module();
+        let interfaces: Vec<Vec<mr::Operand>> =
+            m.entry_points.iter().map(|inst| inst.operands[3..].to_vec()).collect();
+        assert_eq!(vec![vec![mr::Operand::IdRef(2), mr::Operand::IdRef(3)],
+                        vec![mr::Operand::IdRef(3), mr::Operand::IdRef(2)],
+                        vec![]],
+                   interfaces);
+    }
+
+    #[test]
+    fn test_checked_module() {
+        let build = |offset: spirv::Word| {
+            let mut b = Builder::new();
+            let void = b.type_void();
+            let voidfvoid = b.type_function(void, vec![]);
+            let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+            b.begin_basic_block(None).unwrap();
+            b.entry_point(spirv::ExecutionModel::Vertex, f + offset, "main", vec![]);
+            b
+        };
+        let mut b = build(0);
+        assert_eq!(Error::UnclosedBasicBlock, b.checked_module().unwrap_err());
+
+        b = build(0);
+        b.ret().unwrap();
+        assert_eq!(Error::UnclosedFunction, b.checked_module().unwrap_err());
+
+        b = build(10);
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        assert_eq!(Error::UndefinedEntryPoint, b.checked_module().unwrap_err());
+
+        b = build(0);
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.name(u32::max_value(), "far");
+        assert_eq!(Error::IdOverflow, b.checked_module().unwrap_err());
+
+        b = build(0);
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        assert_eq!(1, b.checked_module().unwrap().functions.len());
+    }
+
     #[test]
     fn test_decoration_no_additional_params() {
         let mut b = Builder::new();
-        b.member_decorate(1, 0, spirv::Decoration::RelaxedPrecision, vec![]);
+        b.member_decorate(1, 0, spirv::Decoration::RelaxedPrecision, vec![]).unwrap();
         let m = b.module();
         assert!(has_only_one_global_inst(&m));
         let inst = m.annotations.last().unwrap();
-        assert_eq!("MemberDecorate", inst.class.opname);
+        assert_eq!("MemberDecorate", inst.grammar().opname);
         assert_eq!(3, inst.operands.len());
         assert_eq!(mr::Operand::IdRef(1), inst.operands[0]);
         assert_eq!(mr::Operand::from(0u32), inst.operands[1]);
@@ -531,11 +944,12 @@ mod tests {
         let mut b = Builder::new();
         b.decorate(1,
                    spirv::Decoration::LinkageAttributes,
-                   vec![mr::Operand::from("name"), mr::Operand::from(spirv::LinkageType::Export)]);
+                   vec![mr::Operand::from("name"), mr::Operand::from(spirv::LinkageType::Export)])
+            .unwrap();
         let m = b.module();
         assert!(has_only_one_global_inst(&m));
         let inst = m.annotations.last().unwrap();
-        assert_eq!("Decorate", inst.class.opname);
+        assert_eq!("Decorate", inst.grammar().opname);
         assert_eq!(4, inst.operands.len());
         assert_eq!(mr::Operand::IdRef(1), inst.operands[0]);
         assert_eq!(mr::Operand::from(spirv::Decoration::LinkageAttributes),
@@ -545,6 +959,62 @@ mod tests {
                    inst.operands[3]);
     }
 
+    #[test]
+    fn test_decorate_uniform_block() {
+        let mut b = Builder::new();
+        let float = b.type_float(32);
+        let v4float = b.type_vector(float, 4);
+        let block = b.type_struct(vec![v4float, float]);
+        let uniform = b.type_pointer(None, spirv::StorageClass::Uniform, block);
+        let file = b.string("block.glsl");
+        b.source(spirv::SourceLanguage::GLSL, 450, Some(file), None::<String>);
+
+        let fvoid = b.type_void();
+        let voidfvoid = b.type_function(fvoid, vec![]);
+        let ubo = b.variable(uniform, None, spirv::StorageClass::Uniform, None).unwrap();
+        b.begin_function(fvoid, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        // Annotations and debug names go to the module even inside basic blocks.
+        b.decorate(block, spirv::Decoration::Block, vec![]).unwrap();
+        b.member_decorate(block, 0, spirv::Decoration::Offset, vec![mr::Operand::from(0u32)])
+            .unwrap();
+        b.member_decorate(block, 1, spirv::Decoration::Offset, vec![mr::Operand::from(16u32)])
+            .unwrap();
+        b.decorate(ubo, spirv::Decoration::DescriptorSet, vec![mr::Operand::from(0u32)])
+            .unwrap();
+        b.decorate(ubo, spirv::Decoration::Binding, vec![mr::Operand::from(1u32)]).unwrap();
+        b.name(block, "Block");
+        b.member_name(block, 0, "color");
+        b.name(ubo, "ubo");
+
+        // Parameters are checked against the grammar.
+        assert_eq!(Err(Error::WrongDecorationOperand),
+                   b.decorate(ubo, spirv::Decoration::Binding, vec![]));
+        assert_eq!(Err(Error::WrongDecorationOperand),
+                   b.member_decorate(block,
+                                     0,
+                                     spirv::Decoration::Block,
+                                     vec![mr::Operand::from(0u32)]));
+
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        let m = b.module();
+        assert_eq!(1, m.functions[0].basic_blocks[0].instructions.len());
+        assert_eq!(vec!["%5 = OpString \"block.glsl\"",
+                        "OpSource GLSL 450 %5",
+                        "OpName %3 \"Block\"",
+                        "OpMemberName %3 0 \"color\"",
+                        "OpName %8 \"ubo\""],
+                   m.debugs.iter().map(|inst| inst.disassemble()).collect::<Vec<_>>());
+        assert_eq!(vec!["OpDecorate %3 Block",
+                        "OpMemberDecorate %3 0 Offset 0",
+                        "OpMemberDecorate %3 1 Offset 16",
+                        "OpDecorate %8 DescriptorSet 0",
+                        "OpDecorate %8 Binding 1"],
+                   m.annotations.iter().map(|inst| inst.disassemble()).collect::<Vec<_>>());
+    }
+
     #[test]
     fn test_constant_f32() {
         let mut b = Builder::new();
@@ -564,37 +1034,37 @@ mod tests {
         assert_eq!(7, m.types_global_values.len());
 
         let inst = &m.types_global_values[1];
-        assert_eq!(spirv::Op::Constant, inst.class.opcode);
+        assert_eq!(spirv::Op::Constant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(2), inst.result_id);
         assert_eq!(mr::Operand::from(3.14f32), inst.operands[0]);
 
         let inst = &m.types_global_values[2];
-        assert_eq!(spirv::Op::Constant, inst.class.opcode);
+        assert_eq!(spirv::Op::Constant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(3), inst.result_id);
         assert_eq!(mr::Operand::from(2e-10_f32), inst.operands[0]);
 
         let inst = &m.types_global_values[3];
-        assert_eq!(spirv::Op::Constant, inst.class.opcode);
+        assert_eq!(spirv::Op::Constant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(4), inst.result_id);
         assert_eq!(mr::Operand::from(0.0f32), inst.operands[0]);
 
         let inst = &m.types_global_values[4];
-        assert_eq!(spirv::Op::Constant, inst.class.opcode);
+        assert_eq!(spirv::Op::Constant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(5), inst.result_id);
         assert_eq!(mr::Operand::from(f32::NEG_INFINITY), inst.operands[0]);
 
         let inst = &m.types_global_values[5];
-        assert_eq!(spirv::Op::Constant, inst.class.opcode);
+        assert_eq!(spirv::Op::Constant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(6), inst.result_id);
         assert_eq!(mr::Operand::from(-1.0e-40_f32), inst.operands[0]);
 
         let inst = &m.types_global_values[6];
-        assert_eq!(spirv::Op::Constant, inst.class.opcode);
+        assert_eq!(spirv::Op::Constant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(7), inst.result_id);
         // NaN != NaN
@@ -622,31 +1092,31 @@ mod tests {
         assert_eq!(6, m.types_global_values.len());
 
         let inst = &m.types_global_values[1];
-        assert_eq!(spirv::Op::SpecConstant, inst.class.opcode);
+        assert_eq!(spirv::Op::SpecConstant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(2), inst.result_id);
         assert_eq!(mr::Operand::from(10.0f32), inst.operands[0]);
 
         let inst = &m.types_global_values[2];
-        assert_eq!(spirv::Op::SpecConstant, inst.class.opcode);
+        assert_eq!(spirv::Op::SpecConstant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(3), inst.result_id);
         assert_eq!(mr::Operand::from(-0.0f32), inst.operands[0]);
 
         let inst = &m.types_global_values[3];
-        assert_eq!(spirv::Op::SpecConstant, inst.class.opcode);
+        assert_eq!(spirv::Op::SpecConstant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(4), inst.result_id);
         assert_eq!(mr::Operand::from(f32::INFINITY), inst.operands[0]);
 
         let inst = &m.types_global_values[4];
-        assert_eq!(spirv::Op::SpecConstant, inst.class.opcode);
+        assert_eq!(spirv::Op::SpecConstant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(5), inst.result_id);
         assert_eq!(mr::Operand::from(1.0e-40_f32), inst.operands[0]);
 
         let inst = &m.types_global_values[5];
-        assert_eq!(spirv::Op::SpecConstant, inst.class.opcode);
+        assert_eq!(spirv::Op::SpecConstant, inst.class);
         assert_eq!(Some(1), inst.result_type);
         assert_eq!(Some(6), inst.result_id);
         // NaN != NaN
@@ -673,33 +1143,193 @@ mod tests {
         assert_eq!(4, m.types_global_values.len());
 
         let inst = &m.types_global_values[0];
-        assert_eq!(spirv::Op::TypeFloat, inst.class.opcode);
+        assert_eq!(spirv::Op::TypeFloat, inst.class);
         assert_eq!(None, inst.result_type);
         assert_eq!(Some(1), inst.result_id);
         assert_eq!(vec![mr::Operand::LiteralInt32(32)], inst.operands);
 
         let inst = &m.types_global_values[1];
-        assert_eq!(spirv::Op::TypePointer, inst.class.opcode);
+        assert_eq!(spirv::Op::TypePointer, inst.class);
         assert_eq!(None, inst.result_type);
         assert_eq!(Some(2), inst.result_id);
         assert_eq!(vec![mr::Operand::from(spirv::StorageClass::Input), mr::Operand::IdRef(1)],
                    inst.operands);
 
         let inst = &m.types_global_values[2];
-        assert_eq!(spirv::Op::TypeForwardPointer, inst.class.opcode);
+        assert_eq!(spirv::Op::TypeForwardPointer, inst.class);
         assert_eq!(None, inst.result_type);
         assert_eq!(None, inst.result_id);
         assert_eq!(vec![mr::Operand::IdRef(3), mr::Operand::from(spirv::StorageClass::Output)],
                    inst.operands);
 
         let inst = &m.types_global_values[3];
-        assert_eq!(spirv::Op::TypePointer, inst.class.opcode);
+        assert_eq!(spirv::Op::TypePointer, inst.class);
         assert_eq!(None, inst.result_type);
         assert_eq!(Some(3), inst.result_id);
         assert_eq!(vec![mr::Operand::from(spirv::StorageClass::Output), mr::Operand::IdRef(1)],
                    inst.operands);
     }
 
+    #[test]
+    fn test_build_function_structure() {
+        let mut b = Builder::new();
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let voidffloat = b.type_function(void, vec![float]);
+
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidffloat).unwrap();
+        let param = b.function_parameter(float).unwrap();
+        let label = b.id();
+        let first = b.begin_basic_block(None).unwrap();
+        b.branch(label).unwrap();
+        assert_eq!(label, b.begin_basic_block(Some(label)).unwrap());
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        let m = b.module();
+        assert_eq!(1, m.functions.len());
+        let function = &m.functions[0];
+        assert_eq!(Some(f), function.def.as_ref().unwrap().result_id);
+        assert_eq!(Some(param), function.parameters[0].result_id);
+        assert!(function.end.is_some());
+        assert_eq!(vec![Some(first), Some(label)],
+                   function.basic_blocks
+                       .iter()
+                       .map(|bb| bb.label.as_ref().unwrap().result_id)
+                       .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_build_function_structure_errors() {
+        let mut b = Builder::new();
+        let void = b.type_void();
+        let voidfvoid = b.type_function(void, vec![]);
+
+        // Outside functions.
+        assert_eq!(Err(Error::MismatchedFunctionEnd), b.end_function());
+        assert_eq!(Err(Error::DetachedFunctionParameter),
+                   b.function_parameter(void));
+        assert_eq!(Err(Error::DetachedBasicBlock), b.begin_basic_block(None));
+        assert_eq!(Err(Error::DetachedInstruction), b.nop());
+        assert_eq!(Err(Error::MismatchedTerminator), b.ret());
+
+        // Inside a function but outside basic blocks.
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        assert_eq!(Err(Error::NestedFunction),
+                   b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid));
+        assert_eq!(Err(Error::DetachedInstruction), b.nop());
+        assert_eq!(Err(Error::MismatchedTerminator), b.ret());
+
+        // Inside a basic block.
+        b.begin_basic_block(None).unwrap();
+        assert_eq!(Err(Error::NestedBasicBlock), b.begin_basic_block(None));
+        assert_eq!(Err(Error::UnclosedBasicBlock), b.end_function());
+        // The function and the basic block are still open after errors.
+        assert_eq!(Ok(()), b.nop());
+        assert_eq!(Ok(()), b.ret());
+        assert_eq!(Ok(()), b.end_function());
+
+        let m = b.module();
+        assert_eq!(1, m.functions.len());
+        assert_eq!(1, m.functions[0].basic_blocks.len());
+        assert_eq!(2, m.functions[0].basic_blocks[0].instructions.len());
+    }
+
+    #[test]
+    fn test_dedup_types() {
+        let mut b = Builder::new();
+        let uint = b.type_int(32, 0);
+        assert_eq!(uint, b.type_int(32, 0));
+        let int = b.type_int(32, 1);
+        assert!(int != uint);
+        let s = b.type_struct(vec![uint, int]);
+        assert_eq!(s, b.type_struct(vec![uint, int]));
+        assert!(s != b.type_struct(vec![int, uint]));
+        assert!(s != b.type_struct_unique(vec![uint, int]));
+        let p = b.type_pointer(None, spirv::StorageClass::Uniform, s);
+        assert_eq!(p, b.type_pointer(None, spirv::StorageClass::Uniform, s));
+        let image = b.type_image(uint,
+                                 spirv::Dim::Dim2D,
+                                 0,
+                                 0,
+                                 0,
+                                 1,
+                                 spirv::ImageFormat::Unknown,
+                                 None);
+        assert_eq!(image,
+                   b.type_image(uint,
+                                spirv::Dim::Dim2D,
+                                0,
+                                0,
+                                0,
+                                1,
+                                spirv::ImageFormat::Unknown,
+                                None));
+        assert!(image !=
+                b.type_image(uint,
+                             spirv::Dim::Dim2D,
+                             0,
+                             0,
+                             0,
+                             1,
+                             spirv::ImageFormat::Unknown,
+                             Some(spirv::AccessQualifier::ReadOnly)));
+        assert_eq!(b.type_sampled_image(image), b.type_sampled_image(image));
+
+        let m = b.module();
+        assert_eq!(9, m.types_global_values.len());
+        assert_eq!(1,
+                   m.types_global_values
+                       .iter()
+                       .filter(|inst| inst.class == spirv::Op::TypeInt &&
+                                      inst.operands[1] == mr::Operand::LiteralInt32(0))
+                       .count());
+        assert_eq!(10, m.header.unwrap().bound);
+    }
+
+    #[test]
+    fn test_dedup_constants() {
+        let mut b = Builder::new();
+        let boolean = b.type_bool();
+        let uint = b.type_int(32, 0);
+        let ulong = b.type_int(64, 0);
+        let float = b.type_float(32);
+        let double = b.type_float(64);
+        let v2float = b.type_vector(float, 2);
+
+        let three = b.constant_u32(uint, 3);
+        assert_eq!(three, b.constant_u32(uint, 3));
+        assert!(three != b.constant_u32(uint, 4));
+        assert_eq!(Some(three), b.lookup_constant_u32(uint, 3));
+        assert_eq!(None, b.lookup_constant_u32(uint, 5));
+        assert_eq!(b.constant_u64(ulong, 3), b.constant_u64(ulong, 3));
+
+        // Floats are compared by their bit patterns.
+        let zero = b.constant_f32(float, 0.0);
+        assert_eq!(zero, b.constant_f32(float, 0.0));
+        assert!(zero != b.constant_f32(float, -0.0));
+        let nan = b.constant_f32(float, f32::NAN);
+        assert_eq!(nan, b.constant_f32(float, f32::NAN));
+        assert!(nan != b.constant_f32(float, -f32::NAN));
+        assert_eq!(b.constant_f64(double, 0.5), b.constant_f64(double, 0.5));
+
+        let t = b.constant_true(boolean);
+        assert_eq!(t, b.constant_true(boolean));
+        assert!(t != b.constant_false(boolean));
+        assert_eq!(b.constant_null(uint), b.constant_null(uint));
+        let v = b.constant_composite(v2float, vec![zero, nan]);
+        assert_eq!(v, b.constant_composite(v2float, vec![zero, nan]));
+        assert!(v != b.constant_composite(v2float, vec![nan, zero]));
+
+        // Specialization constants are never merged.
+        assert!(b.spec_constant_u32(uint, 3) != b.spec_constant_u32(uint, 3));
+        assert!(b.spec_constant_true(boolean) != b.spec_constant_true(boolean));
+
+        let m = b.module();
+        assert_eq!(23, m.types_global_values.len());
+        assert_eq!(24, m.header.unwrap().bound);
+    }
+
     #[test]
     fn test_forward_ref_phi() {
         let mut b = Builder::new();
@@ -758,6 +1388,127 @@ mod tests {
                     OpFunctionEnd");
     }
 
+    #[test]
+    fn test_build_diamond() {
+        let mut b = Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let boolean = b.type_bool();
+        let t = b.constant_true(boolean);
+        let voidfvoid = b.type_function(void, vec![]);
+
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        // Forward referenced labels.
+        let (left, right, merge) = (b.id(), b.id(), b.id());
+        b.selection_merge(merge, spirv::SELECTION_CONTROL_NONE).unwrap();
+        b.branch_conditional(t, left, right, vec![]).unwrap();
+        // Nothing can follow a terminator until the next basic block begins.
+        assert_eq!(Err(Error::MismatchedTerminator), b.branch(merge));
+        assert_eq!(Err(Error::DetachedInstruction), b.nop());
+
+        b.begin_basic_block(Some(right)).unwrap();
+        b.branch(merge).unwrap();
+        b.begin_basic_block(Some(left)).unwrap();
+        b.branch(merge).unwrap();
+        b.begin_basic_block(Some(merge)).unwrap();
+        b.ret().unwrap();
+        assert_eq!(Err(Error::MismatchedTerminator), b.unreachable());
+        b.end_function().unwrap();
+
+        let m = mr::load_words(b.module().assemble()).unwrap();
+        assert_eq!(vec![Some(right), Some(left), Some(merge)],
+                   m.functions[0].basic_blocks[1..]
+                       .iter()
+                       .map(|bb| bb.label.as_ref().unwrap().result_id)
+                       .collect::<Vec<_>>());
+        assert_eq!(Ok(()), m.validate_layout());
+    }
+
+    #[test]
+    fn test_merge_validation() {
+        let mut b = Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let boolean = b.type_bool();
+        let t = b.constant_true(boolean);
+        let voidfvoid = b.type_function(void, vec![]);
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        let (header, body, merge) = (b.id(), b.id(), b.id());
+        b.begin_basic_block(None).unwrap();
+        b.branch(header).unwrap();
+
+        // OpSelectionMerge needs a conditional branch or a switch.
+        b.begin_basic_block(Some(header)).unwrap();
+        b.selection_merge(merge, spirv::SELECTION_CONTROL_NONE).unwrap();
+        assert_eq!(Err(Error::MisplacedMergeInstruction), b.branch(body));
+        assert_eq!(Err(Error::MisplacedMergeInstruction), b.ret());
+        // The merge instruction must immediately precede the terminator.
+        b.nop().unwrap();
+        assert_eq!(Err(Error::MisplacedMergeInstruction),
+                   b.branch_conditional(t, body, merge, vec![]));
+        b.basic_block.as_mut().unwrap().instructions.clear();
+
+        // OpLoopMerge needs an unconditional or a conditional branch.
+        b.loop_merge(merge, header, spirv::LOOP_CONTROL_NONE, vec![]).unwrap();
+        assert_eq!(Err(Error::MisplacedMergeInstruction), b.switch(t, merge, vec![]));
+        assert_eq!(Ok(()), b.branch(body));
+        b.begin_basic_block(Some(body)).unwrap();
+        b.branch_conditional(t, header, merge, vec![]).unwrap();
+        b.begin_basic_block(Some(merge)).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        let m = b.module();
+        assert_eq!(Ok(()), m.validate_layout());
+        assert_eq!(vec![mr::Operand::IdRef(merge),
+                        mr::Operand::IdRef(header),
+                        mr::Operand::LoopControl(spirv::LOOP_CONTROL_NONE)],
+                   m.functions[0].basic_blocks[1].instructions[0].operands);
+    }
+
+    #[test]
+    fn test_build_if() {
+        let mut b = Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let boolean = b.type_bool();
+        let t = b.constant_true(boolean);
+        let voidfvoid = b.type_function(void, vec![]);
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        assert_eq!(Err(Error::DetachedInstruction),
+                   b.build_if(t, |_| Ok(()), |_| Ok(())));
+        b.begin_basic_block(None).unwrap();
+        let merge = b.build_if(t,
+                      |b| b.build_if(t, |b| b.nop(), |b| b.kill()).map(|_| ()),
+                      |_| Ok(()))
+            .unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        let m = mr::load_words(b.module().assemble()).unwrap();
+        assert_eq!(Ok(()), m.validate_layout());
+        let blocks = &m.functions[0].basic_blocks;
+        let labels: Vec<spirv::Word> =
+            blocks.iter().map(|bb| bb.label.as_ref().unwrap().result_id.unwrap()).collect();
+        assert_eq!(7, blocks.len());
+        assert_eq!(merge, labels[6]);
+        assert_eq!(vec!["OpSelectionMerge %9 None".to_string(),
+                        "OpBranchConditional %3 %7 %8".to_string()],
+                   blocks[0].instructions.iter().map(|i| i.disassemble()).collect::<Vec<_>>());
+        // The inner if-else, whose else branch kills the invocation.
+        assert_eq!("OpSelectionMerge %12 None", blocks[1].instructions[0].disassemble());
+        assert_eq!(vec!["OpNop", "OpBranch %12"],
+                   blocks[2].instructions.iter().map(|i| i.disassemble()).collect::<Vec<_>>());
+        assert_eq!(vec!["OpKill"],
+                   blocks[3].instructions.iter().map(|i| i.disassemble()).collect::<Vec<_>>());
+        // The inner merge block branches to the outer one.
+        assert_eq!(vec![12, 8, 9], vec![labels[4], labels[5], labels[6]]);
+        assert_eq!("OpBranch %9", blocks[4].instructions[0].disassemble());
+        assert_eq!("OpBranch %9", blocks[5].instructions[0].disassemble());
+    }
+
     #[test]
     fn test_build_variables() {
         let mut b = Builder::new();
@@ -774,7 +1525,7 @@ mod tests {
         assert_eq!(5, voidfvoid);
 
         // Global variable
-        let v1 = b.variable(ifp, None, spirv::StorageClass::Input, None);
+        let v1 = b.variable(ifp, None, spirv::StorageClass::Input, None).unwrap();
         assert_eq!(6, v1);
 
         let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
@@ -782,13 +1533,13 @@ mod tests {
         let bb = b.begin_basic_block(None).unwrap();
         assert_eq!(8, bb);
         // Local variable
-        let v2 = b.variable(ffp, None, spirv::StorageClass::Function, None);
+        let v2 = b.variable(ffp, None, spirv::StorageClass::Function, None).unwrap();
         assert_eq!(9, v2);
         assert!(b.ret().is_ok());
         assert!(b.end_function().is_ok());
 
         // Global variable again
-        let v3 = b.variable(ifp, None, spirv::StorageClass::Input, None);
+        let v3 = b.variable(ifp, None, spirv::StorageClass::Input, None).unwrap();
         assert_eq!(10, v3);
 
         assert_eq!(b.module().disassemble(),
@@ -807,6 +1558,81 @@ mod tests {
                     OpFunctionEnd");
     }
 
+    #[test]
+    fn test_build_variables_misplaced() {
+        let mut b = Builder::new();
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let ffp = b.type_pointer(None, spirv::StorageClass::Function, float);
+        let voidfvoid = b.type_function(void, vec![]);
+        let one = b.constant_f32(float, 1.0);
+
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        let v1 = b.variable(ffp, None, spirv::StorageClass::Function, None).unwrap();
+        b.store(v1, one, None, vec![]).unwrap();
+        assert_eq!(Err(Error::MisplacedVariable),
+                   b.variable(ffp, None, spirv::StorageClass::Function, None));
+        let next = b.id();
+        b.branch(next).unwrap();
+        assert_eq!(Err(Error::DetachedInstruction),
+                   b.variable(ffp, None, spirv::StorageClass::Function, None));
+        b.begin_basic_block(Some(next)).unwrap();
+        assert_eq!(Err(Error::MisplacedVariable),
+                   b.variable(ffp, None, spirv::StorageClass::Function, None));
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        // Only OpVariable, OpStore, and OpBranch.
+        assert_eq!(3, b.module().functions[0].basic_blocks[0].instructions.len());
+    }
+
+    #[test]
+    fn test_build_variables_hoisted() {
+        let mut b = Builder::new();
+        b.hoist_variables(true);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let ffp = b.type_pointer(None, spirv::StorageClass::Function, float);
+        let pfp = b.type_pointer(None, spirv::StorageClass::Private, float);
+        let voidfvoid = b.type_function(void, vec![]);
+        let one = b.constant_f32(float, 1.0);
+
+        // Function variables need a function with a basic block.
+        assert_eq!(Err(Error::DetachedInstruction),
+                   b.variable(ffp, None, spirv::StorageClass::Function, None));
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        assert_eq!(Err(Error::DetachedInstruction),
+                   b.variable(ffp, None, spirv::StorageClass::Function, None));
+
+        b.begin_basic_block(None).unwrap();
+        let v1 = b.variable(ffp, None, spirv::StorageClass::Function, None).unwrap();
+        b.store(v1, one, None, vec![]).unwrap();
+        // In the middle of the entry block.
+        let v2 = b.variable(ffp, None, spirv::StorageClass::Function, Some(one)).unwrap();
+        let next = b.id();
+        b.branch(next).unwrap();
+        b.begin_basic_block(Some(next)).unwrap();
+        // In another basic block.
+        let v3 = b.variable(ffp, None, spirv::StorageClass::Function, None).unwrap();
+        // Not a function variable.
+        let v4 = b.variable(pfp, None, spirv::StorageClass::Private, None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        let m = mr::load_words(b.module().assemble()).unwrap();
+        assert_eq!(Ok(()), m.validate_layout());
+        assert_eq!(Some(v4), m.types_global_values.last().unwrap().result_id);
+        let blocks = &m.functions[0].basic_blocks;
+        assert_eq!(vec![format!("%{} = OpVariable  %{}  Function", v1, ffp),
+                        format!("%{} = OpVariable  %{}  Function %{}", v2, ffp, one),
+                        format!("%{} = OpVariable  %{}  Function", v3, ffp),
+                        format!("OpStore %{} %{}", v1, one),
+                        format!("OpBranch %{}", next)],
+                   blocks[0].instructions.iter().map(|i| i.disassemble()).collect::<Vec<_>>());
+        assert_eq!(1, blocks[1].instructions.len());
+    }
+
     #[test]
     fn test_build_undefs() {
         let mut b = Builder::new();
@@ -849,4 +1675,21 @@ mod tests {
                     OpReturn\n\
                     OpFunctionEnd");
     }
+
+    #[test]
+    fn test_build_module_bound() {
+        let mut b = Builder::new();
+        let void = b.type_void();
+        let voidfvoid = b.type_function(void, vec![]);
+        b.begin_function(void, Some(10), spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        assert_eq!(11, b.module().header.unwrap().bound);
+
+        let mut b = Builder::new();
+        b.type_void();
+        b.id();
+        assert_eq!(3, b.module().header.unwrap().bound);
+    }
 }
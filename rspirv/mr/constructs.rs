@@ -13,10 +13,16 @@
 // limitations under the License.
 
 use grammar;
+#[cfg(feature = "serialize")]
+use serde;
 use spirv;
 
 use spirv::Word;
-use std::{convert, fmt, iter};
+use std::{cmp, convert, error, fmt, iter, vec};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use utils::num::{f32_to_u32, f64_to_u64, sign_extend_u32};
 
 /// Data representation of a SPIR-V module.
 ///
@@ -26,7 +32,8 @@ use std::{convert, fmt, iter};
 /// The order of its fields basically reveal the requirements in the
 /// [Logical Layout of a Module](https://goo.gl/2kVnfX) of the SPIR-V
 /// of the SPIR-V specification.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Module {
     /// The module header.
     pub header: Option<ModuleHeader>,
@@ -58,18 +65,89 @@ pub struct Module {
     pub functions: Vec<Function>,
 }
 
+/// An index from result ids to the instructions defining them in a
+/// `Module`, as built by [`Module::def_index`](struct.Module.html#method.def_index).
+///
+/// The index does not borrow the module, so it goes stale once the
+/// module's instructions are added, removed, or renumbered. Lookups check
+/// that the indexed instruction still defines the id and return `None`
+/// otherwise, instead of another instruction.
+#[derive(Clone, Debug, Default)]
+pub struct DefIndex {
+    locations: HashMap<Word, DefLocation>,
+}
+
+/// The location of an instruction defining a result id in a `Module`.
+#[derive(Clone, Copy, Debug)]
+enum DefLocation {
+    ExtInstImport(usize),
+    Debug(usize),
+    Annotation(usize),
+    TypeGlobalValue(usize),
+    /// (function index)
+    Function(usize),
+    /// (function index, parameter index)
+    Parameter(usize, usize),
+    /// (function index, basic block index)
+    Label(usize, usize),
+    /// (function index, basic block index, instruction index)
+    Instruction(usize, usize, usize),
+}
+
+impl DefIndex {
+    /// Returns the instruction in `module` defining the given result `id`,
+    /// or `None` if `id` is not in the index or the indexed instruction no
+    /// longer defines it.
+    pub fn get<'m>(&self, module: &'m Module, id: Word) -> Option<&'m Instruction> {
+        self.locations
+            .get(&id)
+            .and_then(|&location| module.inst_at(location))
+            .and_then(|inst| if inst.result_id == Some(id) { Some(inst) } else { None })
+    }
+
+    /// Returns the instruction in `module` defining the given result `id`
+    /// for mutation. See [`get`](#method.get) for details.
+    pub fn get_mut<'m>(&self, module: &'m mut Module, id: Word) -> Option<&'m mut Instruction> {
+        self.locations
+            .get(&id)
+            .and_then(move |&location| module.inst_at_mut(location))
+            .and_then(|inst| if inst.result_id == Some(id) { Some(inst) } else { None })
+    }
+}
+
+/// The value of a constant, as returned by
+/// [`Module::get_constant_composite`](struct.Module.html#method.get_constant_composite).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstantValue {
+    /// OpConstantTrue or OpConstantFalse
+    Bool(bool),
+    /// Constant of an unsigned integer type, zero-extended
+    UInt(u64),
+    /// Constant of a signed integer type, sign-extended
+    SInt(i64),
+    /// Constant of the 32-bit floating point type
+    Float32(f32),
+    /// Constant of the 64-bit floating point type
+    Float64(f64),
+    /// OpConstantComposite, with the values of its constituents
+    Composite(Vec<ConstantValue>),
+}
+
 /// Data representation of a SPIR-V module header.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct ModuleHeader {
     pub magic_number: Word,
     pub version: Word,
     pub generator: Word,
     pub bound: Word,
+    /// The instruction schema, which is required to be 0 for now.
     pub reserved_word: Word,
 }
 
 /// Data representation of a SPIR-V function.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Function {
     /// First (defining) instruction in this function.
     pub def: Option<Instruction>,
@@ -82,7 +160,8 @@ pub struct Function {
 }
 
 /// Data representation of a SPIR-V basic block.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct BasicBlock {
     /// The label starting this basic block.
     pub label: Option<Instruction>,
@@ -91,10 +170,26 @@ pub struct BasicBlock {
 }
 
 /// Data representation of a SPIR-V instruction.
-#[derive(Debug)]
+///
+/// Instructions are compared and hashed by their opcode, result type id,
+/// result id, and operands; see [`Operand`](enum.Operand.html) for how
+/// floating point literals are treated.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Instruction {
-    /// The class (grammar specification) of this instruction.
-    pub class: &'static grammar::Instruction<'static>,
+    /// The class (opcode) of this instruction.
+    ///
+    /// Its grammar specification is looked up by
+    /// [`grammar`](#method.grammar) when needed. Instructions unknown to
+    /// the grammar have no class of their own; theirs is a placeholder, not
+    /// encoded or compared, and [`unknown_opcode`](#structfield.unknown_opcode)
+    /// is set instead.
+    #[cfg_attr(feature = "serialize", serde(rename = "opcode"))]
+    pub class: spirv::Op,
+    /// The opcode of this instruction if it is unknown to the grammar, as
+    /// created by [`unknown`](#method.unknown).
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub unknown_opcode: Option<u16>,
     /// Result type id.
     pub result_type: Option<Word>,
     /// Result id.
@@ -103,6 +198,26 @@ pub struct Instruction {
     pub operands: Vec<Operand>,
 }
 
+impl PartialEq for Instruction {
+    fn eq(&self, other: &Instruction) -> bool {
+        self.opcode() == other.opcode() && self.unknown_opcode == other.unknown_opcode &&
+        self.result_type == other.result_type && self.result_id == other.result_id &&
+        self.operands == other.operands
+    }
+}
+
+impl Eq for Instruction {}
+
+impl Hash for Instruction {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.opcode().hash(state);
+        self.unknown_opcode.hash(state);
+        self.result_type.hash(state);
+        self.result_id.hash(state);
+        self.operands.hash(state);
+    }
+}
+
 /// Instruction iterator.
 pub struct InstIter<'i> {
     instructions: Vec<&'i Instruction>,
@@ -132,6 +247,25 @@ impl<'i> iter::Iterator for InstIter<'i> {
     }
 }
 
+/// Mutable instruction iterator.
+pub struct InstIterMut<'i> {
+    instructions: vec::IntoIter<&'i mut Instruction>,
+}
+
+impl<'i> InstIterMut<'i> {
+    pub fn new(insts: Vec<&'i mut Instruction>) -> InstIterMut<'i> {
+        InstIterMut { instructions: insts.into_iter() }
+    }
+}
+
+impl<'i> iter::Iterator for InstIterMut<'i> {
+    type Item = &'i mut Instruction;
+
+    fn next(&mut self) -> Option<&'i mut Instruction> {
+        self.instructions.next()
+    }
+}
+
 include!("operand.rs");
 
 impl Module {
@@ -152,6 +286,118 @@ impl Module {
         }
     }
 
+    /// Returns the instruction defining the given result `id`, searching
+    /// all global instructions, function definitions and parameters, basic
+    /// block labels, and instructions inside basic blocks.
+    ///
+    /// Each call scans the module; build a [`DefIndex`](struct.DefIndex.html)
+    /// with [`def_index`](#method.def_index) for looking up many ids.
+    pub fn def(&self, id: Word) -> Option<&Instruction> {
+        self.all_inst_iter().find(|inst| inst.result_id == Some(id))
+    }
+
+    /// Returns the instruction defining the given result `id` for mutation.
+    /// See [`def`](#method.def) for details.
+    pub fn def_mut(&mut self, id: Word) -> Option<&mut Instruction> {
+        self.all_inst_iter_mut().find(|inst| inst.result_id == Some(id))
+    }
+
+    /// Builds an index from the result ids defined in this module to the
+    /// instructions defining them, covering the same instructions as
+    /// [`def`](#method.def). If an id is defined more than once, the first
+    /// definition is kept.
+    ///
+    /// The index has to be built again after changing the module.
+    pub fn def_index(&self) -> DefIndex {
+        let mut locations = HashMap::new();
+        {
+            let mut add = |inst: &Instruction, location: DefLocation| {
+                if let Some(id) = inst.result_id {
+                    locations.entry(id).or_insert(location);
+                }
+            };
+            for (i, inst) in self.ext_inst_imports.iter().enumerate() {
+                add(inst, DefLocation::ExtInstImport(i));
+            }
+            for (i, inst) in self.debugs.iter().enumerate() {
+                add(inst, DefLocation::Debug(i));
+            }
+            for (i, inst) in self.annotations.iter().enumerate() {
+                add(inst, DefLocation::Annotation(i));
+            }
+            for (i, inst) in self.types_global_values.iter().enumerate() {
+                add(inst, DefLocation::TypeGlobalValue(i));
+            }
+            for (f, function) in self.functions.iter().enumerate() {
+                if let Some(ref def) = function.def {
+                    add(def, DefLocation::Function(f));
+                }
+                for (p, param) in function.parameters.iter().enumerate() {
+                    add(param, DefLocation::Parameter(f, p));
+                }
+                for (b, block) in function.basic_blocks.iter().enumerate() {
+                    if let Some(ref label) = block.label {
+                        add(label, DefLocation::Label(f, b));
+                    }
+                    for (i, inst) in block.instructions.iter().enumerate() {
+                        add(inst, DefLocation::Instruction(f, b, i));
+                    }
+                }
+            }
+        }
+        DefIndex { locations: locations }
+    }
+
+    fn inst_at(&self, location: DefLocation) -> Option<&Instruction> {
+        match location {
+            DefLocation::ExtInstImport(i) => self.ext_inst_imports.get(i),
+            DefLocation::Debug(i) => self.debugs.get(i),
+            DefLocation::Annotation(i) => self.annotations.get(i),
+            DefLocation::TypeGlobalValue(i) => self.types_global_values.get(i),
+            DefLocation::Function(f) => self.functions.get(f).and_then(|f| f.def.as_ref()),
+            DefLocation::Parameter(f, p) => {
+                self.functions.get(f).and_then(|f| f.parameters.get(p))
+            }
+            DefLocation::Label(f, b) => {
+                self.functions
+                    .get(f)
+                    .and_then(|f| f.basic_blocks.get(b))
+                    .and_then(|b| b.label.as_ref())
+            }
+            DefLocation::Instruction(f, b, i) => {
+                self.functions
+                    .get(f)
+                    .and_then(|f| f.basic_blocks.get(b))
+                    .and_then(|b| b.instructions.get(i))
+            }
+        }
+    }
+
+    fn inst_at_mut(&mut self, location: DefLocation) -> Option<&mut Instruction> {
+        match location {
+            DefLocation::ExtInstImport(i) => self.ext_inst_imports.get_mut(i),
+            DefLocation::Debug(i) => self.debugs.get_mut(i),
+            DefLocation::Annotation(i) => self.annotations.get_mut(i),
+            DefLocation::TypeGlobalValue(i) => self.types_global_values.get_mut(i),
+            DefLocation::Function(f) => self.functions.get_mut(f).and_then(|f| f.def.as_mut()),
+            DefLocation::Parameter(f, p) => {
+                self.functions.get_mut(f).and_then(|f| f.parameters.get_mut(p))
+            }
+            DefLocation::Label(f, b) => {
+                self.functions
+                    .get_mut(f)
+                    .and_then(|f| f.basic_blocks.get_mut(b))
+                    .and_then(|b| b.label.as_mut())
+            }
+            DefLocation::Instruction(f, b, i) => {
+                self.functions
+                    .get_mut(f)
+                    .and_then(|f| f.basic_blocks.get_mut(b))
+                    .and_then(|b| b.instructions.get_mut(i))
+            }
+        }
+    }
+
     /// Returns an iterator over all global instructions.
     ///
     /// This method internally creates a vector of references to all global
@@ -179,6 +425,823 @@ impl Module {
         insts.append(&mut i);
         InstIter::new(insts)
     }
+
+    /// Returns an iterator over all instructions in this module, in the
+    /// order they are assembled: global instructions section by section,
+    /// followed by each function's definition, parameters, basic blocks
+    /// (label first), and end.
+    ///
+    /// Like [`global_inst_iter`](#method.global_inst_iter), this method
+    /// internally creates a vector of references to all instructions.
+    pub fn all_inst_iter(&self) -> InstIter {
+        let mut insts: Vec<&Instruction> = self.global_inst_iter().collect();
+        for f in &self.functions {
+            insts.extend(f.def.iter().chain(f.parameters.iter()));
+            for bb in &f.basic_blocks {
+                insts.extend(bb.label.iter().chain(bb.instructions.iter()));
+            }
+            insts.extend(f.end.iter());
+        }
+        InstIter::new(insts)
+    }
+
+    /// Returns an iterator over mutable references to all instructions in
+    /// this module, in the same order as
+    /// [`all_inst_iter`](#method.all_inst_iter).
+    pub fn all_inst_iter_mut(&mut self) -> InstIterMut {
+        let mut insts: Vec<&mut Instruction> = vec![];
+        insts.extend(self.capabilities.iter_mut());
+        insts.extend(self.extensions.iter_mut());
+        insts.extend(self.ext_inst_imports.iter_mut());
+        insts.extend(self.memory_model.iter_mut());
+        insts.extend(self.entry_points.iter_mut());
+        insts.extend(self.execution_modes.iter_mut());
+        insts.extend(self.debugs.iter_mut());
+        insts.extend(self.annotations.iter_mut());
+        insts.extend(self.types_global_values.iter_mut());
+        for f in &mut self.functions {
+            insts.extend(f.def.iter_mut().chain(f.parameters.iter_mut()));
+            for bb in &mut f.basic_blocks {
+                insts.extend(bb.label.iter_mut().chain(bb.instructions.iter_mut()));
+            }
+            insts.extend(f.end.iter_mut());
+        }
+        InstIterMut::new(insts)
+    }
+
+    /// Computes the id bound of this module from all ids used in it, i.e.,
+    /// one more than the largest id among the result ids, result type ids,
+    /// and id operands of its instructions.
+    ///
+    /// This also covers ids only referenced, e.g., forward references to
+    /// labels or ids in decorations whose definitions have been removed.
+    /// Words of instructions unknown to the grammar are not looked into.
+    ///
+    /// Returns `None` if some id is `u32::max_value()`, which no bound
+    /// covers.
+    pub fn compute_id_bound(&self) -> Option<Word> {
+        let max = self.all_inst_iter()
+            .flat_map(|inst| {
+                let ids = inst.operands.iter().filter_map(|o| o.id());
+                inst.result_id.into_iter().chain(inst.result_type).chain(ids)
+            })
+            .max();
+        max.unwrap_or(0).checked_add(1)
+    }
+
+    /// Writes the id bound computed by
+    /// [`compute_id_bound`](#method.compute_id_bound) into the module
+    /// header, creating the header if there is none, and returns it.
+    ///
+    /// Returns `None` and leaves the header untouched if no bound covers
+    /// all ids.
+    pub fn update_id_bound(&mut self) -> Option<Word> {
+        let bound = self.compute_id_bound()?;
+        match self.header {
+            Some(ref mut header) => header.bound = bound,
+            None => self.header = Some(ModuleHeader::new(bound)),
+        }
+        Some(bound)
+    }
+
+    /// Returns a copy of this module with `offset` added to all ids in it,
+    /// e.g., to move its id space above another module's bound before
+    /// linking them together.
+    ///
+    /// Result ids, result type ids, and id operands are offset, including
+    /// those following literals like OpSwitch targets or those among the
+    /// parameters of bit masks like ImageOperands. Literals are left as
+    /// they are, even if their values look like ids. The header bound, if
+    /// any, is increased by `offset`. Words of instructions unknown to the
+    /// grammar are not looked into.
+    ///
+    /// Returns `None` if an id or the header bound does not fit in a word
+    /// after adding `offset`.
+    pub fn clone_with_id_offset(&self, offset: Word) -> Option<Module> {
+        let mut module = self.clone();
+        for inst in module.all_inst_iter_mut() {
+            let ids = inst.result_id
+                .iter_mut()
+                .chain(inst.result_type.iter_mut())
+                .chain(inst.operands.iter_mut().filter_map(|o| o.id_mut()));
+            for id in ids {
+                *id = id.checked_add(offset)?;
+            }
+        }
+        if let Some(ref mut header) = module.header {
+            header.bound = header.bound.checked_add(offset)?;
+        }
+        Some(module)
+    }
+
+    /// Removes all instructions for which `pred` returns false, returning
+    /// how many were removed.
+    ///
+    /// `pred` is called on instructions in the order they are assembled.
+    /// The instructions giving functions and basic blocks their structure,
+    /// i.e., OpFunction, OpFunctionParameter, OpFunctionEnd, OpLabel, and
+    /// the last instruction of each basic block if it is a terminator, are
+    /// always kept and not passed to `pred`.
+    pub fn retain_insts<F>(&mut self, mut pred: F) -> usize
+        where F: FnMut(&Instruction) -> bool
+    {
+        let mut removed = 0;
+        {
+            let mut retain = |insts: &mut Vec<Instruction>| {
+                let len = insts.len();
+                insts.retain(|inst| pred(inst));
+                removed += len - insts.len();
+            };
+            retain(&mut self.capabilities);
+            retain(&mut self.extensions);
+            retain(&mut self.ext_inst_imports);
+            let mut memory_model: Vec<Instruction> = self.memory_model
+                .take()
+                .into_iter()
+                .collect();
+            retain(&mut memory_model);
+            self.memory_model = memory_model.pop();
+            retain(&mut self.entry_points);
+            retain(&mut self.execution_modes);
+            retain(&mut self.debugs);
+            retain(&mut self.annotations);
+            retain(&mut self.types_global_values);
+            for f in &mut self.functions {
+                for bb in &mut f.basic_blocks {
+                    let has_terminator = bb.instructions
+                        .last()
+                        .map_or(false, |inst| grammar::reflect::is_terminator(inst.class));
+                    let terminator = if has_terminator {
+                        bb.instructions.pop()
+                    } else {
+                        None
+                    };
+                    retain(&mut bb.instructions);
+                    bb.instructions.extend(terminator);
+                }
+            }
+        }
+        removed
+    }
+
+    /// Returns the names given by all OpName instructions as (target id,
+    /// name) pairs, in the order of the instructions, including multiple
+    /// names for the same id.
+    pub fn all_names(&self) -> Vec<(Word, &str)> {
+        self.debugs.iter().filter_map(name_of).collect()
+    }
+
+    /// Returns the names given by OpName instructions, keyed by target id.
+    ///
+    /// If an id is named more than once, the last name wins, as in other
+    /// tools; see [`all_names`](#method.all_names) for all of them.
+    pub fn names(&self) -> HashMap<Word, &str> {
+        self.all_names().into_iter().collect()
+    }
+
+    /// Returns the names given by OpMemberName instructions, keyed by
+    /// (structure type id, member index). The last name wins for members
+    /// named more than once.
+    pub fn member_names(&self) -> HashMap<(Word, u32), &str> {
+        self.debugs.iter().filter_map(member_name_of).collect()
+    }
+
+    /// Returns the id named `name` by OpName instructions, if any.
+    ///
+    /// Only the names winning in [`names`](#method.names) are considered.
+    /// If several ids have the same name, the one named first is returned.
+    pub fn id_by_name(&self, name: &str) -> Option<Word> {
+        let names = self.names();
+        self.all_names()
+            .into_iter()
+            .find(|&(id, _)| names.get(&id) == Some(&name))
+            .map(|(id, _)| id)
+    }
+
+    /// Returns the entry points declared by the OpEntryPoint instructions,
+    /// in order.
+    pub fn entry_points(&self) -> Vec<EntryPointInfo> {
+        self.entry_points.iter().filter_map(|inst| self.entry_point_info(inst)).collect()
+    }
+
+    /// Returns the first entry point with the given `name`.
+    ///
+    /// The same name may be used by entry points of different execution
+    /// models; use [`entry_points`](#method.entry_points) to find them all.
+    pub fn entry_point_by_name(&self, name: &str) -> Option<EntryPointInfo> {
+        self.entry_points().into_iter().find(|entry| entry.name == name)
+    }
+
+    /// Returns the function of the given entry point.
+    pub fn function_for_entry_point(&self, entry: &EntryPointInfo) -> Option<&Function> {
+        self.functions.iter().find(|f| f.result_id() == Some(entry.function_id))
+    }
+
+    fn entry_point_info<'m>(&'m self, inst: &'m Instruction) -> Option<EntryPointInfo<'m>> {
+        if inst.class != spirv::Op::EntryPoint {
+            return None;
+        }
+        match (inst.operands.get(0), inst.operands.get(1), inst.operands.get(2)) {
+            (Some(&Operand::ExecutionModel(model)),
+             Some(&Operand::IdRef(function_id)),
+             Some(&Operand::LiteralString(ref name))) => {
+                Some(EntryPointInfo {
+                    execution_model: model,
+                    function_id: function_id,
+                    name: &**name,
+                    interface: inst.operands[3..].iter().filter_map(Operand::id_ref).collect(),
+                    execution_modes: self.execution_modes
+                        .iter()
+                        .filter(|mode| {
+                            mode.class == spirv::Op::ExecutionMode &&
+                            mode.operands.get(0) == Some(&Operand::IdRef(function_id))
+                        })
+                        .collect(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the global variables, i.e., the OpVariable instructions in
+    /// the types and global values section, in order.
+    ///
+    /// Variables local to functions are not included.
+    pub fn global_variables(&self) -> Vec<VariableInfo> {
+        self.types_global_values
+            .iter()
+            .filter(|inst| inst.class == spirv::Op::Variable)
+            .filter_map(|inst| {
+                match (inst.result_id, inst.result_type, inst.operands.get(0)) {
+                    (Some(id), Some(type_id), Some(&Operand::StorageClass(storage_class))) => {
+                        Some(VariableInfo {
+                            id: id,
+                            type_id: type_id,
+                            storage_class: storage_class,
+                            initializer: inst.operands.get(1).and_then(Operand::id_ref),
+                        })
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the global variables of the given `storage_class`, in order.
+    pub fn variables_with_storage_class(&self,
+                                        storage_class: spirv::StorageClass)
+                                        -> Vec<VariableInfo> {
+        self.global_variables()
+            .into_iter()
+            .filter(|var| var.storage_class == storage_class)
+            .collect()
+    }
+
+    /// Returns the names of the extensions declared by the OpExtension
+    /// instructions, in order.
+    pub fn extensions(&self) -> Vec<&str> {
+        self.extensions
+            .iter()
+            .filter(|inst| inst.class == spirv::Op::Extension)
+            .filter_map(|inst| inst.operands.get(0).and_then(Operand::literal_string))
+            .collect()
+    }
+
+    /// Returns true if the extension `name` is declared by an OpExtension
+    /// instruction.
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions().contains(&name)
+    }
+
+    /// Returns the result ids of the OpExtInstImport instructions, keyed by
+    /// the names of the extended instruction sets they import.
+    ///
+    /// If a set is imported more than once, the first import is returned.
+    pub fn ext_inst_imports(&self) -> HashMap<&str, Word> {
+        let mut imports = HashMap::new();
+        for (id, name) in self.ext_inst_imports.iter().filter_map(ext_inst_import_of) {
+            imports.entry(name).or_insert(id);
+        }
+        imports
+    }
+
+    /// Returns the name of the extended instruction set imported by the
+    /// OpExtInstImport instruction with the result `id`.
+    pub fn ext_inst_import_name(&self, id: Word) -> Option<&str> {
+        self.ext_inst_imports
+            .iter()
+            .filter_map(ext_inst_import_of)
+            .find(|&(import, _)| import == id)
+            .map(|(_, name)| name)
+    }
+
+    /// Returns the OpDecorate instructions decorating `id`, in order.
+    ///
+    /// Decorations of the decoration groups applied to `id` via
+    /// OpGroupDecorate are included; their target is the group instead.
+    pub fn decorations(&self, id: Word) -> Vec<&Instruction> {
+        let groups: HashSet<Word> = self.annotations
+            .iter()
+            .filter(|inst| {
+                inst.class == spirv::Op::GroupDecorate &&
+                inst.operands.iter().skip(1).any(|o| *o == Operand::IdRef(id))
+            })
+            .filter_map(|inst| inst.operands.get(0).and_then(Operand::id_ref))
+            .collect();
+        self.annotations
+            .iter()
+            .filter(|inst| {
+                inst.class == spirv::Op::Decorate &&
+                match inst.operands.get(0) {
+                    Some(&Operand::IdRef(target)) => target == id || groups.contains(&target),
+                    _ => false,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the OpMemberDecorate instructions decorating the `member` of
+    /// the structure type `id`, in order.
+    ///
+    /// The OpDecorate instructions of the decoration groups applied to the
+    /// member via OpGroupMemberDecorate are included.
+    pub fn member_decorations(&self, id: Word, member: u32) -> Vec<&Instruction> {
+        let target = [Operand::IdRef(id), Operand::LiteralInt32(member)];
+        let groups: HashSet<Word> = self.annotations
+            .iter()
+            .filter(|inst| {
+                inst.class == spirv::Op::GroupMemberDecorate &&
+                inst.operands.len() > 1 && inst.operands[1..].chunks(2).any(|t| t == &target[..])
+            })
+            .filter_map(|inst| inst.operands.get(0).and_then(Operand::id_ref))
+            .collect();
+        self.annotations
+            .iter()
+            .filter(|inst| match inst.class {
+                spirv::Op::MemberDecorate => inst.operands.starts_with(&target),
+                spirv::Op::Decorate => {
+                    inst.operands.get(0).and_then(Operand::id_ref).map_or(false, |group| {
+                        groups.contains(&group)
+                    })
+                }
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Returns true if `id` is decorated with `decoration`, directly or via
+    /// a decoration group.
+    pub fn has_decoration(&self, id: Word, decoration: spirv::Decoration) -> bool {
+        self.decorations(id).into_iter().any(|inst| decoration_of(inst) == Some(decoration))
+    }
+
+    /// Returns the literal number decorating `id` with `decoration`, e.g.,
+    /// its binding for `Decoration::Binding`, directly or via a decoration
+    /// group.
+    ///
+    /// The first such decoration wins. Returns `None` if there is none, or
+    /// if its first parameter is not a literal number.
+    pub fn get_decoration_u32(&self, id: Word, decoration: spirv::Decoration) -> Option<u32> {
+        self.decorations(id).into_iter().filter_map(|inst| decoration_u32(inst, decoration)).next()
+    }
+
+    /// Returns true if the `member` of the structure type `id` is
+    /// decorated with `decoration`, directly or via a decoration group.
+    pub fn has_member_decoration(&self,
+                                 id: Word,
+                                 member: u32,
+                                 decoration: spirv::Decoration)
+                                 -> bool {
+        self.member_decorations(id, member)
+            .into_iter()
+            .any(|inst| decoration_of(inst) == Some(decoration))
+    }
+
+    /// Returns the literal number decorating the `member` of the structure
+    /// type `id` with `decoration`, e.g., its offset for
+    /// `Decoration::Offset`. See
+    /// [`get_decoration_u32`](#method.get_decoration_u32) for details.
+    pub fn get_member_decoration_u32(&self,
+                                     id: Word,
+                                     member: u32,
+                                     decoration: spirv::Decoration)
+                                     -> Option<u32> {
+        self.member_decorations(id, member)
+            .into_iter()
+            .filter_map(|inst| decoration_u32(inst, decoration))
+            .next()
+    }
+
+    /// Returns the value of the OpConstant `id` of an integer type no
+    /// wider than 32 bits, zero-extended.
+    ///
+    /// Returns `None` for anything else, including spec constants.
+    pub fn get_constant_u32(&self, id: Word) -> Option<u32> {
+        self.constant_int(id, 32).map(|v| v as u32)
+    }
+
+    /// Returns the value of the OpConstant `id` of an integer type no
+    /// wider than 64 bits, zero-extended.
+    ///
+    /// Returns `None` for anything else, including spec constants.
+    pub fn get_constant_u64(&self, id: Word) -> Option<u64> {
+        self.constant_int(id, 64)
+    }
+
+    /// Returns the value of the OpConstant `id` of the 32-bit floating
+    /// point type, or `None` for anything else.
+    pub fn get_constant_f32(&self, id: Word) -> Option<f32> {
+        match self.constant_value(id, 0) {
+            Some(ConstantValue::Float32(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the OpConstant `id` of the 64-bit floating
+    /// point type, or `None` for anything else.
+    pub fn get_constant_f64(&self, id: Word) -> Option<f64> {
+        match self.constant_value(id, 0) {
+            Some(ConstantValue::Float64(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the OpConstantTrue or OpConstantFalse `id`, or
+    /// `None` for anything else.
+    pub fn get_constant_bool(&self, id: Word) -> Option<bool> {
+        match self.constant_value(id, 0) {
+            Some(ConstantValue::Bool(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the values of the constituents of the OpConstantComposite
+    /// `id`, resolving nested composites.
+    ///
+    /// Returns `None` if `id` or any of its constituents is not a constant
+    /// supported by [`ConstantValue`](enum.ConstantValue.html), e.g., a
+    /// spec constant or OpConstantNull.
+    pub fn get_constant_composite(&self, id: Word) -> Option<Vec<ConstantValue>> {
+        match self.constant_value(id, 0) {
+            Some(ConstantValue::Composite(values)) => Some(values),
+            _ => None,
+        }
+    }
+
+    fn constant_int(&self, id: Word, max_width: u32) -> Option<u64> {
+        let inst = match self.def(id) {
+            Some(inst) if inst.class == spirv::Op::Constant => inst,
+            _ => return None,
+        };
+        match self.type_of(inst) {
+            Some(ty) if ty.class == spirv::Op::TypeInt => {
+                match ty.operands.get(0) {
+                    Some(&Operand::LiteralInt32(width)) if width <= max_width => {
+                        inst.operands.get(0).and_then(|v| v.literal_int_unsigned(width))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the constant `id`, `depth` composites deep.
+    fn constant_value(&self, id: Word, depth: usize) -> Option<ConstantValue> {
+        // Constituents are defined before their composites in valid
+        // modules, so nesting deeper than that means a cycle.
+        if depth > self.types_global_values.len() {
+            return None;
+        }
+        let inst = match self.def(id) {
+            Some(inst) => inst,
+            None => return None,
+        };
+        let ty = match self.type_of(inst) {
+            Some(ty) => ty,
+            None => return None,
+        };
+        let width = match ty.operands.get(0) {
+            Some(&Operand::LiteralInt32(width)) => width,
+            _ => 0,
+        };
+        match (inst.class, ty.class) {
+            (spirv::Op::ConstantTrue, spirv::Op::TypeBool) => Some(ConstantValue::Bool(true)),
+            (spirv::Op::ConstantFalse, spirv::Op::TypeBool) => Some(ConstantValue::Bool(false)),
+            (spirv::Op::Constant, spirv::Op::TypeInt) => {
+                let value = match inst.operands.get(0) {
+                    Some(value) => value,
+                    None => return None,
+                };
+                match ty.operands.get(1) {
+                    Some(&Operand::LiteralInt32(0)) => {
+                        value.literal_int_unsigned(width).map(ConstantValue::UInt)
+                    }
+                    _ => value.literal_int_signed(width).map(ConstantValue::SInt),
+                }
+            }
+            (spirv::Op::Constant, spirv::Op::TypeFloat) => {
+                match (width, inst.operands.get(0)) {
+                    (32, Some(&Operand::LiteralFloat32(v))) => Some(ConstantValue::Float32(v)),
+                    (64, Some(&Operand::LiteralFloat64(v))) => Some(ConstantValue::Float64(v)),
+                    _ => None,
+                }
+            }
+            (spirv::Op::ConstantComposite, _) => {
+                let mut values = vec![];
+                for operand in &inst.operands {
+                    match *operand {
+                        Operand::IdRef(id) => {
+                            match self.constant_value(id, depth + 1) {
+                                Some(value) => values.push(value),
+                                None => return None,
+                            }
+                        }
+                        _ => return None,
+                    }
+                }
+                Some(ConstantValue::Composite(values))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the instruction defining the result type of `inst`.
+    fn type_of(&self, inst: &Instruction) -> Option<&Instruction> {
+        inst.result_type.and_then(|id| self.def(id))
+    }
+
+    /// Returns the sources declared by the OpSource instructions, with the
+    /// text of the OpSourceContinued instructions following each appended.
+    ///
+    /// OpSourceContinued must immediately follow OpSource or another
+    /// OpSourceContinued. The indices into `debugs` of those violating it
+    /// are returned as `detached_continuations`; their text is still
+    /// appended to the source declared before them, if any.
+    pub fn debug_sources(&self) -> DebugSources {
+        let mut sources: Vec<DebugSource> = vec![];
+        let mut detached = vec![];
+        let mut previous = None;
+        for (index, inst) in self.debugs.iter().enumerate() {
+            match inst.class {
+                spirv::Op::Source => {
+                    if let (Some(&Operand::SourceLanguage(language)),
+                            Some(&Operand::LiteralInt32(version))) = (inst.operands.get(0),
+                                                                      inst.operands.get(1)) {
+                        let mut source = DebugSource {
+                            language: language,
+                            version: version,
+                            file: None,
+                            text: None,
+                        };
+                        for operand in &inst.operands[2..] {
+                            match *operand {
+                                Operand::IdRef(file) => source.file = Some(file),
+                                Operand::LiteralString(ref text) => {
+                                    source.text = Some(text.to_string())
+                                }
+                                _ => (),
+                            }
+                        }
+                        sources.push(source);
+                    }
+                }
+                spirv::Op::SourceContinued => {
+                    if previous != Some(spirv::Op::Source) &&
+                       previous != Some(spirv::Op::SourceContinued) {
+                        detached.push(index);
+                    }
+                    if let (Some(source), Some(&Operand::LiteralString(ref text))) =
+                           (sources.last_mut(), inst.operands.get(0)) {
+                        source.text = Some(source.text.take().unwrap_or(String::new()) + &**text);
+                    }
+                }
+                _ => (),
+            }
+            previous = Some(inst.class);
+        }
+        DebugSources {
+            sources: sources,
+            detached_continuations: detached,
+        }
+    }
+
+    /// Reorders the instructions in `types_global_values` so that every id
+    /// is defined before it is used there, keeping the original relative
+    /// order of the instructions otherwise.
+    ///
+    /// Pointer types declared by OpTypeForwardPointer may be used before
+    /// their definitions; the OpTypeForwardPointer is placed before such
+    /// uses instead. Ids defined outside of `types_global_values` are not
+    /// considered. On error, `types_global_values` is left untouched.
+    pub fn sort_global_section(&mut self) -> Result<(), SortError> {
+        let order = {
+            let insts = &self.types_global_values;
+            let mut defs = HashMap::new();
+            let mut forwards = HashMap::new();
+            for (index, inst) in insts.iter().enumerate() {
+                if let Some(id) = inst.result_id {
+                    if defs.insert(id, index).is_some() {
+                        return Err(SortError::IdRedefined(id));
+                    }
+                }
+                if inst.class == spirv::Op::TypeForwardPointer {
+                    if let Some(&Operand::IdRef(id)) = inst.operands.get(0) {
+                        forwards.insert(id, index);
+                    }
+                }
+            }
+            let deps: Vec<Vec<usize>> = insts.iter()
+                .enumerate()
+                .map(|(index, inst)| {
+                    if inst.class == spirv::Op::TypeForwardPointer {
+                        return vec![];
+                    }
+                    let ids = inst.operands.iter().filter_map(|o| o.id());
+                    inst.result_type
+                        .into_iter()
+                        .chain(ids)
+                        .filter_map(|id| forwards.get(&id).or_else(|| defs.get(&id)).cloned())
+                        .filter(|&dep| dep != index)
+                        .collect()
+                })
+                .collect();
+
+            // Depth-first search from each instruction in order, emitting its
+            // dependencies first. `visiting` marks those on the current path.
+            let mut order = Vec::with_capacity(insts.len());
+            let mut done = vec![false; insts.len()];
+            let mut visiting = vec![false; insts.len()];
+            for root in 0..insts.len() {
+                if done[root] {
+                    continue;
+                }
+                let mut stack = vec![(root, 0)];
+                visiting[root] = true;
+                loop {
+                    let (index, next) = match stack.last() {
+                        Some(&top) => top,
+                        None => break,
+                    };
+                    if next == deps[index].len() {
+                        stack.pop();
+                        visiting[index] = false;
+                        done[index] = true;
+                        order.push(index);
+                        continue;
+                    }
+                    stack.last_mut().unwrap().1 += 1;
+                    let dep = deps[index][next];
+                    if visiting[dep] {
+                        let start = stack.iter().position(|&(i, _)| i == dep).unwrap();
+                        let ids = stack[start..].iter().filter_map(|&(i, _)| insts[i].result_id);
+                        return Err(SortError::DependencyCycle(ids.collect()));
+                    }
+                    if !done[dep] {
+                        visiting[dep] = true;
+                        stack.push((dep, 0));
+                    }
+                }
+            }
+            order
+        };
+
+        let mut insts: Vec<Option<Instruction>> =
+            self.types_global_values.drain(..).map(Some).collect();
+        self.types_global_values = order.into_iter().map(|i| insts[i].take().unwrap()).collect();
+        Ok(())
+    }
+}
+
+/// Errors from [`Module::sort_global_section`](struct.Module.html#method.sort_global_section).
+#[derive(Debug, PartialEq)]
+pub enum SortError {
+    /// The same id defined by more than one instruction (id)
+    IdRedefined(Word),
+    /// Definitions depending on each other (ids, each depending on the next
+    /// one and the last on the first)
+    DependencyCycle(Vec<Word>),
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SortError::IdRedefined(id) => write!(f, "id %{} defined more than once", id),
+            SortError::DependencyCycle(ref ids) => {
+                let ids: Vec<String> = ids.iter().map(|id| format!("%{}", id)).collect();
+                write!(f, "definitions depend on each other: {}", ids.join(" -> "))
+            }
+        }
+    }
+}
+
+impl error::Error for SortError {
+    fn description(&self) -> &str {
+        match *self {
+            SortError::IdRedefined(..) => "id defined more than once",
+            SortError::DependencyCycle(..) => "definitions depend on each other",
+        }
+    }
+}
+
+/// Errors from inserting instructions into basic blocks.
+#[derive(Debug, PartialEq)]
+pub enum InsertError {
+    /// An index beyond the end of the basic block (index)
+    IndexOutOfBounds(usize),
+    /// An index after the terminator of the basic block (index)
+    AfterTerminator(usize),
+    /// An OpPhi after other instructions (index)
+    PhiMisplaced(usize),
+    /// An instruction other than OpPhi before an OpPhi (index)
+    BeforePhi(usize),
+}
+
+impl fmt::Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InsertError::IndexOutOfBounds(index) => {
+                write!(f, "index {} beyond the end of basic block", index)
+            }
+            InsertError::AfterTerminator(index) => {
+                write!(f, "index {} after the terminator of basic block", index)
+            }
+            InsertError::PhiMisplaced(index) => {
+                write!(f, "OpPhi at index {} after other instructions", index)
+            }
+            InsertError::BeforePhi(index) => {
+                write!(f, "instruction at index {} before OpPhi", index)
+            }
+        }
+    }
+}
+
+impl error::Error for InsertError {
+    fn description(&self) -> &str {
+        match *self {
+            InsertError::IndexOutOfBounds(..) => "index beyond the end of basic block",
+            InsertError::AfterTerminator(..) => "index after the terminator of basic block",
+            InsertError::PhiMisplaced(..) => "OpPhi after other instructions",
+            InsertError::BeforePhi(..) => "instruction before OpPhi",
+        }
+    }
+}
+
+/// A source declared by OpSource, with the text of continuing
+/// OpSourceContinued instructions stitched together.
+#[derive(Debug, PartialEq)]
+pub struct DebugSource {
+    /// The source language.
+    pub language: spirv::SourceLanguage,
+    /// The version of the source language.
+    pub version: u32,
+    /// The id of the OpString holding the file name.
+    pub file: Option<Word>,
+    /// The source text.
+    pub text: Option<String>,
+}
+
+/// The sources of a module, as returned by
+/// [`Module::debug_sources`](struct.Module.html#method.debug_sources).
+#[derive(Debug, PartialEq)]
+pub struct DebugSources {
+    /// The sources declared, in order.
+    pub sources: Vec<DebugSource>,
+    /// The indices into `debugs` of OpSourceContinued instructions not
+    /// immediately following OpSource or another OpSourceContinued.
+    pub detached_continuations: Vec<usize>,
+}
+
+/// An entry point declared by OpEntryPoint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryPointInfo<'m> {
+    /// The execution model of the entry point.
+    pub execution_model: spirv::ExecutionModel,
+    /// The result id of the entry point function.
+    pub function_id: Word,
+    /// The name of the entry point.
+    pub name: &'m str,
+    /// The ids of the global variables in the interface of the entry point.
+    pub interface: Vec<Word>,
+    /// The OpExecutionMode instructions for the entry point function, in
+    /// order.
+    ///
+    /// Execution modes are declared per function, so entry points sharing
+    /// a function also share these.
+    pub execution_modes: Vec<&'m Instruction>,
+}
+
+/// A global variable declared by OpVariable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VariableInfo {
+    /// The result id of the variable.
+    pub id: Word,
+    /// The id of the pointer type of the variable.
+    pub type_id: Word,
+    /// The storage class of the variable.
+    pub storage_class: spirv::StorageClass,
+    /// The id of the initializer, if any.
+    pub initializer: Option<Word>,
 }
 
 impl ModuleHeader {
@@ -186,8 +1249,8 @@ impl ModuleHeader {
     pub fn new(bound: Word) -> ModuleHeader {
         ModuleHeader {
             magic_number: spirv::MAGIC_NUMBER,
-            version: (spirv::MAJOR_VERSION << 16) | (spirv::MAJOR_VERSION << 8),
-            generator: 0x000f0000, // TODO: lower 16-bit: tool version number
+            version: (spirv::MAJOR_VERSION << 16) | (spirv::MINOR_VERSION << 8),
+            generator: spirv::GENERATOR_RSPIRV,
             bound: bound,
             reserved_word: 0,
         }
@@ -198,95 +1261,1135 @@ impl ModuleHeader {
         (((self.version & 0xff0000) >> 16) as u8, ((self.version & 0xff00) >> 8) as u8)
     }
 
+    /// Sets the major and minor version numbers.
+    pub fn set_version(&mut self, major: u8, minor: u8) {
+        self.version = ((major as u32) << 16) | ((minor as u32) << 8);
+    }
+
+    /// Sets the generator to the given vendor id and tool version.
+    ///
+    /// Modules created by the builder carry
+    /// [`GENERATOR_RSPIRV`](../../spirv/constant.GENERATOR_RSPIRV.html) and
+    /// parsed modules keep the generator of the original binary; this
+    /// overrides either.
+    pub fn set_generator(&mut self, vendor: u16, version: u16) {
+        self.generator = ((vendor as u32) << 16) | version as u32;
+    }
+
+    /// Returns the vendor id of the generator, kept in the high 16 bits of
+    /// the generator word.
+    pub fn generator_vendor(&self) -> u16 {
+        (self.generator >> 16) as u16
+    }
+
+    /// Returns the version of the generator, kept in the low 16 bits of the
+    /// generator word.
+    pub fn generator_version(&self) -> u16 {
+        (self.generator & 0xffff) as u16
+    }
+
+    /// Returns the name of the vendor of the generator as registered with
+    /// Khronos, if known.
+    pub fn generator_vendor_name(&self) -> Option<&'static str> {
+        GENERATORS.get(self.generator_vendor() as usize).map(|&(vendor, _)| vendor)
+    }
+
     /// Returns the generator's name and version as a tuple.
+    ///
+    /// The name is that of the tool registered with Khronos, or of its
+    /// vendor if no tool is registered, and `"Unknown"` for unregistered
+    /// vendors.
     pub fn generator(&self) -> (&str, u16) {
-        let tool = (self.generator & 0xffff0000) >> 16;
-        let version = (self.generator & 0xffff) as u16;
-        let tool: &str = match tool {
-            0 => "The Khronos Group",
-            1 => "LunarG",
-            2 => "Valve",
-            3 => "Codeplay",
-            4 => "NVIDIA",
-            5 => "ARM",
-            6 => "LLVM/SPIR-V Translator",
-            7 => "SPIR-V Tools Assembler",
-            8 => "Glslang",
-            9 => "Qualcomm",
-            10 => "AMD",
-            11 => "Intel",
-            12 => "Imagination",
-            13 => "Shaderc",
-            14 => "spiregg",
-            15 => "rspirv",
-            _ => "Unknown",
+        let name = match GENERATORS.get(self.generator_vendor() as usize) {
+            Some(&(_, Some(tool))) => tool,
+            Some(&(vendor, None)) => vendor,
+            None => "Unknown",
         };
-        (tool, version)
+        (name, self.generator_version())
     }
 }
 
-impl Function {
-    /// Creates a new empty `Function` instance.
-    pub fn new() -> Function {
-        Function {
-            def: None,
-            end: None,
-            parameters: vec![],
-            basic_blocks: vec![],
+/// Returns the target id and name of the given OpName instruction.
+fn name_of(inst: &Instruction) -> Option<(Word, &str)> {
+    if inst.class != spirv::Op::Name {
+        return None;
+    }
+    match (inst.operands.get(0), inst.operands.get(1)) {
+        (Some(&Operand::IdRef(id)), Some(&Operand::LiteralString(ref name))) => {
+            Some((id, &**name))
         }
+        _ => None,
     }
 }
 
-impl BasicBlock {
-    /// Creates a new empty `BasicBlock` instance.
-    pub fn new() -> BasicBlock {
-        BasicBlock {
-            label: None,
-            instructions: vec![],
-        }
+/// Returns the result id and set name of the given OpExtInstImport
+/// instruction.
+fn ext_inst_import_of(inst: &Instruction) -> Option<(Word, &str)> {
+    if inst.class != spirv::Op::ExtInstImport {
+        return None;
+    }
+    match (inst.result_id, inst.operands.get(0)) {
+        (Some(id), Some(&Operand::LiteralString(ref name))) => Some((id, &**name)),
+        _ => None,
     }
 }
 
-impl Instruction {
-    /// Creates a new `Instruction` instance.
-    pub fn new(opcode: spirv::Op,
-               result_type: Option<Word>,
-               result_id: Option<Word>,
-               operands: Vec<Operand>)
-               -> Instruction {
-        Instruction {
-            class: grammar::InstructionTable::get(opcode),
-            result_type: result_type,
-            result_id: result_id,
-            operands: operands,
-        }
+/// Returns the (structure type id, member index) and name of the given
+/// OpMemberName instruction.
+fn member_name_of(inst: &Instruction) -> Option<((Word, u32), &str)> {
+    if inst.class != spirv::Op::MemberName {
+        return None;
+    }
+    match (inst.operands.get(0), inst.operands.get(1), inst.operands.get(2)) {
+        (Some(&Operand::IdRef(id)),
+         Some(&Operand::LiteralInt32(member)),
+         Some(&Operand::LiteralString(ref name))) => Some(((id, member), &**name)),
+        _ => None,
     }
 }
 
-// Sadly cannot use impl<T: Into<String>> here.
-impl<'a> convert::From<&'a str> for Operand {
-    fn from(val: &'a str) -> Self {
-        Operand::LiteralString(val.to_owned())
-    }
+/// Returns the decoration operand and its parameters of the given
+/// OpDecorate or OpMemberDecorate instruction.
+fn decoration_operands(inst: &Instruction) -> &[Operand] {
+    let start = match inst.class {
+        spirv::Op::Decorate => 1,
+        spirv::Op::MemberDecorate => 2,
+        _ => inst.operands.len(),
+    };
+    &inst.operands[cmp::min(start, inst.operands.len())..]
 }
 
-impl convert::From<u32> for Operand {
-    /// Converts the given `u32` `val` into an `Operand::LiteralInt32`.
-    fn from(val: u32) -> Self {
-        Operand::LiteralInt32(val)
+/// Returns the decoration of the given OpDecorate or OpMemberDecorate
+/// instruction.
+fn decoration_of(inst: &Instruction) -> Option<spirv::Decoration> {
+    decoration_operands(inst).first().and_then(Operand::decoration)
+}
+
+/// Returns the literal number parameter of the given OpDecorate or
+/// OpMemberDecorate instruction if it applies `decoration`.
+fn decoration_u32(inst: &Instruction, decoration: spirv::Decoration) -> Option<u32> {
+    let operands = decoration_operands(inst);
+    match (operands.get(0), operands.get(1)) {
+        (Some(&Operand::Decoration(d)), Some(&Operand::LiteralInt32(v))) if d == decoration => {
+            Some(v)
+        }
+        _ => None,
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use mr;
+/// Prints the header comment block like spirv-dis, e.g.,
+///
+/// ```text
+/// ; SPIR-V
+/// ; Version: 1.1
+/// ; Generator: Khronos Glslang Reference Front End; 1
+/// ; Bound: 42
+/// ; Schema: 0
+/// ```
+///
+/// without a trailing newline. Unregistered generator vendors are printed
+/// as `Unknown(0xXXXX)`.
+///
+/// The alternate form (`{:#}`) prints the same fields on one line instead,
+/// e.g., `Version: 1.1, Generator: Khronos Glslang Reference Front End; 1,
+/// Bound: 42, Schema: 0`.
+impl fmt::Display for ModuleHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (major, minor) = self.version();
+        let generator = match GENERATORS.get(self.generator_vendor() as usize) {
+            Some(&(vendor, Some(tool))) => format!("{} {}", vendor, tool),
+            Some(&(vendor, None)) => vendor.to_string(),
+            None => format!("Unknown({:#06x})", self.generator_vendor()),
+        };
+        if f.alternate() {
+            write!(f,
+                   "Version: {}.{}, Generator: {}; {}, Bound: {}, Schema: {}",
+                   major,
+                   minor,
+                   generator,
+                   self.generator_version(),
+                   self.bound,
+                   self.reserved_word)
+        } else {
+            write!(f,
+                   "; SPIR-V\n; Version: {}.{}\n; Generator: {}; {}\n; Bound: {}\n; Schema: {}",
+                   major,
+                   minor,
+                   generator,
+                   self.generator_version(),
+                   self.bound,
+                   self.reserved_word)
+        }
+    }
+}
+
+/// Generator vendors and tools registered with Khronos in the SPIR-V XML
+/// registry, indexed by vendor id.
+const GENERATORS: &'static [(&'static str, Option<&'static str>)] = &[
+    ("Khronos", None),
+    ("LunarG", None),
+    ("Valve", None),
+    ("Codeplay", None),
+    ("NVIDIA", None),
+    ("ARM", None),
+    ("Khronos", Some("LLVM/SPIR-V Translator")),
+    ("Khronos", Some("SPIR-V Tools Assembler")),
+    ("Khronos", Some("Glslang Reference Front End")),
+    ("Qualcomm", None),
+    ("AMD", None),
+    ("Intel", None),
+    ("Imagination", None),
+    ("Google", Some("Shaderc over Glslang")),
+    ("Google", Some("spiregg")),
+    ("Google", Some("rspirv")),
+];
+
+impl Function {
+    /// Creates a new empty `Function` instance.
+    pub fn new() -> Function {
+        Function {
+            def: None,
+            end: None,
+            parameters: vec![],
+            basic_blocks: vec![],
+        }
+    }
+
+    /// Returns the result id of the function, defined by its OpFunction.
+    pub fn result_id(&self) -> Option<Word> {
+        self.def.as_ref().and_then(|def| def.result_id)
+    }
+
+    /// Returns the id of the OpTypeFunction declaring the function type,
+    /// which is the last operand of its OpFunction.
+    pub fn type_id(&self) -> Option<Word> {
+        match self.def.as_ref().and_then(|def| def.operands.last()) {
+            Some(&Operand::IdRef(id)) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns the id of the return type of the function, which is the
+    /// result type of its OpFunction.
+    pub fn return_type_id(&self) -> Option<Word> {
+        self.def.as_ref().and_then(|def| def.result_type)
+    }
+
+    /// Returns the OpFunctionParameter instructions of the function, in
+    /// order.
+    pub fn parameters(&self) -> &[Instruction] {
+        &self.parameters
+    }
+
+    /// Returns the result ids of the parameters of the function, in order.
+    pub fn parameter_ids(&self) -> Vec<Word> {
+        self.parameters.iter().filter_map(|param| param.result_id).collect()
+    }
+
+    /// Returns the entry block of the function, i.e., its first basic
+    /// block.
+    pub fn entry_block(&self) -> Option<&BasicBlock> {
+        self.basic_blocks.first()
+    }
+
+    /// Returns the OpVariable instructions declaring the local variables
+    /// of the function, in order.
+    ///
+    /// Local variables must be declared at the beginning of the entry
+    /// block, so only the OpVariable instructions there are returned,
+    /// skipping any OpLine and OpNoLine in between.
+    pub fn local_variables(&self) -> Vec<&Instruction> {
+        let block = match self.entry_block() {
+            Some(block) => block,
+            None => return vec![],
+        };
+        block.instructions
+            .iter()
+            .filter(|inst| {
+                inst.class != spirv::Op::Line && inst.class != spirv::Op::NoLine
+            })
+            .take_while(|inst| inst.class == spirv::Op::Variable)
+            .collect()
+    }
+}
+
+impl BasicBlock {
+    /// Creates a new empty `BasicBlock` instance.
+    pub fn new() -> BasicBlock {
+        BasicBlock {
+            label: None,
+            instructions: vec![],
+        }
+    }
+
+    /// Returns the id of the OpLabel starting this basic block.
+    pub fn label_id(&self) -> Option<Word> {
+        self.label.as_ref().and_then(|label| label.result_id)
+    }
+
+    /// Returns the terminator ending this basic block, i.e., its last
+    /// instruction, or `None` if the basic block is not terminated yet.
+    pub fn terminator(&self) -> Option<&Instruction> {
+        self.instructions
+            .last()
+            .and_then(|inst| if grammar::reflect::is_terminator(inst.class) {
+                Some(inst)
+            } else {
+                None
+            })
+    }
+
+    /// Returns the terminator ending this basic block for modification;
+    /// see [`terminator`](#method.terminator).
+    pub fn terminator_mut(&mut self) -> Option<&mut Instruction> {
+        match self.instructions.last_mut() {
+            Some(inst) => {
+                if grammar::reflect::is_terminator(inst.class) {
+                    Some(inst)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        }
+    }
+
+    /// Returns the OpSelectionMerge or OpLoopMerge instruction immediately
+    /// preceding the terminator, if any.
+    pub fn merge_inst(&self) -> Option<&Instruction> {
+        if self.terminator().is_none() || self.instructions.len() < 2 {
+            return None;
+        }
+        let inst = &self.instructions[self.instructions.len() - 2];
+        match inst.class {
+            spirv::Op::SelectionMerge | spirv::Op::LoopMerge => Some(inst),
+            _ => None,
+        }
+    }
+
+    /// Returns the labels of the basic blocks the terminator may branch
+    /// to, in the order they appear in it.
+    ///
+    /// For OpSwitch, the default comes first, followed by the target of
+    /// each case; labels targeted by several cases are repeated.
+    pub fn successors(&self) -> Vec<Word> {
+        let terminator = match self.terminator() {
+            Some(inst) => inst,
+            None => return vec![],
+        };
+        let targets = match terminator.class {
+            spirv::Op::Branch => &terminator.operands[..],
+            spirv::Op::BranchConditional => {
+                &terminator.operands[cmp::min(1, terminator.operands.len())..
+                                     cmp::min(3, terminator.operands.len())]
+            }
+            spirv::Op::Switch => &terminator.operands[cmp::min(1, terminator.operands.len())..],
+            _ => return vec![],
+        };
+        targets.iter().filter_map(Operand::id_ref).collect()
+    }
+
+    /// Checks that `inst` can be inserted at the given `index`.
+    fn check_insert(&self, index: usize, inst: &Instruction) -> Result<(), InsertError> {
+        let is_line = |opcode: spirv::Op| opcode == spirv::Op::Line || opcode == spirv::Op::NoLine;
+        if index > self.instructions.len() {
+            return Err(InsertError::IndexOutOfBounds(index));
+        }
+        if index == self.instructions.len() && self.terminator().is_some() {
+            return Err(InsertError::AfterTerminator(index));
+        }
+        let opcode = inst.class;
+        if opcode == spirv::Op::Phi {
+            let (before, _) = self.instructions.split_at(index);
+            let is_phi_or_line = |i: &Instruction| {
+                i.class == spirv::Op::Phi || is_line(i.class)
+            };
+            if !before.iter().all(is_phi_or_line) {
+                return Err(InsertError::PhiMisplaced(index));
+            }
+        } else if !is_line(opcode) {
+            let (_, after) = self.instructions.split_at(index);
+            if after.iter().any(|i| i.class == spirv::Op::Phi) {
+                return Err(InsertError::BeforePhi(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts `inst` at the given `index` among the instructions of this
+    /// basic block, shifting all instructions after it.
+    ///
+    /// Fails if `index` is beyond the terminator, or if the OpPhi
+    /// instructions starting this basic block would no longer be
+    /// contiguous, i.e., when inserting an OpPhi after other instructions,
+    /// or other instructions before an OpPhi. OpLine and OpNoLine can be
+    /// inserted anywhere before the terminator.
+    pub fn insert_at(&mut self, index: usize, inst: Instruction) -> Result<(), InsertError> {
+        try!(self.check_insert(index, &inst));
+        self.instructions.insert(index, inst);
+        Ok(())
+    }
+
+    /// Inserts `inst` right before the terminator of this basic block, or
+    /// appends it if the basic block is not terminated yet. See
+    /// [`insert_at`](#method.insert_at) for when this fails.
+    pub fn insert_before_terminator(&mut self, inst: Instruction) -> Result<(), InsertError> {
+        let index = match self.terminator() {
+            Some(_) => self.instructions.len() - 1,
+            None => self.instructions.len(),
+        };
+        self.insert_at(index, inst)
+    }
+
+    /// Returns a cursor walking over the instructions of this basic block,
+    /// allowing to insert instructions around the current one on the way.
+    pub fn iter_with_cursor(&mut self) -> BasicBlockCursor {
+        BasicBlockCursor {
+            block: self,
+            current: None,
+            next: 0,
+        }
+    }
+}
+
+/// A cursor over the instructions of a basic block, created by
+/// [`BasicBlock::iter_with_cursor`](struct.BasicBlock.html#method.iter_with_cursor).
+///
+/// The cursor starts before the first instruction; call
+/// [`advance`](#method.advance) to move it onto the next one:
+///
+/// ```
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::mr::{BasicBlock, Instruction, Operand};
+///
+/// fn main() {
+///     let mut block = BasicBlock::new();
+///     for id in 3..5 {
+///         block.instructions.push(Instruction::new(spirv::Op::Load,
+///                                                  Some(1),
+///                                                  Some(id),
+///                                                  vec![Operand::IdRef(2)]));
+///     }
+///     block.instructions.push(Instruction::new(spirv::Op::Return, None, None, vec![]));
+///
+///     // Follow each load with a no-op.
+///     {
+///         let mut cursor = block.iter_with_cursor();
+///         while cursor.advance() {
+///             if cursor.current().unwrap().class == spirv::Op::Load {
+///                 cursor.insert_after(Instruction::nop()).unwrap();
+///             }
+///         }
+///     }
+///     let opcodes: Vec<_> = block.instructions.iter().map(|inst| inst.class).collect();
+///     assert_eq!(vec![spirv::Op::Load, spirv::Op::Nop, spirv::Op::Load, spirv::Op::Nop,
+///                     spirv::Op::Return],
+///                opcodes);
+/// }
+/// ```
+///
+/// Instructions inserted through the cursor are not visited by it.
+#[derive(Debug)]
+pub struct BasicBlockCursor<'b> {
+    block: &'b mut BasicBlock,
+    /// The index of the current instruction
+    current: Option<usize>,
+    /// The index of the instruction to visit next
+    next: usize,
+}
+
+impl<'b> BasicBlockCursor<'b> {
+    /// Moves the cursor onto the next instruction, returning false if
+    /// there are no more instructions.
+    pub fn advance(&mut self) -> bool {
+        if self.next < self.block.instructions.len() {
+            self.current = Some(self.next);
+            self.next += 1;
+            true
+        } else {
+            self.current = None;
+            false
+        }
+    }
+
+    /// Returns the instruction under the cursor, or `None` if the cursor is
+    /// before the first or after the last instruction.
+    pub fn current(&self) -> Option<&Instruction> {
+        match self.current {
+            Some(index) => Some(&self.block.instructions[index]),
+            None => None,
+        }
+    }
+
+    /// Returns the instruction under the cursor for modification; see
+    /// [`current`](#method.current).
+    pub fn current_mut(&mut self) -> Option<&mut Instruction> {
+        match self.current {
+            Some(index) => Some(&mut self.block.instructions[index]),
+            None => None,
+        }
+    }
+
+    /// Inserts `inst` before the instruction under the cursor, or before
+    /// the next instruction to visit if there is none. See
+    /// [`BasicBlock::insert_at`](struct.BasicBlock.html#method.insert_at)
+    /// for when this fails.
+    pub fn insert_before(&mut self, inst: Instruction) -> Result<(), InsertError> {
+        let index = self.current.unwrap_or(self.next);
+        try!(self.block.insert_at(index, inst));
+        if let Some(ref mut current) = self.current {
+            *current += 1;
+        }
+        self.next += 1;
+        Ok(())
+    }
+
+    /// Inserts `inst` after the instruction under the cursor, or before
+    /// the next instruction to visit if there is none. See
+    /// [`BasicBlock::insert_at`](struct.BasicBlock.html#method.insert_at)
+    /// for when this fails.
+    pub fn insert_after(&mut self, inst: Instruction) -> Result<(), InsertError> {
+        let index = self.current.map_or(self.next, |index| index + 1);
+        try!(self.block.insert_at(index, inst));
+        self.next += 1;
+        Ok(())
+    }
+}
+
+impl Instruction {
+    /// Creates a new `Instruction` instance.
+    ///
+    /// The `opcode` is kept as `class`; encoding only uses it, the result
+    /// ids, and the `operands`. The grammar specification is only looked up
+    /// when operand metadata is needed, e.g., when checking operands.
+    pub fn new(opcode: spirv::Op,
+               result_type: Option<Word>,
+               result_id: Option<Word>,
+               operands: Vec<Operand>)
+               -> Instruction {
+        Instruction {
+            class: opcode,
+            unknown_opcode: None,
+            result_type: result_type,
+            result_id: result_id,
+            operands: operands,
+        }
+    }
+
+    /// Creates a new `Instruction` instance for the given `opcode` unknown
+    /// to the grammar, keeping all its `operands` words verbatim.
+    ///
+    /// Such an instruction has its `unknown_opcode` set, no result type or
+    /// result id, and a single `Operand::RawWords` operand holding all the
+    /// words of the instruction, including the first word encoding the word
+    /// count and opcode.
+    pub fn unknown(opcode: u16, operands: Vec<Word>) -> Instruction {
+        let mut words = vec![((operands.len() as Word + 1) << 16) | opcode as Word];
+        words.extend(operands);
+        Instruction {
+            class: spirv::Op::Nop,
+            unknown_opcode: Some(opcode),
+            result_type: None,
+            result_id: None,
+            operands: vec![Operand::RawWords(words)],
+        }
+    }
+
+    /// Creates a new OpNop instruction.
+    pub fn nop() -> Instruction {
+        Instruction::new(spirv::Op::Nop, None, None, vec![])
+    }
+
+    /// Creates a new OpName instruction naming `target`.
+    pub fn name<T: Into<String>>(target: Word, name: T) -> Instruction {
+        Instruction::new(spirv::Op::Name,
+                         None,
+                         None,
+                         vec![Operand::IdRef(target), Operand::from(name.into())])
+    }
+
+    /// Creates a new OpDecorate instruction decorating `target`, with the
+    /// `extra_operands` of the `decoration` following it.
+    pub fn decorate(target: Word,
+                    decoration: spirv::Decoration,
+                    extra_operands: Vec<Operand>)
+                    -> Instruction {
+        let mut operands = vec![Operand::IdRef(target), Operand::Decoration(decoration)];
+        operands.extend(extra_operands);
+        Instruction::new(spirv::Op::Decorate, None, None, operands)
+    }
+
+    /// Returns the grammar specification of this instruction's class.
+    ///
+    /// Instructions unknown to the grammar get that of the placeholder
+    /// class.
+    pub fn grammar(&self) -> &'static grammar::Instruction<'static> {
+        grammar::InstructionTable::get(self.class)
+    }
+
+    /// Returns the opcode of this instruction.
+    ///
+    /// Returns `None` for instructions unknown to the grammar; see
+    /// [`unknown_opcode`](#structfield.unknown_opcode).
+    pub fn opcode(&self) -> Option<spirv::Op> {
+        match self.unknown_opcode {
+            Some(_) => None,
+            None => Some(self.class),
+        }
+    }
+
+    /// Returns the operands following the operand at `index` as its
+    /// parameters, e.g., the ids following an `ImageOperands` mask.
+    ///
+    /// Returns an empty slice if the operand does not exist or has no
+    /// parameters.
+    pub fn operand_parameters(&self, index: usize) -> &[Operand] {
+        let count = self.operands.get(index).map_or(0, |o| o.num_parameters());
+        let start = cmp::min(index + 1, self.operands.len());
+        let end = cmp::min(start + count, self.operands.len());
+        &self.operands[start..end]
+    }
+
+    /// Returns the operands of this instruction grouped into logical
+    /// operands, each an operand followed by its parameters, if any.
+    ///
+    /// For example, the operands of `OpExecutionMode %1 LocalSize 8 8 1`
+    /// are grouped into `[%1]` and `[LocalSize, 8, 8, 1]`. Missing
+    /// parameters shorten the last group.
+    pub fn logical_operands(&self) -> Vec<&[Operand]> {
+        let mut groups = vec![];
+        let mut start = 0;
+        while start < self.operands.len() {
+            let end = cmp::min(start + 1 + self.operands[start].num_parameters(),
+                               self.operands.len());
+            groups.push(&self.operands[start..end]);
+            start = end;
+        }
+        groups
+    }
+
+    /// Checks the result type id, result id, and operands of this
+    /// instruction against its grammar.
+    ///
+    /// Parameters following an enumerant are accepted as they are, and so
+    /// are the operands following an extended instruction number, an
+    /// OpSpecConstantOp opcode, or an unknown enumerant, since they follow
+    /// grammars not known here. Instructions created by
+    /// [`unknown`](#method.unknown) are always accepted.
+    pub fn validate_operands(&self) -> Result<(), OperandMismatch> {
+        if self.unknown_opcode.is_some() {
+            return Ok(());
+        }
+        let opcode = self.class;
+        let grammar = self.grammar().operands;
+        let has_kind = |kind| grammar.iter().any(|o| o.kind == kind);
+        let expected = has_kind(grammar::OperandKind::IdResultType);
+        if expected != self.result_type.is_some() {
+            return Err(OperandMismatch::ResultType(opcode, expected));
+        }
+        let expected = has_kind(grammar::OperandKind::IdResult);
+        if expected != self.result_id.is_some() {
+            return Err(OperandMismatch::ResultId(opcode, expected));
+        }
+
+        let mut index = 0;
+        for (lindex, loperand) in grammar.iter().enumerate() {
+            if loperand.kind == grammar::OperandKind::IdResultType ||
+               loperand.kind == grammar::OperandKind::IdResult {
+                continue;
+            }
+            loop {
+                let rest = &self.operands[index..];
+                if rest.is_empty() {
+                    if let grammar::OperandQuantifier::One = loperand.quantifier {
+                        return Err(OperandMismatch::OperandMissing(opcode, lindex, loperand.kind));
+                    }
+                    break;
+                }
+                match match_operands(loperand.kind, rest) {
+                    Some(count) => index += count,
+                    None => {
+                        if let grammar::OperandQuantifier::One = loperand.quantifier {
+                            return Err(OperandMismatch::OperandKindMismatch(opcode,
+                                                                            lindex,
+                                                                            loperand.kind,
+                                                                            rest[0].clone()));
+                        }
+                        break;
+                    }
+                }
+                if let grammar::OperandQuantifier::ZeroOrMore = loperand.quantifier {
+                    continue;
+                }
+                break;
+            }
+        }
+        if index < self.operands.len() {
+            return Err(OperandMismatch::OperandExceeded(opcode, index));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the number of operands at the beginning of `operands` making up
+/// an operand of the given grammar `kind`, including its parameters, or
+/// `None` if they do not match.
+fn match_operands(kind: grammar::OperandKind, operands: &[Operand]) -> Option<usize> {
+    use grammar::OperandKind as GOpKind;
+
+    let pair = |first: bool, second: bool| if first && second { Some(2) } else { None };
+    let is_id = |index: usize| match operands.get(index) {
+        Some(&Operand::IdRef(_)) => true,
+        _ => false,
+    };
+    let is_int = |index: usize| match operands.get(index) {
+        Some(&Operand::LiteralInt32(_)) |
+        Some(&Operand::LiteralInt64(_)) => true,
+        _ => false,
+    };
+    match (kind, &operands[0]) {
+        (_, &Operand::UnknownEnumerant(name, _)) if name == format!("{:?}", kind) => {
+            Some(operands.len())
+        }
+        (GOpKind::LiteralInteger, &Operand::LiteralInt32(_)) => Some(1),
+        (GOpKind::LiteralContextDependentNumber, &Operand::LiteralInt32(_)) |
+        (GOpKind::LiteralContextDependentNumber, &Operand::LiteralInt64(_)) |
+        (GOpKind::LiteralContextDependentNumber, &Operand::LiteralFloat32(_)) |
+        (GOpKind::LiteralContextDependentNumber, &Operand::LiteralFloat64(_)) => Some(1),
+        (GOpKind::LiteralExtInstInteger, &Operand::LiteralExtInstInteger(_)) |
+        (GOpKind::LiteralSpecConstantOpInteger, &Operand::LiteralSpecConstantOpInteger(_)) => {
+            Some(operands.len())
+        }
+        (GOpKind::PairLiteralIntegerIdRef, _) => pair(is_int(0), is_id(1)),
+        (GOpKind::PairIdRefLiteralInteger, _) => pair(is_id(0), is_int(1)),
+        (GOpKind::PairIdRefIdRef, _) => pair(is_id(0), is_id(1)),
+        (kind, operand) if operand.is_of_kind(kind) => {
+            Some(cmp::min(1 + operand.num_parameters(), operands.len()))
+        }
+        _ => None,
+    }
+}
+
+/// Mismatch between an instruction and its grammar, found by
+/// [`Instruction::validate_operands`](struct.Instruction.html#method.validate_operands).
+///
+/// Operand indices are logical operand indices into the grammar, which
+/// count the result type id and result id if any.
+#[derive(Debug, PartialEq)]
+pub enum OperandMismatch {
+    /// Result type id presence not matching the grammar
+    /// (opcode, whether the grammar requires one)
+    ResultType(spirv::Op, bool),
+    /// Result id presence not matching the grammar
+    /// (opcode, whether the grammar requires one)
+    ResultId(spirv::Op, bool),
+    /// Required operand missing (opcode, logical operand index, expected kind)
+    OperandMissing(spirv::Op, usize, grammar::OperandKind),
+    /// Operand of a kind not matching the grammar
+    /// (opcode, logical operand index, expected kind, operand found)
+    OperandKindMismatch(spirv::Op, usize, grammar::OperandKind, Operand),
+    /// Operands left after matching all logical operands
+    /// (opcode, index into the operands of the first one left)
+    OperandExceeded(spirv::Op, usize),
+}
+
+impl fmt::Display for OperandMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let presence = |required| if required { "required" } else { "not allowed" };
+        match *self {
+            OperandMismatch::ResultType(opcode, required) => {
+                write!(f, "Op{:?}: result type id {}", opcode, presence(required))
+            }
+            OperandMismatch::ResultId(opcode, required) => {
+                write!(f, "Op{:?}: result id {}", opcode, presence(required))
+            }
+            OperandMismatch::OperandMissing(opcode, index, kind) => {
+                write!(f, "Op{:?}: missing operand {} of kind {:?}", opcode, index, kind)
+            }
+            OperandMismatch::OperandKindMismatch(opcode, index, kind, ref operand) => {
+                write!(f,
+                       "Op{:?}: expected operand {} of kind {:?}, found {:?}",
+                       opcode,
+                       index,
+                       kind,
+                       operand)
+            }
+            OperandMismatch::OperandExceeded(opcode, index) => {
+                write!(f, "Op{:?}: unexpected operands starting at {}", opcode, index)
+            }
+        }
+    }
+}
+
+impl error::Error for OperandMismatch {
+    fn description(&self) -> &str {
+        match *self {
+            OperandMismatch::ResultType(..) => "result type id not matching the grammar",
+            OperandMismatch::ResultId(..) => "result id not matching the grammar",
+            OperandMismatch::OperandMissing(..) => "missing operand",
+            OperandMismatch::OperandKindMismatch(..) => "operand kind not matching the grammar",
+            OperandMismatch::OperandExceeded(..) => "unexpected operands",
+        }
+    }
+}
+
+impl Operand {
+    /// Returns the id if this operand is an id operand, i.e., an
+    /// `Operand::IdRef`, `Operand::IdScope`, or `Operand::IdMemorySemantics`.
+    pub fn id(&self) -> Option<Word> {
+        match *self {
+            Operand::IdMemorySemantics(id) |
+            Operand::IdScope(id) |
+            Operand::IdRef(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns the id for mutation if this operand is an id operand. See
+    /// [`id`](#method.id) for details.
+    pub fn id_mut(&mut self) -> Option<&mut Word> {
+        match *self {
+            Operand::IdMemorySemantics(ref mut id) |
+            Operand::IdScope(ref mut id) |
+            Operand::IdRef(ref mut id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this operand is a literal integer, interpreted
+    /// as an unsigned integer of the given bit `width`.
+    ///
+    /// Bits in a 32-bit literal higher than `width` are masked off. `None`
+    /// is returned if the operand is not a literal integer or cannot hold
+    /// an integer of the given `width`.
+    pub fn literal_int_unsigned(&self, width: u32) -> Option<u64> {
+        match *self {
+            Operand::LiteralInt32(v) if width > 0 && width < 32 => {
+                Some((v & ((1 << width) - 1)) as u64)
+            }
+            Operand::LiteralInt32(v) if width == 32 => Some(v as u64),
+            Operand::LiteralInt64(v) if width == 64 => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this operand is a literal integer, interpreted
+    /// as a signed integer of the given bit `width`.
+    ///
+    /// 32-bit literals are sign-extended from the lowest `width` bits. `None`
+    /// is returned if the operand is not a literal integer or cannot hold
+    /// an integer of the given `width`.
+    pub fn literal_int_signed(&self, width: u32) -> Option<i64> {
+        match *self {
+            Operand::LiteralInt32(v) if width > 0 && width <= 32 => {
+                Some(sign_extend_u32(v, width) as i64)
+            }
+            Operand::LiteralInt64(v) if width == 64 => Some(v as i64),
+            _ => None,
+        }
+    }
+}
+
+// Sadly cannot use impl<T: Into<String>> here.
+impl<'a> convert::From<&'a str> for Operand {
+    fn from(val: &'a str) -> Self {
+        Operand::LiteralString(val.into())
+    }
+}
+
+impl convert::From<String> for Operand {
+    fn from(val: String) -> Self {
+        Operand::LiteralString(val.into())
+    }
+}
+
+/// A `u32` is taken as a literal number rather than an id, since ids are
+/// also used as other kinds of operands, e.g., `Operand::IdScope`. Ids are
+/// to be wrapped explicitly, e.g., `Operand::IdRef(id)`.
+impl convert::From<u32> for Operand {
+    /// Converts the given `u32` `val` into an `Operand::LiteralInt32`.
+    fn from(val: u32) -> Self {
+        Operand::LiteralInt32(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mr;
     use spirv;
 
+    use binary::Assemble;
+    use std::collections::HashSet;
+    use std::collections::hash_map::DefaultHasher;
+    use std::f32;
+    use std::hash::{Hash, Hasher};
+
+    #[test]
+    fn test_function_and_basic_block_helpers() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let boolean = b.type_bool();
+        let voidfvoid = b.type_function(void, vec![]);
+        let voidfbool = b.type_function(void, vec![boolean]);
+
+        let first = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid)
+            .unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        let second = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfbool)
+            .unwrap();
+        let condition = b.function_parameter(boolean).unwrap();
+        b.begin_basic_block(None).unwrap();
+        let (then, merge) = (b.id(), b.id());
+        b.selection_merge(merge, spirv::SELECTION_CONTROL_NONE).unwrap();
+        b.branch_conditional(condition, then, merge, vec![]).unwrap();
+        b.begin_basic_block(Some(then)).unwrap();
+        b.branch(merge).unwrap();
+        b.begin_basic_block(Some(merge)).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        // Check the loaded module, which gets its structure from the loader.
+        let module = mr::load_words(b.module().assemble()).unwrap();
+        assert_eq!(2, module.functions.len());
+        let (f, g) = (&module.functions[0], &module.functions[1]);
+        assert_eq!((Some(first), Some(voidfvoid)), (f.result_id(), f.type_id()));
+        assert_eq!((Some(second), Some(voidfbool)), (g.result_id(), g.type_id()));
+        assert!(f.parameters.is_empty());
+        assert_eq!(Some(condition), g.parameters[0].result_id);
+
+        assert_eq!(3, g.basic_blocks.len());
+        assert_eq!(vec![Some(then), Some(merge)],
+                   g.basic_blocks[1..].iter().map(|bb| bb.label_id()).collect::<Vec<_>>());
+        let terminators: Vec<spirv::Op> = g.basic_blocks
+            .iter()
+            .map(|bb| bb.terminator().unwrap().class)
+            .collect();
+        assert_eq!(vec![spirv::Op::BranchConditional, spirv::Op::Branch, spirv::Op::Return],
+                   terminators);
+
+        let mut unterminated = mr::BasicBlock::new();
+        assert!(unterminated.terminator().is_none());
+        unterminated.instructions.push(g.basic_blocks[0].instructions[0].clone());
+        assert!(unterminated.terminator().is_none());
+        assert!(mr::Function::new().type_id().is_none());
+    }
+
+    #[test]
+    fn test_function_signature_and_locals() {
+        let mut b = mr::Builder::new();
+        let uint = b.type_int(32, 0);
+        let float = b.type_float(32);
+        let ptr = b.type_pointer(None, spirv::StorageClass::Function, float);
+        let ufuff = b.type_function(uint, vec![uint, float, float]);
+        let f = b.begin_function(uint, None, spirv::FUNCTION_CONTROL_NONE, ufuff).unwrap();
+        let params: Vec<spirv::Word> = (0..3)
+            .map(|i| b.function_parameter(if i == 0 { uint } else { float }).unwrap())
+            .collect();
+        b.begin_basic_block(None).unwrap();
+        let first = b.variable(ptr, None, spirv::StorageClass::Function, None).unwrap();
+        let second = b.variable(ptr, None, spirv::StorageClass::Function, None).unwrap();
+        b.store(first, params[1], None, vec![]).unwrap();
+        let next = b.id();
+        b.branch(next).unwrap();
+        b.begin_basic_block(Some(next)).unwrap();
+        b.ret_value(params[0]).unwrap();
+        b.end_function().unwrap();
+
+        let mut module = b.module();
+        // Not a local variable declaration, being past the beginning.
+        let class = mr::Operand::StorageClass(spirv::StorageClass::Function);
+        let misplaced = mr::Instruction::new(spirv::Op::Variable, Some(ptr), Some(100), vec![class]);
+        module.functions[0].basic_blocks[0].instructions.insert(3, misplaced);
+        let function = &module.functions[0];
+        assert_eq!(Some(f), function.result_id());
+        assert_eq!(Some(uint), function.return_type_id());
+        assert_eq!(3, function.parameters().len());
+        assert_eq!(params, function.parameter_ids());
+        assert_eq!(function.basic_blocks.first(), function.entry_block());
+        let locals: Vec<Option<spirv::Word>> =
+            function.local_variables().iter().map(|inst| inst.result_id).collect();
+        assert_eq!(vec![Some(first), Some(second)], locals);
+
+        let empty = mr::Function::new();
+        assert!(empty.return_type_id().is_none());
+        assert!(empty.parameter_ids().is_empty());
+        assert!(empty.entry_block().is_none());
+        assert!(empty.local_variables().is_empty());
+    }
+
+    #[test]
+    fn test_basic_block_control_flow() {
+        let mut b = mr::Builder::new();
+        let void = b.type_void();
+        let uint = b.type_int(32, 0);
+        let boolean = b.type_bool();
+        let voidfuint = b.type_function(void, vec![uint]);
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfuint).unwrap();
+        let selector = b.function_parameter(uint).unwrap();
+        let entry = b.begin_basic_block(None).unwrap();
+        let (header, body, merge, case, exit) = (b.id(), b.id(), b.id(), b.id(), b.id());
+        b.branch(header).unwrap();
+        b.begin_basic_block(Some(header)).unwrap();
+        let counter = b.phi(uint, None, vec![(selector, entry), (selector, body)]).unwrap();
+        let condition = b.undef(boolean, None);
+        b.loop_merge(merge, body, spirv::LOOP_CONTROL_NONE, vec![]).unwrap();
+        b.branch_conditional(condition, body, merge, vec![]).unwrap();
+        b.begin_basic_block(Some(body)).unwrap();
+        b.branch(header).unwrap();
+        b.begin_basic_block(Some(merge)).unwrap();
+        b.selection_merge(exit, spirv::SELECTION_CONTROL_NONE).unwrap();
+        b.switch(counter, exit, vec![(1, case), (2, case), (3, exit)]).unwrap();
+        b.begin_basic_block(Some(case)).unwrap();
+        b.branch(exit).unwrap();
+        b.begin_basic_block(Some(exit)).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        // OpPhi does not end the block when loading.
+        let mut module = mr::load_words(b.module().assemble()).unwrap();
+        {
+            let blocks = &module.functions[0].basic_blocks;
+            assert_eq!(6, blocks.len());
+            assert_eq!(spirv::Op::Phi, blocks[1].instructions[0].class);
+
+            let successors: Vec<Vec<spirv::Word>> =
+                blocks.iter().map(|bb| bb.successors()).collect();
+            assert_eq!(vec![vec![header],
+                            vec![body, merge],
+                            vec![header],
+                            vec![exit, case, case, exit],
+                            vec![exit],
+                            vec![]],
+                       successors);
+
+            let merges: Vec<Option<spirv::Op>> = blocks.iter()
+                .map(|bb| bb.merge_inst().map(|inst| inst.class))
+                .collect();
+            assert_eq!(vec![None,
+                            Some(spirv::Op::LoopMerge),
+                            None,
+                            Some(spirv::Op::SelectionMerge),
+                            None,
+                            None],
+                       merges);
+
+            let mut unterminated = mr::BasicBlock::new();
+            unterminated.instructions.push(blocks[1].instructions[2].clone());
+            assert!(unterminated.merge_inst().is_none());
+            assert!(unterminated.successors().is_empty());
+            assert!(unterminated.terminator_mut().is_none());
+        }
+
+        let last = module.functions[0].basic_blocks.last_mut().unwrap();
+        last.terminator_mut().unwrap().class = spirv::Op::Kill;
+        assert_eq!(spirv::Op::Kill, last.terminator().unwrap().class);
+        assert!(last.successors().is_empty());
+    }
+
+    #[test]
+    fn test_basic_block_insertion() {
+        let inst = |opcode, result_id| mr::Instruction::new(opcode, Some(1), result_id, vec![]);
+        let mut block = mr::BasicBlock::new();
+        block.instructions = vec![inst(spirv::Op::Phi, Some(2)),
+                                  inst(spirv::Op::Load, Some(3)),
+                                  inst(spirv::Op::Load, Some(4)),
+                                  inst(spirv::Op::Return, None)];
+
+        assert_eq!(Err(mr::InsertError::IndexOutOfBounds(5)),
+                   block.insert_at(5, inst(spirv::Op::Undef, None)));
+        assert_eq!(Err(mr::InsertError::AfterTerminator(4)),
+                   block.insert_at(4, inst(spirv::Op::Undef, None)));
+        assert_eq!(Err(mr::InsertError::BeforePhi(0)),
+                   block.insert_at(0, inst(spirv::Op::Undef, None)));
+        assert_eq!(Err(mr::InsertError::PhiMisplaced(3)),
+                   block.insert_before_terminator(inst(spirv::Op::Phi, None)));
+        assert_eq!(4, block.instructions.len());
+
+        assert_eq!(Ok(()), block.insert_at(0, inst(spirv::Op::NoLine, None)));
+        assert_eq!(Ok(()), block.insert_at(1, inst(spirv::Op::Phi, Some(5))));
+        assert_eq!(Ok(()), block.insert_before_terminator(inst(spirv::Op::Undef, Some(6))));
+        assert_eq!(vec![Some(5), Some(2), Some(3), Some(4), Some(6), None],
+                   block.instructions[1..].iter().map(|i| i.result_id).collect::<Vec<_>>());
+
+        // Inserting unterminated basic blocks appends.
+        let mut unterminated = mr::BasicBlock::new();
+        assert_eq!(Ok(()), unterminated.insert_before_terminator(inst(spirv::Op::Undef, None)));
+        assert_eq!(1, unterminated.instructions.len());
+    }
+
+    #[test]
+    fn test_basic_block_cursor() {
+        let inst = |opcode, result_id| mr::Instruction::new(opcode, Some(1), result_id, vec![]);
+        let mut block = mr::BasicBlock::new();
+        block.instructions = vec![inst(spirv::Op::Phi, Some(2)),
+                                  inst(spirv::Op::Load, Some(3)),
+                                  inst(spirv::Op::Undef, Some(4)),
+                                  inst(spirv::Op::Load, Some(5)),
+                                  inst(spirv::Op::Return, None)];
+        let mut visited = vec![];
+        {
+            let mut cursor = block.iter_with_cursor();
+            assert!(cursor.current().is_none());
+            let mut id = 10;
+            while cursor.advance() {
+                let opcode = cursor.current().unwrap().class;
+                visited.push(opcode);
+                match opcode {
+                    spirv::Op::Load => {
+                        cursor.insert_before(inst(spirv::Op::Undef, Some(id))).unwrap();
+                        cursor.insert_after(inst(spirv::Op::Undef, Some(id + 1))).unwrap();
+                        id += 2;
+                    }
+                    spirv::Op::Phi => {
+                        assert_eq!(Err(mr::InsertError::BeforePhi(0)),
+                                   cursor.insert_before(inst(spirv::Op::Undef, None)));
+                    }
+                    spirv::Op::Return => {
+                        assert_eq!(Err(mr::InsertError::AfterTerminator(9)),
+                                   cursor.insert_after(inst(spirv::Op::Undef, None)));
+                        cursor.current_mut().unwrap().class = spirv::Op::Kill;
+                    }
+                    _ => (),
+                }
+            }
+            assert!(cursor.current().is_none());
+        }
+        // Inserted instructions are not visited.
+        assert_eq!(vec![spirv::Op::Phi,
+                        spirv::Op::Load,
+                        spirv::Op::Undef,
+                        spirv::Op::Load,
+                        spirv::Op::Return],
+                   visited);
+        assert_eq!(vec![Some(2), Some(10), Some(3), Some(11), Some(4), Some(12), Some(5),
+                        Some(13), None],
+                   block.instructions.iter().map(|i| i.result_id).collect::<Vec<_>>());
+        assert_eq!(spirv::Op::Kill, block.instructions[8].class);
+    }
+
+    #[test]
+    fn test_instruction_opcode() {
+        let inst = mr::Instruction::new(spirv::Op::Store,
+                                        None,
+                                        None,
+                                        vec![mr::Operand::IdRef(1), mr::Operand::IdRef(2)]);
+        assert_eq!(Some(spirv::Op::Store), inst.opcode());
+        assert_eq!("Store", inst.grammar().opname);
+        let unknown = mr::Instruction::unknown(0xfff0, vec![1]);
+        assert_eq!((None, Some(0xfff0)), (unknown.opcode(), unknown.unknown_opcode));
+    }
+
     #[test]
     fn test_convert_from_string() {
-        assert_eq!(mr::Operand::LiteralString("wow".to_string()),
+        assert_eq!(mr::Operand::LiteralString("wow".into()),
                    mr::Operand::from("wow"));
-        assert_eq!(mr::Operand::LiteralString("wow".to_string()),
+        assert_eq!(mr::Operand::LiteralString("wow".into()),
                    mr::Operand::from("wow".to_string()));
     }
 
@@ -318,9 +2421,1097 @@ mod tests {
                    mr::Operand::from(spirv::Capability::Pipes));
     }
 
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_module_all_inst_iter() {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let uint = b.type_int(32, 0);
+        let fty = b.type_function(void, vec![uint]);
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, fty).unwrap();
+        b.function_parameter(uint).unwrap();
+        b.begin_basic_block(None).unwrap();
+        let next = b.id();
+        b.branch(next).unwrap();
+        b.begin_basic_block(Some(next)).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.entry_point(spirv::ExecutionModel::Vertex, f, "main", vec![]);
+        b.name(f, "main");
+        let mut module = b.module();
+
+        // Instructions are visited in the order they are assembled.
+        let words: Vec<u32> = module.all_inst_iter().flat_map(|i| i.assemble()).collect();
+        assert_eq!(&module.assemble()[5..], &words[..]);
+        assert_eq!(14, module.all_inst_iter().count());
+        assert_eq!(Some(spirv::Op::Label),
+                   module.all_inst_iter().nth(9).map(|i| i.class));
+
+        for inst in module.all_inst_iter_mut() {
+            if inst.result_id == Some(next) {
+                inst.result_id = Some(100);
+            }
+            for operand in &mut inst.operands {
+                if *operand == mr::Operand::IdRef(next) {
+                    *operand = mr::Operand::IdRef(100);
+                }
+            }
+        }
+        let opcodes: Vec<spirv::Op> = module.all_inst_iter().map(|i| i.class).collect();
+        let ids: Vec<Option<u32>> = module.all_inst_iter().map(|i| i.result_id).collect();
+        assert_eq!(spirv::Op::FunctionEnd, opcodes[13]);
+        assert_eq!(Some(100), ids[11]);
+        assert_eq!(Some(&mr::Operand::IdRef(100)),
+                   module.all_inst_iter().nth(10).and_then(|i| i.operands.get(0)));
+    }
+
+    #[cfg(feature = "serialize")]
+    fn assert_same_module(expected: &mr::Module, actual: &mr::Module) {
+        assert_eq!(expected.header, actual.header);
+        let expected_insts: Vec<&mr::Instruction> = expected.all_inst_iter().collect();
+        let actual_insts: Vec<&mr::Instruction> = actual.all_inst_iter().collect();
+        assert_eq!(expected_insts, actual_insts);
+        assert_eq!(expected.functions, actual.functions);
+        assert_eq!(expected.assemble(), actual.assemble());
+    }
+
+    #[cfg(feature = "serialize")]
+    fn serde_test_module() -> mr::Module {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let half = b.constant_f32(float, 0.5);
+        let voidfvoid = b.type_function(void, vec![]);
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_INLINE, voidfvoid)
+                 .unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.entry_point(spirv::ExecutionModel::Fragment, f, "main", vec![]);
+        b.name(half, "half");
+        let mut module = b.module();
+        module.types_global_values.push(mr::Instruction::unknown(0xfff0, vec![7]));
+        module
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_module_serde_json() {
+        use serde_json;
+
+        let module = serde_test_module();
+        let json = serde_json::to_string(&module).unwrap();
+        let back: mr::Module = serde_json::from_str(&json).unwrap();
+        assert_same_module(&module, &back);
+        // Grammar references are resolved again from the opcodes.
+        assert_eq!(spirv::Op::Constant, back.def(3).unwrap().class);
+        assert_eq!("Constant", back.def(3).unwrap().grammar().opname);
+
+        let unknown = mr::Operand::UnknownEnumerant("Capability", 1234);
+        let json = serde_json::to_string(&unknown).unwrap();
+        assert_eq!(unknown, serde_json::from_str(&json).unwrap());
+        let json = json.replace("Capability", "Nonsense");
+        assert!(serde_json::from_str::<mr::Operand>(&json).is_err());
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_module_serde_bincode() {
+        use bincode;
+
+        let module = serde_test_module();
+        let bytes = bincode::serialize(&module, bincode::Infinite).unwrap();
+        let back: mr::Module = bincode::deserialize(&bytes).unwrap();
+        assert_same_module(&module, &back);
+    }
+
+    #[test]
+    fn test_module_constants() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let boolean = b.type_bool();
+        let uint = b.type_int(32, 0);
+        let int = b.type_int(32, 1);
+        let ulong = b.type_int(64, 0);
+        let float = b.type_float(32);
+        let double = b.type_float(64);
+        let v2float = b.type_vector(float, 2);
+        let pair = b.type_struct(vec![uint, int]);
+        let outer = b.type_struct(vec![pair, v2float]);
+        let t = b.constant_true(boolean);
+        let sixteen = b.constant_u32(uint, 16);
+        let minus_one = b.constant_u32(int, 0xffffffff);
+        let half = b.constant_f32(float, 0.5);
+        let spec = b.spec_constant_u32(uint, 7);
+        let v = b.constant_composite(v2float, vec![half, half]);
+        let mixed = b.constant_composite(pair, vec![sixteen, minus_one]);
+        let nested = b.constant_composite(outer, vec![mixed, v]);
+        let with_spec = b.constant_composite(pair, vec![sixteen, spec]);
+        let mut module = b.module();
+        let big = module.compute_id_bound().unwrap();
+        let pi = big + 1;
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::Constant,
+                                       Some(ulong),
+                                       Some(big),
+                                       vec![mr::Operand::LiteralInt64(1 << 40)]));
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::Constant,
+                                       Some(double),
+                                       Some(pi),
+                                       vec![mr::Operand::LiteralFloat64(3.14)]));
+
+        for module in &[mr::load_words(module.assemble()).unwrap(), module] {
+            assert_eq!(Some(true), module.get_constant_bool(t));
+            assert_eq!(Some(16), module.get_constant_u32(sixteen));
+            assert_eq!(Some(16), module.get_constant_u64(sixteen));
+            assert_eq!(Some(0xffffffff), module.get_constant_u32(minus_one));
+            assert_eq!(Some(1 << 40), module.get_constant_u64(big));
+            assert_eq!(None, module.get_constant_u32(big));
+            assert_eq!(Some(0.5), module.get_constant_f32(half));
+            assert_eq!(Some(3.14), module.get_constant_f64(pi));
+            assert_eq!(None, module.get_constant_f32(pi));
+            assert_eq!(None, module.get_constant_u32(spec));
+            assert_eq!(None, module.get_constant_u32(half));
+            assert_eq!(None, module.get_constant_bool(sixteen));
+
+            use super::ConstantValue::*;
+            assert_eq!(Some(vec![Float32(0.5), Float32(0.5)]),
+                       module.get_constant_composite(v));
+            assert_eq!(Some(vec![UInt(16), SInt(-1)]),
+                       module.get_constant_composite(mixed));
+            assert_eq!(Some(vec![Composite(vec![UInt(16), SInt(-1)]),
+                                 Composite(vec![Float32(0.5), Float32(0.5)])]),
+                       module.get_constant_composite(nested));
+            assert_eq!(None, module.get_constant_composite(with_spec));
+            assert_eq!(None, module.get_constant_composite(sixteen));
+        }
+    }
+
+    #[test]
+    fn test_module_decorations() {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Linkage);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let group = b.decoration_group();
+        b.decorate(group, spirv::Decoration::DescriptorSet, vec![mr::Operand::LiteralInt32(1)])
+            .unwrap();
+        b.decorate(group, spirv::Decoration::Restrict, vec![]).unwrap();
+        let float = b.type_float(32);
+        let block = b.type_struct(vec![float, float]);
+        let (x, y, z) = (b.id(), b.id(), b.id());
+        b.decorate(x, spirv::Decoration::Binding, vec![mr::Operand::LiteralInt32(3)]).unwrap();
+        b.decorate(y, spirv::Decoration::Binding, vec![mr::Operand::LiteralInt32(4)]).unwrap();
+        b.decorate(z,
+                   spirv::Decoration::LinkageAttributes,
+                   vec![mr::Operand::from("z"),
+                        mr::Operand::LinkageType(spirv::LinkageType::Export)]).unwrap();
+        b.group_decorate(group, vec![x, y]);
+        b.member_decorate(block, 1, spirv::Decoration::Offset, vec![mr::Operand::LiteralInt32(4)])
+            .unwrap();
+        b.group_member_decorate(group, vec![(block, 0)]);
+        let module = b.module();
+
+        assert_eq!(3, module.decorations(x).len());
+        assert_eq!(Some(3), module.get_decoration_u32(x, spirv::Decoration::Binding));
+        assert_eq!(Some(4), module.get_decoration_u32(y, spirv::Decoration::Binding));
+        // Via the decoration group.
+        assert_eq!(Some(1), module.get_decoration_u32(y, spirv::Decoration::DescriptorSet));
+        assert!(module.has_decoration(y, spirv::Decoration::Restrict));
+        assert!(!module.has_decoration(z, spirv::Decoration::Restrict));
+        assert_eq!(None, module.get_decoration_u32(z, spirv::Decoration::DescriptorSet));
+
+        // Parameters other than a literal number.
+        let linkage = module.decorations(z);
+        assert_eq!(1, linkage.len());
+        assert_eq!(mr::Operand::from("z"), linkage[0].operands[2]);
+        assert!(module.has_decoration(z, spirv::Decoration::LinkageAttributes));
+        assert_eq!(None,
+                   module.get_decoration_u32(z, spirv::Decoration::LinkageAttributes));
+
+        assert_eq!(Some(4),
+                   module.get_member_decoration_u32(block, 1, spirv::Decoration::Offset));
+        assert_eq!(None,
+                   module.get_member_decoration_u32(block, 0, spirv::Decoration::Offset));
+        assert_eq!(Some(1),
+                   module.get_member_decoration_u32(block,
+                                                    0,
+                                                    spirv::Decoration::DescriptorSet));
+        assert!(module.has_member_decoration(block, 0, spirv::Decoration::Restrict));
+        assert!(!module.has_member_decoration(block, 1, spirv::Decoration::Restrict));
+        assert!(module.decorations(block).is_empty());
+    }
+
+    #[test]
+    fn test_operand_conversions() {
+        use mr::Operand;
+
+        assert_eq!(Operand::LiteralInt32(4), Operand::from(4u32));
+        assert_eq!(Operand::LiteralInt64(4), Operand::from(4u64));
+        assert_eq!(Operand::LiteralFloat32(0.5), Operand::from(0.5f32));
+        assert_eq!(Operand::LiteralFloat64(0.5), Operand::from(0.5f64));
+        assert_eq!(Operand::LiteralString("s".into()), Operand::from("s"));
+        assert_eq!(Operand::LiteralString("s".into()), Operand::from("s".to_string()));
+        assert_eq!(Operand::StorageClass(spirv::StorageClass::Uniform),
+                   spirv::StorageClass::Uniform.into());
+        assert_eq!(Operand::FunctionControl(spirv::FUNCTION_CONTROL_INLINE),
+                   spirv::FUNCTION_CONTROL_INLINE.into());
+
+        let decorate = mr::Instruction::new(spirv::Op::Decorate,
+                                            None,
+                                            None,
+                                            operands![Operand::IdRef(1),
+                                                      spirv::Decoration::Location,
+                                                      4u32]);
+        assert_eq!(mr::Instruction::decorate(1,
+                                             spirv::Decoration::Location,
+                                             vec![Operand::LiteralInt32(4)]),
+                   decorate);
+
+        let mut b = mr::Builder::new();
+        let float = b.type_float(32);
+        let half = b.constant_f32(float, 0.5);
+        let constant = mr::Instruction::new(spirv::Op::Constant,
+                                            Some(float),
+                                            Some(half),
+                                            operands![0.5f32,]);
+        assert_eq!(b.module().types_global_values[1], constant);
+        assert!(operands![].is_empty());
+    }
+
+    #[test]
+    fn test_instruction_constructors() {
+        let nop = mr::Instruction::nop();
+        assert_eq!(Some(spirv::Op::Nop), nop.opcode());
+        assert_eq!((None, None), (nop.result_type, nop.result_id));
+        assert!(nop.operands.is_empty());
+        assert_eq!(None, nop.unknown_opcode);
+
+        let name = mr::Instruction::name(3, "main");
+        assert_eq!(spirv::Op::Name, name.class);
+        assert_eq!(vec![mr::Operand::IdRef(3), mr::Operand::from("main")], name.operands);
+
+        let decorate = mr::Instruction::decorate(4,
+                                                 spirv::Decoration::Location,
+                                                 vec![mr::Operand::LiteralInt32(1)]);
+        assert_eq!(spirv::Op::Decorate, decorate.class);
+        assert_eq!(vec![mr::Operand::IdRef(4),
+                        mr::Operand::Decoration(spirv::Decoration::Location),
+                        mr::Operand::LiteralInt32(1)],
+                   decorate.operands);
+        for inst in &[nop, name, decorate] {
+            assert_eq!(Ok(()), inst.validate_operands());
+        }
+
+        // Same as built by the builder.
+        let mut b = mr::Builder::new();
+        b.name(3, "main");
+        b.decorate(4, spirv::Decoration::Location, vec![mr::Operand::LiteralInt32(1)]).unwrap();
+        let module = b.module();
+        assert_eq!(vec![mr::Instruction::name(3, "main")], module.debugs);
+        assert_eq!(vec![mr::Instruction::decorate(4,
+                                                  spirv::Decoration::Location,
+                                                  vec![mr::Operand::LiteralInt32(1)])],
+                   module.annotations);
+    }
+
+    #[test]
+    fn test_module_entry_points() {
+        use spirv::ExecutionMode as EM;
+        use spirv::ExecutionModel as Model;
+
+        let mut b = mr::Builder::new();
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let ptr = b.type_pointer(None, spirv::StorageClass::Input, float);
+        let input = b.variable(ptr, None, spirv::StorageClass::Input, None).unwrap();
+        let voidfvoid = b.type_function(void, vec![]);
+        let mut functions = vec![];
+        for _ in 0..2 {
+            let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid)
+                     .unwrap();
+            b.begin_basic_block(None).unwrap();
+            b.ret().unwrap();
+            b.end_function().unwrap();
+            functions.push(f);
+        }
+        let (shared, compute) = (functions[0], functions[1]);
+        b.entry_point(Model::Vertex, shared, "main", vec![input]);
+        b.entry_point(Model::Fragment, shared, "main", vec![]);
+        b.entry_point(Model::GLCompute, compute, "cs", vec![]);
+        b.execution_mode(shared, EM::OriginUpperLeft, vec![]).unwrap();
+        b.execution_mode(compute, EM::LocalSize, vec![8, 8, 1]).unwrap();
+        let module = b.module();
+
+        let entries = module.entry_points();
+        assert_eq!(3, entries.len());
+        assert_eq!(Model::Vertex, entries[0].execution_model);
+        assert_eq!(shared, entries[0].function_id);
+        assert_eq!("main", entries[0].name);
+        assert_eq!(vec![input], entries[0].interface);
+        assert_eq!(Model::Fragment, entries[1].execution_model);
+        assert!(entries[1].interface.is_empty());
+        // Entry points sharing a function share its execution modes.
+        assert_eq!(vec![&module.execution_modes[0]], entries[0].execution_modes);
+        assert_eq!(entries[0].execution_modes, entries[1].execution_modes);
+        assert_eq!(vec![&module.execution_modes[1]], entries[2].execution_modes);
+
+        assert_eq!(Some(entries[0].clone()), module.entry_point_by_name("main"));
+        assert_eq!(Some(entries[2].clone()), module.entry_point_by_name("cs"));
+        assert_eq!(None, module.entry_point_by_name("ps"));
+
+        assert_eq!(Some(&module.functions[0]), module.function_for_entry_point(&entries[0]));
+        assert_eq!(Some(&module.functions[0]), module.function_for_entry_point(&entries[1]));
+        assert_eq!(Some(&module.functions[1]), module.function_for_entry_point(&entries[2]));
+
+        let mut missing = entries[2].clone();
+        missing.function_id = 100;
+        assert_eq!(None, module.function_for_entry_point(&missing));
+        assert!(mr::Module::new().entry_points().is_empty());
+    }
+
+    #[test]
+    fn test_module_global_variables() {
+        use super::VariableInfo;
+        use spirv::StorageClass as SC;
+
+        let mut b = mr::Builder::new();
+        let float = b.type_float(32);
+        let one = b.constant_f32(float, 1.0);
+        let classes = [SC::UniformConstant, SC::Input, SC::Uniform, SC::Output,
+                       SC::Workgroup, SC::Private, SC::PushConstant, SC::AtomicCounter];
+        let mut expected = vec![];
+        for &class in &classes {
+            let ptr = b.type_pointer(None, class, float);
+            let initializer = if class == SC::Private { Some(one) } else { None };
+            let id = b.variable(ptr, None, class, initializer).unwrap();
+            expected.push(VariableInfo {
+                id: id,
+                type_id: ptr,
+                storage_class: class,
+                initializer: initializer,
+            });
+        }
+        let void = b.type_void();
+        let voidfvoid = b.type_function(void, vec![]);
+        let ptr = b.type_pointer(None, SC::Function, float);
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.variable(ptr, None, SC::Function, None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        let module = b.module();
+
+        assert_eq!(expected, module.global_variables());
+        for (class, var) in classes.iter().zip(expected.iter()) {
+            assert_eq!(vec![*var], module.variables_with_storage_class(*class));
+        }
+        assert!(module.variables_with_storage_class(SC::Function).is_empty());
+        assert!(mr::Module::new().global_variables().is_empty());
+    }
+
+    #[test]
+    fn test_module_extensions() {
+        let mut b = mr::Builder::new();
+        b.extension("SPV_KHR_storage_buffer_storage_class");
+        b.extension("SPV_KHR_variable_pointers");
+        let glsl = b.ext_inst_import("GLSL.std.450");
+        let opencl = b.ext_inst_import("OpenCL.std");
+        b.ext_inst_import("GLSL.std.450");
+        let module = b.module();
+
+        assert_eq!(vec!["SPV_KHR_storage_buffer_storage_class", "SPV_KHR_variable_pointers"],
+                   module.extensions());
+        assert!(module.has_extension("SPV_KHR_variable_pointers"));
+        assert!(!module.has_extension("SPV_KHR_16bit_storage"));
+
+        let imports = module.ext_inst_imports();
+        assert_eq!(2, imports.len());
+        assert_eq!(Some(&glsl), imports.get("GLSL.std.450"));
+        assert_eq!(Some(&opencl), imports.get("OpenCL.std"));
+        assert_eq!(Some("OpenCL.std"), module.ext_inst_import_name(opencl));
+        assert_eq!(None, module.ext_inst_import_name(100));
+
+        let empty = mr::Module::new();
+        assert!(empty.extensions().is_empty());
+        assert!(empty.ext_inst_imports().is_empty());
+    }
+
+    #[test]
+    fn test_module_names() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let float = b.type_float(32);
+        let v4float = b.type_vector(float, 4);
+        let block = b.type_struct(vec![v4float, float]);
+        b.name(float, "float");
+        b.name(block, "gl_PerVertex");
+        b.member_name(block, 0, "gl_Position");
+        b.member_name(block, 1, "gl_PointSize");
+        b.name(v4float, "vec4");
+        b.name(v4float, "v4float");
+        b.name(float, "f32");
+        let module = b.module();
+
+        // Hand-built and parsed modules alike.
+        for module in &[mr::load_words(module.assemble()).unwrap(), module] {
+            assert_eq!(5, module.all_names().len());
+            let names = module.names();
+            assert_eq!(3, names.len());
+            assert_eq!(Some(&"v4float"), names.get(&v4float));
+            assert_eq!(Some(&"f32"), names.get(&float));
+            let member_names = module.member_names();
+            assert_eq!(Some(&"gl_Position"), member_names.get(&(block, 0)));
+            assert_eq!(Some(&"gl_PointSize"), member_names.get(&(block, 1)));
+
+            assert_eq!(Some(block), module.id_by_name("gl_PerVertex"));
+            assert_eq!(Some(v4float), module.id_by_name("v4float"));
+            assert_eq!(None, module.id_by_name("vec4"));
+            assert_eq!(None, module.id_by_name("gl_Position"));
+        }
+    }
+
+    #[test]
+    fn test_update_id_bound() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        b.decorate(float, spirv::Decoration::RelaxedPrecision, vec![]).unwrap();
+        let mut module = b.module();
+        module.update_id_bound();
+        assert_eq!(3, module.header.as_ref().unwrap().bound);
+
+        // Still referenced by the decoration.
+        module.types_global_values.pop();
+        assert_eq!(Some(3), module.update_id_bound());
+        assert_eq!(3, module.header.as_ref().unwrap().bound);
+
+        module.annotations.clear();
+        module.update_id_bound();
+        assert_eq!(2, module.header.as_ref().unwrap().bound);
+
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::TypePointer,
+                                       None,
+                                       Some(4),
+                                       vec![mr::Operand::StorageClass(spirv::StorageClass::Input),
+                                            mr::Operand::IdRef(void)]));
+        module.update_id_bound();
+        assert_eq!(5, module.header.as_ref().unwrap().bound);
+
+        // Forward references count too.
+        module.debugs.push(mr::Instruction::new(spirv::Op::Name,
+                                                None,
+                                                None,
+                                                vec![mr::Operand::IdRef(9),
+                                                     mr::Operand::from("later")]));
+        module.header = None;
+        module.update_id_bound();
+        assert_eq!(10, module.header.as_ref().unwrap().bound);
+
+        // No bound covers the largest id.
+        module.debugs[0].operands[0] = mr::Operand::IdRef(u32::max_value());
+        assert_eq!(Some(u32::max_value()), module.debugs[0].operands[0].id());
+        assert_eq!(None, module.compute_id_bound());
+        assert_eq!(None, module.update_id_bound());
+        assert_eq!(10, module.header.as_ref().unwrap().bound);
+    }
+
+    #[test]
+    fn test_clone_with_id_offset() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let group = b.decoration_group();
+        let void = b.type_void();
+        let int = b.type_int(32, 1);
+        let three = b.constant_u32(int, 3);
+        b.group_decorate(group, vec![void, int]);
+        let voidfvoid = b.type_function(void, vec![]);
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        let (first, second) = (b.id(), b.id());
+        b.switch(three, first, vec![(2, first), (4, second)]).unwrap();
+        b.begin_basic_block(Some(first)).unwrap();
+        b.ret().unwrap();
+        b.begin_basic_block(Some(second)).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        let mut module = b.module();
+        // The lod id follows the ImageOperands mask.
+        let fetch = mr::Instruction::new(spirv::Op::ImageFetch,
+                                         Some(int),
+                                         Some(12),
+                                         vec![mr::Operand::IdRef(three),
+                                              mr::Operand::IdRef(three),
+                                              mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_LOD),
+                                              mr::Operand::IdRef(three)]);
+        module.functions[0].basic_blocks[1].instructions.insert(0, fetch);
+        module.update_id_bound();
+
+        let mut offset = module.clone_with_id_offset(100).unwrap();
+        assert_eq!(113, offset.header.as_ref().unwrap().bound);
+        assert_eq!(mr::Instruction::new(spirv::Op::GroupDecorate,
+                                        None,
+                                        None,
+                                        vec![mr::Operand::IdRef(101),
+                                             mr::Operand::IdRef(102),
+                                             mr::Operand::IdRef(103)]),
+                   offset.annotations[1]);
+        // The constant value and switch literals stay.
+        assert_eq!(mr::Instruction::new(spirv::Op::Constant,
+                                        Some(103),
+                                        Some(104),
+                                        vec![mr::Operand::LiteralInt32(3)]),
+                   offset.types_global_values[2]);
+        assert_eq!(vec![mr::Operand::IdRef(104),
+                        mr::Operand::IdRef(108),
+                        mr::Operand::LiteralInt32(2),
+                        mr::Operand::IdRef(108),
+                        mr::Operand::LiteralInt32(4),
+                        mr::Operand::IdRef(109)],
+                   offset.functions[0].basic_blocks[0].terminator().unwrap().operands);
+        assert_eq!(vec![mr::Operand::IdRef(104),
+                        mr::Operand::IdRef(104),
+                        mr::Operand::ImageOperands(spirv::IMAGE_OPERANDS_LOD),
+                        mr::Operand::IdRef(104)],
+                   offset.functions[0].basic_blocks[1].instructions[0].operands);
+
+        // Renumbering back gives the original module.
+        for inst in offset.all_inst_iter_mut() {
+            for id in inst.result_id.iter_mut().chain(inst.result_type.iter_mut()) {
+                *id -= 100;
+            }
+            for operand in &mut inst.operands {
+                if let mr::Operand::IdRef(ref mut id) = *operand {
+                    *id -= 100;
+                }
+            }
+        }
+        offset.update_id_bound();
+        assert_eq!(module.header, offset.header);
+        assert_eq!(module.all_inst_iter().collect::<Vec<_>>(),
+                   offset.all_inst_iter().collect::<Vec<_>>());
+
+        // The bound is 13, so the largest id is 12.
+        assert!(module.clone_with_id_offset(u32::max_value() - 13).is_some());
+        assert!(module.clone_with_id_offset(u32::max_value() - 12).is_none());
+        module.header = None;
+        assert!(module.clone_with_id_offset(u32::max_value() - 12).is_some());
+        assert!(module.clone_with_id_offset(u32::max_value() - 11).is_none());
+    }
+
+    #[test]
+    fn test_retain_insts() {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let voidfvoid = b.type_function(void, vec![]);
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.undef(float, None);
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.name(f, "main");
+        b.name(float, "float");
+        b.decorate(float, spirv::Decoration::RelaxedPrecision, vec![]).unwrap();
+        let mut module = mr::load_words(b.module().assemble()).unwrap();
+        let count = module.all_inst_iter().count();
+
+        assert_eq!(2, module.retain_insts(|inst| inst.class != spirv::Op::Name));
+        assert!(module.debugs.is_empty());
+        assert_eq!(1, module.annotations.len());
+        let mut module = mr::load_words(module.assemble()).unwrap();
+        assert_eq!(count - 2, module.all_inst_iter().count());
+
+        // Function and basic block structure is kept.
+        assert_eq!(7, module.retain_insts(|_| false));
+        assert_eq!(None, module.memory_model);
+        let function = &module.functions[0];
+        assert!(function.def.is_some() && function.end.is_some());
+        assert_eq!(1, function.basic_blocks.len());
+        assert!(function.basic_blocks[0].label.is_some());
+        assert_eq!(vec![mr::Instruction::new(spirv::Op::Return, None, None, vec![])],
+                   function.basic_blocks[0].instructions);
+    }
+
+    #[test]
+    fn test_module_header_display() {
+        // Header words of a fragment shader compiled by glslangValidator.
+        let glslang = mr::load_words(&[0x07230203, 0x00010000, 0x00080001, 0x0000001d, 0])
+            .unwrap()
+            .header
+            .unwrap();
+        assert_eq!("; SPIR-V\n\
+                    ; Version: 1.0\n\
+                    ; Generator: Khronos Glslang Reference Front End; 1\n\
+                    ; Bound: 29\n\
+                    ; Schema: 0",
+                   format!("{}", glslang));
+
+        // As produced by glslang via shaderc, targeting SPIR-V 1.3.
+        let mut shaderc = glslang.clone();
+        shaderc.generator = 0x000d0007;
+        shaderc.set_version(1, 3);
+        shaderc.bound = 1234;
+        assert_eq!("; SPIR-V\n\
+                    ; Version: 1.3\n\
+                    ; Generator: Google Shaderc over Glslang; 7\n\
+                    ; Bound: 1234\n\
+                    ; Schema: 0",
+                   format!("{}", shaderc));
+
+        let mut unknown = glslang.clone();
+        unknown.generator = 0x00ab0002;
+        assert_eq!("; SPIR-V\n\
+                    ; Version: 1.0\n\
+                    ; Generator: Unknown(0x00ab); 2\n\
+                    ; Bound: 29\n\
+                    ; Schema: 0",
+                   format!("{}", unknown));
+    }
+
+    #[test]
+    fn test_module_header_details() {
+        // As produced by glslang and by dxc (spiregg).
+        let mut header = mr::ModuleHeader::new(5);
+        header.generator = 0x00080001;
+        assert_eq!((8, 1), (header.generator_vendor(), header.generator_version()));
+        assert_eq!(Some("Khronos"), header.generator_vendor_name());
+        header.set_version(1, 0);
+        assert_eq!(0x00010000, header.version);
+        assert_eq!((1, 0), header.version());
+        assert_eq!("Version: 1.0, Generator: Khronos Glslang Reference Front End; 1, \
+                    Bound: 5, Schema: 0",
+                   format!("{:#}", header));
+
+        header.generator = 0x000e0000;
+        header.set_version(1, 1);
+        assert_eq!(Some("Google"), header.generator_vendor_name());
+        assert_eq!(("spiregg", 0), header.generator());
+        assert_eq!("Version: 1.1, Generator: Google spiregg; 0, Bound: 5, Schema: 0",
+                   format!("{:#}", header));
+
+        header.generator = 0x00020005;
+        assert_eq!(("Valve", 5), header.generator());
+        assert_eq!("Version: 1.1, Generator: Valve; 5, Bound: 5, Schema: 0",
+                   format!("{:#}", header));
+        header.generator = 0x12340001;
+        assert_eq!(None, header.generator_vendor_name());
+        assert_eq!(("Unknown", 1), header.generator());
+        assert_eq!("Version: 1.1, Generator: Unknown(0x1234); 1, Bound: 5, Schema: 0",
+                   format!("{:#}", header));
+
+        assert_eq!((spirv::MAJOR_VERSION as u8, spirv::MINOR_VERSION as u8),
+                   mr::ModuleHeader::new(0).version());
+    }
+
+    #[test]
+    fn test_module_def() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let glsl = b.ext_inst_import("GLSL.std.450");
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let voidffloat = b.type_function(void, vec![float]);
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidffloat).unwrap();
+        let param = b.function_parameter(float).unwrap();
+        let label = b.begin_basic_block(None).unwrap();
+        let undef = b.undef(float, None);
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        let mut module = b.module();
+
+        let opcode = |module: &mr::Module, id| module.def(id).map(|inst| inst.class);
+        assert_eq!(Some(spirv::Op::ExtInstImport), opcode(&module, glsl));
+        assert_eq!(Some(spirv::Op::TypeFloat), opcode(&module, float));
+        assert_eq!(Some(spirv::Op::Function), opcode(&module, f));
+        assert_eq!(Some(spirv::Op::FunctionParameter), opcode(&module, param));
+        assert_eq!(Some(spirv::Op::Label), opcode(&module, label));
+        assert_eq!(Some(spirv::Op::Undef), opcode(&module, undef));
+        assert_eq!(None, opcode(&module, 100));
+
+        let index = module.def_index();
+        for &id in &[glsl, float, f, param, label, undef] {
+            assert_eq!(module.def(id), index.get(&module, id));
+        }
+        assert_eq!(None, index.get(&module, 100));
+
+        module.types_global_values.remove(0);
+        assert_eq!(None, opcode(&module, void));
+        assert_eq!(Some(spirv::Op::TypeFloat), opcode(&module, float));
+        module.functions[0].basic_blocks[0]
+            .instructions
+            .insert(0, mr::Instruction::new(spirv::Op::Undef, Some(float), Some(100), vec![]));
+        assert_eq!(Some(spirv::Op::Undef), opcode(&module, 100));
+        assert_eq!(Some(spirv::Op::Undef), opcode(&module, undef));
+
+        module.def_mut(undef).unwrap().result_id = Some(101);
+        assert_eq!(None, opcode(&module, undef));
+        assert_eq!(Some(spirv::Op::Undef), opcode(&module, 101));
+
+        // Stale locations are not followed to other instructions.
+        assert_eq!(None, index.get(&module, float));
+        assert_eq!(None, index.get(&module, undef));
+        let index = module.def_index();
+        assert_eq!(Some(spirv::Op::TypeFloat),
+                   index.get(&module, float).map(|inst| inst.class));
+        index.get_mut(&mut module, 101).unwrap().result_id = Some(undef);
+        assert_eq!(Some(spirv::Op::Undef), opcode(&module, undef));
+    }
+
+    #[test]
+    fn test_instruction_eq_and_hash() {
+        fn constant(value: f32) -> mr::Instruction {
+            mr::Instruction::new(spirv::Op::Constant,
+                                 Some(1),
+                                 Some(2),
+                                 vec![mr::Operand::LiteralFloat32(value)])
+        }
+
+        assert_eq!(constant(1.5), constant(1.5));
+        assert_eq!(hash_of(&constant(1.5)), hash_of(&constant(1.5)));
+        assert!(constant(1.5) != constant(2.5));
+        // Floats go by their bits.
+        assert_eq!(constant(f32::NAN), constant(f32::NAN));
+        assert_eq!(hash_of(&constant(f32::NAN)), hash_of(&constant(f32::NAN)));
+        assert!(constant(0.0) != constant(-0.0));
+
+        let mut other = constant(1.5);
+        other.result_id = Some(3);
+        assert!(constant(1.5) != other);
+        assert!(mr::Operand::IdRef(1) != mr::Operand::IdScope(1));
+        assert!(mr::Operand::LiteralFloat32(0.0) != mr::Operand::LiteralInt32(0));
+
+        let mut set = HashSet::new();
+        set.insert(constant(1.5));
+        set.insert(constant(1.5));
+        set.insert(mr::Instruction::unknown(0xfff0, vec![1]));
+        set.insert(mr::Instruction::unknown(0xfff1, vec![1]));
+        assert_eq!(3, set.len());
+
+        let mut block = mr::BasicBlock::new();
+        block.instructions.push(constant(1.5));
+        assert_eq!(block, block.clone());
+        assert_eq!(hash_of(&block), hash_of(&block.clone()));
+        let mut function = mr::Function::new();
+        function.basic_blocks.push(block);
+        assert_eq!(function, function.clone());
+        let header = mr::ModuleHeader::new(7);
+        assert_eq!(hash_of(&header), hash_of(&header.clone()));
+    }
+
+    #[test]
+    fn test_operand_accessors() {
+        let id = mr::Operand::IdRef(7);
+        assert!(id.is_id_ref());
+        assert!(!id.is_literal_int32());
+        assert_eq!(Some(7), id.id_ref());
+        assert_eq!(7, id.unwrap_id_ref());
+        assert_eq!(None, id.literal_int32());
+        assert_eq!(None, id.id_scope());
+
+        let capability = mr::Operand::from(spirv::Capability::Shader);
+        assert_eq!(spirv::Capability::Shader, capability.unwrap_capability());
+        let s = mr::Operand::from("main");
+        assert_eq!(Some("main"), s.literal_string());
+        assert_eq!("main", s.unwrap_literal_string());
+        let words = mr::Operand::RawWords(vec![1, 2]);
+        assert_eq!(Some(&[1, 2][..]), words.raw_words());
+        assert!(mr::Operand::UnknownEnumerant("Capability", 1234).capability().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "called `Operand::unwrap_id_ref()` on LiteralInt32(7)")]
+    fn test_operand_unwrap_mismatch() {
+        mr::Operand::LiteralInt32(7).unwrap_id_ref();
+    }
+
+    #[test]
+    #[should_panic(expected = "called `Operand::unwrap_literal_string()` on IdRef(3)")]
+    fn test_operand_unwrap_string_mismatch() {
+        mr::Operand::IdRef(3).unwrap_literal_string();
+    }
+
+    #[test]
+    fn test_64bit_literals() {
+        let o = mr::Operand::from(0x1122334455667788u64);
+        assert_eq!(Some(0x1122334455667788), o.literal_int64());
+        assert_eq!(None, o.literal_float64());
+        assert_eq!("1234605616436508552", format!("{}", o));
+
+        let o = mr::Operand::from(-2.5f64);
+        assert_eq!(Some(-2.5), o.literal_float64());
+        assert_eq!(None, o.literal_int64());
+        assert_eq!("-2.5", format!("{}", o));
+
+        assert_eq!(None, mr::Operand::LiteralInt32(1).literal_int64());
+    }
+
+    #[test]
+    fn test_literal_int_signedness() {
+        let o = mr::Operand::LiteralInt32(0x0000ffff);
+        assert_eq!(Some(-1), o.literal_int_signed(16));
+        assert_eq!(Some(0xffff), o.literal_int_signed(32));
+        assert_eq!(Some(0xffff), o.literal_int_unsigned(16));
+        assert_eq!(Some(0xff), o.literal_int_unsigned(8));
+        assert_eq!(None, o.literal_int_signed(64));
+
+        let o = mr::Operand::LiteralInt32(0xffffffff);
+        assert_eq!(Some(-1), o.literal_int_signed(16));
+        assert_eq!(Some(0xffff), o.literal_int_unsigned(16));
+        assert_eq!(Some(0xffffffff), o.literal_int_unsigned(32));
+
+        let o = mr::Operand::LiteralInt64(0xfffffffffffffffe);
+        assert_eq!(Some(-2), o.literal_int_signed(64));
+        assert_eq!(Some(0xfffffffffffffffe), o.literal_int_unsigned(64));
+        assert_eq!(None, o.literal_int_signed(32));
+
+        assert_eq!(None, mr::Operand::IdRef(1).literal_int_signed(32));
+    }
+
+    #[test]
+    fn test_debug_sources() {
+        fn source(text: &str) -> mr::Instruction {
+            mr::Instruction::new(spirv::Op::Source,
+                                 None,
+                                 None,
+                                 vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL),
+                                      mr::Operand::LiteralInt32(450),
+                                      mr::Operand::IdRef(1),
+                                      mr::Operand::from(text)])
+        }
+        fn continued(text: &str) -> mr::Instruction {
+            mr::Instruction::new(spirv::Op::SourceContinued,
+                                 None,
+                                 None,
+                                 vec![mr::Operand::from(text)])
+        }
+
+        let mut module = mr::Module::new();
+        module.debugs.push(source("void "));
+        module.debugs.push(continued("main() "));
+        module.debugs.push(continued("{ "));
+        module.debugs.push(continued("}"));
+        module.debugs.push(mr::Instruction::new(spirv::Op::SourceExtension,
+                                                None,
+                                                None,
+                                                vec![mr::Operand::from("ext")]));
+        module.debugs.push(continued("// detached"));
+
+        let debug_sources = module.debug_sources();
+        assert_eq!(vec![mr::DebugSource {
+                            language: spirv::SourceLanguage::GLSL,
+                            version: 450,
+                            file: Some(1),
+                            text: Some("void main() { }// detached".to_string()),
+                        }],
+                   debug_sources.sources);
+        assert_eq!(vec![5], debug_sources.detached_continuations);
+    }
+
+    #[test]
+    fn test_validate_operands() {
+        use grammar::OperandKind as GOpKind;
+
+        let inst = mr::Instruction::new(spirv::Op::Decorate,
+                                        None,
+                                        None,
+                                        vec![mr::Operand::IdRef(1),
+                                             mr::Operand::Decoration(spirv::Decoration::BuiltIn),
+                                             mr::Operand::BuiltIn(spirv::BuiltIn::Position)]);
+        assert_eq!(Ok(()), inst.validate_operands());
+
+        let inst = mr::Instruction::new(spirv::Op::Switch,
+                                        None,
+                                        None,
+                                        vec![mr::Operand::IdRef(1),
+                                             mr::Operand::IdRef(2),
+                                             mr::Operand::LiteralInt32(3),
+                                             mr::Operand::IdRef(4),
+                                             mr::Operand::LiteralInt32(5),
+                                             mr::Operand::IdRef(6)]);
+        assert_eq!(Ok(()), inst.validate_operands());
+
+        let inst = mr::Instruction::new(spirv::Op::TypeInt, None, None, vec![]);
+        assert_eq!(Err(mr::OperandMismatch::ResultId(spirv::Op::TypeInt, true)),
+                   inst.validate_operands());
+
+        let inst = mr::Instruction::new(spirv::Op::IAdd,
+                                        Some(1),
+                                        Some(2),
+                                        vec![mr::Operand::IdRef(3)]);
+        assert_eq!(Err(mr::OperandMismatch::OperandMissing(spirv::Op::IAdd, 3, GOpKind::IdRef)),
+                   inst.validate_operands());
+
+        let inst = mr::Instruction::new(spirv::Op::Source,
+                                        None,
+                                        None,
+                                        vec![mr::Operand::SourceLanguage(spirv::SourceLanguage::GLSL),
+                                             mr::Operand::LiteralInt32(450),
+                                             mr::Operand::from("text"),
+                                             mr::Operand::IdRef(1)]);
+        let mismatch = mr::OperandMismatch::OperandExceeded(spirv::Op::Source, 3);
+        assert_eq!(Err(mismatch), inst.validate_operands());
+        assert_eq!("OpSource: unexpected operands starting at 3",
+                   format!("{}", inst.validate_operands().unwrap_err()));
+
+        assert_eq!(Ok(()), mr::Instruction::unknown(0xfffe, vec![1]).validate_operands());
+    }
+
+    #[test]
+    fn test_operand_parameters() {
+        let inst = mr::Instruction::new(spirv::Op::Decorate,
+                                        None,
+                                        None,
+                                        vec![mr::Operand::IdRef(1),
+                                             mr::Operand::Decoration(spirv::Decoration::Location),
+                                             mr::Operand::LiteralInt32(2)]);
+        assert_eq!(0, inst.operands[0].num_parameters());
+        assert_eq!(1, inst.operands[1].num_parameters());
+        assert_eq!(&[mr::Operand::LiteralInt32(2)], inst.operand_parameters(1));
+        assert!(inst.operand_parameters(0).is_empty());
+        assert!(inst.operand_parameters(3).is_empty());
+
+        let access = spirv::MEMORY_ACCESS_VOLATILE | spirv::MEMORY_ACCESS_ALIGNED;
+        assert_eq!(1, mr::Operand::MemoryAccess(access).num_parameters());
+        assert_eq!(0, mr::Operand::MemoryAccess(spirv::MEMORY_ACCESS_VOLATILE).num_parameters());
+        // Truncated parameters
+        let inst = mr::Instruction::new(spirv::Op::Store,
+                                        None,
+                                        None,
+                                        vec![mr::Operand::IdRef(1),
+                                             mr::Operand::IdRef(2),
+                                             mr::Operand::MemoryAccess(access)]);
+        assert!(inst.operand_parameters(2).is_empty());
+    }
+
+    #[test]
+    fn test_logical_operands() {
+        let mut b = mr::Builder::new();
+        b.execution_mode(1, spirv::ExecutionMode::LocalSize, vec![8, 8, 1]).unwrap();
+        b.execution_mode(1, spirv::ExecutionMode::OriginUpperLeft, vec![]).unwrap();
+        b.decorate(2,
+                   spirv::Decoration::LinkageAttributes,
+                   vec![mr::Operand::from("f"),
+                        mr::Operand::LinkageType(spirv::LinkageType::Export)]).unwrap();
+        let module = b.module();
+
+        let local_size = &module.execution_modes[0];
+        let groups = local_size.logical_operands();
+        assert_eq!(2, groups.len());
+        assert_eq!(&local_size.operands[..1], groups[0]);
+        assert_eq!(&local_size.operands[1..], groups[1]);
+        let origin = &module.execution_modes[1];
+        assert_eq!(vec![&origin.operands[..1], &origin.operands[1..]],
+                   origin.logical_operands());
+        let linkage = &module.annotations[0];
+        assert_eq!(vec![&linkage.operands[..1], &linkage.operands[1..]],
+                   linkage.logical_operands());
+
+        // Bit masks with parameters, and truncated parameters.
+        let access = spirv::MEMORY_ACCESS_VOLATILE | spirv::MEMORY_ACCESS_ALIGNED;
+        let mut store = mr::Instruction::new(spirv::Op::Store,
+                                             None,
+                                             None,
+                                             vec![mr::Operand::IdRef(1),
+                                                  mr::Operand::IdRef(2),
+                                                  mr::Operand::MemoryAccess(access),
+                                                  mr::Operand::LiteralInt32(4)]);
+        assert_eq!(vec![&store.operands[..1], &store.operands[1..2], &store.operands[2..]],
+                   store.logical_operands());
+        store.operands.pop();
+        assert_eq!(vec![&store.operands[..1], &store.operands[1..2], &store.operands[2..]],
+                   store.logical_operands());
+        assert!(mr::Instruction::nop().logical_operands().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_instruction() {
+        let inst = mr::Instruction::unknown(0xffff, vec![1, 2]);
+        assert_eq!(Some(0xffff), inst.unknown_opcode);
+        assert_eq!(None, inst.opcode());
+        assert_eq!(vec![mr::Operand::RawWords(vec![0x0003ffff, 1, 2])],
+                   inst.operands);
+
+        // Not the same as an OpNop with the same operands.
+        let mut nop = mr::Instruction::nop();
+        nop.operands = inst.operands.clone();
+        assert_eq!(None, nop.unknown_opcode);
+        assert!(nop != inst);
+    }
+
     #[test]
     fn test_convert_from_op() {
         assert_eq!(mr::Operand::LiteralSpecConstantOpInteger(spirv::Op::IAdd),
                    mr::Operand::from(spirv::Op::IAdd));
     }
+
+    fn global(opcode: spirv::Op, result_id: u32, operands: Vec<mr::Operand>) -> mr::Instruction {
+        mr::Instruction::new(opcode, None, Some(result_id), operands)
+    }
+
+    fn result_ids(module: &mr::Module) -> Vec<u32> {
+        module.types_global_values.iter().map(|i| i.result_id.unwrap_or(0)).collect()
+    }
+
+    #[test]
+    fn test_sort_global_section() {
+        let mut module = mr::Module::new();
+        module.types_global_values = vec![
+            mr::Instruction::new(spirv::Op::Constant, Some(3), Some(4),
+                                 vec![mr::Operand::LiteralFloat32(1.0)]),
+            global(spirv::Op::TypeVoid, 1, vec![]),
+            global(spirv::Op::TypeVector, 2, vec![mr::Operand::IdRef(3),
+                                                  mr::Operand::LiteralInt32(4)]),
+            global(spirv::Op::TypeFloat, 3, vec![mr::Operand::LiteralInt32(32)]),
+        ];
+        assert_eq!(Ok(()), module.sort_global_section());
+        assert_eq!(vec![3, 4, 1, 2], result_ids(&module));
+        // Already sorted
+        assert_eq!(Ok(()), module.sort_global_section());
+        assert_eq!(vec![3, 4, 1, 2], result_ids(&module));
+    }
+
+    #[test]
+    fn test_sort_global_section_forward_pointer() {
+        let sc = mr::Operand::StorageClass(spirv::StorageClass::Uniform);
+        let mut module = mr::Module::new();
+        module.types_global_values = vec![
+            global(spirv::Op::TypePointer, 3, vec![sc.clone(), mr::Operand::IdRef(2)]),
+            global(spirv::Op::TypeStruct, 2, vec![mr::Operand::IdRef(3)]),
+            mr::Instruction::new(spirv::Op::TypeForwardPointer, None, None,
+                                 vec![mr::Operand::IdRef(3), sc]),
+        ];
+        assert_eq!(Ok(()), module.sort_global_section());
+        assert_eq!(vec![0, 2, 3], result_ids(&module));
+    }
+
+    #[test]
+    fn test_sort_global_section_errors() {
+        let mut module = mr::Module::new();
+        module.types_global_values = vec![
+            global(spirv::Op::TypeFloat, 5, vec![mr::Operand::LiteralInt32(32)]),
+            global(spirv::Op::TypeStruct, 1, vec![mr::Operand::IdRef(2)]),
+            global(spirv::Op::TypeStruct, 2, vec![mr::Operand::IdRef(5), mr::Operand::IdRef(1)]),
+        ];
+        let err = module.sort_global_section().unwrap_err();
+        assert_eq!(mr::SortError::DependencyCycle(vec![1, 2]), err);
+        assert_eq!("definitions depend on each other: %1 -> %2", format!("{}", err));
+        assert_eq!(vec![5, 1, 2], result_ids(&module));
+
+        module.types_global_values.push(global(spirv::Op::TypeBool, 5, vec![]));
+        assert_eq!(Err(mr::SortError::IdRedefined(5)), module.sort_global_section());
+    }
 }
@@ -0,0 +1,324 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mr;
+
+use binary::{Disassemble, EncodeSection as Section};
+use grammar::reflect;
+use std::{cmp, fmt};
+
+/// Options for [`diff_with_options`](fn.diff_with_options.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiffOptions {
+    /// The maximal number of mismatches to report.
+    pub max_entries: usize,
+    /// Whether to skip debug instructions, including OpLine and OpNoLine.
+    pub ignore_debug: bool,
+    /// Whether to skip annotation instructions.
+    pub ignore_decorations: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> DiffOptions {
+        DiffOptions {
+            max_entries: 10,
+            ignore_debug: false,
+            ignore_decorations: false,
+        }
+    }
+}
+
+/// A mismatch between two modules found by [`diff`](fn.diff.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffEntry {
+    /// The section the mismatch is in, or `None` for the module header.
+    pub section: Option<Section>,
+    /// The index of the basic block in the function, or `None` if the
+    /// mismatch is not inside a basic block.
+    pub block: Option<usize>,
+    /// The index of the instruction in the section, or in the basic block
+    /// with its OpLabel as #0. Outside basic blocks, instructions in a
+    /// function are indexed from its OpFunction, over its parameters, to
+    /// its OpFunctionEnd.
+    pub index: usize,
+    /// The disassembled instruction of the first module, or `None` if it
+    /// has no instruction there.
+    pub left: Option<String>,
+    /// The disassembled instruction of the second module, or `None` if it
+    /// has no instruction there.
+    pub right: Option<String>,
+}
+
+impl fmt::Display for DiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.section, self.block) {
+            (None, _) => try!(write!(f, "header:")),
+            (Some(Section::Function(function)), Some(block)) => {
+                try!(write!(f,
+                            "instruction #{} in basic block #{} in function #{}:",
+                            self.index,
+                            block,
+                            function))
+            }
+            (Some(Section::Function(function)), None) => {
+                try!(write!(f, "instruction #{} in function #{}:", self.index, function))
+            }
+            (Some(section), _) => try!(write!(f, "instruction #{} in {:?}:", self.index, section)),
+        }
+        if let Some(ref left) = self.left {
+            try!(write!(f, "\n- {}", left));
+        }
+        if let Some(ref right) = self.right {
+            try!(write!(f, "\n+ {}", right));
+        }
+        Ok(())
+    }
+}
+
+/// Compares the given modules, returning the mismatches found with the
+/// default [`DiffOptions`](struct.DiffOptions.html).
+pub fn diff(a: &mr::Module, b: &mr::Module) -> Vec<DiffEntry> {
+    diff_with_options(a, b, &DiffOptions::default())
+}
+
+/// Compares the given modules, returning the first
+/// `options.max_entries` mismatches found.
+///
+/// Headers are compared first, followed by each section and each function
+/// in the order they are assembled. Instructions in the same position of
+/// the same section, or of the same basic block, are compared with each
+/// other, so an instruction inserted or removed in one module makes all
+/// following instructions of its section or basic block mismatch.
+pub fn diff_with_options(a: &mr::Module, b: &mr::Module, options: &DiffOptions) -> Vec<DiffEntry> {
+    let mut differ = Differ {
+        options: options,
+        entries: vec![],
+    };
+    if a.header != b.header {
+        differ.push(DiffEntry {
+            section: None,
+            block: None,
+            index: 0,
+            left: a.header.as_ref().map(|h| format!("{:#}", h)),
+            right: b.header.as_ref().map(|h| format!("{:#}", h)),
+        });
+    }
+    differ.diff(Section::Capabilities, None, &a.capabilities, &b.capabilities);
+    differ.diff(Section::Extensions, None, &a.extensions, &b.extensions);
+    differ.diff(Section::ExtInstImports,
+                None,
+                &a.ext_inst_imports,
+                &b.ext_inst_imports);
+    differ.diff_refs(Section::MemoryModel,
+                     None,
+                     a.memory_model.iter().collect(),
+                     b.memory_model.iter().collect());
+    differ.diff(Section::EntryPoints, None, &a.entry_points, &b.entry_points);
+    differ.diff(Section::ExecutionModes,
+                None,
+                &a.execution_modes,
+                &b.execution_modes);
+    differ.diff(Section::Debugs, None, &a.debugs, &b.debugs);
+    differ.diff(Section::Annotations, None, &a.annotations, &b.annotations);
+    differ.diff(Section::TypesGlobalValues,
+                None,
+                &a.types_global_values,
+                &b.types_global_values);
+    let empty = mr::Function::new();
+    for index in 0..cmp::max(a.functions.len(), b.functions.len()) {
+        let fa = a.functions.get(index).unwrap_or(&empty);
+        let fb = b.functions.get(index).unwrap_or(&empty);
+        let section = Section::Function(index);
+        differ.diff_refs(section, None, outer_insts(fa), outer_insts(fb));
+        let empty = mr::BasicBlock::new();
+        for block in 0..cmp::max(fa.basic_blocks.len(), fb.basic_blocks.len()) {
+            let ba = fa.basic_blocks.get(block).unwrap_or(&empty);
+            let bb = fb.basic_blocks.get(block).unwrap_or(&empty);
+            differ.diff_refs(section,
+                             Some(block),
+                             ba.label.iter().chain(ba.instructions.iter()).collect(),
+                             bb.label.iter().chain(bb.instructions.iter()).collect());
+        }
+    }
+    differ.entries
+}
+
+/// Returns the instructions of `function` outside its basic blocks.
+fn outer_insts(function: &mr::Function) -> Vec<&mr::Instruction> {
+    function.def.iter().chain(function.parameters.iter()).chain(function.end.iter()).collect()
+}
+
+struct Differ<'o> {
+    options: &'o DiffOptions,
+    entries: Vec<DiffEntry>,
+}
+
+impl<'o> Differ<'o> {
+    fn push(&mut self, entry: DiffEntry) {
+        if self.entries.len() < self.options.max_entries {
+            self.entries.push(entry);
+        }
+    }
+
+    fn is_ignored(&self, inst: &mr::Instruction) -> bool {
+        let opcode = inst.class;
+        (self.options.ignore_debug && reflect::is_debug(opcode)) ||
+        (self.options.ignore_decorations && reflect::is_annotation(opcode))
+    }
+
+    fn diff(&mut self,
+            section: Section,
+            block: Option<usize>,
+            a: &[mr::Instruction],
+            b: &[mr::Instruction]) {
+        self.diff_refs(section, block, a.iter().collect(), b.iter().collect())
+    }
+
+    /// Compares the instructions `a` and `b` not ignored position by
+    /// position, reporting mismatches with their original indices.
+    fn diff_refs(&mut self,
+                 section: Section,
+                 block: Option<usize>,
+                 a: Vec<&mr::Instruction>,
+                 b: Vec<&mr::Instruction>) {
+        let a: Vec<(usize, &mr::Instruction)> =
+            a.into_iter().enumerate().filter(|&(_, inst)| !self.is_ignored(inst)).collect();
+        let b: Vec<(usize, &mr::Instruction)> =
+            b.into_iter().enumerate().filter(|&(_, inst)| !self.is_ignored(inst)).collect();
+        for i in 0..cmp::max(a.len(), b.len()) {
+            let (left, right) = (a.get(i), b.get(i));
+            let index = match (left, right) {
+                (Some(&(_, l)), Some(&(_, r))) if l == r => continue,
+                (Some(&(index, _)), _) |
+                (None, Some(&(index, _))) => index,
+                (None, None) => unreachable!(),
+            };
+            self.push(DiffEntry {
+                section: Some(section),
+                block: block,
+                index: index,
+                left: left.map(|&(_, inst)| inst.disassemble()),
+                right: right.map(|&(_, inst)| inst.disassemble()),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mr;
+    use spirv;
+
+    use binary::EncodeSection as Section;
+    use super::{DiffEntry, DiffOptions};
+
+    fn build_module() -> mr::Module {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let one = b.constant_f32(float, 1.0);
+        let voidfvoid = b.type_function(void, vec![]);
+        let f = b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.fadd(float, None, one, one).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.name(f, "main");
+        b.decorate(one, spirv::Decoration::RelaxedPrecision, vec![]).unwrap();
+        b.module()
+    }
+
+    #[test]
+    fn test_diff_same() {
+        let module = build_module();
+        assert!(mr::diff(&module, &module).is_empty());
+        assert!(mr::diff(&module, &module.clone_with_id_offset(0).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_operand() {
+        let a = build_module();
+        let mut b = a.clone_with_id_offset(0).unwrap();
+        b.functions[0].basic_blocks[0].instructions[0].operands[1] = mr::Operand::IdRef(2);
+        assert_eq!(vec![DiffEntry {
+                            section: Some(Section::Function(0)),
+                            block: Some(0),
+                            index: 1,
+                            left: Some("%7 = OpFAdd  %2  %3 %3".to_string()),
+                            right: Some("%7 = OpFAdd  %2  %3 %2".to_string()),
+                        }],
+                   mr::diff(&a, &b));
+        assert_eq!("instruction #1 in basic block #0 in function #0:\n\
+                    - %7 = OpFAdd  %2  %3 %3\n\
+                    + %7 = OpFAdd  %2  %3 %2",
+                   format!("{}", mr::diff(&a, &b)[0]));
+    }
+
+    #[test]
+    fn test_diff_missing() {
+        let a = build_module();
+        let mut b = a.clone_with_id_offset(0).unwrap();
+        b.header.as_mut().unwrap().bound += 1;
+        b.capabilities.clear();
+        b.functions.push(mr::Function::new());
+        b.functions[1].basic_blocks.push(mr::BasicBlock::new());
+        b.functions[1].basic_blocks[0].instructions.push(mr::Instruction::nop());
+        let diff = mr::diff(&a, &b);
+        assert_eq!(3, diff.len());
+        assert_eq!((None, None), (diff[0].section, diff[0].block));
+        assert_eq!(DiffEntry {
+                       section: Some(Section::Capabilities),
+                       block: None,
+                       index: 0,
+                       left: Some("OpCapability Shader".to_string()),
+                       right: None,
+                   },
+                   diff[1]);
+        assert_eq!(DiffEntry {
+                       section: Some(Section::Function(1)),
+                       block: Some(0),
+                       index: 0,
+                       left: None,
+                       right: Some("OpNop".to_string()),
+                   },
+                   diff[2]);
+    }
+
+    #[test]
+    fn test_diff_options() {
+        let a = build_module();
+        let mut b = a.clone_with_id_offset(0).unwrap();
+        b.debugs[0].operands[1] = mr::Operand::from("other");
+        b.annotations.clear();
+        b.types_global_values.clear();
+        assert_eq!(6, mr::diff(&a, &b).len());
+
+        let mut options = DiffOptions::default();
+        options.max_entries = 3;
+        let diff = mr::diff_with_options(&a, &b, &options);
+        assert_eq!(vec![Some(Section::Debugs),
+                        Some(Section::Annotations),
+                        Some(Section::TypesGlobalValues)],
+                   diff.iter().map(|e| e.section).collect::<Vec<_>>());
+
+        options.max_entries = 10;
+        options.ignore_debug = true;
+        options.ignore_decorations = true;
+        let diff = mr::diff_with_options(&a, &b, &options);
+        assert_eq!(4, diff.len());
+        assert!(diff.iter().all(|e| e.section == Some(Section::TypesGlobalValues)));
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mr;
+
+use binary::{Assemble, AssembleOptions, EncodeError, ParseState};
+use std::{error, fmt, fs, io};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Errors from loading a module from a file.
+#[derive(Debug)]
+pub enum LoadError {
+    /// Reading the file failed (path, error)
+    IoError(PathBuf, io::Error),
+    /// The file size is not a multiple of four (path, size in bytes)
+    SizeUnaligned(PathBuf, usize),
+    /// Parsing the file contents failed (path, state)
+    ParseFailed(PathBuf, ParseState),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::IoError(ref path, ref err) => {
+                write!(f, "failed to read {}: {}", path.display(), err)
+            }
+            LoadError::SizeUnaligned(ref path, size) => {
+                write!(f,
+                       "{} is not a SPIR-V binary: its size ({} bytes) is not a multiple of four",
+                       path.display(),
+                       size)
+            }
+            LoadError::ParseFailed(ref path, ref state) => {
+                write!(f, "failed to parse {}: {}", path.display(), state)
+            }
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn description(&self) -> &str {
+        match *self {
+            LoadError::IoError(..) => "I/O error",
+            LoadError::SizeUnaligned(..) => "file size not a multiple of four",
+            LoadError::ParseFailed(..) => "parsing failed",
+        }
+    }
+}
+
+/// Errors from saving a module to a file.
+#[derive(Debug)]
+pub enum SaveError {
+    /// Writing the file failed (path, error)
+    IoError(PathBuf, io::Error),
+    /// Encoding the module failed (path, error)
+    EncodeFailed(PathBuf, EncodeError),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SaveError::IoError(ref path, ref err) => {
+                write!(f, "failed to write {}: {}", path.display(), err)
+            }
+            SaveError::EncodeFailed(ref path, ref err) => {
+                write!(f, "failed to encode module for {}: {}", path.display(), err)
+            }
+        }
+    }
+}
+
+impl error::Error for SaveError {
+    fn description(&self) -> &str {
+        match *self {
+            SaveError::IoError(..) => "I/O error",
+            SaveError::EncodeFailed(..) => "encoding failed",
+        }
+    }
+}
+
+/// Loads the SPIR-V binary in the file at `path` into memory and returns a
+/// `Module`.
+///
+/// The file is read as a whole and then parsed with
+/// [`load_bytes`](fn.load_bytes.html).
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<mr::Module, LoadError> {
+    let path = path.as_ref();
+    let mut bytes = vec![];
+    if let Err(err) = fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)) {
+        return Err(LoadError::IoError(path.to_path_buf(), err));
+    }
+    if bytes.len() % 4 != 0 {
+        return Err(LoadError::SizeUnaligned(path.to_path_buf(), bytes.len()));
+    }
+    mr::load_bytes(bytes).map_err(|state| LoadError::ParseFailed(path.to_path_buf(), state))
+}
+
+impl mr::Module {
+    /// Assembles this module and writes the binary code to the file at
+    /// `path`, in little-endian byte order.
+    ///
+    /// The module is checked as in
+    /// [`AssembleOptions::assemble`](../binary/struct.AssembleOptions.html#method.assemble)
+    /// before anything is written. The file is then written as in
+    /// [`Assemble::write_to_file`](../binary/trait.Assemble.html#method.write_to_file),
+    /// so that `path` never holds a partially written module.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        let path = path.as_ref();
+        try!(AssembleOptions::default()
+            .assemble(self)
+            .map_err(|err| SaveError::EncodeFailed(path.to_path_buf(), err)));
+        self.write_to_file(path).map_err(|err| SaveError::IoError(path.to_path_buf(), err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mr;
+    use spirv;
+
+    use binary::EncodeError;
+    use std::{env, fs};
+    use super::{LoadError, SaveError, load_file};
+
+    #[test]
+    fn test_save_and_load_file() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        b.name(void, "void");
+        let module = b.module();
+
+        let path = env::temp_dir().join("rspirv-test-save-and-load-file.spv");
+        module.save(&path).unwrap();
+        // Replaces the existing file, without leaving the temporary file.
+        module.save(&path).unwrap();
+        assert!(!env::temp_dir().join("rspirv-test-save-and-load-file.spv.tmp").exists());
+        let loaded = load_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(module.header.unwrap().bound, loaded.unwrap().header.unwrap().bound);
+    }
+
+    #[test]
+    fn test_load_file_errors() {
+        let path = env::temp_dir().join("rspirv-test-load-file-unaligned.spv");
+        fs::File::create(&path).and_then(|mut f| {
+            use std::io::Write;
+            f.write_all(&[0x03, 0x02, 0x23, 0x07, 0x00])
+        }).unwrap();
+        let err = load_file(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_matches!(err, LoadError::SizeUnaligned(_, 5));
+        assert_eq!(format!("{} is not a SPIR-V binary: its size (5 bytes) is not a multiple of four",
+                           path.display()),
+                   format!("{}", err));
+
+        let path = env::temp_dir().join("rspirv-test-load-file-missing.spv");
+        let err = load_file(&path).unwrap_err();
+        assert_matches!(err, LoadError::IoError(..));
+        assert!(format!("{}", err).starts_with(&format!("failed to read {}: ", path.display())));
+    }
+
+    #[test]
+    fn test_save_encode_failed() {
+        let path = env::temp_dir().join("rspirv-test-save-encode-failed.spv");
+        let err = mr::Module::new().save(&path).unwrap_err();
+        assert_matches!(err, SaveError::EncodeFailed(_, EncodeError::MemoryModelMissing));
+        assert_eq!(format!("failed to encode module for {}: module has no OpMemoryModel",
+                           path.display()),
+                   format!("{}", err));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mr;
+
+use std::fmt::Write;
+
+impl mr::Module {
+    /// Returns a JSON document describing this module, for debugging and
+    /// structured diffing.
+    ///
+    /// The document is an object with two fields:
+    ///
+    /// * `"header"`: `null`, or an object with the `"magic_number"`,
+    ///   `"version"`, `"generator"`, `"bound"`, and `"reserved_word"`
+    ///   fields of the header, as numbers.
+    /// * `"instructions"`: an array of all instructions, in the order they
+    ///   are assembled, one per line. Each instruction is an object with
+    ///   its `"opcode"` name (or number, if unknown to the grammar),
+    ///   `"result_type"` and `"result_id"` (`null` if absent), and
+    ///   `"operands"`.
+    ///
+    /// Each operand is an object with its `"kind"`, as returned by
+    /// [`Operand::kind_name`](enum.Operand.html#method.kind_name), and its
+    /// `"value"`:
+    ///
+    /// * Value enumerants and OpSpecConstantOp opcodes are strings of
+    ///   their names, e.g., `"Shader"`.
+    /// * Bit masks, ids, and 32-bit integers are numbers.
+    /// * 64-bit integers are strings of their decimal values, since many
+    ///   JSON readers cannot represent them exactly as numbers.
+    /// * Floating point numbers are numbers, or the strings `"NaN"`,
+    ///   `"inf"`, and `"-inf"` if not finite.
+    /// * Literal strings are strings.
+    /// * Raw words are arrays of numbers.
+    /// * Unknown enumerants are numbers, with an extra `"enumerant_kind"`
+    ///   field naming the operand kind they are of.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        match self.header {
+            Some(ref h) => {
+                write!(json,
+                       "{{\n  \"header\": {{\"magic_number\": {}, \"version\": {}, \
+                        \"generator\": {}, \"bound\": {}, \"reserved_word\": {}}},\n",
+                       h.magic_number,
+                       h.version,
+                       h.generator,
+                       h.bound,
+                       h.reserved_word)
+                    .unwrap()
+            }
+            None => json.push_str("{\n  \"header\": null,\n"),
+        }
+        json.push_str("  \"instructions\": [");
+        for (index, inst) in self.all_inst_iter().enumerate() {
+            json.push_str(if index == 0 { "\n    " } else { ",\n    " });
+            write_inst(&mut json, inst);
+        }
+        json.push_str("\n  ]\n}\n");
+        json
+    }
+}
+
+fn write_inst(json: &mut String, inst: &mr::Instruction) {
+    let opcode = match inst.unknown_opcode {
+        Some(opcode) => opcode.to_string(),
+        None => format!("\"{}\"", inst.grammar().opname),
+    };
+    write!(json,
+           "{{\"opcode\": {}, \"result_type\": {}, \"result_id\": {}, \"operands\": [",
+           opcode,
+           optional_id(inst.result_type),
+           optional_id(inst.result_id))
+        .unwrap();
+    for (index, operand) in inst.operands.iter().enumerate() {
+        if index != 0 {
+            json.push_str(", ");
+        }
+        write_operand(json, operand);
+    }
+    json.push_str("]}");
+}
+
+fn optional_id(id: Option<u32>) -> String {
+    id.map_or("null".to_string(), |id| id.to_string())
+}
+
+fn write_operand(json: &mut String, operand: &mr::Operand) {
+    write!(json, "{{\"kind\": \"{}\", ", operand.kind_name()).unwrap();
+    let result = match *operand {
+        mr::Operand::ImageOperands(v) => write!(json, "\"value\": {}", v.bits()),
+        mr::Operand::FPFastMathMode(v) => write!(json, "\"value\": {}", v.bits()),
+        mr::Operand::SelectionControl(v) => write!(json, "\"value\": {}", v.bits()),
+        mr::Operand::LoopControl(v) => write!(json, "\"value\": {}", v.bits()),
+        mr::Operand::FunctionControl(v) => write!(json, "\"value\": {}", v.bits()),
+        mr::Operand::MemorySemantics(v) => write!(json, "\"value\": {}", v.bits()),
+        mr::Operand::MemoryAccess(v) => write!(json, "\"value\": {}", v.bits()),
+        mr::Operand::KernelProfilingInfo(v) => write!(json, "\"value\": {}", v.bits()),
+        mr::Operand::IdMemorySemantics(v) |
+        mr::Operand::IdScope(v) |
+        mr::Operand::IdRef(v) |
+        mr::Operand::LiteralInt32(v) |
+        mr::Operand::LiteralExtInstInteger(v) => write!(json, "\"value\": {}", v),
+        mr::Operand::LiteralInt64(v) => write!(json, "\"value\": \"{}\"", v),
+        mr::Operand::LiteralFloat32(v) if v.is_finite() => write!(json, "\"value\": {:?}", v),
+        mr::Operand::LiteralFloat32(v) => write!(json, "\"value\": \"{:?}\"", v),
+        mr::Operand::LiteralFloat64(v) if v.is_finite() => write!(json, "\"value\": {:?}", v),
+        mr::Operand::LiteralFloat64(v) => write!(json, "\"value\": \"{:?}\"", v),
+        mr::Operand::LiteralSpecConstantOpInteger(v) => write!(json, "\"value\": \"{:?}\"", v),
+        mr::Operand::LiteralString(ref v) => {
+            json.push_str("\"value\": ");
+            write_str(json, v);
+            Ok(())
+        }
+        mr::Operand::RawWords(ref v) => {
+            let words: Vec<String> = v.iter().map(|w| w.to_string()).collect();
+            write!(json, "\"value\": [{}]", words.join(", "))
+        }
+        mr::Operand::UnknownEnumerant(kind, v) => {
+            write!(json, "\"enumerant_kind\": \"{}\", \"value\": {}", kind, v)
+        }
+        // All remaining kinds are value enums.
+        ref v => write!(json, "\"value\": \"{}\"", v),
+    };
+    result.unwrap();
+    json.push('}');
+}
+
+/// Writes `s` as a JSON string literal.
+fn write_str(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use mr;
+    use spirv;
+
+    use std::f32;
+
+    #[test]
+    fn test_to_json() {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let float = b.type_float(32);
+        let half = b.constant_f32(float, 0.5);
+        b.constant_f32(float, f32::INFINITY);
+        b.name(half, "say \"half\"\n");
+        let mut module = b.module();
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::Constant,
+                                       Some(float),
+                                       Some(4),
+                                       vec![mr::Operand::LiteralInt64(u64::max_value())]));
+        module.annotations.push(mr::Instruction::new(spirv::Op::Decorate,
+                                                     None,
+                                                     None,
+                                                     vec![mr::Operand::IdRef(2),
+                                                          mr::Operand::UnknownEnumerant("Decoration", 9999)]));
+
+        let header = module.header.as_ref().unwrap();
+        let expected = format!("{{\n  \"header\": {{\"magic_number\": {}, \"version\": {}, \
+                                \"generator\": {}, \"bound\": {}, \"reserved_word\": 0}},\n  \
+                                \"instructions\": [\n    \
+                                {{\"opcode\": \"Capability\", \"result_type\": null, \
+                                \"result_id\": null, \"operands\": [\
+                                {{\"kind\": \"Capability\", \"value\": \"Shader\"}}]}},\n    \
+                                {{\"opcode\": \"MemoryModel\", \"result_type\": null, \
+                                \"result_id\": null, \"operands\": [\
+                                {{\"kind\": \"AddressingModel\", \"value\": \"Logical\"}}, \
+                                {{\"kind\": \"MemoryModel\", \"value\": \"GLSL450\"}}]}},\n    \
+                                {{\"opcode\": \"Name\", \"result_type\": null, \
+                                \"result_id\": null, \"operands\": [\
+                                {{\"kind\": \"IdRef\", \"value\": 2}}, \
+                                {{\"kind\": \"LiteralString\", \
+                                \"value\": \"say \\\"half\\\"\\n\"}}]}},\n    \
+                                {{\"opcode\": \"Decorate\", \"result_type\": null, \
+                                \"result_id\": null, \"operands\": [\
+                                {{\"kind\": \"IdRef\", \"value\": 2}}, \
+                                {{\"kind\": \"UnknownEnumerant\", \
+                                \"enumerant_kind\": \"Decoration\", \"value\": 9999}}]}},\n    \
+                                {{\"opcode\": \"TypeFloat\", \"result_type\": null, \
+                                \"result_id\": 1, \"operands\": [\
+                                {{\"kind\": \"LiteralInt32\", \"value\": 32}}]}},\n    \
+                                {{\"opcode\": \"Constant\", \"result_type\": 1, \
+                                \"result_id\": 2, \"operands\": [\
+                                {{\"kind\": \"LiteralFloat32\", \"value\": 0.5}}]}},\n    \
+                                {{\"opcode\": \"Constant\", \"result_type\": 1, \
+                                \"result_id\": 3, \"operands\": [\
+                                {{\"kind\": \"LiteralFloat32\", \"value\": \"inf\"}}]}},\n    \
+                                {{\"opcode\": \"Constant\", \"result_type\": 1, \
+                                \"result_id\": 4, \"operands\": [\
+                                {{\"kind\": \"LiteralInt64\", \
+                                \"value\": \"18446744073709551615\"}}]}}\n  \
+                                ]\n}}\n",
+                               header.magic_number,
+                               header.version,
+                               header.generator,
+                               header.bound);
+        assert_eq!(expected, module.to_json());
+    }
+
+    #[test]
+    fn test_to_json_unknown_instruction() {
+        let mut module = mr::Module::new();
+        module.types_global_values.push(mr::Instruction::unknown(0xfff0, vec![7]));
+        assert_eq!("{\n  \"header\": null,\n  \"instructions\": [\n    \
+                    {\"opcode\": 65520, \"result_type\": null, \"result_id\": null, \
+                    \"operands\": [{\"kind\": \"RawWords\", \"value\": [196592, 7]}]}\n  \
+                    ]\n}\n",
+                   module.to_json());
+    }
+
+    #[test]
+    fn test_to_json_empty() {
+        assert_eq!("{\n  \"header\": null,\n  \"instructions\": [\n  ]\n}\n",
+                   mr::Module::new().to_json());
+    }
+}
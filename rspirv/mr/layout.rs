@@ -0,0 +1,426 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mr;
+use spirv;
+
+use binary::{EncodeLocation as Location, EncodeSection as Section};
+use grammar::reflect;
+use std::{error, fmt};
+use super::loader::is_module_level;
+
+/// Errors from [`Module::validate_layout`](struct.Module.html#method.validate_layout).
+#[derive(Debug, PartialEq)]
+pub enum LayoutError {
+    /// The module has no OpMemoryModel
+    MemoryModelMissing,
+    /// An instruction not allowed where it is (location, opcode)
+    InstructionMisplaced(Location, spirv::Op),
+    /// A function without OpFunction (function index)
+    FunctionDefMissing(usize),
+    /// A function without OpFunctionEnd (function index)
+    FunctionEndMissing(usize),
+    /// A basic block without OpLabel (function index, basic block index)
+    LabelMissing(usize, usize),
+    /// A basic block not ending with a terminator (function index, basic
+    /// block index)
+    TerminatorMissing(usize, usize),
+    /// A terminator before the end of its basic block (location, opcode)
+    TerminatorMisplaced(Location, spirv::Op),
+    /// An OpVariable in a function not at the beginning of its entry block
+    /// (location)
+    VariableMisplaced(Location),
+    /// An OpPhi not at the beginning of its basic block (location)
+    PhiMisplaced(Location),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LayoutError::MemoryModelMissing => write!(f, "missing OpMemoryModel"),
+            LayoutError::InstructionMisplaced(location, opcode) => {
+                write!(f, "Op{:?} not allowed at {}", opcode, location)
+            }
+            LayoutError::FunctionDefMissing(function) => {
+                write!(f, "function #{} does not start with OpFunction", function)
+            }
+            LayoutError::FunctionEndMissing(function) => {
+                write!(f, "function #{} does not end with OpFunctionEnd", function)
+            }
+            LayoutError::LabelMissing(function, block) => {
+                write!(f,
+                       "basic block #{} in function #{} does not start with OpLabel",
+                       block,
+                       function)
+            }
+            LayoutError::TerminatorMissing(function, block) => {
+                write!(f,
+                       "basic block #{} in function #{} does not end with a terminator",
+                       block,
+                       function)
+            }
+            LayoutError::TerminatorMisplaced(location, opcode) => {
+                write!(f, "terminator Op{:?} before the end of its basic block at {}",
+                       opcode,
+                       location)
+            }
+            LayoutError::VariableMisplaced(location) => {
+                write!(f,
+                       "OpVariable not at the beginning of the entry block at {}",
+                       location)
+            }
+            LayoutError::PhiMisplaced(location) => {
+                write!(f, "OpPhi not at the beginning of its basic block at {}", location)
+            }
+        }
+    }
+}
+
+impl error::Error for LayoutError {
+    fn description(&self) -> &str {
+        match *self {
+            LayoutError::MemoryModelMissing => "missing OpMemoryModel",
+            LayoutError::InstructionMisplaced(..) => "instruction not allowed there",
+            LayoutError::FunctionDefMissing(..) => "function without OpFunction",
+            LayoutError::FunctionEndMissing(..) => "function without OpFunctionEnd",
+            LayoutError::LabelMissing(..) => "basic block without OpLabel",
+            LayoutError::TerminatorMissing(..) => "basic block without terminator",
+            LayoutError::TerminatorMisplaced(..) => "terminator before the end of basic block",
+            LayoutError::VariableMisplaced(..) => "OpVariable not at the beginning of entry block",
+            LayoutError::PhiMisplaced(..) => "OpPhi not at the beginning of basic block",
+        }
+    }
+}
+
+/// Returns true if `inst` is an OpVariable of the Function storage class.
+fn is_function_variable(inst: &mr::Instruction) -> bool {
+    inst.class == spirv::Op::Variable &&
+    inst.operands.get(0) == Some(&mr::Operand::StorageClass(spirv::StorageClass::Function))
+}
+
+/// Checks that each instruction in the given `section` is `allowed` there.
+/// Instructions unknown to the grammar are allowed anywhere.
+fn check_section(errors: &mut Vec<LayoutError>,
+                 section: Section,
+                 insts: &[mr::Instruction],
+                 allowed: &Fn(&mr::Instruction) -> bool) {
+    for (index, inst) in insts.iter().enumerate() {
+        if inst.unknown_opcode.is_none() && !allowed(inst) {
+            let location = Location {
+                section: section,
+                index: index,
+            };
+            errors.push(LayoutError::InstructionMisplaced(location, inst.class));
+        }
+    }
+}
+
+fn check_function(errors: &mut Vec<LayoutError>, index: usize, function: &mr::Function) {
+    let section = Section::Function(index);
+    // Instructions are counted the same way as for encoder errors.
+    let mut count = 0;
+    let mut location = || {
+        count += 1;
+        Location {
+            section: section,
+            index: count - 1,
+        }
+    };
+
+    match function.def {
+        Some(ref def) if def.class == spirv::Op::Function => {
+            location();
+        }
+        Some(ref def) => {
+            errors.push(LayoutError::InstructionMisplaced(location(), def.class))
+        }
+        None => errors.push(LayoutError::FunctionDefMissing(index)),
+    }
+    for param in &function.parameters {
+        let location = location();
+        if param.class != spirv::Op::FunctionParameter {
+            errors.push(LayoutError::InstructionMisplaced(location, param.class));
+        }
+    }
+    for (block_index, block) in function.basic_blocks.iter().enumerate() {
+        match block.label {
+            Some(ref label) if label.class == spirv::Op::Label => {
+                location();
+            }
+            Some(ref label) => {
+                errors.push(LayoutError::InstructionMisplaced(location(), label.class))
+            }
+            None => errors.push(LayoutError::LabelMissing(index, block_index)),
+        }
+        // Whether only OpVariable, OpPhi, OpLine, and OpNoLine are seen so
+        // far in the basic block.
+        let mut at_start = true;
+        for (inst_index, inst) in block.instructions.iter().enumerate() {
+            let location = location();
+            let opcode = inst.class;
+            if inst.unknown_opcode.is_some() {
+                at_start = false;
+                continue;
+            }
+            match opcode {
+                spirv::Op::Line | spirv::Op::NoLine => (),
+                spirv::Op::Variable if !is_function_variable(inst) => {
+                    errors.push(LayoutError::InstructionMisplaced(location, opcode))
+                }
+                spirv::Op::Variable => {
+                    if block_index != 0 || !at_start {
+                        errors.push(LayoutError::VariableMisplaced(location))
+                    }
+                }
+                spirv::Op::Phi => {
+                    if !at_start {
+                        errors.push(LayoutError::PhiMisplaced(location))
+                    }
+                }
+                opcode if reflect::is_terminator(opcode) => {
+                    if inst_index + 1 != block.instructions.len() {
+                        errors.push(LayoutError::TerminatorMisplaced(location, opcode))
+                    }
+                    at_start = false;
+                }
+                spirv::Op::Function |
+                spirv::Op::FunctionParameter |
+                spirv::Op::FunctionEnd |
+                spirv::Op::Label => {
+                    errors.push(LayoutError::InstructionMisplaced(location, opcode))
+                }
+                opcode if is_module_level(opcode) => {
+                    errors.push(LayoutError::InstructionMisplaced(location, opcode))
+                }
+                _ => at_start = false,
+            }
+        }
+        match block.instructions.last() {
+            Some(inst) if reflect::is_terminator(inst.class) => (),
+            _ => errors.push(LayoutError::TerminatorMissing(index, block_index)),
+        }
+    }
+    match function.end {
+        Some(ref end) if end.class == spirv::Op::FunctionEnd => (),
+        Some(ref end) => {
+            errors.push(LayoutError::InstructionMisplaced(location(), end.class))
+        }
+        None => errors.push(LayoutError::FunctionEndMissing(index)),
+    }
+}
+
+impl mr::Module {
+    /// Checks that this module follows the
+    /// [Logical Layout of a Module](https://goo.gl/2kVnfX) of the SPIR-V
+    /// specification, returning all violations found.
+    ///
+    /// The order of the sections is given by the data representation, so
+    /// this checks that each instruction is in a section allowing it: for
+    /// example, that no types are declared inside functions or among the
+    /// annotations. Functions are checked to start with OpFunction, their
+    /// basic blocks to start with OpLabel and end with exactly one
+    /// terminator, OpPhi instructions to come first in their basic blocks,
+    /// and local variables to come first in the entry block.
+    ///
+    /// This is a cheap structural check, not a full validation; operands
+    /// and ids are not looked into.
+    pub fn validate_layout(&self) -> Result<(), Vec<LayoutError>> {
+        let mut errors = vec![];
+        match self.memory_model {
+            Some(ref inst) if inst.class != spirv::Op::MemoryModel => {
+                let location = Location {
+                    section: Section::MemoryModel,
+                    index: 0,
+                };
+                errors.push(LayoutError::InstructionMisplaced(location, inst.class))
+            }
+            Some(_) => (),
+            None => errors.push(LayoutError::MemoryModelMissing),
+        }
+        check_section(&mut errors,
+                      Section::Capabilities,
+                      &self.capabilities,
+                      &|inst| inst.class == spirv::Op::Capability);
+        check_section(&mut errors,
+                      Section::Extensions,
+                      &self.extensions,
+                      &|inst| inst.class == spirv::Op::Extension);
+        check_section(&mut errors,
+                      Section::ExtInstImports,
+                      &self.ext_inst_imports,
+                      &|inst| inst.class == spirv::Op::ExtInstImport);
+        check_section(&mut errors,
+                      Section::EntryPoints,
+                      &self.entry_points,
+                      &|inst| inst.class == spirv::Op::EntryPoint);
+        check_section(&mut errors,
+                      Section::ExecutionModes,
+                      &self.execution_modes,
+                      &|inst| inst.class == spirv::Op::ExecutionMode);
+        check_section(&mut errors, Section::Debugs, &self.debugs, &|inst| {
+            reflect::is_nonlocation_debug(inst.class) ||
+            inst.class == spirv::Op::ModuleProcessed
+        });
+        check_section(&mut errors,
+                      Section::Annotations,
+                      &self.annotations,
+                      &|inst| reflect::is_annotation(inst.class));
+        check_section(&mut errors,
+                      Section::TypesGlobalValues,
+                      &self.types_global_values,
+                      &|inst| {
+            let opcode = inst.class;
+            reflect::is_type(opcode) || reflect::is_constant(opcode) ||
+            reflect::is_location_debug(opcode) || opcode == spirv::Op::Undef ||
+            (opcode == spirv::Op::Variable && !is_function_variable(inst))
+        });
+        for (index, function) in self.functions.iter().enumerate() {
+            check_function(&mut errors, index, function);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mr;
+    use spirv;
+
+    use binary::{EncodeLocation as Location, EncodeSection as Section};
+    use super::LayoutError;
+
+    fn build_module() -> mr::Module {
+        let mut b = mr::Builder::new();
+        b.capability(spirv::Capability::Shader);
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let ptr = b.type_pointer(None, spirv::StorageClass::Function, float);
+        let voidfvoid = b.type_function(void, vec![]);
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.variable(ptr, None, spirv::StorageClass::Function, None).unwrap();
+        let next = b.id();
+        b.branch(next).unwrap();
+        b.begin_basic_block(Some(next)).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.module()
+    }
+
+    fn location(section: Section, index: usize) -> Location {
+        Location {
+            section: section,
+            index: index,
+        }
+    }
+
+    #[test]
+    fn test_valid_layout() {
+        assert_eq!(Ok(()), build_module().validate_layout());
+    }
+
+    #[test]
+    fn test_module_sections() {
+        let mut module = build_module();
+        module.memory_model = None;
+        let capability = module.capabilities[0].clone();
+        module.types_global_values.push(capability);
+        let ty = module.types_global_values[0].clone();
+        module.annotations.push(ty.clone());
+        module.functions[0].basic_blocks[1].instructions.insert(0, ty);
+        assert_eq!(Err(vec![LayoutError::MemoryModelMissing,
+                            LayoutError::InstructionMisplaced(location(Section::Annotations, 0),
+                                                              spirv::Op::TypeVoid),
+                            LayoutError::InstructionMisplaced(location(Section::TypesGlobalValues,
+                                                                       4),
+                                                              spirv::Op::Capability),
+                            LayoutError::InstructionMisplaced(location(Section::Function(0), 5),
+                                                              spirv::Op::TypeVoid)]),
+                   module.validate_layout());
+    }
+
+    #[test]
+    fn test_function_structure() {
+        let mut module = build_module();
+        {
+            let function = &mut module.functions[0];
+            function.end = None;
+            function.basic_blocks[1].label = None;
+            let terminator = function.basic_blocks[0].instructions.pop().unwrap();
+            function.basic_blocks[1].instructions.insert(0, terminator);
+        }
+        assert_eq!(Err(vec![LayoutError::TerminatorMissing(0, 0),
+                            LayoutError::LabelMissing(0, 1),
+                            LayoutError::TerminatorMisplaced(location(Section::Function(0), 3),
+                                                             spirv::Op::Branch),
+                            LayoutError::FunctionEndMissing(0)]),
+                   module.validate_layout());
+
+        let mut module = build_module();
+        module.functions[0].def = None;
+        assert_eq!(Err(vec![LayoutError::FunctionDefMissing(0)]), module.validate_layout());
+    }
+
+    #[test]
+    fn test_variables_and_phis() {
+        let mut module = build_module();
+        let variable = module.functions[0].basic_blocks[0].instructions[0].clone();
+        let phi = mr::Instruction::new(spirv::Op::Phi,
+                                       Some(2),
+                                       Some(20),
+                                       vec![mr::Operand::IdRef(21), mr::Operand::IdRef(5)]);
+        {
+            let blocks = &mut module.functions[0].basic_blocks;
+            // Local variables are only allowed in the entry block, and
+            // neither after other instructions.
+            blocks[1].instructions.insert(0, variable.clone());
+            blocks[0].instructions.insert(1, variable.clone());
+            blocks[0].instructions.insert(1, mr::Instruction::nop());
+            // OpLine is fine anywhere.
+            blocks[0].instructions.insert(1, mr::Instruction::new(spirv::Op::NoLine,
+                                                                  None,
+                                                                  None,
+                                                                  vec![]));
+            // OpPhi is only allowed before other instructions.
+            blocks[1].instructions.insert(1, phi.clone());
+            blocks[1].instructions.insert(2, mr::Instruction::nop());
+            blocks[1].instructions.insert(3, phi);
+        }
+        module.types_global_values.push(variable);
+        assert_eq!(Err(vec![LayoutError::InstructionMisplaced(location(Section::TypesGlobalValues,
+                                                                       4),
+                                                              spirv::Op::Variable),
+                            LayoutError::VariableMisplaced(location(Section::Function(0), 5)),
+                            LayoutError::VariableMisplaced(location(Section::Function(0), 8)),
+                            LayoutError::PhiMisplaced(location(Section::Function(0), 11))]),
+                   module.validate_layout());
+    }
+
+    #[test]
+    fn test_layout_error_display() {
+        let error = LayoutError::PhiMisplaced(location(Section::Function(1), 3));
+        assert_eq!("OpPhi not at the beginning of its basic block at instruction #3 in \
+                    function #1",
+                   format!("{}", error));
+        let error = LayoutError::InstructionMisplaced(location(Section::Annotations, 0),
+                                                      spirv::Op::TypeVoid);
+        assert_eq!("OpTypeVoid not allowed at instruction #0 in Annotations",
+                   format!("{}", error));
+    }
+}
@@ -17,11 +17,11 @@ use mr;
 use spirv;
 use grammar;
 
-use binary::{ParseAction, ParseResult};
+use binary::{InstContext, ParseAction, ParseResult};
 use std::{error, fmt};
 
 /// Data representation loading errors.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Error {
     NestedFunction,
     UnclosedFunction,
@@ -32,11 +32,19 @@ pub enum Error {
     UnclosedBasicBlock,
     MismatchedTerminator,
     DetachedInstruction,
+    MisplacedInstruction,
+    MisplacedMergeInstruction,
+    MisplacedVariable,
+    MissingEntryPoint,
+    UndefinedEntryPoint,
+    IdOverflow,
     WrongOpCapabilityOperand,
     WrongOpExtensionOperand,
     WrongOpExtInstImportOperand,
     WrongOpMemoryModelOperand,
     WrongOpNameOperand,
+    WrongOpExecutionModeOperand,
+    WrongDecorationOperand,
 }
 
 impl Error {
@@ -57,11 +65,23 @@ impl Error {
             Error::UnclosedBasicBlock => "found basic block without terminator",
             Error::MismatchedTerminator => "found mismatched terminator",
             Error::DetachedInstruction => "found instruction not inside basic block",
+            Error::MisplacedInstruction => "found module-level instruction inside function",
+            Error::MisplacedMergeInstruction => {
+                "found merge instruction not immediately before a matching branch"
+            }
+            Error::MisplacedVariable => {
+                "found function variable not at the beginning of the entry block"
+            }
+            Error::MissingEntryPoint => "found no OpEntryPoint for function",
+            Error::UndefinedEntryPoint => "found OpEntryPoint for undefined function",
+            Error::IdOverflow => "found id not covered by any id bound",
             Error::WrongOpCapabilityOperand => "wrong OpCapability operand",
             Error::WrongOpExtensionOperand => "wrong OpExtension operand",
             Error::WrongOpExtInstImportOperand => "wrong OpExtInstImport operand",
             Error::WrongOpMemoryModelOperand => "wrong OpMemoryModel operand",
             Error::WrongOpNameOperand => "wrong OpName operand",
+            Error::WrongOpExecutionModeOperand => "wrong OpExecutionMode operand",
+            Error::WrongDecorationOperand => "wrong decoration operand",
         }
     }
 }
@@ -78,6 +98,28 @@ impl fmt::Display for Error {
     }
 }
 
+/// A loading error caused by an instruction, reported by the
+/// [`Loader`](struct.Loader.html) with the number of that instruction.
+#[derive(Debug)]
+pub struct LocatedError {
+    /// The number of the instruction, starting from 1 as in the parser
+    pub index: usize,
+    /// The error
+    pub error: Error,
+}
+
+impl error::Error for LocatedError {
+    fn description(&self) -> &str {
+        self.error.describe()
+    }
+}
+
+impl fmt::Display for LocatedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at instruction #{}", self.error, self.index)
+    }
+}
+
 /// The data representation loader.
 ///
 /// Constructs a [`Module`](struct.Module.html) from the module header and
@@ -85,11 +127,23 @@ impl fmt::Display for Error {
 ///
 /// It implements the [`Consumer`](../binary/trait.Consumer.html) trait and
 /// works with the [`Parser`](../binary/struct.Parser.html).
+///
+/// Instructions with opcodes unknown to the grammar (see
+/// [`Instruction::unknown`](struct.Instruction.html#method.unknown)) are
+/// placed into the current basic block if inside a function; otherwise,
+/// they are placed into `types_global_values` since which section they
+/// belong to is unknown.
+///
+/// Instructions in positions they are not allowed in, e.g., OpCapability
+/// inside a function or an instruction outside any basic block, error out
+/// with a [`LocatedError`](struct.LocatedError.html) giving the number of
+/// the instruction.
 #[derive(Default)]
 pub struct Loader {
     module: mr::Module,
     function: Option<mr::Function>,
     block: Option<mr::BasicBlock>,
+    index: usize,
 }
 
 impl Loader {
@@ -99,6 +153,7 @@ impl Loader {
             module: mr::Module::new(),
             function: None,
             block: None,
+            index: 0,
         }
     }
 
@@ -111,38 +166,44 @@ impl Loader {
 /// Returns `$error` if `$condition` evaluates to false.
 macro_rules! if_ret_err {
     ($condition: expr, $error: ident) => (if $condition {
-        return ParseAction::Error(Box::new(Error::$error))
+        return Err(Error::$error)
     });
 }
 
-/// Converts possible error from the given `$result` to a ParseAction::Error
-/// and returns it.
-macro_rules! try_call {
-    ($result: expr) => (match $result {
-        Ok(_) => (),
-        Err(err) => return ParseAction::Error(Box::new(err))
-    })
-}
-
-impl binary::Consumer for Loader {
-    fn initialize(&mut self) -> ParseAction {
-        ParseAction::Continue
-    }
-
-    fn finalize(&mut self) -> ParseAction {
-        if_ret_err!(self.block.is_some(), UnclosedBasicBlock);
-        if_ret_err!(self.function.is_some(), UnclosedFunction);
-        ParseAction::Continue
-    }
-
-    fn consume_header(&mut self, header: mr::ModuleHeader) -> ParseAction {
-        self.module.header = Some(header);
-        ParseAction::Continue
+/// Returns true if instructions with the given opcode are only allowed
+/// outside functions.
+pub fn is_module_level(opcode: spirv::Op) -> bool {
+    match opcode {
+        spirv::Op::Capability |
+        spirv::Op::Extension |
+        spirv::Op::ExtInstImport |
+        spirv::Op::MemoryModel |
+        spirv::Op::EntryPoint |
+        spirv::Op::ExecutionMode => true,
+        opcode => {
+            grammar::reflect::is_nonlocation_debug(opcode) ||
+            grammar::reflect::is_annotation(opcode) ||
+            grammar::reflect::is_type(opcode) || grammar::reflect::is_constant(opcode)
+        }
     }
+}
 
-    fn consume_instruction(&mut self, inst: mr::Instruction) -> ParseAction {
-        let opcode = inst.class.opcode;
+impl Loader {
+    fn load_instruction(&mut self, inst: mr::Instruction) -> Result<(), Error> {
+        if inst.unknown_opcode.is_some() {
+            if self.function.is_none() {
+                self.module.types_global_values.push(inst);
+                return Ok(());
+            }
+            if_ret_err!(self.block.is_none(), DetachedInstruction);
+            self.block.as_mut().unwrap().instructions.push(inst);
+            return Ok(());
+        }
+        let opcode = inst.class;
         match opcode {
+            opcode if self.function.is_some() && is_module_level(opcode) => {
+                return Err(Error::MisplacedInstruction)
+            }
             spirv::Op::Capability => self.module.capabilities.push(inst),
             spirv::Op::Extension => self.module.extensions.push(inst),
             spirv::Op::ExtInstImport => self.module.ext_inst_imports.push(inst),
@@ -153,8 +214,12 @@ impl binary::Consumer for Loader {
                 self.module.debugs.push(inst)
             }
             opcode if grammar::reflect::is_annotation(opcode) => self.module.annotations.push(inst),
-            opcode if grammar::reflect::is_type(opcode) || grammar::reflect::is_constant(opcode) ||
-                      grammar::reflect::is_variable(opcode) => {
+            opcode if grammar::reflect::is_type(opcode) ||
+                      grammar::reflect::is_constant(opcode) => {
+                self.module.types_global_values.push(inst)
+            }
+            // Variables inside functions go into their basic blocks below.
+            opcode if grammar::reflect::is_variable(opcode) && self.function.is_none() => {
                 self.module.types_global_values.push(inst)
             }
             spirv::Op::Function => {
@@ -196,8 +261,50 @@ impl binary::Consumer for Loader {
                 self.block.as_mut().unwrap().instructions.push(inst)
             }
         }
+        Ok(())
+    }
+
+    fn consume_indexed(&mut self, inst: mr::Instruction) -> ParseAction {
+        match self.load_instruction(inst) {
+            Ok(()) => ParseAction::Continue,
+            Err(error) => {
+                ParseAction::Error(Box::new(LocatedError {
+                    index: self.index,
+                    error: error,
+                }))
+            }
+        }
+    }
+}
+
+impl binary::Consumer for Loader {
+    fn finalize(&mut self) -> ParseAction {
+        if self.block.is_some() {
+            return ParseAction::Error(Box::new(Error::UnclosedBasicBlock));
+        }
+        if self.function.is_some() {
+            return ParseAction::Error(Box::new(Error::UnclosedFunction));
+        }
         ParseAction::Continue
     }
+
+    fn consume_header(&mut self, header: mr::ModuleHeader) -> ParseAction {
+        self.module.header = Some(header);
+        ParseAction::Continue
+    }
+
+    fn consume_instruction(&mut self, inst: mr::Instruction) -> ParseAction {
+        self.index += 1;
+        self.consume_indexed(inst)
+    }
+
+    fn consume_instruction_span(&mut self,
+                                context: InstContext,
+                                inst: mr::Instruction)
+                                -> ParseAction {
+        self.index = context.index;
+        self.consume_indexed(inst)
+    }
 }
 
 /// Loads the SPIR-V `binary` into memory and returns a `Module`.
@@ -227,8 +334,8 @@ impl binary::Consumer for Loader {
 ///
 /// assert_eq!(dis,
 ///            "; SPIR-V\n\
-///             ; Version: 1.1\n\
-///             ; Generator: rspirv\n\
+///             ; Version: 1.0\n\
+///             ; Generator: Khronos\n\
 ///             ; Bound: 0\n\
 ///             OpMemoryModel Logical GLSL450");
 /// ```
@@ -264,8 +371,8 @@ pub fn load_bytes<T: AsRef<[u8]>>(binary: T) -> ParseResult<mr::Module> {
 ///
 /// assert_eq!(dis,
 ///            "; SPIR-V\n\
-///             ; Version: 1.1\n\
-///             ; Generator: rspirv\n\
+///             ; Version: 1.0\n\
+///             ; Generator: Khronos\n\
 ///             ; Bound: 0\n\
 ///             OpMemoryModel Logical GLSL450");
 /// ```
@@ -274,3 +381,110 @@ pub fn load_words<T: AsRef<[u32]>>(binary: T) -> ParseResult<mr::Module> {
     binary::parse_words(binary, &mut loader)?;
     Ok(loader.module())
 }
+
+#[cfg(test)]
+mod tests {
+    use mr;
+    use spirv;
+
+    use binary::{Assemble, Consumer, ParseAction, ParseState};
+    use std::sync::Arc;
+    use super::{Error, LocatedError};
+
+    fn located(state: ParseState) -> (usize, String) {
+        match state {
+            ParseState::ConsumerError(err) => {
+                let message = format!("{}", err);
+                let err = err.downcast::<LocatedError>().unwrap();
+                (err.index, message)
+            }
+            state => panic!("unexpected state: {}", state),
+        }
+    }
+
+    #[test]
+    fn test_load_function_variable() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let float = b.type_float(32);
+        let pfloat = b.type_pointer(None, spirv::StorageClass::Function, float);
+        let voidfvoid = b.type_function(void, vec![]);
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.variable(pfloat, None, spirv::StorageClass::Function, None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        let module = mr::load_words(b.module().assemble()).unwrap();
+        assert_eq!(4, module.types_global_values.len());
+        let block = &module.functions[0].basic_blocks[0];
+        assert_eq!(spirv::Op::Variable, block.instructions[0].class);
+    }
+
+    #[test]
+    fn test_load_misplaced_instructions() {
+        let mut b = mr::Builder::new();
+        b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+        let void = b.type_void();
+        let voidfvoid = b.type_function(void, vec![]);
+        b.begin_function(void, None, spirv::FUNCTION_CONTROL_NONE, voidfvoid).unwrap();
+        b.begin_basic_block(None).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        let mut module = b.module();
+
+        // OpCapability right after OpFunction, as the 5th instruction.
+        module.functions[0]
+            .parameters
+            .push(mr::Instruction::new(spirv::Op::Capability,
+                                       None,
+                                       None,
+                                       vec![mr::Operand::Capability(spirv::Capability::Shader)]));
+        let (index, message) = located(mr::load_words(module.assemble()).unwrap_err());
+        assert_eq!(5, index);
+        assert_eq!(format!("{} at instruction #5", Error::MisplacedInstruction),
+                   message);
+
+        // OpUndef before OpLabel.
+        module.functions[0].parameters[0] =
+            mr::Instruction::new(spirv::Op::Undef, Some(void), Some(10), vec![]);
+        let (index, message) = located(mr::load_words(module.assemble()).unwrap_err());
+        assert_eq!(5, index);
+        assert_eq!("found instruction not inside basic block at instruction #5",
+                   message);
+
+        // Loaders driven without instruction numbers count on their own.
+        let mut loader = mr::Loader::new();
+        let void = mr::Instruction::new(spirv::Op::TypeVoid, None, Some(1), vec![]);
+        let ret = mr::Instruction::new(spirv::Op::Return, None, None, vec![]);
+        loader.consume_instruction(void);
+        match loader.consume_instruction(ret) {
+            ParseAction::Error(err) => {
+                assert_eq!(2, err.downcast::<LocatedError>().unwrap().index)
+            }
+            _ => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn test_load_shares_strings() {
+        let mut b = mr::Builder::new();
+        let float = b.type_float(32);
+        let int = b.type_int(32, 1);
+        b.name(float, "value");
+        b.name(int, "value");
+        b.name(int, "other");
+        let module = mr::load_words(b.module().assemble()).unwrap();
+        let names: Vec<&Arc<str>> = module.debugs
+            .iter()
+            .map(|inst| match inst.operands[1] {
+                mr::Operand::LiteralString(ref name) => name,
+                _ => panic!("expected a string"),
+            })
+            .collect();
+        assert!(Arc::ptr_eq(names[0], names[1]));
+        assert!(!Arc::ptr_eq(names[0], names[2]));
+        assert_eq!("other", &**names[2]);
+    }
+}
@@ -32,13 +32,58 @@
 //! provides a [loader](struct.Loader.html) for loading SPIR-V binaries
 //! (together with the [parser](../binary/struct.Parser.html)) and a
 //! [builder](struct.Builder.html) for building a SPIR-V data representation
-//! interactively.
+//! interactively. With the `std` feature (enabled by default), modules can
+//! also be loaded from and saved to files directly, via
+//! [`load_file`](fn.load_file.html) and
+//! [`Module::save`](struct.Module.html#method.save).
+
+/// Creates a `Vec<mr::Operand>` of the given values, each converted into an
+/// `mr::Operand` via `From`.
+///
+/// Enumerants, bit masks, strings, and literal numbers are converted into
+/// the operands of their kinds. A `u32` is converted into a 32-bit literal
+/// number, so ids must be given as `Operand::IdRef` explicitly; integer
+/// literals need a suffix, e.g., `4u32`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate rspirv;
+/// extern crate spirv_headers as spirv;
+///
+/// use rspirv::mr::Operand;
+///
+/// fn main() {
+///     let operands = operands![Operand::IdRef(1), spirv::Decoration::Location, 4u32];
+///     assert_eq!(vec![Operand::IdRef(1),
+///                     Operand::Decoration(spirv::Decoration::Location),
+///                     Operand::LiteralInt32(4)],
+///                operands);
+/// }
+/// ```
+#[macro_export]
+macro_rules! operands {
+    ($($operand:expr),* $(,)*) => {{
+        let operands: Vec<$crate::mr::Operand> = vec![$($crate::mr::Operand::from($operand)),*];
+        operands
+    }};
+}
 
 pub use self::builder::Builder;
-pub use self::constructs::{BasicBlock, Function, Instruction, InstIter};
-pub use self::constructs::{Module, ModuleHeader, Operand};
-pub use self::loader::{Error, load_bytes, load_words, Loader};
+pub use self::constructs::{BasicBlock, DebugSource, Function, Instruction, InstIter, InstIterMut};
+pub use self::constructs::{BasicBlockCursor, DefIndex, InsertError};
+pub use self::constructs::{ConstantValue, Module, ModuleHeader, Operand, OperandMismatch, SortError};
+pub use self::constructs::{DebugSources, EntryPointInfo, VariableInfo};
+pub use self::diff::{diff, diff_with_options, DiffEntry, DiffOptions};
+pub use self::layout::LayoutError;
+pub use self::loader::{Error, load_bytes, load_words, Loader, LocatedError};
+#[cfg(feature = "std")]
+pub use self::file::{LoadError, load_file, SaveError};
 
 mod builder;
 mod constructs;
+mod diff;
+#[cfg(feature = "std")]
+mod file;
+mod json;
+mod layout;
 mod loader;
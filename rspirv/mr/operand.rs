@@ -17,7 +17,8 @@
 // DO NOT MODIFY!
 
 /// Data representation of a SPIR-V operand.
-#[derive(Debug, PartialEq, From)]
+#[derive(Clone, Debug, From)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 pub enum Operand {
     ImageOperands(spirv::ImageOperands),
     FPFastMathMode(spirv::FPFastMathMode),
@@ -58,7 +59,154 @@ pub enum Operand {
     LiteralFloat64(f64),
     LiteralExtInstInteger(u32),
     LiteralSpecConstantOpInteger(spirv::Op),
-    LiteralString(String),
+    LiteralString(Arc<str>),
+    RawWords(Vec<spirv::Word>),
+    UnknownEnumerant(&'static str, spirv::Word),
+}
+
+/// Names of the operand kinds unknown enumerants can be of.
+#[cfg(feature = "serialize")]
+static ENUM_KIND_NAMES: &'static [&'static str] = &[
+    "ImageOperands",
+    "FPFastMathMode",
+    "SelectionControl",
+    "LoopControl",
+    "FunctionControl",
+    "MemorySemantics",
+    "MemoryAccess",
+    "KernelProfilingInfo",
+    "SourceLanguage",
+    "ExecutionModel",
+    "AddressingModel",
+    "MemoryModel",
+    "ExecutionMode",
+    "StorageClass",
+    "Dim",
+    "SamplerAddressingMode",
+    "SamplerFilterMode",
+    "ImageFormat",
+    "ImageChannelOrder",
+    "ImageChannelDataType",
+    "FPRoundingMode",
+    "LinkageType",
+    "AccessQualifier",
+    "FunctionParameterAttribute",
+    "Decoration",
+    "BuiltIn",
+    "Scope",
+    "GroupOperation",
+    "KernelEnqueueFlags",
+    "Capability",
+];
+
+/// Mirror of `Operand` for deserialization, owning the operand kind of
+/// unknown enumerants.
+#[cfg(feature = "serialize")]
+#[derive(Deserialize)]
+#[serde(rename = "Operand")]
+enum OperandDe {
+    ImageOperands(spirv::ImageOperands),
+    FPFastMathMode(spirv::FPFastMathMode),
+    SelectionControl(spirv::SelectionControl),
+    LoopControl(spirv::LoopControl),
+    FunctionControl(spirv::FunctionControl),
+    MemorySemantics(spirv::MemorySemantics),
+    MemoryAccess(spirv::MemoryAccess),
+    KernelProfilingInfo(spirv::KernelProfilingInfo),
+    SourceLanguage(spirv::SourceLanguage),
+    ExecutionModel(spirv::ExecutionModel),
+    AddressingModel(spirv::AddressingModel),
+    MemoryModel(spirv::MemoryModel),
+    ExecutionMode(spirv::ExecutionMode),
+    StorageClass(spirv::StorageClass),
+    Dim(spirv::Dim),
+    SamplerAddressingMode(spirv::SamplerAddressingMode),
+    SamplerFilterMode(spirv::SamplerFilterMode),
+    ImageFormat(spirv::ImageFormat),
+    ImageChannelOrder(spirv::ImageChannelOrder),
+    ImageChannelDataType(spirv::ImageChannelDataType),
+    FPRoundingMode(spirv::FPRoundingMode),
+    LinkageType(spirv::LinkageType),
+    AccessQualifier(spirv::AccessQualifier),
+    FunctionParameterAttribute(spirv::FunctionParameterAttribute),
+    Decoration(spirv::Decoration),
+    BuiltIn(spirv::BuiltIn),
+    Scope(spirv::Scope),
+    GroupOperation(spirv::GroupOperation),
+    KernelEnqueueFlags(spirv::KernelEnqueueFlags),
+    Capability(spirv::Capability),
+    IdMemorySemantics(spirv::Word),
+    IdScope(spirv::Word),
+    IdRef(spirv::Word),
+    LiteralInt32(u32),
+    LiteralInt64(u64),
+    LiteralFloat32(f32),
+    LiteralFloat64(f64),
+    LiteralExtInstInteger(u32),
+    LiteralSpecConstantOpInteger(spirv::Op),
+    LiteralString(Arc<str>),
+    RawWords(Vec<spirv::Word>),
+    UnknownEnumerant(String, spirv::Word),
+}
+
+#[cfg(feature = "serialize")]
+impl<'de> serde::Deserialize<'de> for Operand {
+    fn deserialize<D>(deserializer: D) -> Result<Operand, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        Ok(match try!(<OperandDe as serde::Deserialize>::deserialize(deserializer)) {
+            OperandDe::ImageOperands(v) => Operand::ImageOperands(v),
+            OperandDe::FPFastMathMode(v) => Operand::FPFastMathMode(v),
+            OperandDe::SelectionControl(v) => Operand::SelectionControl(v),
+            OperandDe::LoopControl(v) => Operand::LoopControl(v),
+            OperandDe::FunctionControl(v) => Operand::FunctionControl(v),
+            OperandDe::MemorySemantics(v) => Operand::MemorySemantics(v),
+            OperandDe::MemoryAccess(v) => Operand::MemoryAccess(v),
+            OperandDe::KernelProfilingInfo(v) => Operand::KernelProfilingInfo(v),
+            OperandDe::SourceLanguage(v) => Operand::SourceLanguage(v),
+            OperandDe::ExecutionModel(v) => Operand::ExecutionModel(v),
+            OperandDe::AddressingModel(v) => Operand::AddressingModel(v),
+            OperandDe::MemoryModel(v) => Operand::MemoryModel(v),
+            OperandDe::ExecutionMode(v) => Operand::ExecutionMode(v),
+            OperandDe::StorageClass(v) => Operand::StorageClass(v),
+            OperandDe::Dim(v) => Operand::Dim(v),
+            OperandDe::SamplerAddressingMode(v) => Operand::SamplerAddressingMode(v),
+            OperandDe::SamplerFilterMode(v) => Operand::SamplerFilterMode(v),
+            OperandDe::ImageFormat(v) => Operand::ImageFormat(v),
+            OperandDe::ImageChannelOrder(v) => Operand::ImageChannelOrder(v),
+            OperandDe::ImageChannelDataType(v) => Operand::ImageChannelDataType(v),
+            OperandDe::FPRoundingMode(v) => Operand::FPRoundingMode(v),
+            OperandDe::LinkageType(v) => Operand::LinkageType(v),
+            OperandDe::AccessQualifier(v) => Operand::AccessQualifier(v),
+            OperandDe::FunctionParameterAttribute(v) => Operand::FunctionParameterAttribute(v),
+            OperandDe::Decoration(v) => Operand::Decoration(v),
+            OperandDe::BuiltIn(v) => Operand::BuiltIn(v),
+            OperandDe::Scope(v) => Operand::Scope(v),
+            OperandDe::GroupOperation(v) => Operand::GroupOperation(v),
+            OperandDe::KernelEnqueueFlags(v) => Operand::KernelEnqueueFlags(v),
+            OperandDe::Capability(v) => Operand::Capability(v),
+            OperandDe::IdMemorySemantics(v) => Operand::IdMemorySemantics(v),
+            OperandDe::IdScope(v) => Operand::IdScope(v),
+            OperandDe::IdRef(v) => Operand::IdRef(v),
+            OperandDe::LiteralString(v) => Operand::LiteralString(v),
+            OperandDe::LiteralInt32(v) => Operand::LiteralInt32(v),
+            OperandDe::LiteralInt64(v) => Operand::LiteralInt64(v),
+            OperandDe::LiteralFloat32(v) => Operand::LiteralFloat32(v),
+            OperandDe::LiteralFloat64(v) => Operand::LiteralFloat64(v),
+            OperandDe::LiteralExtInstInteger(v) => Operand::LiteralExtInstInteger(v),
+            OperandDe::LiteralSpecConstantOpInteger(v) => Operand::LiteralSpecConstantOpInteger(v),
+            OperandDe::RawWords(v) => Operand::RawWords(v),
+            OperandDe::UnknownEnumerant(kind, v) => {
+                match ENUM_KIND_NAMES.iter().find(|name| **name == kind) {
+                    Some(name) => Operand::UnknownEnumerant(name, v),
+                    None => {
+                        let msg = format!("unknown operand kind `{}`", kind);
+                        return Err(serde::de::Error::custom(msg));
+                    }
+                }
+            }
+        })
+    }
 }
 
 impl fmt::Display for Operand {
@@ -104,6 +252,1660 @@ impl fmt::Display for Operand {
             Operand::LiteralInt64(ref v) => write!(f, "{:?}", v),
             Operand::LiteralFloat32(ref v) => write!(f, "{:?}", v),
             Operand::LiteralFloat64(ref v) => write!(f, "{:?}", v),
+            Operand::RawWords(ref v) => write!(f, "{:?}", v),
+            Operand::UnknownEnumerant(kind, v) => write!(f, "{}({})", kind, v),
+        }
+    }
+}
+
+impl Operand {
+    /// Returns the number of operands following this operand as its
+    /// parameters, as decided by its enumerant value or bits set.
+    pub fn num_parameters(&self) -> usize {
+        match *self {
+            Operand::ImageOperands(v) => {
+                let mut count = 0;
+                if v.contains(spirv::IMAGE_OPERANDS_BIAS) {
+                    count += 1;
+                }
+                if v.contains(spirv::IMAGE_OPERANDS_LOD) {
+                    count += 1;
+                }
+                if v.contains(spirv::IMAGE_OPERANDS_GRAD) {
+                    count += 2;
+                }
+                if v.contains(spirv::IMAGE_OPERANDS_CONST_OFFSET) {
+                    count += 1;
+                }
+                if v.contains(spirv::IMAGE_OPERANDS_OFFSET) {
+                    count += 1;
+                }
+                if v.contains(spirv::IMAGE_OPERANDS_CONST_OFFSETS) {
+                    count += 1;
+                }
+                if v.contains(spirv::IMAGE_OPERANDS_SAMPLE) {
+                    count += 1;
+                }
+                if v.contains(spirv::IMAGE_OPERANDS_MIN_LOD) {
+                    count += 1;
+                }
+                count
+            }
+            Operand::LoopControl(v) => {
+                let mut count = 0;
+                if v.contains(spirv::LOOP_CONTROL_DEPENDENCY_LENGTH) {
+                    count += 1;
+                }
+                count
+            }
+            Operand::MemoryAccess(v) => {
+                let mut count = 0;
+                if v.contains(spirv::MEMORY_ACCESS_ALIGNED) {
+                    count += 1;
+                }
+                count
+            }
+            Operand::ExecutionMode(v) => {
+                match v {
+                    spirv::ExecutionMode::Invocations => 1,
+                    spirv::ExecutionMode::LocalSize => 3,
+                    spirv::ExecutionMode::LocalSizeHint => 3,
+                    spirv::ExecutionMode::OutputVertices => 1,
+                    spirv::ExecutionMode::VecTypeHint => 1,
+                    spirv::ExecutionMode::SubgroupSize => 1,
+                    spirv::ExecutionMode::SubgroupsPerWorkgroup => 1,
+                    _ => 0,
+                }
+            }
+            Operand::Decoration(v) => {
+                match v {
+                    spirv::Decoration::SpecId => 1,
+                    spirv::Decoration::ArrayStride => 1,
+                    spirv::Decoration::MatrixStride => 1,
+                    spirv::Decoration::BuiltIn => 1,
+                    spirv::Decoration::Stream => 1,
+                    spirv::Decoration::Location => 1,
+                    spirv::Decoration::Component => 1,
+                    spirv::Decoration::Index => 1,
+                    spirv::Decoration::Binding => 1,
+                    spirv::Decoration::DescriptorSet => 1,
+                    spirv::Decoration::Offset => 1,
+                    spirv::Decoration::XfbBuffer => 1,
+                    spirv::Decoration::XfbStride => 1,
+                    spirv::Decoration::FuncParamAttr => 1,
+                    spirv::Decoration::FPRoundingMode => 1,
+                    spirv::Decoration::FPFastMathMode => 1,
+                    spirv::Decoration::LinkageAttributes => 2,
+                    spirv::Decoration::InputAttachmentIndex => 1,
+                    spirv::Decoration::Alignment => 1,
+                    spirv::Decoration::MaxByteOffset => 1,
+                    _ => 0,
+                }
+            }
+            _ => 0,
+        }
+    }
+}
+
+impl Operand {
+    /// Returns true if this operand is of the given grammar operand
+    /// `kind`.
+    ///
+    /// Literal numbers and pairs are not checked here, since their
+    /// representation depends on the context.
+    pub fn is_of_kind(&self, kind: grammar::OperandKind) -> bool {
+        match (self, kind) {
+            (&Operand::ImageOperands(_), grammar::OperandKind::ImageOperands) => true,
+            (&Operand::FPFastMathMode(_), grammar::OperandKind::FPFastMathMode) => true,
+            (&Operand::SelectionControl(_), grammar::OperandKind::SelectionControl) => true,
+            (&Operand::LoopControl(_), grammar::OperandKind::LoopControl) => true,
+            (&Operand::FunctionControl(_), grammar::OperandKind::FunctionControl) => true,
+            (&Operand::MemorySemantics(_), grammar::OperandKind::MemorySemantics) => true,
+            (&Operand::MemoryAccess(_), grammar::OperandKind::MemoryAccess) => true,
+            (&Operand::KernelProfilingInfo(_), grammar::OperandKind::KernelProfilingInfo) => true,
+            (&Operand::SourceLanguage(_), grammar::OperandKind::SourceLanguage) => true,
+            (&Operand::ExecutionModel(_), grammar::OperandKind::ExecutionModel) => true,
+            (&Operand::AddressingModel(_), grammar::OperandKind::AddressingModel) => true,
+            (&Operand::MemoryModel(_), grammar::OperandKind::MemoryModel) => true,
+            (&Operand::ExecutionMode(_), grammar::OperandKind::ExecutionMode) => true,
+            (&Operand::StorageClass(_), grammar::OperandKind::StorageClass) => true,
+            (&Operand::Dim(_), grammar::OperandKind::Dim) => true,
+            (&Operand::SamplerAddressingMode(_), grammar::OperandKind::SamplerAddressingMode) => true,
+            (&Operand::SamplerFilterMode(_), grammar::OperandKind::SamplerFilterMode) => true,
+            (&Operand::ImageFormat(_), grammar::OperandKind::ImageFormat) => true,
+            (&Operand::ImageChannelOrder(_), grammar::OperandKind::ImageChannelOrder) => true,
+            (&Operand::ImageChannelDataType(_), grammar::OperandKind::ImageChannelDataType) => true,
+            (&Operand::FPRoundingMode(_), grammar::OperandKind::FPRoundingMode) => true,
+            (&Operand::LinkageType(_), grammar::OperandKind::LinkageType) => true,
+            (&Operand::AccessQualifier(_), grammar::OperandKind::AccessQualifier) => true,
+            (&Operand::FunctionParameterAttribute(_), grammar::OperandKind::FunctionParameterAttribute) => true,
+            (&Operand::Decoration(_), grammar::OperandKind::Decoration) => true,
+            (&Operand::BuiltIn(_), grammar::OperandKind::BuiltIn) => true,
+            (&Operand::Scope(_), grammar::OperandKind::Scope) => true,
+            (&Operand::GroupOperation(_), grammar::OperandKind::GroupOperation) => true,
+            (&Operand::KernelEnqueueFlags(_), grammar::OperandKind::KernelEnqueueFlags) => true,
+            (&Operand::Capability(_), grammar::OperandKind::Capability) => true,
+            (&Operand::IdMemorySemantics(_), grammar::OperandKind::IdMemorySemantics) => true,
+            (&Operand::IdScope(_), grammar::OperandKind::IdScope) => true,
+            (&Operand::IdRef(_), grammar::OperandKind::IdRef) => true,
+            (&Operand::LiteralString(_), grammar::OperandKind::LiteralString) => true,
+            (&Operand::LiteralExtInstInteger(_), grammar::OperandKind::LiteralExtInstInteger) => true,
+            (&Operand::LiteralSpecConstantOpInteger(_), grammar::OperandKind::LiteralSpecConstantOpInteger) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Operand {
+    /// Returns true if this operand is an `Operand::ImageOperands`.
+    pub fn is_image_operands(&self) -> bool {
+        match *self {
+            Operand::ImageOperands(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::ImageOperands`.
+    pub fn image_operands(&self) -> Option<spirv::ImageOperands> {
+        match *self {
+            Operand::ImageOperands(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::ImageOperands`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::ImageOperands`.
+    pub fn unwrap_image_operands(&self) -> spirv::ImageOperands {
+        match *self {
+            Operand::ImageOperands(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_image_operands()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::FPFastMathMode`.
+    pub fn is_fpfast_math_mode(&self) -> bool {
+        match *self {
+            Operand::FPFastMathMode(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::FPFastMathMode`.
+    pub fn fpfast_math_mode(&self) -> Option<spirv::FPFastMathMode> {
+        match *self {
+            Operand::FPFastMathMode(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::FPFastMathMode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::FPFastMathMode`.
+    pub fn unwrap_fpfast_math_mode(&self) -> spirv::FPFastMathMode {
+        match *self {
+            Operand::FPFastMathMode(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_fpfast_math_mode()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::SelectionControl`.
+    pub fn is_selection_control(&self) -> bool {
+        match *self {
+            Operand::SelectionControl(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::SelectionControl`.
+    pub fn selection_control(&self) -> Option<spirv::SelectionControl> {
+        match *self {
+            Operand::SelectionControl(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::SelectionControl`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::SelectionControl`.
+    pub fn unwrap_selection_control(&self) -> spirv::SelectionControl {
+        match *self {
+            Operand::SelectionControl(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_selection_control()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::LoopControl`.
+    pub fn is_loop_control(&self) -> bool {
+        match *self {
+            Operand::LoopControl(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::LoopControl`.
+    pub fn loop_control(&self) -> Option<spirv::LoopControl> {
+        match *self {
+            Operand::LoopControl(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::LoopControl`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::LoopControl`.
+    pub fn unwrap_loop_control(&self) -> spirv::LoopControl {
+        match *self {
+            Operand::LoopControl(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_loop_control()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::FunctionControl`.
+    pub fn is_function_control(&self) -> bool {
+        match *self {
+            Operand::FunctionControl(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::FunctionControl`.
+    pub fn function_control(&self) -> Option<spirv::FunctionControl> {
+        match *self {
+            Operand::FunctionControl(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::FunctionControl`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::FunctionControl`.
+    pub fn unwrap_function_control(&self) -> spirv::FunctionControl {
+        match *self {
+            Operand::FunctionControl(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_function_control()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::MemorySemantics`.
+    pub fn is_memory_semantics(&self) -> bool {
+        match *self {
+            Operand::MemorySemantics(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::MemorySemantics`.
+    pub fn memory_semantics(&self) -> Option<spirv::MemorySemantics> {
+        match *self {
+            Operand::MemorySemantics(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::MemorySemantics`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::MemorySemantics`.
+    pub fn unwrap_memory_semantics(&self) -> spirv::MemorySemantics {
+        match *self {
+            Operand::MemorySemantics(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_memory_semantics()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::MemoryAccess`.
+    pub fn is_memory_access(&self) -> bool {
+        match *self {
+            Operand::MemoryAccess(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::MemoryAccess`.
+    pub fn memory_access(&self) -> Option<spirv::MemoryAccess> {
+        match *self {
+            Operand::MemoryAccess(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::MemoryAccess`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::MemoryAccess`.
+    pub fn unwrap_memory_access(&self) -> spirv::MemoryAccess {
+        match *self {
+            Operand::MemoryAccess(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_memory_access()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::KernelProfilingInfo`.
+    pub fn is_kernel_profiling_info(&self) -> bool {
+        match *self {
+            Operand::KernelProfilingInfo(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::KernelProfilingInfo`.
+    pub fn kernel_profiling_info(&self) -> Option<spirv::KernelProfilingInfo> {
+        match *self {
+            Operand::KernelProfilingInfo(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::KernelProfilingInfo`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::KernelProfilingInfo`.
+    pub fn unwrap_kernel_profiling_info(&self) -> spirv::KernelProfilingInfo {
+        match *self {
+            Operand::KernelProfilingInfo(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_kernel_profiling_info()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::SourceLanguage`.
+    pub fn is_source_language(&self) -> bool {
+        match *self {
+            Operand::SourceLanguage(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::SourceLanguage`.
+    pub fn source_language(&self) -> Option<spirv::SourceLanguage> {
+        match *self {
+            Operand::SourceLanguage(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::SourceLanguage`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::SourceLanguage`.
+    pub fn unwrap_source_language(&self) -> spirv::SourceLanguage {
+        match *self {
+            Operand::SourceLanguage(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_source_language()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::ExecutionModel`.
+    pub fn is_execution_model(&self) -> bool {
+        match *self {
+            Operand::ExecutionModel(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::ExecutionModel`.
+    pub fn execution_model(&self) -> Option<spirv::ExecutionModel> {
+        match *self {
+            Operand::ExecutionModel(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::ExecutionModel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::ExecutionModel`.
+    pub fn unwrap_execution_model(&self) -> spirv::ExecutionModel {
+        match *self {
+            Operand::ExecutionModel(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_execution_model()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::AddressingModel`.
+    pub fn is_addressing_model(&self) -> bool {
+        match *self {
+            Operand::AddressingModel(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::AddressingModel`.
+    pub fn addressing_model(&self) -> Option<spirv::AddressingModel> {
+        match *self {
+            Operand::AddressingModel(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::AddressingModel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::AddressingModel`.
+    pub fn unwrap_addressing_model(&self) -> spirv::AddressingModel {
+        match *self {
+            Operand::AddressingModel(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_addressing_model()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::MemoryModel`.
+    pub fn is_memory_model(&self) -> bool {
+        match *self {
+            Operand::MemoryModel(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::MemoryModel`.
+    pub fn memory_model(&self) -> Option<spirv::MemoryModel> {
+        match *self {
+            Operand::MemoryModel(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::MemoryModel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::MemoryModel`.
+    pub fn unwrap_memory_model(&self) -> spirv::MemoryModel {
+        match *self {
+            Operand::MemoryModel(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_memory_model()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::ExecutionMode`.
+    pub fn is_execution_mode(&self) -> bool {
+        match *self {
+            Operand::ExecutionMode(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::ExecutionMode`.
+    pub fn execution_mode(&self) -> Option<spirv::ExecutionMode> {
+        match *self {
+            Operand::ExecutionMode(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::ExecutionMode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::ExecutionMode`.
+    pub fn unwrap_execution_mode(&self) -> spirv::ExecutionMode {
+        match *self {
+            Operand::ExecutionMode(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_execution_mode()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::StorageClass`.
+    pub fn is_storage_class(&self) -> bool {
+        match *self {
+            Operand::StorageClass(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::StorageClass`.
+    pub fn storage_class(&self) -> Option<spirv::StorageClass> {
+        match *self {
+            Operand::StorageClass(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::StorageClass`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::StorageClass`.
+    pub fn unwrap_storage_class(&self) -> spirv::StorageClass {
+        match *self {
+            Operand::StorageClass(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_storage_class()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::Dim`.
+    pub fn is_dim(&self) -> bool {
+        match *self {
+            Operand::Dim(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::Dim`.
+    pub fn dim(&self) -> Option<spirv::Dim> {
+        match *self {
+            Operand::Dim(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::Dim`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::Dim`.
+    pub fn unwrap_dim(&self) -> spirv::Dim {
+        match *self {
+            Operand::Dim(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_dim()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::SamplerAddressingMode`.
+    pub fn is_sampler_addressing_mode(&self) -> bool {
+        match *self {
+            Operand::SamplerAddressingMode(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::SamplerAddressingMode`.
+    pub fn sampler_addressing_mode(&self) -> Option<spirv::SamplerAddressingMode> {
+        match *self {
+            Operand::SamplerAddressingMode(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::SamplerAddressingMode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::SamplerAddressingMode`.
+    pub fn unwrap_sampler_addressing_mode(&self) -> spirv::SamplerAddressingMode {
+        match *self {
+            Operand::SamplerAddressingMode(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_sampler_addressing_mode()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::SamplerFilterMode`.
+    pub fn is_sampler_filter_mode(&self) -> bool {
+        match *self {
+            Operand::SamplerFilterMode(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::SamplerFilterMode`.
+    pub fn sampler_filter_mode(&self) -> Option<spirv::SamplerFilterMode> {
+        match *self {
+            Operand::SamplerFilterMode(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::SamplerFilterMode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::SamplerFilterMode`.
+    pub fn unwrap_sampler_filter_mode(&self) -> spirv::SamplerFilterMode {
+        match *self {
+            Operand::SamplerFilterMode(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_sampler_filter_mode()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::ImageFormat`.
+    pub fn is_image_format(&self) -> bool {
+        match *self {
+            Operand::ImageFormat(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::ImageFormat`.
+    pub fn image_format(&self) -> Option<spirv::ImageFormat> {
+        match *self {
+            Operand::ImageFormat(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::ImageFormat`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::ImageFormat`.
+    pub fn unwrap_image_format(&self) -> spirv::ImageFormat {
+        match *self {
+            Operand::ImageFormat(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_image_format()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::ImageChannelOrder`.
+    pub fn is_image_channel_order(&self) -> bool {
+        match *self {
+            Operand::ImageChannelOrder(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::ImageChannelOrder`.
+    pub fn image_channel_order(&self) -> Option<spirv::ImageChannelOrder> {
+        match *self {
+            Operand::ImageChannelOrder(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::ImageChannelOrder`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::ImageChannelOrder`.
+    pub fn unwrap_image_channel_order(&self) -> spirv::ImageChannelOrder {
+        match *self {
+            Operand::ImageChannelOrder(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_image_channel_order()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::ImageChannelDataType`.
+    pub fn is_image_channel_data_type(&self) -> bool {
+        match *self {
+            Operand::ImageChannelDataType(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::ImageChannelDataType`.
+    pub fn image_channel_data_type(&self) -> Option<spirv::ImageChannelDataType> {
+        match *self {
+            Operand::ImageChannelDataType(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::ImageChannelDataType`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::ImageChannelDataType`.
+    pub fn unwrap_image_channel_data_type(&self) -> spirv::ImageChannelDataType {
+        match *self {
+            Operand::ImageChannelDataType(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_image_channel_data_type()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::FPRoundingMode`.
+    pub fn is_fprounding_mode(&self) -> bool {
+        match *self {
+            Operand::FPRoundingMode(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::FPRoundingMode`.
+    pub fn fprounding_mode(&self) -> Option<spirv::FPRoundingMode> {
+        match *self {
+            Operand::FPRoundingMode(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::FPRoundingMode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::FPRoundingMode`.
+    pub fn unwrap_fprounding_mode(&self) -> spirv::FPRoundingMode {
+        match *self {
+            Operand::FPRoundingMode(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_fprounding_mode()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::LinkageType`.
+    pub fn is_linkage_type(&self) -> bool {
+        match *self {
+            Operand::LinkageType(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::LinkageType`.
+    pub fn linkage_type(&self) -> Option<spirv::LinkageType> {
+        match *self {
+            Operand::LinkageType(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::LinkageType`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::LinkageType`.
+    pub fn unwrap_linkage_type(&self) -> spirv::LinkageType {
+        match *self {
+            Operand::LinkageType(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_linkage_type()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::AccessQualifier`.
+    pub fn is_access_qualifier(&self) -> bool {
+        match *self {
+            Operand::AccessQualifier(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::AccessQualifier`.
+    pub fn access_qualifier(&self) -> Option<spirv::AccessQualifier> {
+        match *self {
+            Operand::AccessQualifier(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::AccessQualifier`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::AccessQualifier`.
+    pub fn unwrap_access_qualifier(&self) -> spirv::AccessQualifier {
+        match *self {
+            Operand::AccessQualifier(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_access_qualifier()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::FunctionParameterAttribute`.
+    pub fn is_function_parameter_attribute(&self) -> bool {
+        match *self {
+            Operand::FunctionParameterAttribute(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::FunctionParameterAttribute`.
+    pub fn function_parameter_attribute(&self) -> Option<spirv::FunctionParameterAttribute> {
+        match *self {
+            Operand::FunctionParameterAttribute(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::FunctionParameterAttribute`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::FunctionParameterAttribute`.
+    pub fn unwrap_function_parameter_attribute(&self) -> spirv::FunctionParameterAttribute {
+        match *self {
+            Operand::FunctionParameterAttribute(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_function_parameter_attribute()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::Decoration`.
+    pub fn is_decoration(&self) -> bool {
+        match *self {
+            Operand::Decoration(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::Decoration`.
+    pub fn decoration(&self) -> Option<spirv::Decoration> {
+        match *self {
+            Operand::Decoration(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::Decoration`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::Decoration`.
+    pub fn unwrap_decoration(&self) -> spirv::Decoration {
+        match *self {
+            Operand::Decoration(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_decoration()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::BuiltIn`.
+    pub fn is_built_in(&self) -> bool {
+        match *self {
+            Operand::BuiltIn(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::BuiltIn`.
+    pub fn built_in(&self) -> Option<spirv::BuiltIn> {
+        match *self {
+            Operand::BuiltIn(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::BuiltIn`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::BuiltIn`.
+    pub fn unwrap_built_in(&self) -> spirv::BuiltIn {
+        match *self {
+            Operand::BuiltIn(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_built_in()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::Scope`.
+    pub fn is_scope(&self) -> bool {
+        match *self {
+            Operand::Scope(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::Scope`.
+    pub fn scope(&self) -> Option<spirv::Scope> {
+        match *self {
+            Operand::Scope(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::Scope`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::Scope`.
+    pub fn unwrap_scope(&self) -> spirv::Scope {
+        match *self {
+            Operand::Scope(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_scope()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::GroupOperation`.
+    pub fn is_group_operation(&self) -> bool {
+        match *self {
+            Operand::GroupOperation(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::GroupOperation`.
+    pub fn group_operation(&self) -> Option<spirv::GroupOperation> {
+        match *self {
+            Operand::GroupOperation(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::GroupOperation`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::GroupOperation`.
+    pub fn unwrap_group_operation(&self) -> spirv::GroupOperation {
+        match *self {
+            Operand::GroupOperation(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_group_operation()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::KernelEnqueueFlags`.
+    pub fn is_kernel_enqueue_flags(&self) -> bool {
+        match *self {
+            Operand::KernelEnqueueFlags(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::KernelEnqueueFlags`.
+    pub fn kernel_enqueue_flags(&self) -> Option<spirv::KernelEnqueueFlags> {
+        match *self {
+            Operand::KernelEnqueueFlags(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::KernelEnqueueFlags`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::KernelEnqueueFlags`.
+    pub fn unwrap_kernel_enqueue_flags(&self) -> spirv::KernelEnqueueFlags {
+        match *self {
+            Operand::KernelEnqueueFlags(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_kernel_enqueue_flags()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::Capability`.
+    pub fn is_capability(&self) -> bool {
+        match *self {
+            Operand::Capability(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::Capability`.
+    pub fn capability(&self) -> Option<spirv::Capability> {
+        match *self {
+            Operand::Capability(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::Capability`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::Capability`.
+    pub fn unwrap_capability(&self) -> spirv::Capability {
+        match *self {
+            Operand::Capability(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_capability()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::IdMemorySemantics`.
+    pub fn is_id_memory_semantics(&self) -> bool {
+        match *self {
+            Operand::IdMemorySemantics(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::IdMemorySemantics`.
+    pub fn id_memory_semantics(&self) -> Option<spirv::Word> {
+        match *self {
+            Operand::IdMemorySemantics(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::IdMemorySemantics`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::IdMemorySemantics`.
+    pub fn unwrap_id_memory_semantics(&self) -> spirv::Word {
+        match *self {
+            Operand::IdMemorySemantics(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_id_memory_semantics()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::IdScope`.
+    pub fn is_id_scope(&self) -> bool {
+        match *self {
+            Operand::IdScope(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::IdScope`.
+    pub fn id_scope(&self) -> Option<spirv::Word> {
+        match *self {
+            Operand::IdScope(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::IdScope`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::IdScope`.
+    pub fn unwrap_id_scope(&self) -> spirv::Word {
+        match *self {
+            Operand::IdScope(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_id_scope()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::IdRef`.
+    pub fn is_id_ref(&self) -> bool {
+        match *self {
+            Operand::IdRef(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::IdRef`.
+    pub fn id_ref(&self) -> Option<spirv::Word> {
+        match *self {
+            Operand::IdRef(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::IdRef`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::IdRef`.
+    pub fn unwrap_id_ref(&self) -> spirv::Word {
+        match *self {
+            Operand::IdRef(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_id_ref()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::LiteralInt32`.
+    pub fn is_literal_int32(&self) -> bool {
+        match *self {
+            Operand::LiteralInt32(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::LiteralInt32`.
+    pub fn literal_int32(&self) -> Option<u32> {
+        match *self {
+            Operand::LiteralInt32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::LiteralInt32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::LiteralInt32`.
+    pub fn unwrap_literal_int32(&self) -> u32 {
+        match *self {
+            Operand::LiteralInt32(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_literal_int32()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::LiteralInt64`.
+    pub fn is_literal_int64(&self) -> bool {
+        match *self {
+            Operand::LiteralInt64(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::LiteralInt64`.
+    pub fn literal_int64(&self) -> Option<u64> {
+        match *self {
+            Operand::LiteralInt64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::LiteralInt64`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::LiteralInt64`.
+    pub fn unwrap_literal_int64(&self) -> u64 {
+        match *self {
+            Operand::LiteralInt64(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_literal_int64()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::LiteralFloat32`.
+    pub fn is_literal_float32(&self) -> bool {
+        match *self {
+            Operand::LiteralFloat32(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::LiteralFloat32`.
+    pub fn literal_float32(&self) -> Option<f32> {
+        match *self {
+            Operand::LiteralFloat32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::LiteralFloat32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::LiteralFloat32`.
+    pub fn unwrap_literal_float32(&self) -> f32 {
+        match *self {
+            Operand::LiteralFloat32(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_literal_float32()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::LiteralFloat64`.
+    pub fn is_literal_float64(&self) -> bool {
+        match *self {
+            Operand::LiteralFloat64(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::LiteralFloat64`.
+    pub fn literal_float64(&self) -> Option<f64> {
+        match *self {
+            Operand::LiteralFloat64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::LiteralFloat64`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::LiteralFloat64`.
+    pub fn unwrap_literal_float64(&self) -> f64 {
+        match *self {
+            Operand::LiteralFloat64(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_literal_float64()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::LiteralExtInstInteger`.
+    pub fn is_literal_ext_inst_integer(&self) -> bool {
+        match *self {
+            Operand::LiteralExtInstInteger(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::LiteralExtInstInteger`.
+    pub fn literal_ext_inst_integer(&self) -> Option<u32> {
+        match *self {
+            Operand::LiteralExtInstInteger(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::LiteralExtInstInteger`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::LiteralExtInstInteger`.
+    pub fn unwrap_literal_ext_inst_integer(&self) -> u32 {
+        match *self {
+            Operand::LiteralExtInstInteger(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_literal_ext_inst_integer()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::LiteralSpecConstantOpInteger`.
+    pub fn is_literal_spec_constant_op_integer(&self) -> bool {
+        match *self {
+            Operand::LiteralSpecConstantOpInteger(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::LiteralSpecConstantOpInteger`.
+    pub fn literal_spec_constant_op_integer(&self) -> Option<spirv::Op> {
+        match *self {
+            Operand::LiteralSpecConstantOpInteger(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::LiteralSpecConstantOpInteger`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::LiteralSpecConstantOpInteger`.
+    pub fn unwrap_literal_spec_constant_op_integer(&self) -> spirv::Op {
+        match *self {
+            Operand::LiteralSpecConstantOpInteger(v) => v,
+            ref other => {
+                panic!("called `Operand::unwrap_literal_spec_constant_op_integer()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::LiteralString`.
+    pub fn is_literal_string(&self) -> bool {
+        match *self {
+            Operand::LiteralString(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::LiteralString`.
+    pub fn literal_string(&self) -> Option<&str> {
+        match *self {
+            Operand::LiteralString(ref v) => Some(&**v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::LiteralString`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::LiteralString`.
+    pub fn unwrap_literal_string(&self) -> &str {
+        match *self {
+            Operand::LiteralString(ref v) => &**v,
+            ref other => {
+                panic!("called `Operand::unwrap_literal_string()` on {:?}", other)
+            }
+        }
+    }
+
+    /// Returns true if this operand is an `Operand::RawWords`.
+    pub fn is_raw_words(&self) -> bool {
+        match *self {
+            Operand::RawWords(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the value if this operand is an `Operand::RawWords`.
+    pub fn raw_words(&self) -> Option<&[spirv::Word]> {
+        match *self {
+            Operand::RawWords(ref v) => Some(&v[..]),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this `Operand::RawWords`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this operand is not an `Operand::RawWords`.
+    pub fn unwrap_raw_words(&self) -> &[spirv::Word] {
+        match *self {
+            Operand::RawWords(ref v) => &v[..],
+            ref other => {
+                panic!("called `Operand::unwrap_raw_words()` on {:?}", other)
+            }
+        }
+    }
+}
+
+impl Operand {
+    /// Returns the name of the kind of this operand, which is the name
+    /// of its variant, e.g., `"IdRef"`.
+    pub fn kind_name(&self) -> &'static str {
+        match *self {
+            Operand::ImageOperands(..) => "ImageOperands",
+            Operand::FPFastMathMode(..) => "FPFastMathMode",
+            Operand::SelectionControl(..) => "SelectionControl",
+            Operand::LoopControl(..) => "LoopControl",
+            Operand::FunctionControl(..) => "FunctionControl",
+            Operand::MemorySemantics(..) => "MemorySemantics",
+            Operand::MemoryAccess(..) => "MemoryAccess",
+            Operand::KernelProfilingInfo(..) => "KernelProfilingInfo",
+            Operand::SourceLanguage(..) => "SourceLanguage",
+            Operand::ExecutionModel(..) => "ExecutionModel",
+            Operand::AddressingModel(..) => "AddressingModel",
+            Operand::MemoryModel(..) => "MemoryModel",
+            Operand::ExecutionMode(..) => "ExecutionMode",
+            Operand::StorageClass(..) => "StorageClass",
+            Operand::Dim(..) => "Dim",
+            Operand::SamplerAddressingMode(..) => "SamplerAddressingMode",
+            Operand::SamplerFilterMode(..) => "SamplerFilterMode",
+            Operand::ImageFormat(..) => "ImageFormat",
+            Operand::ImageChannelOrder(..) => "ImageChannelOrder",
+            Operand::ImageChannelDataType(..) => "ImageChannelDataType",
+            Operand::FPRoundingMode(..) => "FPRoundingMode",
+            Operand::LinkageType(..) => "LinkageType",
+            Operand::AccessQualifier(..) => "AccessQualifier",
+            Operand::FunctionParameterAttribute(..) => "FunctionParameterAttribute",
+            Operand::Decoration(..) => "Decoration",
+            Operand::BuiltIn(..) => "BuiltIn",
+            Operand::Scope(..) => "Scope",
+            Operand::GroupOperation(..) => "GroupOperation",
+            Operand::KernelEnqueueFlags(..) => "KernelEnqueueFlags",
+            Operand::Capability(..) => "Capability",
+            Operand::IdMemorySemantics(..) => "IdMemorySemantics",
+            Operand::IdScope(..) => "IdScope",
+            Operand::IdRef(..) => "IdRef",
+            Operand::LiteralInt32(..) => "LiteralInt32",
+            Operand::LiteralInt64(..) => "LiteralInt64",
+            Operand::LiteralFloat32(..) => "LiteralFloat32",
+            Operand::LiteralFloat64(..) => "LiteralFloat64",
+            Operand::LiteralExtInstInteger(..) => "LiteralExtInstInteger",
+            Operand::LiteralSpecConstantOpInteger(..) => "LiteralSpecConstantOpInteger",
+            Operand::LiteralString(..) => "LiteralString",
+            Operand::RawWords(..) => "RawWords",
+            Operand::UnknownEnumerant(..) => "UnknownEnumerant",
+        }
+    }
+}
+
+/// Floating point literals are compared and hashed by their bits, so
+/// that NaNs with the same bits are equal while `0.0` and `-0.0` are
+/// not, as needed for deduplicating constants.
+impl PartialEq for Operand {
+    fn eq(&self, other: &Operand) -> bool {
+        match (self, other) {
+            (&Operand::ImageOperands(ref a), &Operand::ImageOperands(ref b)) => a == b,
+            (&Operand::FPFastMathMode(ref a), &Operand::FPFastMathMode(ref b)) => a == b,
+            (&Operand::SelectionControl(ref a), &Operand::SelectionControl(ref b)) => a == b,
+            (&Operand::LoopControl(ref a), &Operand::LoopControl(ref b)) => a == b,
+            (&Operand::FunctionControl(ref a), &Operand::FunctionControl(ref b)) => a == b,
+            (&Operand::MemorySemantics(ref a), &Operand::MemorySemantics(ref b)) => a == b,
+            (&Operand::MemoryAccess(ref a), &Operand::MemoryAccess(ref b)) => a == b,
+            (&Operand::KernelProfilingInfo(ref a), &Operand::KernelProfilingInfo(ref b)) => a == b,
+            (&Operand::SourceLanguage(ref a), &Operand::SourceLanguage(ref b)) => a == b,
+            (&Operand::ExecutionModel(ref a), &Operand::ExecutionModel(ref b)) => a == b,
+            (&Operand::AddressingModel(ref a), &Operand::AddressingModel(ref b)) => a == b,
+            (&Operand::MemoryModel(ref a), &Operand::MemoryModel(ref b)) => a == b,
+            (&Operand::ExecutionMode(ref a), &Operand::ExecutionMode(ref b)) => a == b,
+            (&Operand::StorageClass(ref a), &Operand::StorageClass(ref b)) => a == b,
+            (&Operand::Dim(ref a), &Operand::Dim(ref b)) => a == b,
+            (&Operand::SamplerAddressingMode(ref a), &Operand::SamplerAddressingMode(ref b)) => a == b,
+            (&Operand::SamplerFilterMode(ref a), &Operand::SamplerFilterMode(ref b)) => a == b,
+            (&Operand::ImageFormat(ref a), &Operand::ImageFormat(ref b)) => a == b,
+            (&Operand::ImageChannelOrder(ref a), &Operand::ImageChannelOrder(ref b)) => a == b,
+            (&Operand::ImageChannelDataType(ref a), &Operand::ImageChannelDataType(ref b)) => a == b,
+            (&Operand::FPRoundingMode(ref a), &Operand::FPRoundingMode(ref b)) => a == b,
+            (&Operand::LinkageType(ref a), &Operand::LinkageType(ref b)) => a == b,
+            (&Operand::AccessQualifier(ref a), &Operand::AccessQualifier(ref b)) => a == b,
+            (&Operand::FunctionParameterAttribute(ref a), &Operand::FunctionParameterAttribute(ref b)) => a == b,
+            (&Operand::Decoration(ref a), &Operand::Decoration(ref b)) => a == b,
+            (&Operand::BuiltIn(ref a), &Operand::BuiltIn(ref b)) => a == b,
+            (&Operand::Scope(ref a), &Operand::Scope(ref b)) => a == b,
+            (&Operand::GroupOperation(ref a), &Operand::GroupOperation(ref b)) => a == b,
+            (&Operand::KernelEnqueueFlags(ref a), &Operand::KernelEnqueueFlags(ref b)) => a == b,
+            (&Operand::Capability(ref a), &Operand::Capability(ref b)) => a == b,
+            (&Operand::IdMemorySemantics(ref a), &Operand::IdMemorySemantics(ref b)) => a == b,
+            (&Operand::IdScope(ref a), &Operand::IdScope(ref b)) => a == b,
+            (&Operand::IdRef(ref a), &Operand::IdRef(ref b)) => a == b,
+            (&Operand::LiteralInt32(ref a), &Operand::LiteralInt32(ref b)) => a == b,
+            (&Operand::LiteralInt64(ref a), &Operand::LiteralInt64(ref b)) => a == b,
+            (&Operand::LiteralFloat32(a), &Operand::LiteralFloat32(b)) => f32_to_u32(a) == f32_to_u32(b),
+            (&Operand::LiteralFloat64(a), &Operand::LiteralFloat64(b)) => f64_to_u64(a) == f64_to_u64(b),
+            (&Operand::LiteralExtInstInteger(ref a), &Operand::LiteralExtInstInteger(ref b)) => a == b,
+            (&Operand::LiteralSpecConstantOpInteger(ref a), &Operand::LiteralSpecConstantOpInteger(ref b)) => a == b,
+            (&Operand::LiteralString(ref a), &Operand::LiteralString(ref b)) => a == b,
+            (&Operand::RawWords(ref a), &Operand::RawWords(ref b)) => a == b,
+            (&Operand::UnknownEnumerant(ka, a), &Operand::UnknownEnumerant(kb, b)) => ka == kb && a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Operand {}
+
+impl Hash for Operand {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            Operand::ImageOperands(ref v) => {
+                0u8.hash(state);
+                v.hash(state)
+            }
+            Operand::FPFastMathMode(ref v) => {
+                1u8.hash(state);
+                v.hash(state)
+            }
+            Operand::SelectionControl(ref v) => {
+                2u8.hash(state);
+                v.hash(state)
+            }
+            Operand::LoopControl(ref v) => {
+                3u8.hash(state);
+                v.hash(state)
+            }
+            Operand::FunctionControl(ref v) => {
+                4u8.hash(state);
+                v.hash(state)
+            }
+            Operand::MemorySemantics(ref v) => {
+                5u8.hash(state);
+                v.hash(state)
+            }
+            Operand::MemoryAccess(ref v) => {
+                6u8.hash(state);
+                v.hash(state)
+            }
+            Operand::KernelProfilingInfo(ref v) => {
+                7u8.hash(state);
+                v.hash(state)
+            }
+            Operand::SourceLanguage(ref v) => {
+                8u8.hash(state);
+                v.hash(state)
+            }
+            Operand::ExecutionModel(ref v) => {
+                9u8.hash(state);
+                v.hash(state)
+            }
+            Operand::AddressingModel(ref v) => {
+                10u8.hash(state);
+                v.hash(state)
+            }
+            Operand::MemoryModel(ref v) => {
+                11u8.hash(state);
+                v.hash(state)
+            }
+            Operand::ExecutionMode(ref v) => {
+                12u8.hash(state);
+                v.hash(state)
+            }
+            Operand::StorageClass(ref v) => {
+                13u8.hash(state);
+                v.hash(state)
+            }
+            Operand::Dim(ref v) => {
+                14u8.hash(state);
+                v.hash(state)
+            }
+            Operand::SamplerAddressingMode(ref v) => {
+                15u8.hash(state);
+                v.hash(state)
+            }
+            Operand::SamplerFilterMode(ref v) => {
+                16u8.hash(state);
+                v.hash(state)
+            }
+            Operand::ImageFormat(ref v) => {
+                17u8.hash(state);
+                v.hash(state)
+            }
+            Operand::ImageChannelOrder(ref v) => {
+                18u8.hash(state);
+                v.hash(state)
+            }
+            Operand::ImageChannelDataType(ref v) => {
+                19u8.hash(state);
+                v.hash(state)
+            }
+            Operand::FPRoundingMode(ref v) => {
+                20u8.hash(state);
+                v.hash(state)
+            }
+            Operand::LinkageType(ref v) => {
+                21u8.hash(state);
+                v.hash(state)
+            }
+            Operand::AccessQualifier(ref v) => {
+                22u8.hash(state);
+                v.hash(state)
+            }
+            Operand::FunctionParameterAttribute(ref v) => {
+                23u8.hash(state);
+                v.hash(state)
+            }
+            Operand::Decoration(ref v) => {
+                24u8.hash(state);
+                v.hash(state)
+            }
+            Operand::BuiltIn(ref v) => {
+                25u8.hash(state);
+                v.hash(state)
+            }
+            Operand::Scope(ref v) => {
+                26u8.hash(state);
+                v.hash(state)
+            }
+            Operand::GroupOperation(ref v) => {
+                27u8.hash(state);
+                v.hash(state)
+            }
+            Operand::KernelEnqueueFlags(ref v) => {
+                28u8.hash(state);
+                v.hash(state)
+            }
+            Operand::Capability(ref v) => {
+                29u8.hash(state);
+                v.hash(state)
+            }
+            Operand::IdMemorySemantics(ref v) => {
+                30u8.hash(state);
+                v.hash(state)
+            }
+            Operand::IdScope(ref v) => {
+                31u8.hash(state);
+                v.hash(state)
+            }
+            Operand::IdRef(ref v) => {
+                32u8.hash(state);
+                v.hash(state)
+            }
+            Operand::LiteralInt32(ref v) => {
+                33u8.hash(state);
+                v.hash(state)
+            }
+            Operand::LiteralInt64(ref v) => {
+                34u8.hash(state);
+                v.hash(state)
+            }
+            Operand::LiteralFloat32(v) => {
+                35u8.hash(state);
+                f32_to_u32(v).hash(state)
+            }
+            Operand::LiteralFloat64(v) => {
+                36u8.hash(state);
+                f64_to_u64(v).hash(state)
+            }
+            Operand::LiteralExtInstInteger(ref v) => {
+                37u8.hash(state);
+                v.hash(state)
+            }
+            Operand::LiteralSpecConstantOpInteger(ref v) => {
+                38u8.hash(state);
+                v.hash(state)
+            }
+            Operand::LiteralString(ref v) => {
+                39u8.hash(state);
+                v.hash(state)
+            }
+            Operand::RawWords(ref v) => {
+                40u8.hash(state);
+                v.hash(state)
+            }
+            Operand::UnknownEnumerant(kind, v) => {
+                41u8.hash(state);
+                kind.hash(state);
+                v.hash(state)
+            }
         }
     }
 }
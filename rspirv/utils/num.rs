@@ -62,12 +62,28 @@ pub fn bytes_to_u32_le(values: &[u8]) -> u32 {
     word
 }
 
+/// Sign-extends the lowest `width` bits of the given u32 `val`.
+///
+/// `val` is reinterpreted as is if `width` is not in the range of 1 to 31.
+pub fn sign_extend_u32(val: u32, width: u32) -> i32 {
+    if width == 0 || width >= 32 {
+        return val as i32;
+    }
+    let shift = 32 - width;
+    ((val << shift) as i32) >> shift
+}
+
 /// Bitwisely casts the given f32 `value` to u32.
 /// in little-endian format.
 pub fn f32_to_u32(value: f32) -> u32 {
     unsafe { mem::transmute::<f32, u32>(value) }
 }
 
+/// Bitwisely casts the given f64 `value` to u64.
+pub fn f64_to_u64(value: f64) -> u64 {
+    unsafe { mem::transmute::<f64, u64>(value) }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -97,4 +113,15 @@ mod test {
         assert_eq!(0x12u32, bytes_to_u32_le(&[0x12]));
         assert_eq!(0x0u32, bytes_to_u32_le(&[]));
     }
+
+    #[test]
+    fn test_sign_extend_u32() {
+        assert_eq!(-1, sign_extend_u32(0x0000ffff, 16));
+        assert_eq!(-1, sign_extend_u32(0xffffffff, 16));
+        assert_eq!(0x7fff, sign_extend_u32(0x00007fff, 16));
+        assert_eq!(-128, sign_extend_u32(0x00000080, 8));
+        assert_eq!(127, sign_extend_u32(0xffffff7f, 8));
+        assert_eq!(-2, sign_extend_u32(0xfffffffe, 32));
+        assert_eq!(-2, sign_extend_u32(0xfffffffe, 0));
+    }
 }
@@ -29,5 +29,10 @@ extern crate bitflags;
 extern crate num;
 #[macro_use]
 extern crate num_derive;
+#[cfg(feature = "serialize")]
+extern crate serde;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
 
 include!("spirv.rs");
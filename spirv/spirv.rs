@@ -22,8 +22,12 @@ pub const MAJOR_VERSION: u32 = 1;
 pub const MINOR_VERSION: u32 = 1;
 pub const REVISION: u32 = 5;
 
+/// Generator magic number of rspirv: the vendor id registered for rspirv (15) in the high 16 bits and the tool version in the low 16 bits.
+pub const GENERATOR_RSPIRV: u32 = 0x000f0000;
+
 bitflags!{
     /// SPIR-V operand kind: [ImageOperands](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_image_operands_a_image_operands)
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
     pub flags ImageOperands : u32 {
         const IMAGE_OPERANDS_NONE = 0x0000,
         const IMAGE_OPERANDS_BIAS = 0x0001,
@@ -39,6 +43,7 @@ bitflags!{
 
 bitflags!{
     /// SPIR-V operand kind: [FPFastMathMode](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_fp_fast_math_mode_a_fp_fast_math_mode)
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
     pub flags FPFastMathMode : u32 {
         const FPFAST_MATH_MODE_NONE = 0x0000,
         const FPFAST_MATH_MODE_NOT_NAN = 0x0001,
@@ -51,6 +56,7 @@ bitflags!{
 
 bitflags!{
     /// SPIR-V operand kind: [SelectionControl](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_selection_control_a_selection_control)
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
     pub flags SelectionControl : u32 {
         const SELECTION_CONTROL_NONE = 0x0000,
         const SELECTION_CONTROL_FLATTEN = 0x0001,
@@ -60,6 +66,7 @@ bitflags!{
 
 bitflags!{
     /// SPIR-V operand kind: [LoopControl](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_loop_control_a_loop_control)
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
     pub flags LoopControl : u32 {
         const LOOP_CONTROL_NONE = 0x0000,
         const LOOP_CONTROL_UNROLL = 0x0001,
@@ -71,6 +78,7 @@ bitflags!{
 
 bitflags!{
     /// SPIR-V operand kind: [FunctionControl](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_function_control_a_function_control)
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
     pub flags FunctionControl : u32 {
         const FUNCTION_CONTROL_NONE = 0x0000,
         const FUNCTION_CONTROL_INLINE = 0x0001,
@@ -82,6 +90,7 @@ bitflags!{
 
 bitflags!{
     /// SPIR-V operand kind: [MemorySemantics](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_memory_semantics_a_memory_semantics)
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
     pub flags MemorySemantics : u32 {
         const MEMORY_SEMANTICS_RELAXED = 0x0000,
         const MEMORY_SEMANTICS_NONE = 0x0000,
@@ -100,6 +109,7 @@ bitflags!{
 
 bitflags!{
     /// SPIR-V operand kind: [MemoryAccess](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_memory_access_a_memory_access)
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
     pub flags MemoryAccess : u32 {
         const MEMORY_ACCESS_NONE = 0x0000,
         const MEMORY_ACCESS_VOLATILE = 0x0001,
@@ -110,6 +120,7 @@ bitflags!{
 
 bitflags!{
     /// SPIR-V operand kind: [KernelProfilingInfo](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_kernel_profiling_info_a_kernel_profiling_info)
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
     pub flags KernelProfilingInfo : u32 {
         const KERNEL_PROFILING_INFO_NONE = 0x0000,
         const KERNEL_PROFILING_INFO_CMD_EXEC_TIME = 0x0001,
@@ -118,7 +129,8 @@ bitflags!{
 
 /// SPIR-V operand kind: [SourceLanguage](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_source_language_a_source_language)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum SourceLanguage {
     Unknown = 0,
     ESSL = 1,
@@ -129,7 +141,8 @@ pub enum SourceLanguage {
 
 /// SPIR-V operand kind: [ExecutionModel](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_execution_model_a_execution_model)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum ExecutionModel {
     Vertex = 0,
     TessellationControl = 1,
@@ -142,7 +155,8 @@ pub enum ExecutionModel {
 
 /// SPIR-V operand kind: [AddressingModel](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_addressing_model_a_addressing_model)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum AddressingModel {
     Logical = 0,
     Physical32 = 1,
@@ -151,7 +165,8 @@ pub enum AddressingModel {
 
 /// SPIR-V operand kind: [MemoryModel](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_memory_model_a_memory_model)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum MemoryModel {
     Simple = 0,
     GLSL450 = 1,
@@ -160,7 +175,8 @@ pub enum MemoryModel {
 
 /// SPIR-V operand kind: [ExecutionMode](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_execution_mode_a_execution_mode)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum ExecutionMode {
     Invocations = 0,
     SpacingEqual = 1,
@@ -201,7 +217,8 @@ pub enum ExecutionMode {
 
 /// SPIR-V operand kind: [StorageClass](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_storage_class_a_storage_class)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum StorageClass {
     UniformConstant = 0,
     Input = 1,
@@ -219,7 +236,8 @@ pub enum StorageClass {
 
 /// SPIR-V operand kind: [Dim](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_dim_a_dim)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Dim {
     Dim1D = 0,
     Dim2D = 1,
@@ -232,7 +250,8 @@ pub enum Dim {
 
 /// SPIR-V operand kind: [SamplerAddressingMode](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_sampler_addressing_mode_a_sampler_addressing_mode)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum SamplerAddressingMode {
     None = 0,
     ClampToEdge = 1,
@@ -243,7 +262,8 @@ pub enum SamplerAddressingMode {
 
 /// SPIR-V operand kind: [SamplerFilterMode](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_sampler_filter_mode_a_sampler_filter_mode)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum SamplerFilterMode {
     Nearest = 0,
     Linear = 1,
@@ -251,7 +271,8 @@ pub enum SamplerFilterMode {
 
 /// SPIR-V operand kind: [ImageFormat](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_image_format_a_image_format)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum ImageFormat {
     Unknown = 0,
     Rgba32f = 1,
@@ -297,7 +318,8 @@ pub enum ImageFormat {
 
 /// SPIR-V operand kind: [ImageChannelOrder](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_image_channel_order_a_image_channel_order)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum ImageChannelOrder {
     R = 0,
     A = 1,
@@ -323,7 +345,8 @@ pub enum ImageChannelOrder {
 
 /// SPIR-V operand kind: [ImageChannelDataType](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_image_channel_data_type_a_image_channel_data_type)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum ImageChannelDataType {
     SnormInt8 = 0,
     SnormInt16 = 1,
@@ -346,7 +369,8 @@ pub enum ImageChannelDataType {
 
 /// SPIR-V operand kind: [FPRoundingMode](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_fp_rounding_mode_a_fp_rounding_mode)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum FPRoundingMode {
     RTE = 0,
     RTZ = 1,
@@ -356,7 +380,8 @@ pub enum FPRoundingMode {
 
 /// SPIR-V operand kind: [LinkageType](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_linkage_type_a_linkage_type)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum LinkageType {
     Export = 0,
     Import = 1,
@@ -364,7 +389,8 @@ pub enum LinkageType {
 
 /// SPIR-V operand kind: [AccessQualifier](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_access_qualifier_a_access_qualifier)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum AccessQualifier {
     ReadOnly = 0,
     WriteOnly = 1,
@@ -373,7 +399,8 @@ pub enum AccessQualifier {
 
 /// SPIR-V operand kind: [FunctionParameterAttribute](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_function_parameter_attribute_a_function_parameter_attribute)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum FunctionParameterAttribute {
     Zext = 0,
     Sext = 1,
@@ -387,7 +414,8 @@ pub enum FunctionParameterAttribute {
 
 /// SPIR-V operand kind: [Decoration](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_decoration_a_decoration)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Decoration {
     RelaxedPrecision = 0,
     SpecId = 1,
@@ -437,7 +465,8 @@ pub enum Decoration {
 
 /// SPIR-V operand kind: [BuiltIn](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_built_in_a_built_in)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum BuiltIn {
     Position = 0,
     PointSize = 1,
@@ -492,7 +521,8 @@ pub enum BuiltIn {
 
 /// SPIR-V operand kind: [Scope](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_scope_a_scope)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Scope {
     CrossDevice = 0,
     Device = 1,
@@ -503,7 +533,8 @@ pub enum Scope {
 
 /// SPIR-V operand kind: [GroupOperation](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_group_operation_a_group_operation)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum GroupOperation {
     Reduce = 0,
     InclusiveScan = 1,
@@ -512,7 +543,8 @@ pub enum GroupOperation {
 
 /// SPIR-V operand kind: [KernelEnqueueFlags](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_kernel_enqueue_flags_a_kernel_enqueue_flags)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum KernelEnqueueFlags {
     NoWait = 0,
     WaitKernel = 1,
@@ -521,7 +553,8 @@ pub enum KernelEnqueueFlags {
 
 /// SPIR-V operand kind: [Capability](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_capability_a_capability)
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Capability {
     Matrix = 0,
     Shader = 1,
@@ -589,7 +622,8 @@ pub enum Capability {
 
 /// SPIR-V [instructions](https://www.khronos.org/registry/spir-v/specs/1.1/SPIRV.html#_a_id_instructions_a_instructions) opcodes
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Op {
     Nop = 0,
     Undef = 1,